@@ -0,0 +1,1688 @@
+/**
+ * Account lifecycle operations
+ *
+ * Operations spanning secure storage, column configuration and the PDS
+ */
+
+use crate::api::avatars::AvatarCache;
+use crate::api::cache::RequestCache;
+use crate::auth::jwt::decode_claims;
+use crate::auth::oauth::{PendingAuthorization, BSKY_AUTHORIZATION_SERVER};
+use crate::auth::ATProtocolClient;
+use crate::storage::columns::{delete_columns, lock_columns, remove_columns_for_did};
+use crate::storage::StorageManager;
+use crate::types::{
+    Account, AccountPatch, AuthError, AuthToken, ProfileView, SessionResponse,
+};
+use chrono::Utc;
+use futures_util::{stream, StreamExt};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Check that a new session belongs to the identity the user asked for
+///
+/// When the identifier is a handle, it must resolve to the session DID; a mismatch
+/// indicates a hijacked handle or a misconfigured/spoofed server. Email identifiers
+/// are not checked.
+async fn verify_session_identity(
+    client: &ATProtocolClient,
+    identifier: &str,
+    session: &SessionResponse,
+) -> Result<(), AuthError> {
+    let identifier = identifier.trim().trim_start_matches('@');
+
+    if identifier.contains('@') {
+        return Ok(());
+    }
+
+    let resolved_did = if identifier.starts_with("did:") {
+        identifier.to_string()
+    } else {
+        client.resolve_handle(&identifier.to_lowercase()).await?
+    };
+
+    if resolved_did != session.did {
+        return Err(AuthError::IdentityMismatch(format!(
+            "'{}' resolves to {} but the server returned a session for {}",
+            identifier, resolved_did, session.did
+        )));
+    }
+
+    Ok(())
+}
+
+/// Find the PDS hosting an identifier by following its DID document
+///
+/// Handles are first resolved to a DID through `client`'s server; email identifiers
+/// cannot be resolved.
+///
+/// # Returns
+/// The normalized PDS URL, or None if discovery failed (callers fall back to the
+/// server they already have)
+pub async fn discover_pds(client: &ATProtocolClient, identifier: &str) -> Option<String> {
+    let identifier = identifier.trim().trim_start_matches('@');
+    if identifier.contains('@') {
+        return None;
+    }
+
+    let did = if identifier.starts_with("did:") {
+        identifier.to_string()
+    } else {
+        client.resolve_handle(&identifier.to_lowercase()).await.ok()?
+    };
+    let endpoint = client.resolve_pds_endpoint(&did).await.ok()?;
+
+    ATProtocolClient::normalize_server_url(Some(endpoint)).ok()
+}
+
+/// Find the OAuth authorization server of an identifier's PDS
+///
+/// # Returns
+/// The authorization server URL, or `BSKY_AUTHORIZATION_SERVER` when there is no
+/// identifier or discovery failed
+pub async fn discover_authorization_server(
+    client: &ATProtocolClient,
+    identifier: Option<&str>,
+) -> String {
+    let Some(identifier) = identifier else {
+        return BSKY_AUTHORIZATION_SERVER.to_string();
+    };
+
+    match discover_pds(client, identifier).await {
+        Some(pds_url) => client
+            .authorization_server_for_pds(&pds_url)
+            .await
+            .unwrap_or_else(|_| BSKY_AUTHORIZATION_SERVER.to_string()),
+        None => BSKY_AUTHORIZATION_SERVER.to_string(),
+    }
+}
+
+/// Finish an OAuth login and build the account/token pair without persisting anything
+///
+/// # Arguments
+/// * `client` - Any client (the authorization server and PDS URLs come from `pending`)
+/// * `pending` - Authorization started with `start_oauth`
+/// * `timeout` - How long to wait for the browser redirect
+pub async fn establish_oauth_session(
+    client: &ATProtocolClient,
+    pending: PendingAuthorization,
+    timeout: Duration,
+) -> Result<(Account, AuthToken), AuthError> {
+    let session = client.complete_oauth(pending, timeout).await?;
+
+    let account_id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+
+    let account = Account {
+        id: account_id.clone(),
+        did: session.tokens.sub.clone(),
+        handle: session.handle,
+        email: None,
+        display_name: None,
+        avatar: None,
+        server_url: session.pds_url,
+        created_at: now.clone(),
+        last_used_at: now,
+        is_active: true,
+        refresh_failure_count: 0,
+        next_refresh_not_before: None,
+        note: None,
+    };
+    let auth_token = session.tokens.into_auth_token(
+        &account_id,
+        &session.dpop_key,
+        &session.issuer,
+        &session.client_id,
+    );
+
+    Ok((account, auth_token))
+}
+
+/// Create a session and build the account/token pair without persisting anything
+///
+/// # Arguments
+/// * `client` - Client for the PDS to log in to
+/// * `identifier` - Handle, DID or email
+/// * `password` - Account password
+/// * `auth_factor_token` - Emailed two-factor code, if the PDS asked for one
+pub async fn establish_session(
+    client: &ATProtocolClient,
+    identifier: &str,
+    password: &str,
+    auth_factor_token: Option<&str>,
+) -> Result<(Account, AuthToken), AuthError> {
+    // Attempt to create session with retry logic
+    let session = client
+        .with_retry(|| client.create_session(identifier, password, auth_factor_token))
+        .await?;
+
+    verify_session_identity(client, identifier, &session).await?;
+
+    // Create account object
+    let account_id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+
+    let mut account = Account {
+        id: account_id.clone(),
+        did: session.did.clone(),
+        handle: session.handle.clone(),
+        email: session.email.clone(),
+        display_name: session.display_name.clone(),
+        avatar: session.avatar.clone(),
+        server_url: client.server_url().to_string(),
+        created_at: now.clone(),
+        last_used_at: now,
+        is_active: true,
+        refresh_failure_count: 0,
+        next_refresh_not_before: None,
+        note: None,
+    };
+
+    // Create auth token
+    let auth_token = AuthToken::from_session(&account_id, session);
+
+    enrich_from_profile(client, &auth_token.access_jwt, &mut account).await;
+
+    Ok((account, auth_token))
+}
+
+/// Fill in the display name and avatar, which createSession doesn't return
+///
+/// # Note
+/// Best effort: if the profile can't be fetched the account keeps whatever the
+/// session provided (usually None) and is filled in by the next profile refresh.
+async fn enrich_from_profile(client: &ATProtocolClient, access_jwt: &str, account: &mut Account) {
+    if let Ok(profile) = client.get_profile(access_jwt, &account.did).await {
+        account.display_name = profile.display_name.or(account.display_name.take());
+        account.avatar = profile.avatar.or(account.avatar.take());
+    }
+}
+
+/// Warning attached to logins whose password is not shaped like an app password
+pub const APP_PASSWORD_WARNING: &str = "This does not look like an app password. Signing in \
+     with your main password gives the app full control of your account; create an app \
+     password in Settings > Privacy and security instead.";
+
+/// Check whether a password has the shape of a Bluesky app password
+///
+/// App passwords are four groups of four lowercase letters or digits joined by
+/// hyphens (`xxxx-xxxx-xxxx-xxxx`).
+pub fn validate_app_password(password: &str) -> bool {
+    let groups: Vec<&str> = password.split('-').collect();
+    groups.len() == 4
+        && groups.iter().all(|group| {
+            group.len() == 4
+                && group.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        })
+}
+
+/// Result of `login` / `add_account`: the account plus non-blocking warnings
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginResult {
+    #[serde(flatten)]
+    pub account: Account,
+    /// Set when the password is not an app password (the login still succeeded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_warning: Option<String>,
+}
+
+impl LoginResult {
+    /// Wrap a logged-in account, flagging a password that isn't an app password
+    pub fn new(account: Account, password: &str) -> Self {
+        Self {
+            account,
+            password_warning: (!validate_app_password(password))
+                .then(|| APP_PASSWORD_WARNING.to_string()),
+        }
+    }
+}
+
+/// Reject an account whose DID is already stored
+///
+/// # Note
+/// Compared by DID rather than handle: handles can change, so the same identity may
+/// come back under a new handle (and a released handle may belong to someone else).
+pub fn ensure_not_added(existing: &[Account], account: &Account) -> Result<(), String> {
+    match existing.iter().find(|stored| stored.did == account.did) {
+        Some(stored) => Err(format!(
+            "Account '{}' ({}) already exists",
+            stored.handle, stored.did
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Persist a newly established account and its token
+pub async fn save_new_account(
+    storage: &StorageManager,
+    account: &Account,
+    auth_token: &AuthToken,
+) -> Result<(), String> {
+    storage
+        .save_account(account)
+        .await
+        .map_err(|e| format!("Failed to save account: {}", e))?;
+
+    storage
+        .save_auth_token(auth_token)
+        .await
+        .map_err(|e| format!("Failed to save token: {}", e))
+}
+
+/// Maximum length of an account note in characters
+pub const MAX_ACCOUNT_NOTE_CHARS: usize = 500;
+
+/// Set or clear the private note of an account
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `account_id` - Account to annotate
+/// * `note` - New note (None or a blank string clears it)
+///
+/// # Returns
+/// The updated account
+pub async fn set_account_note(
+    storage: &StorageManager,
+    account_id: &str,
+    note: Option<String>,
+) -> Result<Account, AuthError> {
+    let note = normalize_note(note)?;
+
+    storage
+        .modify_account(account_id, |account| {
+            let changed = account.note != note;
+            account.note = note;
+            changed
+        })
+        .await
+}
+
+/// Trim a note, dropping a blank one and rejecting one over `MAX_ACCOUNT_NOTE_CHARS`
+fn normalize_note(note: Option<String>) -> Result<Option<String>, AuthError> {
+    let note = note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+
+    if let Some(note) = &note {
+        let length = note.chars().count();
+        if length > MAX_ACCOUNT_NOTE_CHARS {
+            return Err(AuthError::InvalidInput(format!(
+                "Note is {} characters long (maximum {})",
+                length, MAX_ACCOUNT_NOTE_CHARS
+            )));
+        }
+    }
+
+    Ok(note)
+}
+
+/// Edit the display metadata of an account
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `account_id` - Account to edit
+/// * `patch` - Fields to change; None leaves a field as is, a blank string clears it
+///
+/// # Returns
+/// The updated account
+///
+/// # Note
+/// The note follows the same rules as `set_account_note`. The display name and avatar
+/// are overwritten again by the next profile refresh. Editing is not a use of the
+/// account: `last_used_at` is left alone.
+pub async fn update_account(
+    storage: &StorageManager,
+    account_id: &str,
+    patch: AccountPatch,
+) -> Result<Account, AuthError> {
+    let blank_to_none = |value: String| {
+        let value = value.trim().to_string();
+        (!value.is_empty()).then_some(value)
+    };
+
+    let display_name = patch.display_name.map(blank_to_none);
+    let avatar = patch.avatar.map(blank_to_none);
+    let note = patch.note.map(|note| normalize_note(Some(note))).transpose()?;
+
+    storage
+        .modify_account(account_id, |account| {
+            let mut changed = false;
+            if let Some(display_name) = display_name {
+                changed |= account.display_name != display_name;
+                account.display_name = display_name;
+            }
+            if let Some(avatar) = avatar {
+                changed |= account.avatar != avatar;
+                account.avatar = avatar;
+            }
+            if let Some(note) = note {
+                changed |= account.note != note;
+                account.note = note;
+            }
+            changed
+        })
+        .await
+}
+
+/// Check that the stored access token belongs to the account it is filed under
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `account_id` - Account to check
+///
+/// # Returns
+/// Whether the token's `sub` claim equals the account DID (a mismatch means requests
+/// would run as another account)
+pub async fn verify_token_account_match(
+    storage: &StorageManager,
+    account_id: &str,
+) -> Result<bool, AuthError> {
+    let account = storage.get_account(account_id).await?;
+    let token = storage.get_auth_token(account_id).await?;
+
+    let claims = decode_claims(&token.access_jwt)?;
+    let subject = claims
+        .get("sub")
+        .and_then(|sub| sub.as_str())
+        .ok_or_else(|| AuthError::InvalidCredentials("Access token has no sub claim".into()))?;
+
+    Ok(subject == account.did)
+}
+
+/// Result of `verify_session`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionVerification {
+    /// The PDS accepted the account's token
+    pub valid: bool,
+    /// Account is active on its PDS (false when deactivated, suspended or taken down)
+    pub active: bool,
+    /// Reason the account is inactive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Handle stored before the check, if the PDS reported a different one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_handle: Option<String>,
+    /// Account as stored after the check
+    pub account: Account,
+}
+
+/// Confirm with the PDS that an account's session still works
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `client` - Client for the account's PDS
+/// * `account` - Account to check
+///
+/// # Note
+/// An expired access token is refreshed first. A rejected or unrefreshable token
+/// yields `valid: false`; transport and server errors are returned as errors. A
+/// handle or email change reported by the PDS is saved to the account.
+pub async fn verify_session(
+    storage: &StorageManager,
+    client: &ATProtocolClient,
+    account: &Account,
+) -> Result<SessionVerification, AuthError> {
+    let session = match storage.get_valid_token(&account.id, client).await {
+        Ok(token) => client.get_session(&token.access_jwt).await,
+        Err(e) => Err(e),
+    };
+
+    let session = match session {
+        Ok(session) => session,
+        Err(AuthError::TokenExpired) => {
+            return Ok(SessionVerification {
+                valid: false,
+                active: false,
+                status: None,
+                previous_handle: None,
+                account: account.clone(),
+            });
+        }
+        Err(e) => return Err(e),
+    };
+
+    if session.did != account.did {
+        return Err(AuthError::IdentityMismatch(format!(
+            "Session belongs to {} instead of {}",
+            session.did, account.did
+        )));
+    }
+
+    let mut previous_handle = None;
+    let account = storage
+        .modify_account(&account.id, |account| {
+            previous_handle = (account.handle != session.handle).then(|| account.handle.clone());
+            let email_changed = session.email.is_some() && account.email != session.email;
+            account.handle = session.handle;
+            if email_changed {
+                account.email = session.email;
+            }
+            previous_handle.is_some() || email_changed
+        })
+        .await?;
+
+    Ok(SessionVerification {
+        valid: true,
+        active: session.active,
+        status: session.status,
+        previous_handle,
+        account,
+    })
+}
+
+/// Summary of the data removed by `remove_account_fully`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountRemovalReport {
+    /// Removed account ID
+    pub account_id: String,
+    /// DID of the removed account
+    pub did: String,
+    /// Whether a stored auth token was deleted
+    pub token_removed: bool,
+    /// Whether the server-side session was revoked
+    pub session_revoked: bool,
+    /// Number of deleted deck columns
+    pub columns_removed: usize,
+    /// Number of evicted cached responses
+    pub cache_entries_removed: usize,
+    /// Whether the cached avatar was deleted (kept while another account shows it)
+    pub avatar_evicted: bool,
+    /// Active account afterwards (None if no account is left)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_account_id: Option<String>,
+}
+
+/// Remove an account and everything stored for it
+///
+/// Revokes the session server-side (best effort), deletes the token and account,
+/// the account's deck columns, its cached responses and its cached avatar, then
+/// promotes another account if none is active anymore (see `reconcile_after_removal`).
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory (columns file location)
+/// * `cache` - Request cache
+/// * `avatars` - Avatar cache
+/// * `client` - Client for the account's PDS
+/// * `account_id` - Account to remove
+pub async fn remove_account_fully(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+    cache: &RequestCache,
+    avatars: &AvatarCache,
+    client: &ATProtocolClient,
+    account_id: &str,
+) -> Result<AccountRemovalReport, String> {
+    let account = storage
+        .get_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+
+    let token = storage.get_auth_token(account_id).await.ok();
+
+    // Revoke server-side session; failures (e.g. offline) must not block local removal
+    let session_revoked = match &token {
+        Some(token) => client.delete_session(&token.refresh_jwt).await.is_ok(),
+        None => false,
+    };
+
+    storage
+        .remove_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to delete account: {}", e))?;
+
+    let (active_account_id, columns_removed) =
+        reconcile_after_removal(storage, data_dir, &account.did).await?;
+    let cache_entries_removed = cache.evict_account(account_id);
+
+    let remaining = storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?;
+    let avatar_evicted = account
+        .avatar
+        .as_deref()
+        .is_some_and(|url| avatars.evict(url, &remaining));
+
+    Ok(AccountRemovalReport {
+        account_id: account.id,
+        did: account.did,
+        token_removed: token.is_some(),
+        session_revoked,
+        columns_removed,
+        cache_entries_removed,
+        avatar_evicted,
+        active_account_id,
+    })
+}
+
+/// Confirmation `reset_all_data` requires, so it can't be triggered by accident
+pub const RESET_CONFIRMATION: &str = "RESET";
+
+/// Delete every account, token and the deck configuration ("reset app")
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory (columns file location)
+/// * `cache` - Request cache
+/// * `avatars` - Avatar cache (the accounts' own avatars are deleted)
+/// * `confirmation` - Must equal `RESET_CONFIRMATION`
+///
+/// # Returns
+/// Number of removed accounts
+///
+/// # Note
+/// Sessions are not revoked server-side. Settings are kept.
+pub async fn reset_all_data(
+    storage: &StorageManager,
+    data_dir: &Path,
+    cache: &RequestCache,
+    avatars: &AvatarCache,
+    confirmation: &str,
+) -> Result<usize, String> {
+    if confirmation != RESET_CONFIRMATION {
+        return Err(format!("Reset not confirmed (expected \"{}\")", RESET_CONFIRMATION));
+    }
+
+    let accounts = storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?;
+
+    storage
+        .clear_all()
+        .await
+        .map_err(|e| format!("Failed to clear storage: {}", e))?;
+    {
+        let _guard = lock_columns()?;
+        delete_columns(data_dir)?;
+    }
+    for account in &accounts {
+        cache.evict_account(&account.id);
+        if let Some(url) = &account.avatar {
+            avatars.evict(url, &[]);
+        }
+    }
+
+    Ok(accounts.len())
+}
+
+/// Changes made by `ensure_active_invariant`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveInvariantReport {
+    /// Account activated because none was active
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activated: Option<String>,
+    /// Accounts deactivated because more than one was active
+    pub deactivated: Vec<String>,
+}
+
+/// Most recently used account (unparseable timestamps sort as oldest)
+fn most_recently_used<'a>(accounts: impl Iterator<Item = &'a Account>) -> Option<&'a Account> {
+    accounts.max_by_key(|account| {
+        chrono::DateTime::parse_from_rfc3339(&account.last_used_at)
+            .map(|t| t.timestamp_millis())
+            .unwrap_or(i64::MIN)
+    })
+}
+
+/// Make sure exactly one account is active (when any account exists)
+///
+/// With no active account the most recently used one is activated; with several,
+/// only the most recently used one stays active. Fixes are persisted.
+///
+/// # Arguments
+/// * `storage` - Storage manager
+pub async fn ensure_active_invariant(
+    storage: &StorageManager,
+) -> Result<ActiveInvariantReport, AuthError> {
+    let accounts = storage.list_accounts().await?;
+    let mut report = ActiveInvariantReport::default();
+
+    let active_count = accounts.iter().filter(|a| a.is_active).count();
+
+    if active_count == 0 {
+        if let Some(account) = most_recently_used(accounts.iter()) {
+            storage
+                .modify_account(&account.id, |account| {
+                    let changed = !account.is_active;
+                    account.is_active = true;
+                    changed
+                })
+                .await?;
+            report.activated = Some(account.id.clone());
+        }
+    } else if active_count > 1 {
+        let keep = most_recently_used(accounts.iter().filter(|a| a.is_active))
+            .map(|a| a.id.clone());
+
+        for account in accounts.iter().filter(|a| a.is_active) {
+            if Some(&account.id) == keep.as_ref() {
+                continue;
+            }
+            storage
+                .modify_account(&account.id, |account| {
+                    let changed = account.is_active;
+                    account.is_active = false;
+                    changed
+                })
+                .await?;
+            report.deactivated.push(account.id.clone());
+        }
+        report.deactivated.sort();
+    }
+
+    Ok(report)
+}
+
+/// Clean up after an account was removed from storage
+///
+/// Deletes the account's deck columns and, if no account is active anymore, promotes
+/// the most recently used remaining account.
+///
+/// # Arguments
+/// * `storage` - Storage manager (the account must already be deleted)
+/// * `data_dir` - App data directory (columns file location)
+/// * `did` - DID of the removed account
+///
+/// # Returns
+/// ID of the active account after reconciliation (None if no account is left) and the
+/// number of deleted columns
+pub async fn reconcile_after_removal(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+    did: &str,
+) -> Result<(Option<String>, usize), String> {
+    let columns_removed = remove_columns_for_did(data_dir, did)?;
+
+    ensure_active_invariant(storage)
+        .await
+        .map_err(|e| format!("Failed to update active account: {}", e))?;
+
+    let active_account_id = storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?
+        .into_iter()
+        .find(|account| account.is_active)
+        .map(|account| account.id);
+
+    Ok((active_account_id, columns_removed))
+}
+
+/// Outcome of `logout_account`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutReport {
+    /// Active account afterwards (None if no account is left)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_account_id: Option<String>,
+    /// Whether the session was revoked on the PDS
+    pub session_revoked: bool,
+    /// Why the session could not be revoked (the account was still removed locally)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// Log out of an account: revoke the session on the PDS, then remove it locally
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory (columns file location)
+/// * `client` - Client for the account's PDS
+/// * `account_id` - Account to log out
+///
+/// # Note
+/// A failed revocation (e.g. offline) is reported as `warning` and does not block
+/// the local logout; the refresh token then stays valid until it expires.
+pub async fn logout_account(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+    client: &ATProtocolClient,
+    account_id: &str,
+) -> Result<LogoutReport, String> {
+    let account = storage
+        .get_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+
+    let revoked = match storage.get_auth_token(account_id).await {
+        Ok(token) => client
+            .delete_session(&token.refresh_jwt)
+            .await
+            .map_err(|e| format!("Failed to revoke session: {}", e)),
+        Err(_) => Err("No stored session to revoke".to_string()),
+    };
+
+    storage
+        .remove_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to delete account: {}", e))?;
+
+    let (active_account_id, _) = reconcile_after_removal(storage, data_dir, &account.did).await?;
+
+    Ok(LogoutReport {
+        active_account_id,
+        session_revoked: revoked.is_ok(),
+        warning: revoked.err(),
+    })
+}
+
+/// Maximum number of concurrent getProfile requests in `refresh_all_profiles`
+const PROFILE_REFRESH_CONCURRENCY: usize = 4;
+
+/// Account whose profile could not be refreshed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileRefreshFailure {
+    pub account_id: String,
+    pub error: String,
+}
+
+/// Outcome of `refresh_all_profiles`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileRefreshReport {
+    /// Accounts whose handle, display name or avatar changed (as stored)
+    pub updated: Vec<Account>,
+    /// Accounts whose profile fetch failed
+    pub failed: Vec<ProfileRefreshFailure>,
+}
+
+/// Fetch the own profile of an account
+async fn fetch_own_profile(
+    storage: &StorageManager,
+    client: Result<ATProtocolClient, AuthError>,
+    account: &Account,
+) -> Result<ProfileView, AuthError> {
+    let client = client?;
+    let token = storage.get_valid_token(&account.id, &client).await?;
+    client.get_profile(&token.access_jwt, &account.did).await
+}
+
+/// Store the handle, display name and avatar of a fetched profile
+///
+/// # Returns
+/// The updated account, or `None` if nothing changed
+async fn apply_profile(
+    storage: &StorageManager,
+    account_id: &str,
+    profile: ProfileView,
+) -> Result<Option<Account>, AuthError> {
+    // Applied to the stored account: the token refresh may have updated it meanwhile
+    let mut changed = false;
+    let account = storage
+        .modify_account(account_id, |account| {
+            changed = account.handle != profile.handle
+                || account.display_name != profile.display_name
+                || account.avatar != profile.avatar;
+            account.handle = profile.handle;
+            account.display_name = profile.display_name;
+            account.avatar = profile.avatar;
+            changed
+        })
+        .await?;
+
+    Ok(changed.then_some(account))
+}
+
+/// Refresh the handle, display name and avatar of one account
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `client` - Client for the account's PDS
+/// * `account` - Account to refresh
+///
+/// # Returns
+/// The updated account, or `None` if its metadata was already current
+pub async fn refresh_profile(
+    storage: &StorageManager,
+    client: Result<ATProtocolClient, AuthError>,
+    account: &Account,
+) -> Result<Option<Account>, AuthError> {
+    let profile = fetch_own_profile(storage, client, account).await?;
+    apply_profile(storage, &account.id, profile).await
+}
+
+/// Refresh the handle, display name and avatar of every account
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `client_for` - Builds the client for an account's PDS
+/// * `on_updated` - Called with each account whose metadata changed
+///
+/// # Note
+/// Profiles are fetched concurrently (at most `PROFILE_REFRESH_CONCURRENCY` at a
+/// time). A failed fetch is reported in `failed` and does not abort the batch.
+pub async fn refresh_all_profiles<F, U>(
+    storage: &StorageManager,
+    client_for: F,
+    mut on_updated: U,
+) -> Result<ProfileRefreshReport, AuthError>
+where
+    F: Fn(&Account) -> Result<ATProtocolClient, AuthError>,
+    U: FnMut(&Account),
+{
+    let accounts = storage.list_accounts().await?;
+
+    let mut results: Vec<(Account, Result<ProfileView, AuthError>)> = stream::iter(accounts)
+        .map(|account| {
+            let client = client_for(&account);
+            async move {
+                let profile = fetch_own_profile(storage, client, &account).await;
+                (account, profile)
+            }
+        })
+        .buffer_unordered(PROFILE_REFRESH_CONCURRENCY)
+        .collect()
+        .await;
+    results.sort_by(|(a, _), (b, _)| a.id.cmp(&b.id));
+
+    let mut report = ProfileRefreshReport::default();
+    for (account, profile) in results {
+        let profile = match profile {
+            Ok(profile) => profile,
+            Err(e) => {
+                report.failed.push(ProfileRefreshFailure {
+                    account_id: account.id,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        if let Some(account) = apply_profile(storage, &account.id, profile).await? {
+            on_updated(&account);
+            report.updated.push(account);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::avatars::{cached_avatar_bytes, DEFAULT_AVATAR_TTL};
+    use crate::auth::jwt::tests::make_jwt;
+    use crate::storage::columns::{get_default_columns, load_columns, save_columns};
+    use crate::test_support;
+    use mockito::{Matcher, Mock, Server, ServerGuard};
+    use serde_json::json;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn test_account(id: &str, did: &str) -> Account {
+        Account {
+            did: did.to_string(),
+            ..test_support::account(id)
+        }
+    }
+
+    fn test_token(account_id: &str) -> AuthToken {
+        AuthToken::from_session(
+            account_id,
+            SessionResponse {
+                access_jwt: format!("access-{}", account_id),
+                refresh_jwt: format!("refresh-{}", account_id),
+                did: String::new(),
+                handle: String::new(),
+                email: None,
+                display_name: None,
+                avatar: None,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_remove_account_fully() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        let cache = RequestCache::new(Duration::from_secs(60));
+        let avatars = AvatarCache::new(&data_dir, DEFAULT_AVATAR_TTL);
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", Matcher::Regex("^/avatars/".to_string()))
+            .with_status(200)
+            .with_body("image")
+            .create_async()
+            .await;
+
+        for (id, did) in [("alice", "did:plc:alice"), ("bob", "did:plc:bob")] {
+            let avatar = format!("{}/avatars/{}.jpg", server.url(), id);
+            avatars.get(&avatar).await.unwrap();
+            let account = Account {
+                avatar: Some(avatar),
+                // Only alice is active, so bob has to be promoted once alice is removed
+                is_active: id == "alice",
+                ..test_account(id, did)
+            };
+            storage.save_account(&account).await.unwrap();
+            storage.save_auth_token(&test_token(id)).await.unwrap();
+            let _: u32 = cache
+                .get_or_fetch(id, "app.bsky.notification.getUnreadCount", "", || async {
+                    Ok(1)
+                })
+                .await
+                .unwrap();
+        }
+
+        let mut columns = get_default_columns("did:plc:alice");
+        columns.extend(get_default_columns("did:plc:bob"));
+        columns[1].position = 1;
+        save_columns(&data_dir, columns).unwrap();
+
+        let mock = server
+            .mock("POST", "/xrpc/com.atproto.server.deleteSession")
+            .match_header("authorization", "Bearer refresh-alice")
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let report =
+            remove_account_fully(&storage, &data_dir, &cache, &avatars, &client, "alice")
+                .await
+                .unwrap();
+        mock.assert_async().await;
+
+        assert_eq!(report.did, "did:plc:alice");
+        assert!(report.token_removed);
+        assert!(report.session_revoked);
+        assert_eq!(report.columns_removed, 1);
+        assert_eq!(report.cache_entries_removed, 1);
+        assert!(report.avatar_evicted);
+        let alice_avatar = format!("{}/avatars/alice.jpg", server.url());
+        let bob_avatar = format!("{}/avatars/bob.jpg", server.url());
+        assert_eq!(cached_avatar_bytes(&data_dir, &alice_avatar), 0);
+        assert!(cached_avatar_bytes(&data_dir, &bob_avatar) > 0);
+
+        // The remaining account was promoted
+        assert_eq!(report.active_account_id.as_deref(), Some("bob"));
+        assert!(storage.get_account("bob").await.unwrap().is_active);
+
+        // Alice is gone
+        assert!(storage.get_account("alice").await.is_err());
+        assert!(storage.get_auth_token("alice").await.is_err());
+
+        // Bob is untouched
+        assert!(storage.get_account("bob").await.is_ok());
+        assert!(storage.get_auth_token("bob").await.is_ok());
+        let remaining = load_columns(&data_dir).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].did, "did:plc:bob");
+        assert_eq!(remaining[0].position, 0);
+        assert_eq!(cache.evict_account("bob"), 1);
+    }
+
+    #[test]
+    fn test_duplicate_did_is_rejected() {
+        let existing = vec![test_account("alice", "did:plc:alice")];
+
+        // Same identity under a new handle
+        let mut renamed = test_account("new-id", "did:plc:alice");
+        renamed.handle = "alice.example.com".to_string();
+        assert!(ensure_not_added(&existing, &renamed).is_err());
+
+        // A different identity that took over a released handle
+        let mut other = test_account("bob", "did:plc:bob");
+        other.handle = existing[0].handle.clone();
+        assert!(ensure_not_added(&existing, &other).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reset_all_data_removes_storage_and_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        let cache = RequestCache::new(Duration::from_secs(60));
+        let avatars = AvatarCache::new(&data_dir, DEFAULT_AVATAR_TTL);
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/avatar.jpg")
+            .with_status(200)
+            .with_body("image")
+            .create_async()
+            .await;
+        let avatar = format!("{}/avatar.jpg", server.url());
+        avatars.get(&avatar).await.unwrap();
+        for (id, did) in [("alice", "did:plc:alice"), ("bob", "did:plc:bob")] {
+            let account = Account {
+                avatar: Some(avatar.clone()),
+                ..test_account(id, did)
+            };
+            storage.save_account(&account).await.unwrap();
+            storage.save_auth_token(&test_token(id)).await.unwrap();
+        }
+        save_columns(&data_dir, get_default_columns("did:plc:alice")).unwrap();
+
+        let unconfirmed = reset_all_data(&storage, &data_dir, &cache, &avatars, "yes").await;
+        assert!(unconfirmed.is_err());
+        assert_eq!(storage.list_accounts().await.unwrap().len(), 2);
+        assert!(cached_avatar_bytes(&data_dir, &avatar) > 0);
+
+        let removed =
+            reset_all_data(&storage, &data_dir, &cache, &avatars, RESET_CONFIRMATION).await;
+        assert_eq!(removed.unwrap(), 2);
+        assert!(storage.list_accounts().await.unwrap().is_empty());
+        assert_eq!(cached_avatar_bytes(&data_dir, &avatar), 0);
+        assert!(storage.get_auth_token("alice").await.is_err());
+        assert!(!data_dir.join(crate::storage::columns::COLUMNS_FILE).exists());
+
+        // The store keeps working after the reset and survives a restart
+        storage.save_account(&test_account("carol", "did:plc:carol")).await.unwrap();
+        drop(storage);
+        let reopened = StorageManager::new(data_dir).await.unwrap();
+        assert_eq!(reopened.list_accounts().await.unwrap()[0].id, "carol");
+    }
+
+    #[tokio::test]
+    async fn test_remove_account_fully_offline() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        let cache = RequestCache::new(Duration::from_secs(60));
+
+        storage
+            .save_account(&test_account("alice", "did:plc:alice"))
+            .await
+            .unwrap();
+        storage.save_auth_token(&test_token("alice")).await.unwrap();
+
+        // Nothing listens on this port: revocation fails but local removal proceeds
+        let client = ATProtocolClient::with_base_url("http://127.0.0.1:9");
+        let avatars = AvatarCache::new(&data_dir, DEFAULT_AVATAR_TTL);
+        let report =
+            remove_account_fully(&storage, &data_dir, &cache, &avatars, &client, "alice")
+                .await
+                .unwrap();
+
+        assert!(!report.session_revoked);
+        assert!(report.token_removed);
+        assert!(storage.list_accounts().await.unwrap().is_empty());
+    }
+
+    async fn mock_create_session(server: &mut ServerGuard, did: &str) -> Mock {
+        server
+            .mock("POST", "/xrpc/com.atproto.server.createSession")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                r#"{{"accessJwt":"access","refreshJwt":"refresh","did":"{}","handle":"alice.bsky.social"}}"#,
+                did
+            ))
+            .create_async()
+            .await
+    }
+
+    async fn mock_resolve_handle(server: &mut ServerGuard, did: &str) -> Mock {
+        server
+            .mock("GET", "/xrpc/com.atproto.identity.resolveHandle")
+            .match_query(Matcher::UrlEncoded(
+                "handle".into(),
+                "alice.bsky.social".into(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(format!(r#"{{"did":"{}"}}"#, did))
+            .create_async()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_login_with_matching_identity_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+
+        let mut server = Server::new_async().await;
+        mock_create_session(&mut server, "did:plc:alice").await;
+        let resolve = mock_resolve_handle(&mut server, "did:plc:alice").await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let (account, token) = establish_session(&client, "@Alice.bsky.social", "password", None)
+            .await
+            .unwrap();
+        save_new_account(&storage, &account, &token).await.unwrap();
+
+        resolve.assert_async().await;
+        assert_eq!(account.did, "did:plc:alice");
+        assert_eq!(account.server_url, server.url());
+        assert!(storage.get_account(&account.id).await.is_ok());
+        assert!(storage.get_auth_token(&account.id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_login_fills_in_profile() {
+        let mut server = Server::new_async().await;
+        mock_create_session(&mut server, "did:plc:alice").await;
+        mock_resolve_handle(&mut server, "did:plc:alice").await;
+        let profile = server
+            .mock("GET", "/xrpc/app.bsky.actor.getProfile")
+            .match_query(Matcher::UrlEncoded("actor".into(), "did:plc:alice".into()))
+            .match_header("authorization", "Bearer access")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "did": "did:plc:alice",
+                    "handle": "alice.bsky.social",
+                    "displayName": "Alice",
+                    "avatar": "https://cdn.bsky.app/img/avatar/alice.jpg"
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let (account, _) = establish_session(&client, "alice.bsky.social", "password", None)
+            .await
+            .unwrap();
+
+        profile.assert_async().await;
+        assert_eq!(account.display_name.as_deref(), Some("Alice"));
+        assert_eq!(
+            account.avatar.as_deref(),
+            Some("https://cdn.bsky.app/img/avatar/alice.jpg")
+        );
+
+        // A failing profile call doesn't fail the login
+        let mut server = Server::new_async().await;
+        mock_create_session(&mut server, "did:plc:alice").await;
+        mock_resolve_handle(&mut server, "did:plc:alice").await;
+        server
+            .mock("GET", "/xrpc/app.bsky.actor.getProfile")
+            .match_query(Matcher::Any)
+            .with_status(500)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let (account, _) = establish_session(&client, "alice.bsky.social", "password", None)
+            .await
+            .unwrap();
+
+        assert!(account.display_name.is_none());
+        assert!(account.avatar.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_login_with_mismatched_identity_is_rejected() {
+        let mut server = Server::new_async().await;
+        mock_create_session(&mut server, "did:plc:attacker").await;
+        mock_resolve_handle(&mut server, "did:plc:alice").await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let result = establish_session(&client, "alice.bsky.social", "password", None).await;
+
+        // Nothing is built, so nothing can be persisted
+        assert!(matches!(result, Err(AuthError::IdentityMismatch(_))));
+    }
+
+    #[tokio::test]
+    async fn test_login_with_email_skips_identity_check() {
+        let mut server = Server::new_async().await;
+        mock_create_session(&mut server, "did:plc:alice").await;
+        let resolve = server
+            .mock("GET", "/xrpc/com.atproto.identity.resolveHandle")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let (account, _) = establish_session(&client, "alice@example.com", "password", None)
+            .await
+            .unwrap();
+
+        assert_eq!(account.did, "did:plc:alice");
+        resolve.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_logout_revokes_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account("alice", "did:plc:alice")).await.unwrap();
+        storage.save_auth_token(&test_token("alice")).await.unwrap();
+
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("POST", "/xrpc/com.atproto.server.deleteSession")
+            .match_header("authorization", "Bearer refresh-alice")
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let report = logout_account(&storage, &data_dir, &client, "alice").await.unwrap();
+        mock.assert_async().await;
+
+        assert!(report.session_revoked);
+        assert_eq!(report.warning, None);
+        assert_eq!(report.active_account_id, None);
+        assert!(storage.get_account("alice").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_logout_offline_still_removes_locally() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        for (id, did) in [("alice", "did:plc:alice"), ("bob", "did:plc:bob")] {
+            storage.save_account(&test_account(id, did)).await.unwrap();
+            storage.save_auth_token(&test_token(id)).await.unwrap();
+        }
+        let client = ATProtocolClient::with_base_url("http://127.0.0.1:9");
+
+        let report = logout_account(&storage, &data_dir, &client, "alice").await.unwrap();
+
+        assert!(!report.session_revoked);
+        assert!(report.warning.unwrap().starts_with("Failed to revoke session"));
+        assert_eq!(report.active_account_id.as_deref(), Some("bob"));
+        assert!(storage.get_account("alice").await.is_err());
+        assert!(storage.get_auth_token("alice").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_login_with_email_two_factor() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut server = Server::new_async().await;
+        let required = server
+            .mock("POST", "/xrpc/com.atproto.server.createSession")
+            .match_body(Matcher::Json(json!({
+                "identifier": "alice@example.com",
+                "password": "password"
+            })))
+            .with_status(401)
+            .with_body(
+                json!({ "error": "AuthFactorTokenRequired", "message": "Sign in code sent" })
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let accepted = server
+            .mock("POST", "/xrpc/com.atproto.server.createSession")
+            .match_body(Matcher::PartialJson(json!({ "authFactorToken": "ABCDE-12345" })))
+            .with_status(200)
+            .with_body(
+                json!({
+                    "accessJwt": "access",
+                    "refreshJwt": "refresh",
+                    "did": "did:plc:alice",
+                    "handle": "alice.bsky.social"
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let first = establish_session(&client, "alice@example.com", "password", None).await;
+        assert!(matches!(first, Err(AuthError::AuthFactorTokenRequired)));
+        assert!(matches!(
+            first.unwrap_err().error_type(),
+            crate::types::AuthErrorType::AuthFactorTokenRequired
+        ));
+
+        let (account, token) =
+            establish_session(&client, "alice@example.com", "password", Some("ABCDE-12345"))
+                .await
+                .unwrap();
+        save_new_account(&storage, &account, &token).await.unwrap();
+
+        required.assert_async().await;
+        accepted.assert_async().await;
+        assert_eq!(storage.get_account(&account.id).await.unwrap().did, "did:plc:alice");
+    }
+
+    #[test]
+    fn test_validate_app_password() {
+        for valid in ["abcd-efgh-ijkl-mnop", "2bx7-q9zr-0000-k3lm"] {
+            assert!(validate_app_password(valid), "{}", valid);
+        }
+        for invalid in [
+            "hunter2",
+            "",
+            "abcd-efgh-ijkl",
+            "abcd-efgh-ijkl-mnop-qrst",
+            "ABCD-EFGH-IJKL-MNOP",
+            "abc-defgh-ijkl-mnop",
+            "abcd efgh ijkl mnop",
+            "abcd-efgh-ijkl-mn!p",
+            " abcd-efgh-ijkl-mnop",
+        ] {
+            assert!(!validate_app_password(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_login_result_warns_about_main_password() {
+        let account = test_account("alice", "did:plc:alice");
+
+        let result = LoginResult::new(account.clone(), "abcd-efgh-ijkl-mnop");
+        assert!(result.password_warning.is_none());
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["did"], "did:plc:alice");
+        assert!(json.get("passwordWarning").is_none());
+
+        let result = LoginResult::new(account, "correct horse battery staple");
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["handle"], "alice.bsky.social");
+        assert_eq!(json["passwordWarning"], APP_PASSWORD_WARNING);
+    }
+
+    fn account_used_at(id: &str, is_active: bool, minutes_ago: i64) -> Account {
+        Account {
+            is_active,
+            last_used_at: (Utc::now() - chrono::Duration::minutes(minutes_ago)).to_rfc3339(),
+            ..test_account(id, &format!("did:plc:{}", id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ensure_active_invariant_activates_most_recent() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        storage.save_account(&account_used_at("old", false, 60)).await.unwrap();
+        storage.save_account(&account_used_at("recent", false, 1)).await.unwrap();
+
+        let report = ensure_active_invariant(&storage).await.unwrap();
+
+        assert_eq!(report.activated.as_deref(), Some("recent"));
+        assert!(report.deactivated.is_empty());
+        assert!(storage.get_account("recent").await.unwrap().is_active);
+        assert!(!storage.get_account("old").await.unwrap().is_active);
+
+        // Already consistent: nothing changes
+        let report = ensure_active_invariant(&storage).await.unwrap();
+        assert_eq!(report, ActiveInvariantReport::default());
+    }
+
+    #[tokio::test]
+    async fn test_ensure_active_invariant_keeps_single_active() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        storage.save_account(&account_used_at("a", true, 30)).await.unwrap();
+        storage.save_account(&account_used_at("b", true, 5)).await.unwrap();
+        storage.save_account(&account_used_at("c", true, 90)).await.unwrap();
+
+        let report = ensure_active_invariant(&storage).await.unwrap();
+
+        assert_eq!(report.activated, None);
+        assert_eq!(report.deactivated, vec!["a".to_string(), "c".to_string()]);
+        assert!(storage.get_account("b").await.unwrap().is_active);
+        assert!(!storage.get_account("a").await.unwrap().is_active);
+        assert!(!storage.get_account("c").await.unwrap().is_active);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_after_removing_active_account() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&account_used_at("alice", true, 1)).await.unwrap();
+        storage.save_account(&account_used_at("bob", false, 10)).await.unwrap();
+        storage.save_account(&account_used_at("carol", false, 60)).await.unwrap();
+
+        let mut columns = get_default_columns("did:plc:alice");
+        columns.extend(get_default_columns("did:plc:bob"));
+        columns[1].position = 1;
+        save_columns(&data_dir, columns).unwrap();
+
+        storage.remove_account("alice").await.unwrap();
+        let (active, columns_removed) =
+            reconcile_after_removal(&storage, &data_dir, "did:plc:alice").await.unwrap();
+
+        assert_eq!(active.as_deref(), Some("bob"));
+        assert_eq!(columns_removed, 1);
+        assert!(storage.get_account("bob").await.unwrap().is_active);
+        assert!(!storage.get_account("carol").await.unwrap().is_active);
+        let remaining = load_columns(&data_dir).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert!(remaining.iter().all(|c| c.did != "did:plc:alice"));
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_after_removing_last_account() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        save_columns(&data_dir, get_default_columns("did:plc:alice")).unwrap();
+
+        let (active, _) =
+            reconcile_after_removal(&storage, &data_dir, "did:plc:alice").await.unwrap();
+
+        assert_eq!(active, None);
+        assert!(load_columns(&data_dir).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_set_and_clear_account_note() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        storage.save_account(&test_account("alice", "did:plc:alice")).await.unwrap();
+
+        let account = set_account_note(&storage, "alice", Some(" work alt ".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(account.note.as_deref(), Some("work alt"));
+        let listed = storage.list_accounts().await.unwrap();
+        assert_eq!(listed[0].note.as_deref(), Some("work alt"));
+
+        let account = set_account_note(&storage, "alice", Some(String::new())).await.unwrap();
+        assert_eq!(account.note, None);
+        assert_eq!(storage.get_account("alice").await.unwrap().note, None);
+    }
+
+    #[tokio::test]
+    async fn test_update_account_changes_only_supplied_fields() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut stored = test_account("alice", "did:plc:alice");
+        stored.display_name = Some("Alice".to_string());
+        stored.avatar = Some("https://cdn.example/old.jpg".to_string());
+        stored.last_used_at = "2024-01-01T00:00:00Z".to_string();
+        storage.save_account(&stored).await.unwrap();
+
+        let patch = AccountPatch {
+            display_name: Some(" Work Alice ".to_string()),
+            ..Default::default()
+        };
+        let account = update_account(&storage, "alice", patch).await.unwrap();
+        assert_eq!(account.display_name.as_deref(), Some("Work Alice"));
+        assert_eq!(account.avatar, stored.avatar);
+        assert_eq!(account.note, None);
+        assert_eq!(account.handle, stored.handle);
+        assert_eq!(account.last_used_at, stored.last_used_at);
+
+        let patch = AccountPatch {
+            avatar: Some(String::new()),
+            note: Some("main".to_string()),
+            ..Default::default()
+        };
+        update_account(&storage, "alice", patch).await.unwrap();
+        let account = storage.get_account("alice").await.unwrap();
+        assert_eq!(account.display_name.as_deref(), Some("Work Alice"));
+        assert_eq!(account.avatar, None);
+        assert_eq!(account.note.as_deref(), Some("main"));
+
+        let result = update_account(&storage, "bob", AccountPatch::default()).await;
+        assert!(matches!(result, Err(AuthError::AccountNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_account_note_length_cap() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        storage.save_account(&test_account("alice", "did:plc:alice")).await.unwrap();
+
+        let longest = "é".repeat(MAX_ACCOUNT_NOTE_CHARS);
+        assert!(set_account_note(&storage, "alice", Some(longest)).await.is_ok());
+
+        let too_long = "x".repeat(MAX_ACCOUNT_NOTE_CHARS + 1);
+        let result = set_account_note(&storage, "alice", Some(too_long)).await;
+        assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+        assert_eq!(
+            storage.get_account("alice").await.unwrap().note.map(|n| n.chars().count()),
+            Some(MAX_ACCOUNT_NOTE_CHARS)
+        );
+    }
+
+    #[test]
+    fn test_account_without_note_deserializes_to_none() {
+        let stored = r#"{"id":"alice","did":"did:plc:alice","handle":"alice.bsky.social",
+            "serverUrl":"https://bsky.social","createdAt":"2024-01-01T00:00:00Z",
+            "lastUsedAt":"2024-01-01T00:00:00Z","isActive":true}"#;
+
+        let account: Account = serde_json::from_str(stored).unwrap();
+
+        assert_eq!(account.note, None);
+        assert!(!serde_json::to_string(&account).unwrap().contains("note"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_token_account_match() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        storage.save_account(&test_account("alice", "did:plc:alice")).await.unwrap();
+        storage.save_account(&test_account("bob", "did:plc:bob")).await.unwrap();
+
+        let mut alice_token = test_token("alice");
+        alice_token.access_jwt = make_jwt(&json!({ "sub": "did:plc:alice" }));
+        storage.save_auth_token(&alice_token).await.unwrap();
+
+        // Bob's entry holds Alice's token
+        let mut misfiled = alice_token.clone();
+        misfiled.account_id = "bob".to_string();
+        storage.save_auth_token(&misfiled).await.unwrap();
+
+        assert!(verify_token_account_match(&storage, "alice").await.unwrap());
+        assert!(!verify_token_account_match(&storage, "bob").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_verify_session_picks_up_handle_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let account = test_account("alice", "did:plc:alice");
+        storage.save_account(&account).await.unwrap();
+        storage.save_auth_token(&test_token("alice")).await.unwrap();
+
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/xrpc/com.atproto.server.getSession")
+            .match_header("authorization", "Bearer access-alice")
+            .with_status(200)
+            .with_body(
+                json!({
+                    "did": "did:plc:alice",
+                    "handle": "alice.example.com",
+                    "email": "alice@example.com"
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let result = verify_session(&storage, &client, &account).await.unwrap();
+
+        assert!(result.valid);
+        assert!(result.active);
+        assert_eq!(result.previous_handle.as_deref(), Some("alice.bsky.social"));
+        assert_eq!(result.account.handle, "alice.example.com");
+        let stored = storage.get_account("alice").await.unwrap();
+        assert_eq!(stored.handle, "alice.example.com");
+        assert_eq!(stored.email.as_deref(), Some("alice@example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_session_reports_rejected_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let account = test_account("alice", "did:plc:alice");
+        storage.save_account(&account).await.unwrap();
+        storage.save_auth_token(&test_token("alice")).await.unwrap();
+
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/xrpc/com.atproto.server.getSession")
+            .with_status(401)
+            .with_body(json!({ "error": "ExpiredToken", "message": "expired" }).to_string())
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let result = verify_session(&storage, &client, &account).await.unwrap();
+
+        assert!(!result.valid);
+        assert_eq!(result.previous_handle, None);
+        assert_eq!(storage.get_account("alice").await.unwrap().handle, "alice.bsky.social");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_all_profiles_partial_success() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        for (id, did) in [("alice", "did:plc:alice"), ("bob", "did:plc:bob")] {
+            storage.save_account(&test_account(id, did)).await.unwrap();
+            storage.save_auth_token(&test_token(id)).await.unwrap();
+        }
+
+        let mut server = Server::new_async().await;
+        let alice = server
+            .mock("GET", "/xrpc/app.bsky.actor.getProfile")
+            .match_query(Matcher::UrlEncoded("actor".into(), "did:plc:alice".into()))
+            .with_status(200)
+            .with_body(
+                json!({
+                    "did": "did:plc:alice",
+                    "handle": "alice.bsky.social",
+                    "displayName": "Alice",
+                    "avatar": "https://cdn.example/alice.jpg"
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let bob = server
+            .mock("GET", "/xrpc/app.bsky.actor.getProfile")
+            .match_query(Matcher::UrlEncoded("actor".into(), "did:plc:bob".into()))
+            .with_status(400)
+            .with_body(json!({ "error": "InvalidRequest", "message": "boom" }).to_string())
+            .create_async()
+            .await;
+        let url = server.url();
+
+        let mut events = Vec::new();
+        let report = refresh_all_profiles(
+            &storage,
+            |_| Ok(ATProtocolClient::with_base_url(&url)),
+            |account| events.push(account.id.clone()),
+        )
+        .await
+        .unwrap();
+        alice.assert_async().await;
+        bob.assert_async().await;
+
+        assert_eq!(events, vec!["alice".to_string()]);
+        assert_eq!(report.updated.len(), 1);
+        assert_eq!(report.updated[0].display_name.as_deref(), Some("Alice"));
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].account_id, "bob");
+
+        let stored = storage.get_account("alice").await.unwrap();
+        assert_eq!(stored.avatar.as_deref(), Some("https://cdn.example/alice.jpg"));
+        assert_eq!(storage.get_account("bob").await.unwrap().display_name, None);
+    }
+}
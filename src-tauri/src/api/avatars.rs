@@ -0,0 +1,354 @@
+/**
+ * Avatar image cache
+ *
+ * Keeps downloaded avatars on disk and revalidates them lazily with conditional GETs
+ * (ETag / Last-Modified), so a changed picture shows up without re-downloading
+ * unchanged ones.
+ */
+
+use crate::types::Account;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Directory (inside the data dir) holding cached avatars
+pub const AVATAR_CACHE_DIR: &str = "avatar-cache";
+
+/// Age after which a cached avatar is revalidated
+pub const DEFAULT_AVATAR_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Download timeout
+const AVATAR_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Validators and fetch time stored next to each cached image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AvatarMeta {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    /// Last download or successful revalidation (ISO 8601)
+    checked_at: String,
+}
+
+/// A cached avatar file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedAvatar {
+    /// Path of the image file
+    pub path: PathBuf,
+    /// Content type reported by the CDN
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// File stem of a URL's cache entry
+fn entry_name(url: &str) -> String {
+    Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn image_file(cache_dir: &Path, url: &str) -> PathBuf {
+    cache_dir.join(format!("{}.img", entry_name(url)))
+}
+
+fn meta_file(cache_dir: &Path, url: &str) -> PathBuf {
+    cache_dir.join(format!("{}.json", entry_name(url)))
+}
+
+/// Bytes taken by the cache entry (image + metadata) of an avatar URL (0 if not cached)
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `url` - Avatar URL
+pub fn cached_avatar_bytes(data_dir: &Path, url: &str) -> u64 {
+    let cache_dir = data_dir.join(AVATAR_CACHE_DIR);
+    [image_file(&cache_dir, url), meta_file(&cache_dir, url)]
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Disk cache of avatar images
+pub struct AvatarCache {
+    dir: PathBuf,
+    ttl: Duration,
+    http: reqwest::Client,
+}
+
+impl AvatarCache {
+    /// Create a cache under `<data_dir>/avatar-cache`
+    ///
+    /// # Arguments
+    /// * `data_dir` - App data directory
+    /// * `ttl` - Age after which a cached avatar is revalidated
+    pub fn new(data_dir: &Path, ttl: Duration) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(AVATAR_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            dir: data_dir.join(AVATAR_CACHE_DIR),
+            ttl,
+            http,
+        }
+    }
+
+    fn image_path(&self, url: &str) -> PathBuf {
+        image_file(&self.dir, url)
+    }
+
+    fn meta_path(&self, url: &str) -> PathBuf {
+        meta_file(&self.dir, url)
+    }
+
+    fn read_meta(&self, url: &str) -> Option<AvatarMeta> {
+        let contents = fs::read_to_string(self.meta_path(url)).ok()?;
+        let meta: AvatarMeta = serde_json::from_str(&contents).ok()?;
+        (meta.url == url && self.image_path(url).exists()).then_some(meta)
+    }
+
+    fn write_meta(&self, meta: &AvatarMeta) -> Result<(), String> {
+        let json = serde_json::to_string(meta)
+            .map_err(|e| format!("Failed to serialize avatar metadata: {}", e))?;
+        fs::write(self.meta_path(&meta.url), json)
+            .map_err(|e| format!("Failed to write avatar metadata: {}", e))
+    }
+
+    fn is_stale(&self, meta: &AvatarMeta) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&meta.checked_at)
+            .map(|checked_at| {
+                let age = chrono::Utc::now().signed_duration_since(checked_at);
+                age.to_std().unwrap_or_default() >= self.ttl
+            })
+            .unwrap_or(true)
+    }
+
+    fn cached(&self, meta: &AvatarMeta) -> CachedAvatar {
+        CachedAvatar {
+            path: self.image_path(&meta.url),
+            content_type: meta.content_type.clone(),
+        }
+    }
+
+    /// Get an avatar, downloading or revalidating it as needed
+    ///
+    /// # Arguments
+    /// * `url` - Avatar URL (as found in profile views)
+    ///
+    /// # Note
+    /// A stale entry is revalidated with If-None-Match / If-Modified-Since; the file
+    /// is replaced only on 200, while 304 just renews the entry. If revalidation
+    /// fails (network error, server error) the stale image is still returned.
+    pub async fn get(&self, url: &str) -> Result<CachedAvatar, String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create avatar cache dir: {}", e))?;
+
+        let cached = self.read_meta(url);
+        if let Some(meta) = &cached {
+            if !self.is_stale(meta) {
+                return Ok(self.cached(meta));
+            }
+        }
+
+        let mut request = self.http.get(url);
+        if let Some(meta) = &cached {
+            if let Some(etag) = &meta.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = match (request.send().await, cached) {
+            (Ok(response), _) if response.status().is_success() => response,
+            (Ok(response), Some(mut meta)) if response.status() == StatusCode::NOT_MODIFIED => {
+                meta.checked_at = chrono::Utc::now().to_rfc3339();
+                self.write_meta(&meta)?;
+                return Ok(self.cached(&meta));
+            }
+            // Keep serving the old picture when revalidation fails
+            (_, Some(meta)) => return Ok(self.cached(&meta)),
+            (Ok(response), None) => {
+                return Err(format!("Failed to download avatar: HTTP {}", response.status()));
+            }
+            (Err(e), None) => return Err(format!("Failed to download avatar: {}", e)),
+        };
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let meta = AvatarMeta {
+            url: url.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            content_type: header(CONTENT_TYPE),
+            checked_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to download avatar: {}", e))?;
+
+        let image_path = self.image_path(url);
+        let temp_path = image_path.with_extension("img.tmp");
+        fs::write(&temp_path, &bytes)
+            .and_then(|_| fs::rename(&temp_path, &image_path))
+            .map_err(|e| format!("Failed to write avatar: {}", e))?;
+        self.write_meta(&meta)?;
+
+        Ok(self.cached(&meta))
+    }
+
+    /// Delete the cached avatar of a removed account, unless another account shows it
+    ///
+    /// # Arguments
+    /// * `url` - Avatar URL of the removed account
+    /// * `remaining` - Accounts still stored
+    ///
+    /// # Returns
+    /// Whether a cache entry was deleted
+    pub fn evict(&self, url: &str, remaining: &[Account]) -> bool {
+        if remaining.iter().any(|account| account.avatar.as_deref() == Some(url)) {
+            return false;
+        }
+
+        let image_removed = fs::remove_file(self.image_path(url)).is_ok();
+        let meta_removed = fs::remove_file(self.meta_path(url)).is_ok();
+        image_removed || meta_removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_not_modified_keeps_cached_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = AvatarCache::new(temp_dir.path(), Duration::ZERO);
+        let mut server = Server::new_async().await;
+        let url = format!("{}/avatar.jpg", server.url());
+
+        let download = server
+            .mock("GET", "/avatar.jpg")
+            .match_header("if-none-match", Matcher::Missing)
+            .with_status(200)
+            .with_header("etag", "\"v1\"")
+            .with_body("old-bytes")
+            .create_async()
+            .await;
+        let first = cache.get(&url).await.unwrap();
+        download.assert_async().await;
+
+        let revalidate = server
+            .mock("GET", "/avatar.jpg")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .create_async()
+            .await;
+        let second = cache.get(&url).await.unwrap();
+        revalidate.assert_async().await;
+
+        assert_eq!(second.path, first.path);
+        assert_eq!(fs::read(&second.path).unwrap(), b"old-bytes");
+    }
+
+    #[tokio::test]
+    async fn test_changed_avatar_replaces_cached_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = AvatarCache::new(temp_dir.path(), Duration::ZERO);
+        let mut server = Server::new_async().await;
+        let url = format!("{}/avatar.jpg", server.url());
+
+        server
+            .mock("GET", "/avatar.jpg")
+            .match_header("if-modified-since", Matcher::Missing)
+            .with_status(200)
+            .with_header("last-modified", "Mon, 01 Jan 2024 00:00:00 GMT")
+            .with_body("old-bytes")
+            .create_async()
+            .await;
+        cache.get(&url).await.unwrap();
+
+        let changed = server
+            .mock("GET", "/avatar.jpg")
+            .match_header("if-modified-since", "Mon, 01 Jan 2024 00:00:00 GMT")
+            .with_status(200)
+            .with_header("last-modified", "Tue, 02 Jan 2024 00:00:00 GMT")
+            .with_body("new-bytes")
+            .create_async()
+            .await;
+        let avatar = cache.get(&url).await.unwrap();
+        changed.assert_async().await;
+
+        assert_eq!(fs::read(&avatar.path).unwrap(), b"new-bytes");
+        let meta = cache.read_meta(&url).unwrap();
+        assert_eq!(meta.last_modified.as_deref(), Some("Tue, 02 Jan 2024 00:00:00 GMT"));
+    }
+
+    #[tokio::test]
+    async fn test_fresh_entry_is_not_revalidated() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = AvatarCache::new(temp_dir.path(), DEFAULT_AVATAR_TTL);
+        let mut server = Server::new_async().await;
+        let url = format!("{}/avatar.jpg", server.url());
+        let mock = server
+            .mock("GET", "/avatar.jpg")
+            .with_status(200)
+            .with_body("bytes")
+            .expect(1)
+            .create_async()
+            .await;
+
+        cache.get(&url).await.unwrap();
+        cache.get(&url).await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_evict_keeps_avatars_in_use() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = AvatarCache::new(temp_dir.path(), DEFAULT_AVATAR_TTL);
+        let mut server = Server::new_async().await;
+        let url = format!("{}/avatar.jpg", server.url());
+        server
+            .mock("GET", "/avatar.jpg")
+            .with_status(200)
+            .with_body("bytes")
+            .create_async()
+            .await;
+        cache.get(&url).await.unwrap();
+
+        let bob = Account {
+            avatar: Some(url.clone()),
+            ..crate::test_support::account("bob")
+        };
+        assert!(!cache.evict(&url, &[bob]));
+        assert!(cached_avatar_bytes(temp_dir.path(), &url) > 0);
+
+        assert!(cache.evict(&url, &[crate::test_support::account("carol")]));
+        assert_eq!(cached_avatar_bytes(temp_dir.path(), &url), 0);
+        assert!(!cache.evict(&url, &[]));
+    }
+}
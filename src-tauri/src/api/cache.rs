@@ -0,0 +1,289 @@
+/**
+ * Short-lived cache for read-only XRPC responses
+ *
+ * Deduplicates identical requests made by several columns of the same account
+ */
+
+use crate::api::metrics::{MethodErrorSummary, XrpcErrorMetrics, DEFAULT_ERROR_TTL};
+use crate::types::AuthError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Default lifetime of a cached response
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Default maximum number of cached responses
+pub const DEFAULT_MAX_ENTRIES: usize = 512;
+
+/// A single cached (or in-flight) response
+struct CacheSlot {
+    /// When the slot was created
+    created_at: Instant,
+    /// Response value, set once the first fetch succeeds
+    value: OnceCell<serde_json::Value>,
+}
+
+/// Per-account response cache keyed by account + XRPC method + parameters
+pub struct RequestCache {
+    /// Lifetime of cached responses
+    ttl: Duration,
+    /// Maximum number of slots (the oldest are evicted first)
+    max_entries: usize,
+    /// Cache slots by key
+    slots: Mutex<HashMap<String, Arc<CacheSlot>>>,
+    /// Failures of fetches made through the cache
+    errors: XrpcErrorMetrics,
+}
+
+impl RequestCache {
+    /// Create a new cache with the given TTL (holding up to `DEFAULT_MAX_ENTRIES`)
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_max_entries(ttl, DEFAULT_MAX_ENTRIES)
+    }
+
+    /// Create a new cache with the given TTL and size cap
+    pub fn with_max_entries(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries: max_entries.max(1),
+            slots: Mutex::new(HashMap::new()),
+            errors: XrpcErrorMetrics::new(DEFAULT_ERROR_TTL),
+        }
+    }
+
+    /// Make room for one more slot
+    ///
+    /// Expired slots are dropped first, then the oldest ones until the cap allows
+    /// another entry. Callers still waiting on an evicted slot keep their handle.
+    fn evict_for_insert(&self, slots: &mut HashMap<String, Arc<CacheSlot>>) {
+        slots.retain(|_, slot| slot.created_at.elapsed() < self.ttl);
+
+        while slots.len() >= self.max_entries {
+            let Some(oldest) = slots
+                .iter()
+                .min_by_key(|(_, slot)| slot.created_at)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            slots.remove(&oldest);
+        }
+    }
+
+    /// Recent fetch failures of an account, per XRPC method
+    pub fn method_errors(&self, account_id: &str) -> Vec<MethodErrorSummary> {
+        self.errors.errors_for(account_id)
+    }
+
+    /// Build the cache key for a request
+    fn key(account_id: &str, method: &str, params: &str) -> String {
+        format!("{}|{}|{}", account_id, method, params)
+    }
+
+    /// Drop every cached response of an account
+    ///
+    /// # Returns
+    /// Number of evicted entries
+    pub fn evict_account(&self, account_id: &str) -> usize {
+        let Ok(mut slots) = self.slots.lock() else {
+            return 0;
+        };
+
+        let prefix = format!("{}|", account_id);
+        let before = slots.len();
+        slots.retain(|key, _| !key.starts_with(&prefix));
+        before - slots.len()
+    }
+
+    /// Return the cached response, or run `fetch` once and cache its result
+    ///
+    /// Concurrent callers for the same key wait for the first fetch instead of issuing
+    /// their own request. Failed fetches are not cached but recorded in the error
+    /// metrics (see `method_errors`).
+    pub async fn get_or_fetch<T, F, Fut>(
+        &self,
+        account_id: &str,
+        method: &str,
+        params: &str,
+        fetch: F,
+    ) -> Result<T, AuthError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, AuthError>>,
+    {
+        let slot = {
+            let mut slots = self.slots.lock().map_err(|e| {
+                AuthError::StorageError(format!("Request cache lock error: {}", e))
+            })?;
+
+            let key = Self::key(account_id, method, params);
+            match slots.get(&key) {
+                Some(slot) if slot.created_at.elapsed() < self.ttl => slot.clone(),
+                _ => {
+                    self.evict_for_insert(&mut slots);
+                    let slot = Arc::new(CacheSlot {
+                        created_at: Instant::now(),
+                        value: OnceCell::new(),
+                    });
+                    slots.insert(key, slot.clone());
+                    slot
+                }
+            }
+        };
+
+        let value = slot
+            .value
+            .get_or_try_init(|| async {
+                let result = fetch().await.inspect_err(|e| {
+                    self.errors.record_error(account_id, method, e);
+                })?;
+                serde_json::to_value(result).map_err(|e| {
+                    AuthError::Unknown(format!("Failed to cache {} response: {}", method, e))
+                })
+            })
+            .await?;
+
+        serde_json::from_value(value.clone()).map_err(|e| {
+            AuthError::Unknown(format!("Failed to read cached {} response: {}", method, e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::ATProtocolClient;
+    use crate::types::ProfileView;
+    use mockito::Server;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_concurrent_get_profile_issues_single_request() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/xrpc/app.bsky.actor.getProfile")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "actor".into(),
+                "did:plc:alice".into(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"did":"did:plc:alice","handle":"alice.bsky.social","displayName":"Alice"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = ATProtocolClient::with_base_url(&server.url());
+        let cache = RequestCache::new(Duration::from_secs(60));
+
+        let fetch = || {
+            cache.get_or_fetch("account-1", "app.bsky.actor.getProfile", "did:plc:alice", || {
+                client.get_profile("token", "did:plc:alice")
+            })
+        };
+        let (first, second): (Result<ProfileView, _>, Result<ProfileView, _>) =
+            tokio::join!(fetch(), fetch());
+
+        assert_eq!(first.unwrap().display_name.as_deref(), Some("Alice"));
+        assert_eq!(second.unwrap().handle, "alice.bsky.social");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_failing_method_is_recorded_per_account() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/xrpc/app.bsky.actor.getProfile")
+            .match_query(mockito::Matcher::Any)
+            .with_status(500)
+            .with_body("upstream down")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/xrpc/app.bsky.notification.getUnreadCount")
+            .with_status(200)
+            .with_body(r#"{"count":3}"#)
+            .create_async()
+            .await;
+
+        let client = ATProtocolClient::with_base_url(&server.url());
+        let cache = RequestCache::new(Duration::ZERO);
+
+        for _ in 0..2 {
+            let result: Result<ProfileView, _> = cache
+                .get_or_fetch("account-1", "app.bsky.actor.getProfile", "did:plc:alice", || {
+                    client.get_profile("token", "did:plc:alice")
+                })
+                .await;
+            assert!(result.is_err());
+        }
+        let unread: u32 = cache
+            .get_or_fetch("account-1", "app.bsky.notification.getUnreadCount", "", || {
+                client.get_unread_count("token")
+            })
+            .await
+            .unwrap();
+        assert_eq!(unread, 3);
+
+        let errors = cache.method_errors("account-1");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].method, "app.bsky.actor.getProfile");
+        assert_eq!(errors[0].error_count, 2);
+        assert!(errors[0].last_error.contains("upstream down"));
+        assert!(cache.method_errors("account-2").is_empty());
+    }
+
+    async fn fetch_counted(cache: &RequestCache, calls: &AtomicU32, params: &str) -> u32 {
+        cache
+            .get_or_fetch("account-1", "app.bsky.actor.getProfile", params, || async {
+                Ok(calls.fetch_add(1, Ordering::SeqCst))
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cache_evicts_expired_and_oldest_entries() {
+        let calls = AtomicU32::new(0);
+
+        let expiring = RequestCache::new(Duration::ZERO);
+        for params in ["a", "b", "c"] {
+            fetch_counted(&expiring, &calls, params).await;
+        }
+        assert_eq!(expiring.evict_account("account-1"), 1);
+
+        let capped = RequestCache::with_max_entries(Duration::from_secs(60), 2);
+        for params in ["a", "b", "c"] {
+            fetch_counted(&capped, &calls, params).await;
+        }
+        let before = calls.load(Ordering::SeqCst);
+        fetch_counted(&capped, &calls, "b").await;
+        fetch_counted(&capped, &calls, "c").await;
+        assert_eq!(calls.load(Ordering::SeqCst), before);
+        fetch_counted(&capped, &calls, "a").await;
+        assert_eq!(calls.load(Ordering::SeqCst), before + 1);
+        assert_eq!(capped.evict_account("account-1"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entry_is_refetched() {
+        let cache = RequestCache::new(Duration::ZERO);
+        let calls = AtomicU32::new(0);
+
+        for expected in 0..2 {
+            let count: u32 = cache
+                .get_or_fetch("account-1", "app.bsky.notification.getUnreadCount", "", || async {
+                    Ok(calls.fetch_add(1, Ordering::SeqCst))
+                })
+                .await
+                .unwrap();
+            assert_eq!(count, expected);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+/**
+ * Chat (DM) helpers
+ *
+ * Direct messages live on the Bluesky chat service; the PDS forwards chat.bsky.*
+ * calls there when the `atproto-proxy` header names the service.
+ */
+
+use crate::auth::jwt::SessionScopes;
+use crate::auth::ATProtocolClient;
+use crate::types::AuthError;
+use serde::Deserialize;
+
+/// Service proxied to for chat.bsky.* methods
+const CHAT_PROXY: &str = "did:web:api.bsky.chat#bsky_chat";
+
+/// Page size requested from listConvos (server maximum)
+const PAGE_LIMIT: &str = "100";
+
+/// Safety cap on the number of pages fetched
+const MAX_PAGES: usize = 100;
+
+/// Conversation entry of chat.bsky.convo.listConvos (only the fields used here)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvoView {
+    #[serde(default)]
+    unread_count: u32,
+}
+
+/// Response of chat.bsky.convo.listConvos
+#[derive(Debug, Deserialize)]
+struct ListConvosResponse {
+    convos: Vec<ConvoView>,
+    cursor: Option<String>,
+}
+
+/// Whether an error means the session may not use the chat service
+fn is_missing_chat_access(error: &AuthError) -> bool {
+    match error {
+        AuthError::Unknown(message) => {
+            message.starts_with("HTTP 403") || message.contains("Bad token scope")
+        }
+        _ => false,
+    }
+}
+
+impl ATProtocolClient {
+    /// Sum the unread message counts of all conversations (chat.bsky.convo.listConvos)
+    ///
+    /// # Arguments
+    /// * `access_jwt` - Access token
+    ///
+    /// # Note
+    /// Sessions without DM access (e.g., regular app passwords) yield 0 instead of an
+    /// error, both when the token scope says so and when the chat service refuses.
+    pub async fn get_dm_unread_count(&self, access_jwt: &str) -> Result<u32, AuthError> {
+        let scopes = SessionScopes::from_access_jwt(access_jwt).ok();
+        if scopes.and_then(|s| s.can_access_dms) == Some(false) {
+            return Ok(0);
+        }
+
+        let mut unread = 0u32;
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_PAGES {
+            let mut params = vec![("limit", PAGE_LIMIT.to_string())];
+            if let Some(cursor) = &cursor {
+                params.push(("cursor", cursor.clone()));
+            }
+
+            let page: ListConvosResponse = match self
+                .xrpc_get_with_headers(
+                    "chat.bsky.convo.listConvos",
+                    access_jwt,
+                    &params,
+                    &[("atproto-proxy", CHAT_PROXY)],
+                )
+                .await
+            {
+                Ok(page) => page,
+                Err(e) if is_missing_chat_access(&e) => return Ok(0),
+                Err(e) => return Err(e),
+            };
+
+            unread = page
+                .convos
+                .iter()
+                .fold(unread, |sum, convo| sum.saturating_add(convo.unread_count));
+
+            // Stop on a missing, empty or repeated cursor
+            match page.cursor {
+                Some(next) if !next.is_empty() && cursor.as_deref() != Some(next.as_str()) => {
+                    cursor = Some(next);
+                }
+                _ => break,
+            }
+        }
+
+        Ok(unread)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt::tests::make_jwt;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_get_dm_unread_count_sums_pages() {
+        let mut server = Server::new_async().await;
+        let token = make_jwt(&json!({ "scope": "com.atproto.access" }));
+        let first = server
+            .mock("GET", "/xrpc/chat.bsky.convo.listConvos")
+            .match_query(Matcher::Exact("limit=100".into()))
+            .match_header("atproto-proxy", CHAT_PROXY)
+            .match_header("authorization", format!("Bearer {}", token).as_str())
+            .with_status(200)
+            .with_body(
+                json!({
+                    "convos": [{ "id": "c1", "unreadCount": 2 }, { "id": "c2", "unreadCount": 0 }],
+                    "cursor": "p2"
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let second = server
+            .mock("GET", "/xrpc/chat.bsky.convo.listConvos")
+            .match_query(Matcher::UrlEncoded("cursor".into(), "p2".into()))
+            .match_header("atproto-proxy", CHAT_PROXY)
+            .with_status(200)
+            .with_body(json!({ "convos": [{ "id": "c3", "unreadCount": 5 }] }).to_string())
+            .create_async()
+            .await;
+
+        let client = ATProtocolClient::with_base_url(&server.url());
+        let unread = client.get_dm_unread_count(&token).await.unwrap();
+
+        assert_eq!(unread, 7);
+        first.assert_async().await;
+        second.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_dm_unread_count_without_chat_access() {
+        let mut server = Server::new_async().await;
+        let refused = server
+            .mock("GET", "/xrpc/chat.bsky.convo.listConvos")
+            .match_query(Matcher::Any)
+            .with_status(400)
+            .with_body(json!({ "error": "InvalidToken", "message": "Bad token scope" }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        // App password without DM privilege: no request is made
+        let app_password = make_jwt(&json!({ "scope": "com.atproto.appPass" }));
+        assert_eq!(client.get_dm_unread_count(&app_password).await.unwrap(), 0);
+
+        // Token without scope claim: the chat service refuses it
+        let no_scope = make_jwt(&json!({ "sub": "did:plc:test" }));
+        assert_eq!(client.get_dm_unread_count(&no_scope).await.unwrap(), 0);
+
+        refused.assert_async().await;
+    }
+}
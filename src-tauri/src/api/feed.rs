@@ -0,0 +1,186 @@
+/**
+ * Feed helpers
+ *
+ * Timeline fetching and deduplication of feed items across refreshes
+ */
+
+use crate::auth::ATProtocolClient;
+use crate::types::AuthError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// A page of feed items (app.bsky.feed.defs#feedViewPost objects)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedPage {
+    /// Feed items, newest first
+    pub feed: Vec<Value>,
+    /// Cursor for the next (older) page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// Get the CID of the post wrapped by a feed item
+fn feed_item_cid(item: &Value) -> Option<&str> {
+    item.get("post")?.get("cid")?.as_str()
+}
+
+/// Merge a freshly fetched feed page into what a column already shows
+///
+/// Drops items whose post CID is already known (or repeated within `new_feed`) and
+/// keeps the order of the remaining items. Items without a CID are kept.
+pub fn merge_feed(existing_cids: Vec<String>, new_feed: Vec<Value>) -> Vec<Value> {
+    let mut seen: HashSet<String> = existing_cids.into_iter().collect();
+
+    new_feed
+        .into_iter()
+        .filter(|item| match feed_item_cid(item) {
+            Some(cid) => seen.insert(cid.to_string()),
+            None => true,
+        })
+        .collect()
+}
+
+impl ATProtocolClient {
+    /// Fetch the home timeline using app.bsky.feed.getTimeline
+    ///
+    /// # Arguments
+    /// * `access_jwt` - Access token
+    /// * `cursor` - Pagination cursor from a previous page (optional)
+    /// * `algorithm` - Home timeline algorithm hint, e.g. "reverse-chronological"
+    ///   (optional; the server default is used when omitted)
+    /// * `limit` - Page size (optional; the server default is used when omitted)
+    pub async fn get_timeline(
+        &self,
+        access_jwt: &str,
+        cursor: Option<&str>,
+        algorithm: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<FeedPage, AuthError> {
+        let mut params = Vec::new();
+        if let Some(algorithm) = algorithm {
+            let algorithm = algorithm.trim();
+            if algorithm.is_empty() {
+                return Err(AuthError::InvalidInput(
+                    "algorithm must not be empty".to_string(),
+                ));
+            }
+            params.push(("algorithm", algorithm.to_string()));
+        }
+        if let Some(cursor) = cursor {
+            params.push(("cursor", cursor.to_string()));
+        }
+        if let Some(limit) = limit {
+            params.push(("limit", limit.to_string()));
+        }
+
+        self.xrpc_get("app.bsky.feed.getTimeline", access_jwt, &params)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+    use serde_json::json;
+
+    fn item(cid: &str) -> Value {
+        json!({ "post": { "uri": format!("at://did:plc:a/app.bsky.feed.post/{}", cid), "cid": cid } })
+    }
+
+    fn cids(feed: &[Value]) -> Vec<&str> {
+        feed.iter().filter_map(feed_item_cid).collect()
+    }
+
+    #[test]
+    fn test_merge_feed_drops_known_items() {
+        let merged = merge_feed(
+            vec!["c3".to_string(), "c4".to_string()],
+            vec![item("c1"), item("c2"), item("c3"), item("c4")],
+        );
+
+        assert_eq!(cids(&merged), vec!["c1", "c2"]);
+    }
+
+    #[test]
+    fn test_merge_feed_drops_duplicates_within_page() {
+        let merged = merge_feed(vec![], vec![item("c1"), item("c2"), item("c1")]);
+
+        assert_eq!(cids(&merged), vec!["c1", "c2"]);
+    }
+
+    #[test]
+    fn test_merge_feed_keeps_items_without_cid() {
+        let merged = merge_feed(vec!["c1".to_string()], vec![json!({}), item("c1")]);
+
+        assert_eq!(merged, vec![json!({})]);
+    }
+
+    #[tokio::test]
+    async fn test_get_timeline_passes_cursor() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/xrpc/app.bsky.feed.getTimeline")
+            .match_query(mockito::Matcher::UrlEncoded("cursor".into(), "abc".into()))
+            .match_header("authorization", "Bearer token")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "feed": [item("c1")], "cursor": "def" }).to_string())
+            .create_async()
+            .await;
+
+        let client = ATProtocolClient::with_base_url(&server.url());
+        let page = client.get_timeline("token", Some("abc"), None, None).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(cids(&page.feed), vec!["c1"]);
+        assert_eq!(page.cursor.as_deref(), Some("def"));
+    }
+
+    #[tokio::test]
+    async fn test_get_timeline_forwards_algorithm_only_when_set() {
+        let mut server = Server::new_async().await;
+        let with_algorithm = server
+            .mock("GET", "/xrpc/app.bsky.feed.getTimeline")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "algorithm".into(),
+                "reverse-chronological".into(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "feed": [] }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let without_algorithm = server
+            .mock("GET", "/xrpc/app.bsky.feed.getTimeline")
+            .match_query(mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "feed": [] }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = ATProtocolClient::with_base_url(&server.url());
+        client
+            .get_timeline("token", None, Some("reverse-chronological"), None)
+            .await
+            .unwrap();
+        client.get_timeline("token", None, None, None).await.unwrap();
+
+        with_algorithm.assert_async().await;
+        without_algorithm.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_timeline_rejects_empty_algorithm() {
+        let client = ATProtocolClient::with_base_url("http://127.0.0.1:9");
+
+        let result = client.get_timeline("token", None, Some("  "), None).await;
+
+        assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+    }
+}
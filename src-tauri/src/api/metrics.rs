@@ -0,0 +1,138 @@
+/**
+ * XRPC error metrics
+ *
+ * Tracks recent failures per account and method so support can see which calls
+ * fail for one account while others keep working
+ */
+
+use crate::types::AuthError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long an error entry is kept after its most recent failure
+pub const DEFAULT_ERROR_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum number of (account, method) entries kept
+const MAX_ERROR_ENTRIES: usize = 256;
+
+/// Errors recorded for one (account, method) pair
+struct ErrorEntry {
+    count: u64,
+    last_message: String,
+    last_at: String,
+    last_seen: Instant,
+}
+
+/// Recent failures of one XRPC method for an account
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodErrorSummary {
+    /// XRPC method NSID
+    pub method: String,
+    /// Failures since the entry was created
+    pub error_count: u64,
+    /// Message of the most recent failure
+    pub last_error: String,
+    /// Time of the most recent failure (ISO 8601)
+    pub last_error_at: String,
+}
+
+/// Bounded, expiring error counts keyed by (account, method)
+pub struct XrpcErrorMetrics {
+    /// Entry lifetime after the last failure
+    ttl: Duration,
+    /// Entries by (account ID, method)
+    entries: Mutex<HashMap<(String, String), ErrorEntry>>,
+}
+
+impl XrpcErrorMetrics {
+    /// Create an empty collector whose entries expire `ttl` after their last failure
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a failed call
+    pub fn record_error(&self, account_id: &str, method: &str, error: &AuthError) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+
+        entries.retain(|_, entry| entry.last_seen.elapsed() < self.ttl);
+
+        let key = (account_id.to_string(), method.to_string());
+        if !entries.contains_key(&key) && entries.len() >= MAX_ERROR_ENTRIES {
+            // Make room by dropping the entry idle the longest
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_seen)
+                .map(|(key, _)| key.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+
+        let entry = entries.entry(key).or_insert_with(|| ErrorEntry {
+            count: 0,
+            last_message: String::new(),
+            last_at: String::new(),
+            last_seen: Instant::now(),
+        });
+        entry.count += 1;
+        entry.last_message = error.to_string();
+        entry.last_at = chrono::Utc::now().to_rfc3339();
+        entry.last_seen = Instant::now();
+    }
+
+    /// Recent failures of an account, most recent first
+    pub fn errors_for(&self, account_id: &str) -> Vec<MethodErrorSummary> {
+        let Ok(entries) = self.entries.lock() else {
+            return Vec::new();
+        };
+
+        let mut recent: Vec<(&(String, String), &ErrorEntry)> = entries
+            .iter()
+            .filter(|((account, _), entry)| {
+                account == account_id && entry.last_seen.elapsed() < self.ttl
+            })
+            .collect();
+        recent.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.last_seen));
+
+        recent
+            .into_iter()
+            .map(|((_, method), entry)| MethodErrorSummary {
+                method: method.clone(),
+                error_count: entry.count,
+                last_error: entry.last_message.clone(),
+                last_error_at: entry.last_at.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_bounded_and_expire() {
+        let metrics = XrpcErrorMetrics::new(DEFAULT_ERROR_TTL);
+        let error = AuthError::NetworkError("offline".to_string());
+
+        for index in 0..MAX_ERROR_ENTRIES + 10 {
+            metrics.record_error("account-1", &format!("method.{}", index), &error);
+        }
+
+        let errors = metrics.errors_for("account-1");
+        assert_eq!(errors.len(), MAX_ERROR_ENTRIES);
+        assert!(errors.iter().all(|e| e.method != "method.0"));
+
+        let expiring = XrpcErrorMetrics::new(Duration::ZERO);
+        expiring.record_error("account-1", "app.bsky.actor.getProfile", &error);
+        assert!(expiring.errors_for("account-1").is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+/**
+ * AT Protocol data API (app.bsky.*)
+ *
+ * Read-only XRPC helpers used by the deck columns and account switcher
+ */
+
+pub mod avatars;
+pub mod cache;
+pub mod chat;
+pub mod feed;
+pub mod metrics;
+pub mod moderation;
+
+use crate::auth::ATProtocolClient;
+use crate::types::{AuthError, ProfileView};
+use serde::Deserialize;
+
+/// Response of app.bsky.actor.getPreferences
+#[derive(Debug, Deserialize)]
+struct PreferencesResponse {
+    preferences: Vec<serde_json::Value>,
+}
+
+/// Response of app.bsky.notification.getUnreadCount
+#[derive(Debug, Deserialize)]
+struct UnreadCountResponse {
+    count: u32,
+}
+
+impl ATProtocolClient {
+    /// Fetch an actor profile using app.bsky.actor.getProfile
+    ///
+    /// # Arguments
+    /// * `access_jwt` - Access token
+    /// * `actor` - Handle or DID of the actor
+    pub async fn get_profile(
+        &self,
+        access_jwt: &str,
+        actor: &str,
+    ) -> Result<ProfileView, AuthError> {
+        self.xrpc_get(
+            "app.bsky.actor.getProfile",
+            access_jwt,
+            &[("actor", actor.to_string())],
+        )
+        .await
+    }
+
+    /// Fetch the account preferences using app.bsky.actor.getPreferences
+    ///
+    /// # Returns
+    /// Raw preference objects (each tagged with its `$type`)
+    pub async fn get_preferences(
+        &self,
+        access_jwt: &str,
+    ) -> Result<Vec<serde_json::Value>, AuthError> {
+        let response: PreferencesResponse = self
+            .xrpc_get("app.bsky.actor.getPreferences", access_jwt, &[])
+            .await?;
+
+        Ok(response.preferences)
+    }
+
+    /// Fetch the unread notification count using app.bsky.notification.getUnreadCount
+    pub async fn get_unread_count(&self, access_jwt: &str) -> Result<u32, AuthError> {
+        let response: UnreadCountResponse = self
+            .xrpc_get("app.bsky.notification.getUnreadCount", access_jwt, &[])
+            .await?;
+
+        Ok(response.count)
+    }
+}
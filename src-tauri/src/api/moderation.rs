@@ -0,0 +1,137 @@
+/**
+ * Moderation lists
+ *
+ * Fetches the accounts the user blocks and mutes so columns can hide their content
+ */
+
+use crate::auth::ATProtocolClient;
+use crate::types::AuthError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Page size requested from getBlocks/getMutes (server maximum)
+const PAGE_LIMIT: &str = "100";
+
+/// Safety cap on the number of pages fetched per list
+const MAX_PAGES: usize = 100;
+
+/// DIDs of the accounts the user blocks and mutes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModerationLists {
+    /// Blocked account DIDs
+    pub blocks: HashSet<String>,
+    /// Muted account DIDs
+    pub mutes: HashSet<String>,
+}
+
+impl ATProtocolClient {
+    /// Collect the DIDs of every actor in a paginated actor list
+    ///
+    /// # Arguments
+    /// * `method` - XRPC method (e.g., "app.bsky.graph.getBlocks")
+    /// * `list_key` - Response field holding the actors (e.g., "blocks")
+    async fn collect_actor_dids(
+        &self,
+        access_jwt: &str,
+        method: &str,
+        list_key: &str,
+    ) -> Result<HashSet<String>, AuthError> {
+        let mut dids = HashSet::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_PAGES {
+            let mut params = vec![("limit", PAGE_LIMIT.to_string())];
+            if let Some(cursor) = &cursor {
+                params.push(("cursor", cursor.clone()));
+            }
+
+            let page: Value = self.xrpc_get(method, access_jwt, &params).await?;
+
+            if let Some(actors) = page.get(list_key).and_then(Value::as_array) {
+                dids.extend(
+                    actors
+                        .iter()
+                        .filter_map(|actor| actor.get("did")?.as_str())
+                        .map(str::to_string),
+                );
+            }
+
+            // Stop on a missing, empty or repeated cursor
+            match page.get("cursor").and_then(Value::as_str) {
+                Some(next) if !next.is_empty() && cursor.as_deref() != Some(next) => {
+                    cursor = Some(next.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(dids)
+    }
+
+    /// Fetch every blocked and muted account using app.bsky.graph.getBlocks/getMutes
+    ///
+    /// # Arguments
+    /// * `access_jwt` - Access token
+    pub async fn get_moderation_lists(
+        &self,
+        access_jwt: &str,
+    ) -> Result<ModerationLists, AuthError> {
+        let blocks = self
+            .collect_actor_dids(access_jwt, "app.bsky.graph.getBlocks", "blocks")
+            .await?;
+        let mutes = self
+            .collect_actor_dids(access_jwt, "app.bsky.graph.getMutes", "mutes")
+            .await?;
+
+        Ok(ModerationLists { blocks, mutes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_get_moderation_lists_paginates() {
+        let mut server = Server::new_async().await;
+        let blocks_first = server
+            .mock("GET", "/xrpc/app.bsky.graph.getBlocks")
+            .match_query(Matcher::Exact("limit=100".into()))
+            .with_status(200)
+            .with_body(json!({ "blocks": [{ "did": "did:plc:b1" }], "cursor": "p2" }).to_string())
+            .create_async()
+            .await;
+        let blocks_second = server
+            .mock("GET", "/xrpc/app.bsky.graph.getBlocks")
+            .match_query(Matcher::UrlEncoded("cursor".into(), "p2".into()))
+            .with_status(200)
+            .with_body(json!({ "blocks": [{ "did": "did:plc:b2" }] }).to_string())
+            .create_async()
+            .await;
+        let mutes = server
+            .mock("GET", "/xrpc/app.bsky.graph.getMutes")
+            .match_query(Matcher::Any)
+            .match_header("authorization", "Bearer token")
+            .with_status(200)
+            .with_body(json!({ "mutes": [{ "did": "did:plc:m1" }], "cursor": "" }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = ATProtocolClient::with_base_url(&server.url());
+        let lists = client.get_moderation_lists("token").await.unwrap();
+
+        assert_eq!(
+            lists.blocks,
+            HashSet::from(["did:plc:b1".to_string(), "did:plc:b2".to_string()])
+        );
+        assert_eq!(lists.mutes, HashSet::from(["did:plc:m1".to_string()]));
+        blocks_first.assert_async().await;
+        blocks_second.assert_async().await;
+        mutes.assert_async().await;
+    }
+}
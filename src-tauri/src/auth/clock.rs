@@ -0,0 +1,121 @@
+/**
+ * Device clock skew detection
+ *
+ * Token expiry is checked against the local clock, so a clock that is off by hours
+ * makes every refresh look broken. The server's `Date` header tells how far off it is.
+ */
+
+use crate::auth::{map_request_error, ATProtocolClient};
+use crate::types::AuthError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Offset above which the UI should warn about the device clock
+pub const CLOCK_SKEW_THRESHOLD_SECS: i64 = 5 * 60;
+
+/// Local-vs-server clock comparison
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockSkewReport {
+    /// Local time minus server time in seconds (positive = local clock ahead)
+    pub offset_secs: i64,
+    /// Threshold that was applied in seconds
+    pub threshold_secs: i64,
+    /// Whether the offset exceeds the threshold
+    pub exceeds_threshold: bool,
+    /// Server time from the `Date` header (ISO 8601)
+    pub server_time: String,
+}
+
+impl ATProtocolClient {
+    /// Read the server time from the `Date` header of a health check (GET /xrpc/_health)
+    ///
+    /// # Returns
+    /// (server time, local time at the middle of the request)
+    pub async fn server_time(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), AuthError> {
+        let url = format!("{}/xrpc/_health", self.server_url);
+
+        let sent_at = Utc::now();
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(map_request_error)?;
+        let received_at = Utc::now();
+
+        let date = response
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| AuthError::ServerError("Response has no Date header".to_string()))?;
+        let server_time = DateTime::parse_from_rfc2822(date)
+            .map_err(|e| AuthError::ServerError(format!("Invalid Date header: {}", e)))?
+            .with_timezone(&Utc);
+
+        Ok((server_time, sent_at + (received_at - sent_at) / 2))
+    }
+}
+
+/// Measure the local clock offset against a server
+///
+/// # Arguments
+/// * `client` - Client for the server to compare with
+/// * `threshold_secs` - Offset (either direction) that counts as too far off
+///
+/// # Note
+/// The `Date` header has one-second resolution, so offsets below a second are noise.
+pub async fn check_clock_skew(
+    client: &ATProtocolClient,
+    threshold_secs: i64,
+) -> Result<ClockSkewReport, AuthError> {
+    let (server_time, local_time) = client.server_time().await?;
+    let offset_secs = (local_time - server_time).num_seconds();
+
+    Ok(ClockSkewReport {
+        offset_secs,
+        threshold_secs,
+        exceeds_threshold: offset_secs.abs() > threshold_secs,
+        server_time: server_time.to_rfc3339(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+
+    async fn server_with_date(date: DateTime<Utc>) -> mockito::ServerGuard {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/xrpc/_health")
+            .with_status(200)
+            .with_header("date", &date.to_rfc2822().replace("+0000", "GMT"))
+            .with_body(r#"{"version":"0.4.0"}"#)
+            .create_async()
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_large_skew_fires_warning() {
+        let server = server_with_date(Utc::now() - chrono::Duration::hours(3)).await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let report = check_clock_skew(&client, CLOCK_SKEW_THRESHOLD_SECS).await.unwrap();
+
+        assert!(report.exceeds_threshold);
+        assert!((report.offset_secs - 3 * 3600).abs() <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_small_skew_does_not_warn() {
+        let server = server_with_date(Utc::now() + chrono::Duration::seconds(30)).await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let report = check_clock_skew(&client, CLOCK_SKEW_THRESHOLD_SECS).await.unwrap();
+
+        assert!(!report.exceeds_threshold);
+        assert!((report.offset_secs + 30).abs() <= 2);
+    }
+}
@@ -0,0 +1,132 @@
+/**
+ * JWT inspection helpers
+ *
+ * Decodes token claims locally for display purposes only. Signatures are NOT
+ * verified; the PDS remains the authority on what a token may do.
+ */
+
+use crate::types::AuthError;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Scope granted to full (password) sessions
+pub const SCOPE_ACCESS: &str = "com.atproto.access";
+/// Scope granted to regular app password sessions
+pub const SCOPE_APP_PASS: &str = "com.atproto.appPass";
+/// Scope granted to privileged app password sessions (DM access)
+pub const SCOPE_APP_PASS_PRIVILEGED: &str = "com.atproto.appPassPrivileged";
+
+/// Decode the payload of a JWT without verifying its signature
+///
+/// # Arguments
+/// * `token` - Compact JWT (`header.payload.signature`)
+///
+/// # Returns
+/// The claims object
+pub fn decode_claims(token: &str) -> Result<Value, AuthError> {
+    let mut parts = token.split('.');
+    let payload = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(_), Some(payload), Some(_), None) => payload,
+        _ => return Err(AuthError::InvalidCredentials("Malformed JWT".to_string())),
+    };
+
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| AuthError::InvalidCredentials(format!("Malformed JWT payload: {}", e)))?;
+
+    serde_json::from_slice(&bytes)
+        .map_err(|e| AuthError::InvalidCredentials(format!("Malformed JWT claims: {}", e)))
+}
+
+/// Capabilities of a session as advertised by its access token
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionScopes {
+    /// Raw scopes from the `scope` claim (empty if the claim is missing)
+    pub scopes: Vec<String>,
+    /// Whether the session was created with an app password
+    /// (None when the token carries no scope claim)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_app_password: Option<bool>,
+    /// Whether the session may access direct messages
+    /// (None when the token carries no scope claim)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_access_dms: Option<bool>,
+}
+
+impl SessionScopes {
+    /// Parse the scopes out of an access token
+    ///
+    /// # Note
+    /// The `scope` claim is a space-separated string; tokens without one yield
+    /// an empty scope list and unknown capabilities.
+    pub fn from_access_jwt(access_jwt: &str) -> Result<Self, AuthError> {
+        let claims = decode_claims(access_jwt)?;
+
+        let scopes: Vec<String> = match claims.get("scope").and_then(Value::as_str) {
+            Some(scope) => scope.split_whitespace().map(str::to_string).collect(),
+            None => {
+                return Ok(Self {
+                    scopes: Vec::new(),
+                    is_app_password: None,
+                    can_access_dms: None,
+                })
+            }
+        };
+
+        let has = |s: &str| scopes.iter().any(|scope| scope == s);
+        let is_app_password = has(SCOPE_APP_PASS) || has(SCOPE_APP_PASS_PRIVILEGED);
+        let can_access_dms = has(SCOPE_ACCESS) || has(SCOPE_APP_PASS_PRIVILEGED);
+
+        Ok(Self {
+            is_app_password: Some(is_app_password),
+            can_access_dms: Some(can_access_dms),
+            scopes,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Build an unsigned JWT carrying the given claims
+    pub(crate) fn make_jwt(claims: &Value) -> String {
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"ES256K","typ":"at+jwt"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        format!("{}.{}.signature", header, payload)
+    }
+
+    #[test]
+    fn test_scopes_from_app_password_token() {
+        let token = make_jwt(&json!({
+            "scope": "com.atproto.appPass",
+            "sub": "did:plc:alice",
+        }));
+
+        let scopes = SessionScopes::from_access_jwt(&token).unwrap();
+
+        assert_eq!(scopes.scopes, vec!["com.atproto.appPass".to_string()]);
+        assert_eq!(scopes.is_app_password, Some(true));
+        assert_eq!(scopes.can_access_dms, Some(false));
+    }
+
+    #[test]
+    fn test_scopes_from_token_without_scope_claim() {
+        let token = make_jwt(&json!({ "sub": "did:plc:alice" }));
+
+        let scopes = SessionScopes::from_access_jwt(&token).unwrap();
+
+        assert!(scopes.scopes.is_empty());
+        assert_eq!(scopes.is_app_password, None);
+        assert_eq!(scopes.can_access_dms, None);
+    }
+
+    #[test]
+    fn test_decode_claims_rejects_malformed_token() {
+        assert!(decode_claims("not-a-jwt").is_err());
+        assert!(decode_claims("a.!!!.c").is_err());
+    }
+}
@@ -0,0 +1,1237 @@
+/**
+ * OAuth login (authorization code + PKCE + DPoP)
+ *
+ * Implements the AT Protocol OAuth profile for a native app: pushed authorization
+ * request, browser hand-off with a loopback redirect, and a DPoP-bound token exchange.
+ * The app identifies itself as a loopback ("http://localhost") client, so no client
+ * metadata document has to be hosted.
+ */
+
+use super::{
+    error_from_response, handle_from_document, map_request_error, pds_endpoint_from_document,
+    ATProtocolClient,
+};
+use crate::types::{AuthError, AuthToken};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use reqwest::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Request, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use uuid::Uuid;
+
+/// Authorization server used when the account's own can't be discovered
+pub const BSKY_AUTHORIZATION_SERVER: &str = "https://bsky.social";
+
+/// Scopes requested (the same access an app password grants)
+pub const OAUTH_SCOPE: &str = "atproto transition:generic";
+
+/// How long `wait_for_callback` waits for the browser redirect by default
+pub const OAUTH_CALLBACK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Session lifetime granted to public (non-confidential) clients
+const PUBLIC_CLIENT_SESSION_DAYS: i64 = 14;
+
+/// Access token lifetime assumed when the token response has no `expires_in`
+const DEFAULT_ACCESS_TOKEN_SECS: i64 = 15 * 60;
+
+/// Path of the loopback redirect URI
+const CALLBACK_PATH: &str = "/callback";
+
+/// Response header carrying the server-issued DPoP nonce
+const DPOP_NONCE_HEADER: &str = "DPoP-Nonce";
+
+/// Request header carrying the DPoP proof
+const DPOP_HEADER: &str = "DPoP";
+
+/// Relevant fields of /.well-known/oauth-authorization-server
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthorizationServerMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub pushed_authorization_request_endpoint: String,
+}
+
+/// Relevant fields of a PDS's /.well-known/oauth-protected-resource
+#[derive(Debug, Deserialize)]
+struct ProtectedResourceMetadata {
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+}
+
+/// Response of the pushed authorization request endpoint
+#[derive(Debug, Deserialize)]
+struct PushedAuthorizationResponse {
+    request_uri: String,
+}
+
+/// OAuth error response body (RFC 6749 section 5.2)
+#[derive(Debug, Deserialize)]
+struct OAuthErrorBody {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// Token endpoint response
+#[derive(Debug, Clone, Deserialize)]
+pub struct OAuthTokenResponse {
+    pub access_token: String,
+    /// Must be "DPoP"
+    pub token_type: String,
+    pub refresh_token: String,
+    /// Access token lifetime in seconds
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+    /// DID of the account that authorized the app
+    pub sub: String,
+}
+
+impl OAuthTokenResponse {
+    /// Check that the tokens are DPoP-bound and issued for a DID
+    fn check(&self) -> Result<(), AuthError> {
+        if !self.token_type.eq_ignore_ascii_case("DPoP") {
+            return Err(AuthError::ServerError(format!(
+                "Expected a DPoP-bound token, got token type {}",
+                self.token_type
+            )));
+        }
+        if !self.sub.starts_with("did:") {
+            return Err(AuthError::ServerError(format!(
+                "Token subject is not a DID: {}",
+                self.sub
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Convert into the stored token, keeping what is needed to use and refresh it
+    ///
+    /// # Arguments
+    /// * `account_id` - Account the token belongs to
+    /// * `dpop_key` - Key the tokens are bound to
+    /// * `issuer` - Authorization server that issued them
+    /// * `client_id` - Client ID they were issued to
+    pub fn into_auth_token(
+        self,
+        account_id: &str,
+        dpop_key: &DpopKey,
+        issuer: &str,
+        client_id: &str,
+    ) -> AuthToken {
+        let now = Utc::now();
+        let access_secs = self.expires_in.unwrap_or(DEFAULT_ACCESS_TOKEN_SECS);
+        AuthToken {
+            account_id: account_id.to_string(),
+            access_jwt: self.access_token,
+            refresh_jwt: self.refresh_token,
+            issued_at: now.to_rfc3339(),
+            access_expires_at: (now + chrono::Duration::seconds(access_secs)).to_rfc3339(),
+            refresh_expires_at: (now + chrono::Duration::days(PUBLIC_CLIENT_SESSION_DAYS))
+                .to_rfc3339(),
+            session_string: None,
+            dpop_key: Some(dpop_key.to_stored()),
+            oauth_issuer: Some(issuer.to_string()),
+            oauth_client_id: Some(client_id.to_string()),
+        }
+    }
+}
+
+/// Random URL-safe string with `bytes` bytes of entropy
+fn random_token(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buffer);
+    URL_SAFE_NO_PAD.encode(buffer)
+}
+
+/// PKCE verifier and its S256 challenge (RFC 7636)
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    /// Generate a fresh verifier
+    pub fn generate() -> Self {
+        Self::from_verifier(random_token(32))
+    }
+
+    fn from_verifier(verifier: String) -> Self {
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+/// ES256 key the tokens of one session are bound to (RFC 9449)
+pub struct DpopKey(SigningKey);
+
+impl DpopKey {
+    /// Generate a fresh P-256 key
+    pub fn generate() -> Self {
+        Self(SigningKey::random(&mut OsRng))
+    }
+
+    /// Restore a key saved with `to_stored`
+    pub fn from_stored(stored: &str) -> Result<Self, AuthError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(stored)
+            .map_err(|e| AuthError::StorageError(format!("Invalid DPoP key: {}", e)))?;
+        SigningKey::from_slice(&bytes)
+            .map(Self)
+            .map_err(|e| AuthError::StorageError(format!("Invalid DPoP key: {}", e)))
+    }
+
+    /// Private scalar, base64url encoded (stored inside the encrypted token store)
+    pub fn to_stored(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.0.to_bytes())
+    }
+
+    /// Public key as a JWK
+    pub fn public_jwk(&self) -> Value {
+        let point = self.0.verifying_key().to_encoded_point(false);
+        json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": point.x().map(|x| URL_SAFE_NO_PAD.encode(x)),
+            "y": point.y().map(|y| URL_SAFE_NO_PAD.encode(y)),
+        })
+    }
+
+    /// Build a DPoP proof JWT for one request
+    ///
+    /// # Arguments
+    /// * `method` - HTTP method
+    /// * `url` - Request URL (query and fragment are dropped for `htu`)
+    /// * `nonce` - Latest nonce issued by the server, if any
+    /// * `access_token` - Access token sent with the request (adds the `ath` claim)
+    pub fn proof(
+        &self,
+        method: &str,
+        url: &str,
+        nonce: Option<&str>,
+        access_token: Option<&str>,
+    ) -> String {
+        let header = json!({ "typ": "dpop+jwt", "alg": "ES256", "jwk": self.public_jwk() });
+        let mut claims = json!({
+            "jti": Uuid::new_v4().to_string(),
+            "htm": method,
+            "htu": htu(url),
+            "iat": Utc::now().timestamp(),
+        });
+        if let Some(nonce) = nonce {
+            claims["nonce"] = json!(nonce);
+        }
+        if let Some(access_token) = access_token {
+            claims["ath"] = json!(URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes())));
+        }
+
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature: Signature = self.0.sign(signing_input.as_bytes());
+        format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+    }
+}
+
+/// DPoP key a client signs its resource requests with, and the PDS's latest nonce
+pub struct DpopBinding {
+    key: DpopKey,
+    nonce: Mutex<Option<String>>,
+}
+
+impl DpopBinding {
+    fn nonce(&self) -> Option<String> {
+        self.nonce.lock().ok().and_then(|nonce| nonce.clone())
+    }
+
+    /// Remember the nonce a response carries, if any
+    fn update_nonce(&self, response: &Response) {
+        let fresh = response
+            .headers()
+            .get(DPOP_NONCE_HEADER)
+            .and_then(|value| value.to_str().ok());
+        if let (Some(fresh), Ok(mut nonce)) = (fresh, self.nonce.lock()) {
+            *nonce = Some(fresh.to_string());
+        }
+    }
+}
+
+/// Whether a resource server rejected a request for lacking its current DPoP nonce
+fn demands_dpop_nonce(response: &Response) -> bool {
+    response.status() == StatusCode::UNAUTHORIZED
+        && response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("use_dpop_nonce"))
+}
+
+/// Header value from a token or proof (invalid characters are a credentials error)
+fn header_value(value: String) -> Result<HeaderValue, AuthError> {
+    HeaderValue::from_str(&value).map_err(|_| {
+        AuthError::InvalidCredentials("Token contains invalid header characters".to_string())
+    })
+}
+
+/// `htu` claim of a request URL: the URL without query and fragment
+fn htu(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut url) => {
+            url.set_query(None);
+            url.set_fragment(None);
+            url.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Client ID of a loopback client (`http://localhost` with the redirect URI and scope)
+fn loopback_client_id(redirect_uri: &str) -> String {
+    let params = [("redirect_uri", redirect_uri), ("scope", OAUTH_SCOPE)];
+    let query = Url::parse_with_params("http://localhost", params)
+        .ok()
+        .and_then(|url| url.query().map(str::to_string))
+        .unwrap_or_default();
+    format!("http://localhost?{}", query)
+}
+
+/// Map an OAuth error response to an AuthError
+fn oauth_error(status: StatusCode, error: OAuthErrorBody) -> AuthError {
+    let message = match error.error_description {
+        Some(description) => format!("{}: {}", error.error, description),
+        None => error.error.clone(),
+    };
+
+    match error.error.as_str() {
+        "invalid_grant" | "access_denied" | "invalid_request" => {
+            AuthError::InvalidCredentials(message)
+        }
+        _ if status.is_server_error() => AuthError::ServerError(message),
+        _ => AuthError::Unknown(message),
+    }
+}
+
+/// An authorization started by `start_oauth`, waiting for the browser redirect
+pub struct PendingAuthorization {
+    /// Opaque value tying the redirect to this authorization
+    pub state: String,
+    /// URL to open in the browser
+    pub authorization_url: String,
+    /// Authorization server the login runs against
+    pub issuer: String,
+    metadata: AuthorizationServerMetadata,
+    client_id: String,
+    redirect_uri: String,
+    pkce: Pkce,
+    dpop_key: DpopKey,
+    dpop_nonce: Option<String>,
+    listener: TcpListener,
+    /// When `start_oauth` created it (see `OAuthFlows`)
+    started_at: Instant,
+}
+
+impl PendingAuthorization {
+    /// Wait for the browser to hit the loopback redirect URI
+    ///
+    /// # Arguments
+    /// * `timeout` - How long to wait (see `OAUTH_CALLBACK_TIMEOUT`)
+    ///
+    /// # Returns
+    /// The authorization code
+    ///
+    /// # Note
+    /// Requests to other paths (e.g. /favicon.ico) are answered with 404 and ignored.
+    pub async fn wait_for_callback(&self, timeout: Duration) -> Result<String, AuthError> {
+        tokio::time::timeout(timeout, self.accept_callback())
+            .await
+            .map_err(|_| {
+                AuthError::NetworkError("Timed out waiting for the OAuth redirect".to_string())
+            })?
+    }
+
+    async fn accept_callback(&self) -> Result<String, AuthError> {
+        loop {
+            let (mut stream, _) = self.listener.accept().await.map_err(|e| {
+                AuthError::NetworkError(format!("OAuth redirect listener failed: {}", e))
+            })?;
+
+            let mut buffer = vec![0u8; 8192];
+            let read = stream.read(&mut buffer).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buffer[..read]);
+            let target = request
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+                .unwrap_or_default();
+            let url = Url::parse(&format!("http://127.0.0.1{}", target)).ok();
+
+            let Some(url) = url.filter(|url| url.path() == CALLBACK_PATH) else {
+                let _ = stream
+                    .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                    .await;
+                continue;
+            };
+
+            let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+            let result = self.check_callback(&params);
+            let message = match result {
+                Ok(_) => "Signed in. You can close this window and return to Taurisky.",
+                Err(_) => "Sign-in failed. You can close this window and return to Taurisky.",
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                message.len(),
+                message
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+
+            return result;
+        }
+    }
+
+    fn check_callback(&self, params: &HashMap<String, String>) -> Result<String, AuthError> {
+        if params.get("state") != Some(&self.state) {
+            return Err(AuthError::InvalidCredentials(
+                "OAuth redirect does not match the pending login (state mismatch)".to_string(),
+            ));
+        }
+
+        if let Some(error) = params.get("error") {
+            return Err(AuthError::InvalidCredentials(
+                params.get("error_description").unwrap_or(error).clone(),
+            ));
+        }
+
+        if let Some(issuer) = params.get("iss") {
+            if issuer != &self.issuer {
+                return Err(AuthError::IdentityMismatch(format!(
+                    "OAuth redirect came from {} instead of {}",
+                    issuer, self.issuer
+                )));
+            }
+        }
+
+        params.get("code").cloned().ok_or_else(|| {
+            AuthError::InvalidCredentials(
+                "OAuth redirect is missing the authorization code".to_string(),
+            )
+        })
+    }
+}
+
+/// What the frontend needs to continue a started OAuth login
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthStart {
+    /// URL to open in the system browser
+    pub authorization_url: String,
+    /// Pass to `oauth_complete`
+    pub state: String,
+}
+
+/// A completed OAuth login
+pub struct OAuthSession {
+    pub tokens: OAuthTokenResponse,
+    /// Key the tokens are bound to
+    pub dpop_key: DpopKey,
+    /// Authorization server that issued the tokens
+    pub issuer: String,
+    /// Client ID the tokens were issued to
+    pub client_id: String,
+    /// PDS hosting the account (from its DID document)
+    pub pds_url: String,
+    /// Handle claimed by the DID document (the DID if it claims none)
+    pub handle: String,
+}
+
+/// Authorizations waiting for `oauth_complete` (managed by Tauri)
+///
+/// Authorizations older than `OAUTH_CALLBACK_TIMEOUT` are dropped (closing their
+/// redirect listener) whenever one is inserted or taken.
+#[derive(Default)]
+pub struct OAuthFlows {
+    pending: Mutex<HashMap<String, PendingAuthorization>>,
+}
+
+impl OAuthFlows {
+    /// Register a started authorization under its state
+    pub fn insert(&self, pending: PendingAuthorization) {
+        if let Ok(mut flows) = self.pending.lock() {
+            Self::expire(&mut flows);
+            flows.insert(pending.state.clone(), pending);
+        }
+    }
+
+    /// Remove and return the authorization with the given state (None once expired)
+    pub fn take(&self, state: &str) -> Option<PendingAuthorization> {
+        let mut flows = self.pending.lock().ok()?;
+        Self::expire(&mut flows);
+        flows.remove(state)
+    }
+
+    fn expire(flows: &mut HashMap<String, PendingAuthorization>) {
+        flows.retain(|_, pending| pending.started_at.elapsed() < OAUTH_CALLBACK_TIMEOUT);
+    }
+}
+
+impl ATProtocolClient {
+    /// Fetch the metadata of an authorization server
+    ///
+    /// # Arguments
+    /// * `issuer` - Authorization server URL (e.g., "https://bsky.social")
+    pub async fn get_authorization_server_metadata(
+        &self,
+        issuer: &str,
+    ) -> Result<AuthorizationServerMetadata, AuthError> {
+        let issuer = issuer.trim_end_matches('/');
+        let url = format!("{}/.well-known/oauth-authorization-server", issuer);
+
+        let response = self.client.get(&url).send().await.map_err(map_request_error)?;
+        if !response.status().is_success() {
+            return Err(error_from_response(response).await);
+        }
+
+        let metadata: AuthorizationServerMetadata = response.json().await.map_err(|e| {
+            AuthError::ServerError(format!("Failed to parse authorization server metadata: {}", e))
+        })?;
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err(AuthError::ServerError(format!(
+                "Authorization server metadata names {} instead of {}",
+                metadata.issuer, issuer
+            )));
+        }
+
+        Ok(metadata)
+    }
+
+    /// Find the authorization server protecting a PDS
+    ///
+    /// # Arguments
+    /// * `pds_url` - PDS URL (from the account's DID document)
+    pub async fn authorization_server_for_pds(&self, pds_url: &str) -> Result<String, AuthError> {
+        let url = format!(
+            "{}/.well-known/oauth-protected-resource",
+            pds_url.trim_end_matches('/')
+        );
+
+        let response = self.client.get(&url).send().await.map_err(map_request_error)?;
+        if !response.status().is_success() {
+            return Err(error_from_response(response).await);
+        }
+
+        let metadata: ProtectedResourceMetadata = response.json().await.map_err(|e| {
+            AuthError::ServerError(format!("Failed to parse protected resource metadata: {}", e))
+        })?;
+
+        metadata
+            .authorization_servers
+            .into_iter()
+            .next()
+            .map(|issuer| issuer.trim_end_matches('/').to_string())
+            .ok_or_else(|| {
+                AuthError::ServerError(format!("{} names no authorization server", pds_url))
+            })
+    }
+
+    /// POST a form with a DPoP proof, retrying once when the server demands a nonce
+    ///
+    /// # Arguments
+    /// * `nonce` - Latest server nonce; updated from every response
+    async fn post_with_dpop<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        form: &[(&str, &str)],
+        dpop_key: &DpopKey,
+        nonce: &mut Option<String>,
+    ) -> Result<T, AuthError> {
+        let mut retried = false;
+
+        loop {
+            let response = self
+                .client
+                .post(url)
+                .header(DPOP_HEADER, dpop_key.proof("POST", url, nonce.as_deref(), None))
+                .form(form)
+                .send()
+                .await
+                .map_err(map_request_error)?;
+
+            if let Some(fresh) = response
+                .headers()
+                .get(DPOP_NONCE_HEADER)
+                .and_then(|value| value.to_str().ok())
+            {
+                *nonce = Some(fresh.to_string());
+            }
+
+            let status = response.status();
+            if status.is_success() {
+                return response.json::<T>().await.map_err(|e| {
+                    AuthError::ServerError(format!("Failed to parse OAuth response: {}", e))
+                });
+            }
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                return Err(error_from_response(response).await);
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            match serde_json::from_str::<OAuthErrorBody>(&body) {
+                Ok(error) if error.error == "use_dpop_nonce" && !retried && nonce.is_some() => {
+                    retried = true;
+                }
+                Ok(error) => return Err(oauth_error(status, error)),
+                Err(_) => {
+                    return Err(AuthError::ServerError(format!(
+                        "OAuth request failed ({}): {}",
+                        status, body
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Start an authorization: push the request and open a loopback redirect listener
+    ///
+    /// # Arguments
+    /// * `issuer` - Authorization server URL
+    /// * `login_hint` - Handle or DID to pre-fill on the sign-in page
+    ///
+    /// # Returns
+    /// The pending authorization; open its `authorization_url` in the browser and
+    /// pass it to `complete_oauth`
+    pub async fn start_oauth(
+        &self,
+        issuer: &str,
+        login_hint: Option<&str>,
+    ) -> Result<PendingAuthorization, AuthError> {
+        let metadata = self.get_authorization_server_metadata(issuer).await?;
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.map_err(|e| {
+            AuthError::NetworkError(format!("Failed to start OAuth redirect listener: {}", e))
+        })?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| {
+                AuthError::NetworkError(format!("Failed to start OAuth redirect listener: {}", e))
+            })?
+            .port();
+        let redirect_uri = format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH);
+        let client_id = loopback_client_id(&redirect_uri);
+
+        let pkce = Pkce::generate();
+        let state = random_token(16);
+        let dpop_key = DpopKey::generate();
+        let mut dpop_nonce = None;
+
+        let mut form = vec![
+            ("client_id", client_id.as_str()),
+            ("response_type", "code"),
+            ("code_challenge", pkce.challenge.as_str()),
+            ("code_challenge_method", "S256"),
+            ("state", state.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("scope", OAUTH_SCOPE),
+        ];
+        if let Some(login_hint) = login_hint {
+            form.push(("login_hint", login_hint));
+        }
+
+        let pushed: PushedAuthorizationResponse = self
+            .post_with_dpop(
+                &metadata.pushed_authorization_request_endpoint,
+                &form,
+                &dpop_key,
+                &mut dpop_nonce,
+            )
+            .await?;
+
+        let authorization_url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            [("client_id", client_id.as_str()), ("request_uri", pushed.request_uri.as_str())],
+        )
+        .map_err(|e| AuthError::ServerError(format!("Invalid authorization endpoint: {}", e)))?
+        .to_string();
+
+        Ok(PendingAuthorization {
+            state,
+            authorization_url,
+            issuer: metadata.issuer.trim_end_matches('/').to_string(),
+            metadata,
+            client_id,
+            redirect_uri,
+            pkce,
+            dpop_key,
+            dpop_nonce,
+            listener,
+            started_at: Instant::now(),
+        })
+    }
+
+    /// Exchange an authorization code for DPoP-bound tokens
+    ///
+    /// # Arguments
+    /// * `pending` - The authorization the code was issued for
+    /// * `code` - Code from the redirect
+    pub async fn exchange_authorization_code(
+        &self,
+        pending: &mut PendingAuthorization,
+        code: &str,
+    ) -> Result<OAuthTokenResponse, AuthError> {
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", pending.redirect_uri.as_str()),
+            ("code_verifier", pending.pkce.verifier.as_str()),
+            ("client_id", pending.client_id.as_str()),
+        ];
+
+        let tokens: OAuthTokenResponse = self
+            .post_with_dpop(
+                &pending.metadata.token_endpoint,
+                &form,
+                &pending.dpop_key,
+                &mut pending.dpop_nonce,
+            )
+            .await?;
+
+        tokens.check()?;
+
+        Ok(tokens)
+    }
+
+    /// Refresh an OAuth session with the refresh_token grant of its authorization server
+    ///
+    /// # Arguments
+    /// * `token` - Stored token (must carry its DPoP key, issuer and client ID)
+    ///
+    /// # Returns
+    /// The new token, bound to the same DPoP key
+    ///
+    /// # Note
+    /// A rejected refresh token, or a token saved without its issuer, fails with
+    /// `AuthError::TokenExpired` (the user has to log in again). The session keeps its
+    /// original `refresh_expires_at`: refreshing doesn't extend a public client session.
+    pub async fn refresh_oauth_token(&self, token: &AuthToken) -> Result<AuthToken, AuthError> {
+        let (Some(stored_key), Some(issuer), Some(client_id)) =
+            (&token.dpop_key, &token.oauth_issuer, &token.oauth_client_id)
+        else {
+            return Err(AuthError::TokenExpired);
+        };
+        let dpop_key = DpopKey::from_stored(stored_key)?;
+        let metadata = self.get_authorization_server_metadata(issuer).await?;
+
+        let form = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", token.refresh_jwt.as_str()),
+            ("client_id", client_id.as_str()),
+        ];
+        let mut nonce = None;
+        let tokens: OAuthTokenResponse = self
+            .post_with_dpop(&metadata.token_endpoint, &form, &dpop_key, &mut nonce)
+            .await
+            .map_err(|e| match e {
+                AuthError::InvalidCredentials(_) => AuthError::TokenExpired,
+                e => e,
+            })?;
+        tokens.check()?;
+
+        let refresh_expires_at = token.refresh_expires_at.clone();
+        Ok(AuthToken {
+            refresh_expires_at,
+            ..tokens.into_auth_token(&token.account_id, &dpop_key, issuer, client_id)
+        })
+    }
+
+    /// Copy of this client that authenticates with `token`
+    ///
+    /// # Returns
+    /// A client signing DPoP proofs with the token's key (OAuth sessions), or a plain
+    /// copy using Bearer authorization (app-password sessions)
+    pub fn for_token(&self, token: &AuthToken) -> Result<Self, AuthError> {
+        let dpop = match &token.dpop_key {
+            Some(stored) => Some(Arc::new(DpopBinding {
+                key: DpopKey::from_stored(stored)?,
+                nonce: Mutex::new(None),
+            })),
+            None => None,
+        };
+
+        Ok(Self {
+            dpop,
+            ..self.clone()
+        })
+    }
+
+    /// Send a resource request with DPoP authorization (RFC 9449 section 7)
+    ///
+    /// # Note
+    /// The request is sent again once, with a fresh proof, when the server answers
+    /// that it requires its current nonce.
+    pub(super) async fn send_with_dpop(
+        &self,
+        binding: &DpopBinding,
+        request: Request,
+        access_jwt: &str,
+    ) -> Result<Response, AuthError> {
+        let mut retried = false;
+
+        loop {
+            let mut attempt = request.try_clone().ok_or_else(|| {
+                AuthError::Unknown("Request body can't be sent with DPoP".to_string())
+            })?;
+            let proof = binding.key.proof(
+                attempt.method().as_str(),
+                attempt.url().as_str(),
+                binding.nonce().as_deref(),
+                Some(access_jwt),
+            );
+            let headers = attempt.headers_mut();
+            headers.insert(AUTHORIZATION, header_value(format!("DPoP {}", access_jwt))?);
+            headers.insert(DPOP_HEADER, header_value(proof)?);
+
+            let response = self.client.execute(attempt).await.map_err(map_request_error)?;
+            binding.update_nonce(&response);
+
+            if !retried && demands_dpop_nonce(&response) && binding.nonce().is_some() {
+                retried = true;
+                continue;
+            }
+            return Ok(response);
+        }
+    }
+
+    /// Wait for the redirect, exchange the code and verify who signed in
+    ///
+    /// # Arguments
+    /// * `pending` - Authorization started with `start_oauth`
+    /// * `timeout` - How long to wait for the browser redirect
+    ///
+    /// # Returns
+    /// Tokens and key, PDS URL and handle of the account
+    ///
+    /// # Note
+    /// The account's PDS must name the issuer as its authorization server; otherwise
+    /// the issuer could hand out tokens for accounts it doesn't host.
+    pub async fn complete_oauth(
+        &self,
+        mut pending: PendingAuthorization,
+        timeout: Duration,
+    ) -> Result<OAuthSession, AuthError> {
+        let code = pending.wait_for_callback(timeout).await?;
+        let tokens = self.exchange_authorization_code(&mut pending, &code).await?;
+
+        let document = self.resolve_did_document(&tokens.sub).await?;
+        let pds_url = pds_endpoint_from_document(&tokens.sub, &document)?;
+        let issuer = self.authorization_server_for_pds(&pds_url).await?;
+        if issuer != pending.issuer {
+            return Err(AuthError::IdentityMismatch(format!(
+                "{} is protected by {}, not by {}",
+                tokens.sub, issuer, pending.issuer
+            )));
+        }
+
+        let handle = handle_from_document(&document).unwrap_or_else(|| tokens.sub.clone());
+        Ok(OAuthSession {
+            tokens,
+            dpop_key: pending.dpop_key,
+            issuer: pending.issuer,
+            client_id: pending.client_id,
+            pds_url,
+            handle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use p256::ecdsa::signature::Verifier;
+    use p256::ecdsa::VerifyingKey;
+    use p256::EncodedPoint;
+
+    fn decode_part(part: &str) -> Value {
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(part).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_pkce_s256_challenge() {
+        // RFC 7636 appendix B
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string());
+        assert_eq!(pkce.challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+
+        let generated = Pkce::generate();
+        assert_eq!(generated.verifier.len(), 43);
+        assert_ne!(generated.verifier, Pkce::generate().verifier);
+    }
+
+    #[test]
+    fn test_dpop_proof_is_signed_by_embedded_key() {
+        let key = DpopKey::generate();
+        let proof = key.proof(
+            "POST",
+            "https://bsky.social/oauth/token?x=1#frag",
+            Some("nonce-1"),
+            Some("access-token"),
+        );
+
+        let parts: Vec<&str> = proof.split('.').collect();
+        assert_eq!(parts.len(), 3);
+        let header = decode_part(parts[0]);
+        let claims = decode_part(parts[1]);
+        assert_eq!(header["typ"], "dpop+jwt");
+        assert_eq!(header["alg"], "ES256");
+        assert_eq!(claims["htm"], "POST");
+        assert_eq!(claims["htu"], "https://bsky.social/oauth/token");
+        assert_eq!(claims["nonce"], "nonce-1");
+        assert_eq!(
+            claims["ath"],
+            URL_SAFE_NO_PAD.encode(Sha256::digest(b"access-token")).as_str()
+        );
+
+        // The signature verifies against the JWK in the header
+        let coordinate = |name: &str| URL_SAFE_NO_PAD.decode(header["jwk"][name].as_str().unwrap());
+        let (x, y) = (coordinate("x").unwrap(), coordinate("y").unwrap());
+        let point = EncodedPoint::from_affine_coordinates(
+            x.as_slice().into(),
+            y.as_slice().into(),
+            false,
+        );
+        let verifying_key = VerifyingKey::from_encoded_point(&point).unwrap();
+        let signature =
+            Signature::from_slice(&URL_SAFE_NO_PAD.decode(parts[2]).unwrap()).unwrap();
+        let signing_input = format!("{}.{}", parts[0], parts[1]);
+        assert!(verifying_key.verify(signing_input.as_bytes(), &signature).is_ok());
+
+        // A restored key produces the same public key
+        let restored = DpopKey::from_stored(&key.to_stored()).unwrap();
+        assert_eq!(restored.public_jwk(), key.public_jwk());
+        assert!(DpopKey::from_stored("not a key").is_err());
+    }
+
+    #[test]
+    fn test_loopback_client_id() {
+        assert_eq!(
+            loopback_client_id("http://127.0.0.1:4321/callback"),
+            "http://localhost?redirect_uri=http%3A%2F%2F127.0.0.1%3A4321%2Fcallback\
+             &scope=atproto+transition%3Ageneric"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oauth_flow_against_mock_server() {
+        let mut server = Server::new_async().await;
+        let issuer = server.url();
+        server
+            .mock("GET", "/.well-known/oauth-authorization-server")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "issuer": issuer,
+                    "authorization_endpoint": format!("{}/oauth/authorize", issuer),
+                    "token_endpoint": format!("{}/oauth/token", issuer),
+                    "pushed_authorization_request_endpoint": format!("{}/oauth/par", issuer),
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        // The first pushed request is rejected until it carries the server's nonce
+        let nonce_challenge = server
+            .mock("POST", "/oauth/par")
+            .match_header("dpop", Matcher::Any)
+            .with_status(400)
+            .with_header("DPoP-Nonce", "nonce-1")
+            .with_body(r#"{"error":"use_dpop_nonce"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let par = server
+            .mock("POST", "/oauth/par")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("code_challenge_method".into(), "S256".into()),
+                Matcher::UrlEncoded("login_hint".into(), "alice.bsky.social".into()),
+            ]))
+            .with_status(201)
+            .with_body(
+                json!({ "request_uri": "urn:ietf:params:oauth:request_uri:abc", "expires_in": 299 })
+                    .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = ATProtocolClient::with_base_url(&issuer);
+        let mut pending = client.start_oauth(&issuer, Some("alice.bsky.social")).await.unwrap();
+
+        nonce_challenge.assert_async().await;
+        par.assert_async().await;
+        assert_eq!(pending.dpop_nonce.as_deref(), Some("nonce-1"));
+        let authorization_url = Url::parse(&pending.authorization_url).unwrap();
+        assert_eq!(authorization_url.path(), "/oauth/authorize");
+        let query: HashMap<String, String> =
+            authorization_url.query_pairs().into_owned().collect();
+        assert_eq!(query["request_uri"], "urn:ietf:params:oauth:request_uri:abc");
+        assert!(query["client_id"].starts_with("http://localhost?redirect_uri="));
+
+        // The browser lands on the loopback redirect
+        let redirect = format!(
+            "{}?code=code-123&state={}&iss={}",
+            pending.redirect_uri, pending.state, issuer
+        );
+        let browser = tokio::spawn(async move { reqwest::get(redirect).await });
+        let code = pending.wait_for_callback(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(code, "code-123");
+        assert!(browser.await.unwrap().unwrap().status().is_success());
+
+        let token = server
+            .mock("POST", "/oauth/token")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("grant_type".into(), "authorization_code".into()),
+                Matcher::UrlEncoded("code".into(), "code-123".into()),
+                Matcher::UrlEncoded("code_verifier".into(), pending.pkce.verifier.clone()),
+            ]))
+            .with_status(200)
+            .with_body(
+                json!({
+                    "access_token": "access",
+                    "token_type": "DPoP",
+                    "refresh_token": "refresh",
+                    "expires_in": 3600,
+                    "scope": OAUTH_SCOPE,
+                    "sub": "did:plc:alice",
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let tokens = client.exchange_authorization_code(&mut pending, "code-123").await.unwrap();
+        token.assert_async().await;
+        assert_eq!(tokens.sub, "did:plc:alice");
+
+        let stored = tokens.into_auth_token(
+            "account-1",
+            &pending.dpop_key,
+            &pending.issuer,
+            &pending.client_id,
+        );
+        assert_eq!(stored.access_jwt, "access");
+        assert_eq!(stored.dpop_key, Some(pending.dpop_key.to_stored()));
+        assert!(!stored.is_access_expiring(Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn test_callback_with_wrong_state_is_rejected() {
+        let mut server = Server::new_async().await;
+        let issuer = server.url();
+        server
+            .mock("GET", "/.well-known/oauth-authorization-server")
+            .with_status(200)
+            .with_body(
+                json!({
+                    "issuer": issuer,
+                    "authorization_endpoint": format!("{}/oauth/authorize", issuer),
+                    "token_endpoint": format!("{}/oauth/token", issuer),
+                    "pushed_authorization_request_endpoint": format!("{}/oauth/par", issuer),
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/oauth/par")
+            .with_status(201)
+            .with_body(r#"{"request_uri":"urn:ietf:params:oauth:request_uri:abc"}"#)
+            .create_async()
+            .await;
+
+        let client = ATProtocolClient::with_base_url(&issuer);
+        let pending = client.start_oauth(&issuer, None).await.unwrap();
+
+        let port = pending.listener.local_addr().unwrap().port();
+        let favicon = format!("http://127.0.0.1:{}/favicon.ico", port);
+        let forged = format!("{}?code=stolen&state=forged", pending.redirect_uri);
+        let browser = tokio::spawn(async move {
+            let favicon = reqwest::get(favicon).await.unwrap().status();
+            reqwest::get(forged).await.unwrap();
+            favicon
+        });
+
+        let result = pending.wait_for_callback(Duration::from_secs(5)).await;
+        assert!(matches!(result, Err(AuthError::InvalidCredentials(_))));
+        assert_eq!(browser.await.unwrap(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_stale_authorizations_expire() {
+        let mut server = Server::new_async().await;
+        let issuer = server.url();
+        server
+            .mock("GET", "/.well-known/oauth-authorization-server")
+            .with_status(200)
+            .with_body(
+                json!({
+                    "issuer": issuer,
+                    "authorization_endpoint": format!("{}/oauth/authorize", issuer),
+                    "token_endpoint": format!("{}/oauth/token", issuer),
+                    "pushed_authorization_request_endpoint": format!("{}/oauth/par", issuer),
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/oauth/par")
+            .with_status(201)
+            .with_body(r#"{"request_uri":"urn:ietf:params:oauth:request_uri:abc"}"#)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&issuer);
+        let flows = OAuthFlows::default();
+
+        let mut stale = client.start_oauth(&issuer, None).await.unwrap();
+        stale.started_at = Instant::now() - OAUTH_CALLBACK_TIMEOUT;
+        let (stale_state, stale_port) =
+            (stale.state.clone(), stale.listener.local_addr().unwrap().port());
+        flows.insert(stale);
+
+        // Inserting a fresh authorization drops the stale one and closes its listener
+        let fresh = client.start_oauth(&issuer, None).await.unwrap();
+        let fresh_state = fresh.state.clone();
+        flows.insert(fresh);
+        assert_eq!(flows.pending.lock().unwrap().len(), 1);
+        assert!(reqwest::get(format!("http://127.0.0.1:{}/callback", stale_port))
+            .await
+            .is_err());
+
+        assert!(flows.take(&stale_state).is_none());
+        assert!(flows.take(&fresh_state).is_some());
+    }
+
+    /// Claims of the DPoP proof a mock request carries
+    fn proof_claims(request: &mockito::Request) -> Value {
+        let proof = request.header("dpop")[0].to_str().unwrap().to_string();
+        decode_part(proof.split('.').nth(1).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_oauth_token_is_refreshed_and_used_with_dpop() {
+        let mut server = Server::new_async().await;
+        let issuer = server.url();
+        server
+            .mock("GET", "/.well-known/oauth-authorization-server")
+            .with_status(200)
+            .with_body(
+                json!({
+                    "issuer": issuer,
+                    "authorization_endpoint": format!("{}/oauth/authorize", issuer),
+                    "token_endpoint": format!("{}/oauth/token", issuer),
+                    "pushed_authorization_request_endpoint": format!("{}/oauth/par", issuer),
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let refresh = server
+            .mock("POST", "/oauth/token")
+            .match_header("dpop", Matcher::Any)
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("grant_type".into(), "refresh_token".into()),
+                Matcher::UrlEncoded("refresh_token".into(), "old-refresh".into()),
+                Matcher::UrlEncoded("client_id".into(), "http://localhost?scope=atproto".into()),
+            ]))
+            .with_status(200)
+            .with_body(
+                json!({
+                    "access_token": "new-access",
+                    "token_type": "DPoP",
+                    "refresh_token": "new-refresh",
+                    "expires_in": 3600,
+                    "sub": "did:plc:alice",
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        // The PDS demands its nonce before accepting the DPoP-bound token
+        let nonce_challenge = server
+            .mock("GET", "/xrpc/com.atproto.server.getSession")
+            .match_header("authorization", "DPoP new-access")
+            .match_request(|request| proof_claims(request).get("nonce").is_none())
+            .with_status(401)
+            .with_header("WWW-Authenticate", r#"DPoP error="use_dpop_nonce""#)
+            .with_header("DPoP-Nonce", "pds-nonce")
+            .with_body(r#"{"error":"use_dpop_nonce"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let session = server
+            .mock("GET", "/xrpc/com.atproto.server.getSession")
+            .match_header("authorization", "DPoP new-access")
+            .match_request(|request| {
+                let claims = proof_claims(request);
+                claims["nonce"] == "pds-nonce"
+                    && claims["htm"] == "GET"
+                    && claims["ath"] == URL_SAFE_NO_PAD.encode(Sha256::digest(b"new-access"))
+            })
+            .with_status(200)
+            .with_body(r#"{"did":"did:plc:alice","handle":"alice.bsky.social"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = crate::storage::StorageManager::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let key = DpopKey::generate();
+        let expired = AuthToken {
+            access_jwt: "old-access".to_string(),
+            refresh_jwt: "old-refresh".to_string(),
+            access_expires_at: Utc::now().to_rfc3339(),
+            ..OAuthTokenResponse {
+                access_token: String::new(),
+                token_type: "DPoP".to_string(),
+                refresh_token: String::new(),
+                expires_in: None,
+                sub: "did:plc:alice".to_string(),
+            }
+            .into_auth_token("alice", &key, &issuer, "http://localhost?scope=atproto")
+        };
+        storage.save_auth_token(&expired).await.unwrap();
+
+        let client = ATProtocolClient::with_base_url(&issuer);
+        let token = storage.get_valid_token("alice", &client).await.unwrap();
+        refresh.assert_async().await;
+        assert_eq!(token.access_jwt, "new-access");
+        assert_eq!(token.dpop_key, Some(key.to_stored()));
+        assert_eq!(token.refresh_expires_at, expired.refresh_expires_at);
+
+        let info = client.for_token(&token).unwrap().get_session(&token.access_jwt).await;
+        nonce_challenge.assert_async().await;
+        session.assert_async().await;
+        assert_eq!(info.unwrap().did, "did:plc:alice");
+    }
+}
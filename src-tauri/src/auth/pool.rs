@@ -0,0 +1,134 @@
+/**
+ * Shared AT Protocol clients
+ *
+ * Keeps one client per PDS so connection pools and TLS sessions survive between
+ * commands instead of being rebuilt for every login and refresh.
+ */
+
+use crate::auth::{ATProtocolClient, ClientConfig};
+use crate::types::AuthError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Clients keyed by normalized server URL (managed by Tauri)
+pub struct ClientPool {
+    /// Configuration every pooled client is built with (replaced by `reconfigure`)
+    config: RwLock<ClientConfig>,
+    clients: Mutex<HashMap<String, Arc<ATProtocolClient>>>,
+}
+
+impl ClientPool {
+    /// Create an empty pool
+    ///
+    /// # Arguments
+    /// * `config` - Configuration for the clients
+    pub fn new(config: ClientConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Configuration the pooled clients are built with
+    pub fn config(&self) -> ClientConfig {
+        match self.config.read() {
+            Ok(config) => config.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Replace the configuration and drop the clients built with the old one
+    ///
+    /// # Arguments
+    /// * `config` - New configuration (e.g., resolved again after a settings change)
+    ///
+    /// # Note
+    /// Clients already handed out keep the old configuration until they are dropped.
+    pub fn reconfigure(&self, config: ClientConfig) -> Result<(), AuthError> {
+        // Same lock order as `get`: clients, then config
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|_| AuthError::Unknown("Client pool lock poisoned".to_string()))?;
+        *self
+            .config
+            .write()
+            .map_err(|_| AuthError::Unknown("Client pool lock poisoned".to_string()))? = config;
+        clients.clear();
+        Ok(())
+    }
+
+    /// Get the client for a server, creating it on first use
+    ///
+    /// # Arguments
+    /// * `server_url` - PDS server URL (normalized like `ATProtocolClient::with_config`,
+    ///   trailing slash ignored)
+    ///
+    /// # Note
+    /// Fail-fast clients (`ClientConfig::no_retry`) use a different timeout and are
+    /// not pooled.
+    pub fn get(&self, server_url: &str) -> Result<Arc<ATProtocolClient>, AuthError> {
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|_| AuthError::Unknown("Client pool lock poisoned".to_string()))?;
+        let config = self.config();
+
+        let key = ATProtocolClient::normalize_server_url_with(
+            Some(server_url.trim_end_matches('/').to_string()),
+            config.allow_insecure_localhost,
+        )?;
+        if let Some(client) = clients.get(&key) {
+            return Ok(Arc::clone(client));
+        }
+
+        let client = Arc::new(ATProtocolClient::with_config(Some(key.clone()), config)?);
+        clients.insert(key, Arc::clone(&client));
+        Ok(client)
+    }
+
+    /// Get an owned client for a server (shares the pooled client's connections)
+    ///
+    /// # Arguments
+    /// * `server_url` - PDS server URL
+    pub fn get_owned(&self, server_url: &str) -> Result<ATProtocolClient, AuthError> {
+        self.get(server_url).map(|client| ATProtocolClient::clone(&client))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_server_reuses_client() {
+        let pool = ClientPool::new(ClientConfig::default());
+
+        let first = pool.get("pds.example.com").unwrap();
+        let second = pool.get("https://pds.example.com/").unwrap();
+        let other = pool.get("https://other.example.com").unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(first.server_url(), "https://pds.example.com");
+        assert!(pool.get("http://pds.example.com").is_err());
+    }
+
+    #[test]
+    fn test_reconfigure_rebuilds_clients() {
+        let pool = ClientPool::new(ClientConfig::default());
+        let before = pool.get("pds.example.com").unwrap();
+
+        let config = ClientConfig {
+            max_retries: 1,
+            allow_insecure_localhost: true,
+            ..ClientConfig::default()
+        };
+        pool.reconfigure(config).unwrap();
+
+        let after = pool.get("pds.example.com").unwrap();
+        assert!(!Arc::ptr_eq(&before, &after));
+        assert_eq!(pool.config().max_retries, 1);
+        assert!(pool.get("http://localhost:2583").is_ok());
+    }
+}
@@ -0,0 +1,206 @@
+/**
+ * TLS certificate inspection
+ *
+ * Shows the certificate of a (possibly self-hosted) PDS before the user trusts it.
+ * Nothing is stored; the fingerprint can be used to set up certificate pinning.
+ */
+
+use crate::auth::ATProtocolClient;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// Connect + handshake timeout
+const INSPECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS inspection failure
+#[derive(Debug, thiserror::Error)]
+pub enum TlsInspectError {
+    #[error("Invalid server URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Connection failed: {0}")]
+    Connection(String),
+
+    #[error("TLS handshake failed: {0}")]
+    Handshake(String),
+
+    #[error("Invalid certificate: {0}")]
+    Certificate(String),
+}
+
+/// Leaf certificate details of a TLS endpoint
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsInfo {
+    /// Host that was inspected
+    pub host: String,
+    /// Port that was inspected
+    pub port: u16,
+    /// Certificate subject (RFC 4514 string)
+    pub subject: String,
+    /// Certificate issuer (RFC 4514 string)
+    pub issuer: String,
+    /// Start of the validity period (ISO 8601)
+    pub not_before: String,
+    /// End of the validity period (ISO 8601)
+    pub not_after: String,
+    /// SHA-256 fingerprint of the DER certificate (colon-separated hex)
+    pub sha256_fingerprint: String,
+    /// Whether the chain verified against the system trust store
+    pub trusted: bool,
+    /// Why verification failed (None when trusted)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification_error: Option<String>,
+}
+
+/// Colon-separated uppercase hex SHA-256 of DER bytes
+fn sha256_fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Perform a TLS handshake and return the peer's leaf certificate (DER)
+async fn fetch_leaf_certificate(
+    host: &str,
+    port: u16,
+    verify: bool,
+) -> Result<Vec<u8>, TlsInspectError> {
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(!verify)
+        .danger_accept_invalid_hostnames(!verify)
+        .build()
+        .map_err(|e| TlsInspectError::Handshake(e.to_string()))?;
+    let connector = tokio_native_tls::TlsConnector::from(connector);
+
+    let stream = tokio::time::timeout(INSPECT_TIMEOUT, TcpStream::connect((host, port)))
+        .await
+        .map_err(|_| TlsInspectError::Connection("Connection timeout".to_string()))?
+        .map_err(|e| TlsInspectError::Connection(e.to_string()))?;
+
+    let tls = tokio::time::timeout(INSPECT_TIMEOUT, connector.connect(host, stream))
+        .await
+        .map_err(|_| TlsInspectError::Handshake("Handshake timeout".to_string()))?
+        .map_err(|e| TlsInspectError::Handshake(e.to_string()))?;
+
+    let certificate = tls
+        .get_ref()
+        .peer_certificate()
+        .map_err(|e| TlsInspectError::Certificate(e.to_string()))?
+        .ok_or_else(|| TlsInspectError::Certificate("Server sent no certificate".to_string()))?;
+
+    certificate
+        .to_der()
+        .map_err(|e| TlsInspectError::Certificate(e.to_string()))
+}
+
+/// Inspect the leaf certificate of a PDS server
+///
+/// # Arguments
+/// * `server_url` - PDS server URL (https:// is prepended if missing)
+///
+/// # Note
+/// An untrusted certificate (e.g., self-signed) is still reported, with `trusted`
+/// set to false, so the user can decide whether to pin it.
+pub async fn inspect_tls(server_url: &str) -> Result<TlsInfo, TlsInspectError> {
+    let normalized = ATProtocolClient::normalize_server_url(Some(server_url.to_string()))
+        .map_err(|e| TlsInspectError::InvalidUrl(e.to_string()))?;
+    let url = reqwest::Url::parse(&normalized)
+        .map_err(|e| TlsInspectError::InvalidUrl(e.to_string()))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| TlsInspectError::InvalidUrl("URL has no host".to_string()))?
+        .to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let (der, verification_error) = match fetch_leaf_certificate(&host, port, true).await {
+        Ok(der) => (der, None),
+        Err(TlsInspectError::Handshake(reason)) => {
+            (fetch_leaf_certificate(&host, port, false).await?, Some(reason))
+        }
+        Err(e) => return Err(e),
+    };
+
+    let (_, certificate) = X509Certificate::from_der(&der)
+        .map_err(|e| TlsInspectError::Certificate(e.to_string()))?;
+    let validity = certificate.validity();
+    let to_rfc3339 = |time: &x509_parser::time::ASN1Time| {
+        chrono::DateTime::from_timestamp(time.timestamp(), 0)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default()
+    };
+
+    Ok(TlsInfo {
+        host,
+        port,
+        subject: certificate.subject().to_string(),
+        issuer: certificate.issuer().to_string(),
+        not_before: to_rfc3339(&validity.not_before),
+        not_after: to_rfc3339(&validity.not_after),
+        sha256_fingerprint: sha256_fingerprint(&der),
+        trusted: verification_error.is_none(),
+        verification_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serve TLS handshakes with a fresh self-signed certificate; returns (port, DER)
+    async fn self_signed_tls_server() -> (u16, Vec<u8>) {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let identity = native_tls::Identity::from_pkcs8(
+            certified.cert.pem().as_bytes(),
+            certified.key_pair.serialize_pem().as_bytes(),
+        )
+        .unwrap();
+        let acceptor = tokio_native_tls::TlsAcceptor::from(
+            native_tls::TlsAcceptor::builder(identity).build().unwrap(),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let _ = acceptor.accept(stream).await;
+                });
+            }
+        });
+
+        (port, certified.cert.der().to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_inspect_tls_reports_certificate() {
+        let (port, der) = self_signed_tls_server().await;
+
+        let info = inspect_tls(&format!("https://localhost:{}", port)).await.unwrap();
+
+        assert_eq!(info.port, port);
+        assert_eq!(info.sha256_fingerprint, sha256_fingerprint(&der));
+        assert_eq!(info.sha256_fingerprint.len(), 32 * 3 - 1);
+        assert!(!info.not_before.is_empty());
+        assert!(!info.not_after.is_empty());
+        assert!(info.not_before < info.not_after);
+        // Self-signed: reported, but not trusted
+        assert!(!info.trusted);
+        assert!(info.verification_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_inspect_tls_connection_failure() {
+        let result = inspect_tls("https://127.0.0.1:9").await;
+
+        assert!(matches!(result, Err(TlsInspectError::Connection(_))));
+    }
+}
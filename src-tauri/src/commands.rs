@@ -4,104 +4,302 @@
  * These commands are invoked from the frontend using invoke()
  */
 
-use crate::auth::ATProtocolClient;
-use crate::storage::columns::{get_default_columns, load_columns, save_columns};
-use crate::storage::StorageManager;
-use crate::types::{Account, AuthToken, DeckColumnConfig};
-use chrono::Utc;
-use tauri::{AppHandle, Manager, State};
-use uuid::Uuid;
+use crate::accounts::{
+    self, AccountRemovalReport, ActiveInvariantReport, LoginResult, LogoutReport,
+    ProfileRefreshReport, SessionVerification,
+};
+use crate::api::avatars::{AvatarCache, CachedAvatar};
+use crate::api::cache::RequestCache;
+use crate::api::feed::{merge_feed, FeedPage};
+use crate::api::metrics::MethodErrorSummary;
+use crate::api::moderation::ModerationLists;
+use crate::auth::clock::{self, ClockSkewReport, CLOCK_SKEW_THRESHOLD_SECS};
+use crate::auth::jwt::SessionScopes;
+use crate::auth::oauth::{OAuthFlows, OAuthStart, OAUTH_CALLBACK_TIMEOUT};
+use crate::auth::pool::ClientPool;
+use crate::auth::tls::{self, TlsInfo};
+use crate::filters;
+use crate::handles::{self, HandleValidation};
+use crate::auth::{ATProtocolClient, ClientConfig, EffectiveClientConfig, ServerDescription};
+use crate::realtime::{SubscriptionInfo, SubscriptionRegistry, DEFAULT_JETSTREAM_URL};
+use crate::refresh::{self, RefreshOutcome, RefreshSelfTest, RefreshState, TimelineRefresh};
+use crate::startup::{self, StartupSequenceReport, StartupTimings};
+use crate::storage::account_bundle;
+use crate::storage::backup;
+use crate::storage::columns::{
+    self, get_default_columns, load_columns, BulkRefreshReport, ColumnsMigrationReport,
+};
+use crate::storage::columns_footprint::{self, ColumnsStorageReport};
+use crate::storage::debug_snapshot;
+use crate::storage::maintenance::{self, CleanupReport};
+use crate::storage::passphrase::{self, GeneratedPassphrase};
+use crate::storage::portable::{self, PortableFormat};
+use crate::storage::presets::{self, PresetValidation};
+use crate::storage::schema::{self, SchemaReport};
+use crate::storage::security::SecurityAudit;
+use crate::storage::storage_backup::{self, BackupImport, ImportMode};
+use crate::storage::settings::{self, load_settings, save_settings, SettingsRepair};
+use crate::storage::stronghold_migration::{self, StrongholdMigrationReport};
+use crate::storage::usage::{self, AccountStorageUsage};
+use crate::storage::writability::{self, StorageWritability};
+use crate::storage::{EncryptionCheck, PersistenceMetrics, StorageManager};
+use crate::types::{
+    Account, AccountPatch, AppSettings, AuthError, AuthToken, ColumnPatch, CommandError,
+    DeckColumnConfig, ProfileView,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use zeroize::Zeroizing;
+
+/// Resolve the app data directory
+fn app_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+/// Apply saved settings to the running app (refresh skew and client configuration)
+///
+/// # Note
+/// Environment overrides are resolved again, exactly as at startup.
+fn apply_settings(
+    settings: &AppSettings,
+    storage: &StorageManager,
+    client_pool: &ClientPool,
+) -> Result<(), String> {
+    storage.set_token_refresh_skew(settings.token_refresh_skew());
+    client_pool
+        .reconfigure(ClientConfig::resolve(settings, |key| std::env::var(key).ok()))
+        .map_err(|e| e.to_string())
+}
+
+/// Resolve the PDS server URL for a login, honoring the configured default
+fn resolve_login_server_url(
+    app: &AppHandle,
+    server_url: Option<String>,
+    client_config: &ClientConfig,
+) -> Result<String, CommandError> {
+    let data_dir = app_data_dir(app).map_err(CommandError::storage)?;
+    let settings = load_settings(&data_dir).map_err(CommandError::storage)?;
+
+    settings
+        .resolve_server_url(server_url, client_config.allow_insecure_localhost)
+        .map_err(|e| CommandError::context("Failed to create client", e))
+}
+
+/// Load an account together with a client for its PDS and a valid (refreshed if needed) token
+///
+/// # Note
+/// For OAuth sessions the client is a copy bound to the token's DPoP key, so its
+/// requests carry DPoP authorization instead of a Bearer token.
+async fn authenticated_client(
+    storage: &StorageManager,
+    client_pool: &ClientPool,
+    account_id: &str,
+) -> Result<(Account, Arc<ATProtocolClient>, AuthToken), String> {
+    let account = storage
+        .get_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+
+    let client = client_pool
+        .get(&account.server_url)
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+
+    let token = storage
+        .get_valid_token(account_id, &client)
+        .await
+        .map_err(|e| format!("Failed to get token: {}", e))?;
+    let client = if token.dpop_key.is_some() {
+        Arc::new(
+            client
+                .for_token(&token)
+                .map_err(|e| format!("Failed to create client: {}", e))?,
+        )
+    } else {
+        client
+    };
+
+    storage
+        .mark_account_used(account_id)
+        .await
+        .map_err(|e| format!("Failed to update account: {}", e))?;
+
+    Ok((account, client, token))
+}
 
 /// Login to Bluesky with credentials
 ///
 /// # Arguments
+/// * `app` - Tauri app handle
 /// * `identifier` - User handle (e.g., "user.bsky.social") or email
 /// * `password` - Account password
-/// * `server_url` - Optional custom PDS server URL
+/// * `server_url` - Optional custom PDS server URL (defaults to the PDS in the DID document,
+///   then the configured default server)
+/// * `auth_factor_token` - Emailed two-factor code (after an `AuthFactorTokenRequired` error)
 /// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
 ///
 /// # Returns
-/// Account object with user information
+/// Account object with user information, plus a warning when the password is not an
+/// app password
 #[tauri::command]
 pub async fn login(
+    app: AppHandle,
     identifier: String,
     password: String,
     server_url: Option<String>,
+    auth_factor_token: Option<String>,
     storage: State<'_, StorageManager>,
-) -> Result<Account, String> {
+    client_pool: State<'_, ClientPool>,
+) -> Result<LoginResult, CommandError> {
+    let password = Zeroizing::new(password);
+    let discover = server_url.is_none();
+    let server_url = resolve_login_server_url(&app, server_url, &client_pool.config())?;
+
     // Create AT Protocol client
-    let client = ATProtocolClient::new(server_url.clone())
-        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let mut client = client_pool
+        .get(&server_url)
+        .map_err(|e| CommandError::context("Failed to create client", e))?;
 
-    // Attempt to create session with retry logic
-    let session = client
-        .with_retry(|| client.create_session(&identifier, &password))
-        .await
-        .map_err(|e| format!("Login failed: {}", e))?;
-
-    // Create account object
-    let account_id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
-
-    let account = Account {
-        id: account_id.clone(),
-        did: session.did.clone(),
-        handle: session.handle.clone(),
-        email: session.email.clone(),
-        display_name: session.display_name.clone(),
-        avatar: session.avatar.clone(),
-        server_url: server_url.unwrap_or_else(|| "https://bsky.social".to_string()),
-        created_at: now.clone(),
-        last_used_at: now.clone(),
-        is_active: true,
-    };
+    // Without an explicit server, log in to the PDS named in the DID document
+    if discover {
+        if let Some(pds_url) = accounts::discover_pds(&client, &identifier).await {
+            client = client_pool
+                .get(&pds_url)
+                .map_err(|e| CommandError::context("Failed to create client", e))?;
+        }
+    }
 
-    // Create auth token
-    let auth_token = AuthToken {
-        account_id: account_id.clone(),
-        access_jwt: session.access_jwt,
-        refresh_jwt: session.refresh_jwt,
-        issued_at: now.clone(),
-        // AT Protocol tokens typically expire in ~90 minutes for access, ~60 days for refresh
-        access_expires_at: (Utc::now() + chrono::Duration::minutes(90)).to_rfc3339(),
-        refresh_expires_at: (Utc::now() + chrono::Duration::days(60)).to_rfc3339(),
-        session_string: None,
-    };
+    // Create session and verify it belongs to the requested identity
+    let (account, auth_token) = accounts::establish_session(
+        &client,
+        &identifier,
+        &password,
+        auth_factor_token.as_deref(),
+    )
+    .await
+    .map_err(|e| CommandError::context("Login failed", e))?;
 
     // Save account and token
-    storage
-        .save_account(&account)
+    accounts::save_new_account(&storage, &account, &auth_token)
         .await
-        .map_err(|e| format!("Failed to save account: {}", e))?;
+        .map_err(CommandError::storage)?;
 
-    storage
-        .save_auth_token(&auth_token)
+    Ok(LoginResult::new(account, &password))
+}
+
+/// Start an OAuth login: returns the URL to open in the browser
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `identifier` - Handle or DID to sign in as (selects the account's authorization
+///   server; bsky.social when omitted or not discoverable)
+/// * `client_pool` - Shared client state
+/// * `oauth_flows` - Pending OAuth logins state
+///
+/// # Returns
+/// Authorization URL and the state to pass to `oauth_complete`
+#[tauri::command]
+pub async fn oauth_start(
+    app: AppHandle,
+    identifier: Option<String>,
+    client_pool: State<'_, ClientPool>,
+    oauth_flows: State<'_, OAuthFlows>,
+) -> Result<OAuthStart, String> {
+    let server_url = resolve_login_server_url(&app, None, &client_pool.config())?;
+    let client = client_pool
+        .get(&server_url)
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+
+    let issuer = accounts::discover_authorization_server(&client, identifier.as_deref()).await;
+    let pending = client
+        .start_oauth(&issuer, identifier.as_deref())
         .await
-        .map_err(|e| format!("Failed to save token: {}", e))?;
+        .map_err(|e| format!("Failed to start OAuth login: {}", e))?;
+
+    let start = OAuthStart {
+        authorization_url: pending.authorization_url.clone(),
+        state: pending.state.clone(),
+    };
+    oauth_flows.insert(pending);
+
+    Ok(start)
+}
+
+/// Finish an OAuth login once the user has approved it in the browser
+///
+/// # Arguments
+/// * `state` - State returned by `oauth_start`
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `oauth_flows` - Pending OAuth logins state
+///
+/// # Returns
+/// The logged-in account
+///
+/// # Note
+/// Waits up to `OAUTH_CALLBACK_TIMEOUT` for the browser redirect. The DPoP key is
+/// saved with the token in the encrypted store.
+#[tauri::command]
+pub async fn oauth_complete(
+    state: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    oauth_flows: State<'_, OAuthFlows>,
+) -> Result<Account, String> {
+    let pending = oauth_flows
+        .take(&state)
+        .ok_or_else(|| "No pending OAuth login for this state".to_string())?;
+    let client = client_pool
+        .get(&pending.issuer)
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+
+    let (account, auth_token) =
+        accounts::establish_oauth_session(&client, pending, OAUTH_CALLBACK_TIMEOUT)
+            .await
+            .map_err(|e| format!("Login failed: {}", e))?;
+
+    accounts::save_new_account(&storage, &account, &auth_token).await?;
 
     Ok(account)
 }
 
 /// Logout from a specific account
 ///
+/// The session is revoked on the PDS first; if that fails (e.g. offline) the account
+/// is still removed locally and the failure is returned as a warning.
+///
 /// # Arguments
+/// * `app` - Tauri app handle
 /// * `account_id` - Account ID to logout
 /// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Returns
+/// Active account afterwards, whether the session was revoked, and any warning
 #[tauri::command]
-pub async fn logout(account_id: String, storage: State<'_, StorageManager>) -> Result<(), String> {
-    // Delete auth token (secure data)
-    storage
-        .delete_auth_token(&account_id)
+pub async fn logout(
+    app: AppHandle,
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<LogoutReport, CommandError> {
+    let account = storage
+        .get_account(&account_id)
         .await
-        .map_err(|e| format!("Failed to delete token: {}", e))?;
+        .map_err(|e| CommandError::context("Failed to get account", e))?;
 
-    // Delete account metadata
-    storage
-        .delete_account(&account_id)
-        .await
-        .map_err(|e| format!("Failed to delete account: {}", e))?;
+    let client = client_pool
+        .get(&account.server_url)
+        .map_err(|e| CommandError::context("Failed to create client", e))?;
 
-    Ok(())
+    let data_dir = app_data_dir(&app).map_err(CommandError::storage)?;
+    accounts::logout_account(&storage, &data_dir, &client, &account_id)
+        .await
+        .map_err(CommandError::storage)
 }
 
 /// Refresh an expired access token
@@ -109,6 +307,7 @@ pub async fn logout(account_id: String, storage: State<'_, StorageManager>) -> R
 /// # Arguments
 /// * `account_id` - Account ID to refresh
 /// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
 ///
 /// # Returns
 /// Updated AuthToken with new access/refresh tokens
@@ -116,48 +315,53 @@ pub async fn logout(account_id: String, storage: State<'_, StorageManager>) -> R
 pub async fn refresh_session(
     account_id: String,
     storage: State<'_, StorageManager>,
-) -> Result<AuthToken, String> {
-    // Get existing token
-    let old_token = storage
-        .get_auth_token(&account_id)
-        .await
-        .map_err(|e| format!("Failed to get token: {}", e))?;
-
+    client_pool: State<'_, ClientPool>,
+) -> Result<AuthToken, CommandError> {
     // Get account to retrieve server URL
     let account = storage
         .get_account(&account_id)
         .await
-        .map_err(|e| format!("Failed to get account: {}", e))?;
+        .map_err(|e| CommandError::context("Failed to get account", e))?;
 
     // Create AT Protocol client
-    let client = ATProtocolClient::new(Some(account.server_url))
-        .map_err(|e| format!("Failed to create client: {}", e))?;
-
-    // Refresh session
-    let session = client
-        .refresh_session(&old_token.refresh_jwt)
-        .await
-        .map_err(|e| format!("Refresh failed: {}", e))?;
-
-    // Create new auth token
-    let now = Utc::now().to_rfc3339();
-    let new_token = AuthToken {
-        account_id: account_id.clone(),
-        access_jwt: session.access_jwt,
-        refresh_jwt: session.refresh_jwt,
-        issued_at: now.clone(),
-        access_expires_at: (Utc::now() + chrono::Duration::minutes(90)).to_rfc3339(),
-        refresh_expires_at: (Utc::now() + chrono::Duration::days(60)).to_rfc3339(),
-        session_string: None,
-    };
+    let client = client_pool
+        .get(&account.server_url)
+        .map_err(|e| CommandError::context("Failed to create client", e))?;
 
-    // Save updated token
+    // Refresh session and save the new token (bumps last_used_at)
     storage
-        .save_auth_token(&new_token)
+        .refresh_token(&account_id, &client)
+        .await
+        .map_err(|e| CommandError::context("Refresh failed", e))
+}
+
+/// Run a refresh end-to-end for one account as a health check
+///
+/// # Arguments
+/// * `account_id` - Account to test
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Returns
+/// Which checks passed and how long the refresh took
+#[tauri::command]
+pub async fn selftest_refresh(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<RefreshSelfTest, String> {
+    let account = storage
+        .get_account(&account_id)
         .await
-        .map_err(|e| format!("Failed to save token: {}", e))?;
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+
+    let client = client_pool
+        .get(&account.server_url)
+        .map_err(|e| format!("Failed to create client: {}", e))?;
 
-    Ok(new_token)
+    refresh::selftest_refresh(&storage, &client, &account_id)
+        .await
+        .map_err(|e| format!("Failed to run refresh self-test: {}", e))
 }
 
 /// Restore all saved sessions on app startup
@@ -168,125 +372,183 @@ pub async fn refresh_session(
 /// # Returns
 /// List of all saved accounts
 #[tauri::command]
-pub async fn restore_sessions(storage: State<'_, StorageManager>) -> Result<Vec<Account>, String> {
+pub async fn restore_sessions(
+    storage: State<'_, StorageManager>,
+) -> Result<Vec<Account>, CommandError> {
     storage
         .list_accounts()
         .await
-        .map_err(|e| format!("Failed to list accounts: {}", e))
+        .map_err(|e| CommandError::context("Failed to list accounts", e))
+}
+
+/// Run the startup work (session validation, profiles, avatars) in priority order
+///
+/// # Arguments
+/// * `app` - Tauri app handle (emits "startup-progress" after every step and
+///   "startup-background-finished" once the other accounts are refreshed)
+/// * `storage` - Storage manager state
+/// * `avatars` - Avatar cache state
+/// * `client_pool` - Shared client state
+/// * `refresh_state` - Refresh state (validation is deferred while paused)
+///
+/// # Returns
+/// Restored accounts, the active account's columns, updated, failed and deferred accounts
+///
+/// # Note
+/// Returns once the active account is ready; the other accounts are refreshed by a
+/// spawned, rate-limited task (see `startup::background_refresh`).
+#[tauri::command]
+pub async fn startup_sequence(
+    app: AppHandle,
+    storage: State<'_, StorageManager>,
+    avatars: State<'_, AvatarCache>,
+    client_pool: State<'_, ClientPool>,
+    refresh_state: State<'_, RefreshState>,
+) -> Result<StartupSequenceReport, String> {
+    let data_dir = app_data_dir(&app)?;
+
+    let report = startup::startup_sequence(
+        &storage,
+        &data_dir,
+        &avatars,
+        &refresh_state,
+        |account| client_pool.get_owned(&account.server_url),
+        |progress| {
+            let _ = app.emit("startup-progress", progress);
+        },
+    )
+    .await
+    .map_err(|e| format!("Failed to run startup sequence: {}", e))?;
+
+    if !report.background.is_empty() {
+        let background = report.background.clone();
+        tauri::async_runtime::spawn(async move {
+            let storage = app.state::<StorageManager>();
+            let avatars = app.state::<AvatarCache>();
+            let client_pool = app.state::<ClientPool>();
+            let refresh_state = app.state::<RefreshState>();
+
+            let result = startup::background_refresh(
+                &storage,
+                &avatars,
+                &refresh_state,
+                &background,
+                startup::BACKGROUND_REFRESH_INTERVAL,
+                |account| client_pool.get_owned(&account.server_url),
+                |progress| {
+                    let _ = app.emit("startup-progress", progress);
+                },
+            )
+            .await;
+            let _ = app.emit("startup-background-finished", &result);
+        });
+    }
+
+    Ok(report)
 }
 
 /// Add a new account (similar to login but doesn't set as current)
 ///
 /// # Arguments
+/// * `app` - Tauri app handle
 /// * `identifier` - User handle or email
 /// * `password` - Account password
-/// * `server_url` - Optional custom PDS server URL
+/// * `server_url` - Optional custom PDS server URL (defaults to the PDS in the DID document,
+///   then the configured default server)
+/// * `auth_factor_token` - Emailed two-factor code (after an `AuthFactorTokenRequired` error)
 /// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
 ///
 /// # Returns
-/// Account object with user information
+/// Account object with user information, plus a warning when the password is not an
+/// app password
 #[tauri::command]
 pub async fn add_account(
+    app: AppHandle,
     identifier: String,
     password: String,
     server_url: Option<String>,
+    auth_factor_token: Option<String>,
     storage: State<'_, StorageManager>,
-) -> Result<Account, String> {
-    // Check if account already exists (by handle)
+    client_pool: State<'_, ClientPool>,
+) -> Result<LoginResult, String> {
+    let password = Zeroizing::new(password);
+    // Check if account already exists (by DID, once the session names it)
     let existing_accounts = storage
         .list_accounts()
         .await
         .map_err(|e| format!("Failed to list accounts: {}", e))?;
 
+    let discover = server_url.is_none();
+    let server_url = resolve_login_server_url(&app, server_url, &client_pool.config())?;
+
     // Create AT Protocol client
-    let client = ATProtocolClient::new(server_url.clone())
+    let mut client = client_pool
+        .get(&server_url)
         .map_err(|e| format!("Failed to create client: {}", e))?;
 
-    // Attempt to create session with retry logic
-    let session = client
-        .with_retry(|| client.create_session(&identifier, &password))
-        .await
-        .map_err(|e| format!("Login failed: {}", e))?;
-
-    // Check for duplicate handle
-    if existing_accounts
-        .iter()
-        .any(|acc| acc.handle == session.handle)
-    {
-        return Err(format!(
-            "Account with handle '{}' already exists",
-            session.handle
-        ));
+    // Without an explicit server, log in to the PDS named in the DID document
+    if discover {
+        if let Some(pds_url) = accounts::discover_pds(&client, &identifier).await {
+            client = client_pool
+                .get(&pds_url)
+                .map_err(|e| format!("Failed to create client: {}", e))?;
+        }
     }
 
-    // Create account object
-    let account_id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
-
-    let account = Account {
-        id: account_id.clone(),
-        did: session.did.clone(),
-        handle: session.handle.clone(),
-        email: session.email.clone(),
-        display_name: session.display_name.clone(),
-        avatar: session.avatar.clone(),
-        server_url: server_url.unwrap_or_else(|| "https://bsky.social".to_string()),
-        created_at: now.clone(),
-        last_used_at: now.clone(),
-        is_active: true,
-    };
+    // Create session and verify it belongs to the requested identity
+    let (account, auth_token) = accounts::establish_session(
+        &client,
+        &identifier,
+        &password,
+        auth_factor_token.as_deref(),
+    )
+    .await
+    .map_err(|e| format!("Login failed: {}", e))?;
 
-    // Create auth token
-    let auth_token = AuthToken {
-        account_id: account_id.clone(),
-        access_jwt: session.access_jwt,
-        refresh_jwt: session.refresh_jwt,
-        issued_at: now.clone(),
-        access_expires_at: (Utc::now() + chrono::Duration::minutes(90)).to_rfc3339(),
-        refresh_expires_at: (Utc::now() + chrono::Duration::days(60)).to_rfc3339(),
-        session_string: None,
-    };
+    // Check for a duplicate identity
+    accounts::ensure_not_added(&existing_accounts, &account)?;
 
     // Save account and token
-    storage
-        .save_account(&account)
-        .await
-        .map_err(|e| format!("Failed to save account: {}", e))?;
-
-    storage
-        .save_auth_token(&auth_token)
-        .await
-        .map_err(|e| format!("Failed to save token: {}", e))?;
+    accounts::save_new_account(&storage, &account, &auth_token).await?;
 
-    Ok(account)
+    Ok(LoginResult::new(account, &password))
 }
 
 /// Remove an account and its authentication token
 ///
 /// # Arguments
+/// * `app` - Tauri app handle
 /// * `account_id` - Account ID to remove
 /// * `storage` - Storage manager state
 ///
+/// # Returns
+/// ID of the active account afterwards (None if no account is left)
+///
 /// # Note
-/// Column configurations are preserved (as per FR-012a)
+/// The account's columns are deleted and another account is promoted if the removed
+/// one was active
 #[tauri::command]
 pub async fn remove_account(
+    app: AppHandle,
     account_id: String,
     storage: State<'_, StorageManager>,
-) -> Result<(), String> {
-    // Delete auth token (secure data)
-    storage
-        .delete_auth_token(&account_id)
+) -> Result<Option<String>, String> {
+    let account = storage
+        .get_account(&account_id)
         .await
-        .map_err(|e| format!("Failed to delete token: {}", e))?;
+        .map_err(|e| format!("Failed to get account: {}", e))?;
 
-    // Delete account metadata
+    // Delete account metadata and auth token (secure data) in one write
     storage
-        .delete_account(&account_id)
+        .remove_account(&account_id)
         .await
         .map_err(|e| format!("Failed to delete account: {}", e))?;
 
-    Ok(())
+    let (active_account_id, _) =
+        accounts::reconcile_after_removal(&storage, &app_data_dir(&app)?, &account.did).await?;
+
+    Ok(active_account_id)
 }
 
 /// List all saved accounts
@@ -304,6 +566,61 @@ pub async fn list_accounts(storage: State<'_, StorageManager>) -> Result<Vec<Acc
         .map_err(|e| format!("Failed to list accounts: {}", e))
 }
 
+/// Make an account the active one
+///
+/// # Arguments
+/// * `account_id` - Account ID to activate
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// The activated account (every other account is deactivated)
+#[tauri::command]
+pub async fn switch_active_account(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+) -> Result<Account, String> {
+    storage
+        .switch_active_account(&account_id)
+        .await
+        .map_err(|e| format!("Failed to switch account: {}", e))
+}
+
+/// Get an account's access JWT for direct API calls from the frontend
+///
+/// # Arguments
+/// * `account_id` - Account ID
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Returns
+/// The access JWT (refreshed first if it is about to expire); the refresh JWT is never
+/// returned
+///
+/// # Note
+/// Fails with the `AccountNotFound` type for an unknown account and `TokenExpired` when
+/// the session can't be refreshed (log in again). OAuth access tokens are DPoP-bound and
+/// only usable together with a proof made by the backend.
+#[tauri::command]
+pub async fn get_access_token(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<String, CommandError> {
+    let account = storage
+        .get_account(&account_id)
+        .await
+        .map_err(|e| CommandError::context("Failed to get account", e))?;
+
+    let client = client_pool
+        .get(&account.server_url)
+        .map_err(|e| CommandError::context("Failed to create client", e))?;
+
+    storage
+        .get_access_token(&account_id, &client)
+        .await
+        .map_err(|e| CommandError::context("Failed to get access token", e))
+}
+
 /// Get deck column configurations
 ///
 /// # Arguments
@@ -318,10 +635,7 @@ pub async fn get_columns(
     app: AppHandle,
     storage: State<'_, StorageManager>,
 ) -> Result<Vec<DeckColumnConfig>, String> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let data_dir = app_data_dir(&app)?;
 
     let columns = load_columns(&data_dir).unwrap_or_else(|_| vec![]);
 
@@ -334,10 +648,12 @@ pub async fn get_columns(
             .map_err(|e| format!("Failed to list accounts: {}", e))?;
 
         if let Some(first_account) = accounts.first() {
-            let default_columns = get_default_columns(&first_account.did);
-            // Save default columns for next time
-            let _ = save_columns(&data_dir, default_columns.clone());
-            return Ok(default_columns);
+            // Save default columns for next time (an unreadable file is not replaced)
+            return Ok(
+                columns::ensure_default_columns(&storage, &data_dir, &first_account.did)
+                    .await
+                    .unwrap_or_else(|_| get_default_columns(&first_account.did)),
+            );
         }
 
         // No accounts - return empty (will be handled by frontend)
@@ -352,22 +668,1715 @@ pub async fn get_columns(
 /// # Arguments
 /// * `app` - Tauri app handle
 /// * `columns` - List of column configurations to save
+/// * `storage` - Storage manager state
 ///
 /// # Returns
 /// Success or error message
 ///
 /// # Validation
 /// - At least one column is required (FR-008)
+/// - Every column DID must belong to a stored account
 /// - Timestamps are automatically updated
 #[tauri::command]
 pub async fn save_columns_command(
     app: AppHandle,
     columns: Vec<DeckColumnConfig>,
+    storage: State<'_, StorageManager>,
 ) -> Result<(), String> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let data_dir = app_data_dir(&app)?;
+
+    columns::save_account_columns(&storage, &data_dir, columns).await
+}
+
+/// Create a default timeline column for an account if no columns are stored yet
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `did` - Account DID for the default column (must belong to a stored account)
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// The stored columns, sorted by position
+#[tauri::command]
+pub async fn ensure_default_columns(
+    app: AppHandle,
+    did: String,
+    storage: State<'_, StorageManager>,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    columns::ensure_default_columns(&storage, &app_data_dir(&app)?, &did).await
+}
+
+/// Append a column to the deck
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `config` - New column (placed last; its DID must belong to a stored account)
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// The full updated column list
+#[tauri::command]
+pub async fn add_column(
+    app: AppHandle,
+    config: DeckColumnConfig,
+    storage: State<'_, StorageManager>,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    columns::add_column(&storage, &app_data_dir(&app)?, config).await
+}
+
+/// Remove a column from the deck (the last column cannot be removed)
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `id` - Column to remove
+///
+/// # Returns
+/// The full updated column list
+#[tauri::command]
+pub async fn remove_column(app: AppHandle, id: String) -> Result<Vec<DeckColumnConfig>, String> {
+    columns::remove_column(&app_data_dir(&app)?, &id)
+}
+
+/// Change the title, width or settings of a column
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `id` - Column to change
+/// * `patch` - Fields to change (omitted fields are left unchanged)
+///
+/// # Returns
+/// The full updated column list
+#[tauri::command]
+pub async fn update_column(
+    app: AppHandle,
+    id: String,
+    patch: ColumnPatch,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    columns::update_column(&app_data_dir(&app)?, &id, patch)
+}
+
+/// Put the deck columns in a new order (e.g., after a drag and drop)
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `ordered_ids` - Every column ID exactly once, in the new order
+///
+/// # Returns
+/// The full updated column list with positions 0..n
+#[tauri::command]
+pub async fn reorder_columns(
+    app: AppHandle,
+    ordered_ids: Vec<String>,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    columns::reorder_columns(&app_data_dir(&app)?, &ordered_ids)
+}
+
+/// Swap the positions of two columns (e.g., "move left/right one slot")
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `id_a` - First column
+/// * `id_b` - Second column (must belong to the same account)
+///
+/// # Returns
+/// The two updated columns
+#[tauri::command]
+pub async fn swap_columns(
+    app: AppHandle,
+    id_a: String,
+    id_b: String,
+) -> Result<(DeckColumnConfig, DeckColumnConfig), String> {
+    columns::swap_columns(&app_data_dir(&app)?, &id_a, &id_b)
+}
 
-    save_columns(&data_dir, columns)
+/// Set the auto-refresh interval of every column of an account
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `did` - Account whose columns are updated
+/// * `interval` - Refresh interval in seconds (0 = off, -1 = realtime)
+/// * `scroll_to_top` - Scroll to the top on refresh
+///
+/// # Returns
+/// Updated column IDs and columns skipped because their type cannot use the interval
+#[tauri::command]
+pub async fn set_all_columns_refresh(
+    app: AppHandle,
+    did: String,
+    interval: i64,
+    scroll_to_top: bool,
+) -> Result<BulkRefreshReport, String> {
+    columns::set_all_columns_refresh(&app_data_dir(&app)?, &did, interval, scroll_to_top)
+}
+
+/// Report which accounts already have configured deck columns
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Map of account DID to whether that account has at least one stored column
+#[tauri::command]
+pub async fn accounts_with_columns(
+    app: AppHandle,
+    storage: State<'_, StorageManager>,
+) -> Result<HashMap<String, bool>, String> {
+    let data_dir = app_data_dir(&app)?;
+
+    columns::accounts_with_columns(&storage, &data_dir).await
+}
+
+/// Convert a legacy flat columns file into the per-account layout
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Columns per account and orphaned columns (`migrated` is false if there was no
+/// legacy file)
+///
+/// # Note
+/// The legacy file is archived as `columns.legacy.json`; orphaned columns can be
+/// removed with `prune_orphaned_columns`.
+#[tauri::command]
+pub async fn migrate_legacy_columns(
+    app: AppHandle,
+    storage: State<'_, StorageManager>,
+) -> Result<ColumnsMigrationReport, String> {
+    let data_dir = app_data_dir(&app)?;
+
+    columns::migrate_legacy_columns(&storage, &data_dir).await
+}
+
+/// Remove columns whose account is no longer stored
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Number of removed columns
+#[tauri::command]
+pub async fn prune_orphaned_columns(
+    app: AppHandle,
+    storage: State<'_, StorageManager>,
+) -> Result<usize, String> {
+    let data_dir = app_data_dir(&app)?;
+
+    columns::prune_orphaned_columns(&storage, &data_dir).await
+}
+
+/// Export the deck columns as a shareable preset
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+///
+/// # Returns
+/// Versioned preset JSON
+#[tauri::command]
+pub async fn export_columns_preset(app: AppHandle) -> Result<String, String> {
+    presets::export_columns_preset(&app_data_dir(&app)?)
+}
+
+/// Validate a columns preset without applying it
+///
+/// # Arguments
+/// * `preset` - Preset JSON
+///
+/// # Returns
+/// Every issue found plus the canonicalized columns, so the UI can warn before import
+#[tauri::command]
+pub async fn validate_columns_preset(preset: String) -> Result<PresetValidation, String> {
+    Ok(presets::validate_columns_preset(&preset))
+}
+
+/// Replace the deck columns with a preset
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `preset` - Preset JSON produced by `export_columns_preset`
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// The applied columns (fails without saving if the preset has fatal issues or
+/// columns of accounts that are not logged in)
+#[tauri::command]
+pub async fn import_columns_preset(
+    app: AppHandle,
+    preset: String,
+    storage: State<'_, StorageManager>,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    presets::import_columns_preset(&storage, &app_data_dir(&app)?, &preset).await
+}
+
+/// Reset invalid values in the settings file to their defaults
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `storage` - Storage manager state (receives the repaired refresh skew)
+/// * `client_pool` - Shared client state (rebuilt with the repaired configuration)
+///
+/// # Returns
+/// Repairs made (the file is rewritten only when there were any)
+#[tauri::command]
+pub async fn repair_settings(
+    app: AppHandle,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<Vec<SettingsRepair>, String> {
+    let data_dir = app_data_dir(&app)?;
+    let repairs = settings::repair_settings(&data_dir)?;
+    apply_settings(&load_settings(&data_dir)?, &storage, &client_pool)?;
+    Ok(repairs)
+}
+
+/// Get application settings
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+///
+/// # Returns
+/// Stored settings, or defaults if none were saved yet
+#[tauri::command]
+pub async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
+    load_settings(&app_data_dir(&app)?)
+}
+
+/// Save application settings
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `settings` - Settings to save
+/// * `storage` - Storage manager state (receives the token refresh skew)
+/// * `client_pool` - Shared client state (rebuilt with the saved configuration)
+///
+/// # Validation
+/// - `default_server_url` must be a valid HTTPS server URL (it is stored normalized)
+#[tauri::command]
+pub async fn save_settings_command(
+    app: AppHandle,
+    settings: AppSettings,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<(), String> {
+    let data_dir = app_data_dir(&app)?;
+    save_settings(&data_dir, settings)?;
+    apply_settings(&load_settings(&data_dir)?, &storage, &client_pool)
+}
+
+/// Resend the email confirmation message for an account
+///
+/// # Arguments
+/// * `account_id` - Account whose email should be confirmed
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Note
+/// The access token is refreshed first if it has expired
+#[tauri::command]
+pub async fn request_email_confirmation(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<(), String> {
+    let (_, client, token) = authenticated_client(&storage, &client_pool, &account_id).await?;
+
+    client
+        .request_email_confirmation(&token.access_jwt)
+        .await
+        .map_err(|e| format!("Email confirmation request failed: {}", e))
+}
+
+/// Get an actor profile (cached briefly per account)
+///
+/// # Arguments
+/// * `account_id` - Account used to authenticate the request
+/// * `actor` - Handle or DID to look up (defaults to the account itself)
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `cache` - Request cache state
+#[tauri::command]
+pub async fn get_profile(
+    account_id: String,
+    actor: Option<String>,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    cache: State<'_, RequestCache>,
+) -> Result<ProfileView, String> {
+    let (account, client, token) =
+        authenticated_client(&storage, &client_pool, &account_id).await?;
+    let actor = actor.unwrap_or(account.did);
+
+    cache
+        .get_or_fetch(&account_id, "app.bsky.actor.getProfile", &actor, || {
+            client.get_profile(&token.access_jwt, &actor)
+        })
+        .await
+        .map_err(|e| format!("Failed to get profile: {}", e))
+}
+
+/// Get a locally cached copy of an avatar image
+///
+/// # Arguments
+/// * `url` - Avatar URL from a profile view
+/// * `avatars` - Avatar cache state
+///
+/// # Returns
+/// Cached file path (revalidated against the CDN once it is older than the TTL)
+#[tauri::command]
+pub async fn get_avatar(
+    url: String,
+    avatars: State<'_, AvatarCache>,
+) -> Result<CachedAvatar, String> {
+    avatars.get(&url).await
+}
+
+/// Get the account preferences (cached briefly per account)
+///
+/// # Arguments
+/// * `account_id` - Account whose preferences are fetched
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `cache` - Request cache state
+#[tauri::command]
+pub async fn get_preferences(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    cache: State<'_, RequestCache>,
+) -> Result<Vec<serde_json::Value>, String> {
+    let (_, client, token) = authenticated_client(&storage, &client_pool, &account_id).await?;
+
+    cache
+        .get_or_fetch(&account_id, "app.bsky.actor.getPreferences", "", || {
+            client.get_preferences(&token.access_jwt)
+        })
+        .await
+        .map_err(|e| format!("Failed to get preferences: {}", e))
+}
+
+/// Build a suggested first-run deck from the account's pinned feeds (not saved)
+///
+/// # Arguments
+/// * `account_id` - Account the deck is built for
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Returns
+/// Timeline, notifications and pinned feed columns (just a timeline if the
+/// preferences cannot be fetched); save with `save_columns_command` once confirmed
+#[tauri::command]
+pub async fn generate_starter_deck(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    let (account, client, token) =
+        authenticated_client(&storage, &client_pool, &account_id).await?;
+
+    Ok(columns::generate_starter_deck(&client, &token.access_jwt, &account.did).await)
+}
+
+/// Get the unread notification count (cached briefly per account)
+///
+/// # Arguments
+/// * `account_id` - Account whose notifications are counted
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `cache` - Request cache state
+#[tauri::command]
+pub async fn get_unread_count(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    cache: State<'_, RequestCache>,
+) -> Result<u32, String> {
+    let (_, client, token) = authenticated_client(&storage, &client_pool, &account_id).await?;
+
+    cache
+        .get_or_fetch(&account_id, "app.bsky.notification.getUnreadCount", "", || {
+            client.get_unread_count(&token.access_jwt)
+        })
+        .await
+        .map_err(|e| format!("Failed to get unread count: {}", e))
+}
+
+/// Get the unread direct message count (cached briefly per account)
+///
+/// # Arguments
+/// * `account_id` - Account whose conversations are counted
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `cache` - Request cache state
+///
+/// # Returns
+/// Sum of unread messages over all conversations (0 if the session lacks DM access)
+#[tauri::command]
+pub async fn get_dm_unread_count(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    cache: State<'_, RequestCache>,
+) -> Result<u32, String> {
+    let (_, client, token) = authenticated_client(&storage, &client_pool, &account_id).await?;
+
+    cache
+        .get_or_fetch(&account_id, "chat.bsky.convo.listConvos", "", || {
+            client.get_dm_unread_count(&token.access_jwt)
+        })
+        .await
+        .map_err(|e| format!("Failed to get DM unread count: {}", e))
+}
+
+/// Get the XRPC methods that recently failed for an account
+///
+/// # Arguments
+/// * `account_id` - Account to inspect
+/// * `cache` - Request cache state (records failures of cached fetches)
+///
+/// # Returns
+/// Per-method error count and most recent message, most recent first
+#[tauri::command]
+pub async fn get_account_method_errors(
+    account_id: String,
+    cache: State<'_, RequestCache>,
+) -> Result<Vec<MethodErrorSummary>, String> {
+    Ok(cache.method_errors(&account_id))
+}
+
+/// Cache key of the combined getBlocks/getMutes result
+const MODERATION_LISTS_KEY: &str = "app.bsky.graph.getBlocks+getMutes";
+
+/// Get the DIDs the account blocks and mutes (cached per account)
+///
+/// # Arguments
+/// * `account_id` - Account whose moderation lists are fetched
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `cache` - Request cache state
+///
+/// # Returns
+/// Blocked and muted DIDs (all pages)
+#[tauri::command]
+pub async fn get_moderation_lists(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    cache: State<'_, RequestCache>,
+) -> Result<ModerationLists, String> {
+    let (_, client, token) = authenticated_client(&storage, &client_pool, &account_id).await?;
+
+    cache
+        .get_or_fetch(&account_id, MODERATION_LISTS_KEY, "", || {
+            client.get_moderation_lists(&token.access_jwt)
+        })
+        .await
+        .map_err(|e| format!("Failed to get moderation lists: {}", e))
+}
+
+/// Repair the "exactly one active account" invariant
+///
+/// # Arguments
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Which accounts were activated or deactivated
+#[tauri::command]
+pub async fn ensure_active_invariant(
+    storage: State<'_, StorageManager>,
+) -> Result<ActiveInvariantReport, String> {
+    accounts::ensure_active_invariant(&storage)
+        .await
+        .map_err(|e| format!("Failed to repair active account: {}", e))
+}
+
+/// Export one account (metadata, token and columns) as an encrypted bundle
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `account_id` - Account to export
+/// * `password` - Password protecting the bundle
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Versioned, encrypted bundle JSON
+#[tauri::command]
+pub async fn export_account(
+    app: AppHandle,
+    account_id: String,
+    password: String,
+    storage: State<'_, StorageManager>,
+) -> Result<String, String> {
+    let password = Zeroizing::new(password);
+    account_bundle::export_account(&storage, &app_data_dir(&app)?, &account_id, &password).await
+}
+
+/// Export all accounts and their column layouts in a portable format
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `format` - "json" (`taurisky.portable/1` schema) or "opml"
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Export document (contains no tokens or emails)
+#[tauri::command]
+pub async fn export_portable(
+    app: AppHandle,
+    format: PortableFormat,
+    storage: State<'_, StorageManager>,
+) -> Result<String, String> {
+    portable::export_portable(&storage, &app_data_dir(&app)?, format).await
+}
+
+/// Export a backup of the settings, columns and (encrypted) account storage
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+///
+/// # Returns
+/// Versioned backup JSON with per-file checksums
+#[tauri::command]
+pub async fn export_backup(app: AppHandle) -> Result<String, String> {
+    backup::export_backup(&app_data_dir(&app)?)
+}
+
+/// Restore a backup produced by `export_backup`
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `data` - Backup JSON
+/// * `storage` - Storage manager state
+///
+/// # Note
+/// The backup is staged and verified before the data directory is touched; on
+/// failure the existing data is left intact. No save can run between the swap and
+/// the storage reload.
+#[tauri::command]
+pub async fn import_backup(
+    app: AppHandle,
+    data: String,
+    storage: State<'_, StorageManager>,
+) -> Result<(), String> {
+    let data_dir = app_data_dir(&app)?;
+
+    storage
+        .restore_files(move || {
+            backup::import_backup(&data_dir, &data).map_err(AuthError::StorageError)
+        })
+        .await
+        .map_err(|e| format!("Failed to restore backup: {}", e))
+}
+
+/// Finish an import that was staged and validated but never swapped into place
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `staging_id` - Staging set ID reported by the failed `import_backup`
+/// * `storage` - Storage manager state
+#[tauri::command]
+pub async fn resume_import(
+    app: AppHandle,
+    staging_id: String,
+    storage: State<'_, StorageManager>,
+) -> Result<(), String> {
+    let data_dir = app_data_dir(&app)?;
+
+    storage
+        .restore_files(move || {
+            backup::resume_import(&data_dir, &staging_id).map_err(AuthError::StorageError)
+        })
+        .await
+        .map_err(|e| format!("Failed to restore backup: {}", e))
+}
+
+/// Export the full, unredacted app state for bug reproduction
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `password` - Password protecting the snapshot
+/// * `consent` - Must be true: the user agreed to export secrets
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Encrypted snapshot JSON, labeled as containing secrets
+#[tauri::command]
+pub async fn export_debug_snapshot(
+    app: AppHandle,
+    password: String,
+    consent: bool,
+    storage: State<'_, StorageManager>,
+) -> Result<String, String> {
+    let password = Zeroizing::new(password);
+    debug_snapshot::export_debug_snapshot(&storage, &app_data_dir(&app)?, &password, consent)
+        .await
+}
+
+/// Check an account bundle password before importing it
+///
+/// # Arguments
+/// * `data` - Bundle JSON
+/// * `password` - Password to check
+///
+/// # Returns
+/// Whether the password opens the bundle
+#[tauri::command]
+pub async fn verify_bundle_password(data: String, password: String) -> Result<bool, String> {
+    let password = Zeroizing::new(password);
+    account_bundle::verify_bundle_password(&data, &password)
+}
+
+/// Generate a random diceware-style passphrase
+///
+/// # Arguments
+/// * `words` - Word count (clamped to 4..=12)
+///
+/// # Returns
+/// The passphrase with its entropy estimate
+#[tauri::command]
+pub async fn generate_passphrase(words: u32) -> Result<GeneratedPassphrase, String> {
+    Ok(passphrase::generate_passphrase(words))
+}
+
+/// Import an account bundle produced by `export_account`
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `data` - Bundle JSON
+/// * `password` - Bundle password
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Returns
+/// The imported (or updated, if the DID already existed) account
+#[tauri::command]
+pub async fn import_account(
+    app: AppHandle,
+    data: String,
+    password: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<Account, String> {
+    let password = Zeroizing::new(password);
+    account_bundle::import_account(&storage, &app_data_dir(&app)?, &data, &password, |account| {
+        client_pool.get_owned(&account.server_url)
+    })
+    .await
+}
+
+/// Delete all accounts, tokens and deck columns ("reset app")
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `confirmation` - Must be `RESET` (guards against accidental calls)
+/// * `storage` - Storage manager state
+/// * `cache` - Request cache state
+/// * `avatars` - Avatar cache state
+///
+/// # Returns
+/// Number of removed accounts
+#[tauri::command]
+pub async fn reset_all_data(
+    app: AppHandle,
+    confirmation: String,
+    storage: State<'_, StorageManager>,
+    cache: State<'_, RequestCache>,
+    avatars: State<'_, AvatarCache>,
+) -> Result<usize, String> {
+    let data_dir = app_data_dir(&app)?;
+    accounts::reset_all_data(&storage, &data_dir, &cache, &avatars, &confirmation).await
+}
+
+/// Remove an account together with its deck columns and cached data
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `account_id` - Account ID to remove
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `cache` - Request cache state
+/// * `avatars` - Avatar cache state
+///
+/// # Returns
+/// Report of what was removed
+///
+/// # Note
+/// Unlike `logout`, this also deletes the account's cached data and revokes the
+/// server-side session (best effort)
+#[tauri::command]
+pub async fn remove_account_fully(
+    app: AppHandle,
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    cache: State<'_, RequestCache>,
+    avatars: State<'_, AvatarCache>,
+) -> Result<AccountRemovalReport, String> {
+    let data_dir = app_data_dir(&app)?;
+
+    let account = storage
+        .get_account(&account_id)
+        .await
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+
+    let client = client_pool
+        .get(&account.server_url)
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+
+    accounts::remove_account_fully(&storage, &data_dir, &cache, &avatars, &client, &account_id)
+        .await
+}
+
+/// Check the schema version of every persisted file, migrating outdated ones
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `storage` - Storage manager state (reports and rewrites the encrypted store)
+///
+/// # Returns
+/// Per-file versions and whether a migration ran
+#[tauri::command]
+pub async fn check_schema_versions(
+    app: AppHandle,
+    storage: State<'_, StorageManager>,
+) -> Result<SchemaReport, String> {
+    schema::check_schema_versions(&app_data_dir(&app)?, &storage).await
+}
+
+/// Retire Stronghold vaults written with the legacy key derivation (runs once)
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+///
+/// # Returns
+/// Which legacy vault files were moved aside, or `alreadyMigrated`
+#[tauri::command]
+pub async fn migrate_stronghold_vault(
+    app: AppHandle,
+) -> Result<StrongholdMigrationReport, String> {
+    stronghold_migration::migrate_stronghold_vault(&app_data_dir(&app)?)
+}
+
+/// Check that the data directory is writable and report its free space
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+///
+/// # Returns
+/// Writability report (call before large imports or saves)
+#[tauri::command]
+pub async fn check_storage_writable(app: AppHandle) -> Result<StorageWritability, String> {
+    Ok(writability::check_storage_writable(&app_data_dir(&app)?))
+}
+
+/// Get write latency metrics of the encrypted storage file
+///
+/// # Arguments
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Total writes since start and min/max/avg of recent write durations
+#[tauri::command]
+pub async fn get_persistence_metrics(
+    storage: State<'_, StorageManager>,
+) -> Result<PersistenceMetrics, String> {
+    storage
+        .persistence_metrics()
+        .await
+        .map_err(|e| format!("Failed to get persistence metrics: {}", e))
+}
+
+/// Check that the current encryption key round-trips and decrypts the stored data
+///
+/// # Arguments
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Check result with the cipher in use; stored data is read but never modified
+#[tauri::command]
+pub async fn verify_encryption_roundtrip(
+    storage: State<'_, StorageManager>,
+) -> Result<EncryptionCheck, String> {
+    storage
+        .verify_encryption()
+        .await
+        .map_err(|e| format!("Failed to verify encryption: {}", e))
+}
+
+/// Audit the encryption settings of the store
+///
+/// # Arguments
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Cipher and KDF in use plus findings with suggested actions, most severe first
+#[tauri::command]
+pub async fn security_audit(storage: State<'_, StorageManager>) -> Result<SecurityAudit, String> {
+    storage
+        .security_audit()
+        .await
+        .map_err(|e| format!("Failed to audit storage security: {}", e))
+}
+
+/// Whether the store is protected by a passphrase that hasn't been entered yet
+///
+/// # Arguments
+/// * `storage` - Storage manager state
+#[tauri::command]
+pub async fn is_storage_locked(storage: State<'_, StorageManager>) -> Result<bool, String> {
+    Ok(storage.is_locked())
+}
+
+/// Why the accounts were recovered from the storage backup, if they were
+///
+/// # Arguments
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// The recovery message when the primary storage file was corrupt and the previous save
+/// was loaded instead (shown as a warning), or None
+#[tauri::command]
+pub async fn get_storage_recovery(
+    storage: State<'_, StorageManager>,
+) -> Result<Option<String>, String> {
+    storage
+        .recovery()
+        .map_err(|e| format!("Failed to get storage recovery: {}", e))
+}
+
+/// Unlock a passphrase-protected store (prompted at startup when `is_storage_locked`)
+///
+/// # Arguments
+/// * `password` - Storage passphrase
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Accounts of the unlocked store
+///
+/// # Note
+/// The passphrase is wiped from memory when the command returns; only the derived
+/// key is kept.
+#[tauri::command]
+pub async fn unlock(
+    password: String,
+    storage: State<'_, StorageManager>,
+) -> Result<Vec<Account>, String> {
+    let password = Zeroizing::new(password);
+    storage
+        .unlock(&password)
+        .await
+        .map_err(|e| format!("Failed to unlock storage: {}", e))?;
+    drop(password);
+
+    // The locked store skipped the startup repair
+    let _ = accounts::ensure_active_invariant(&storage).await;
+
+    storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))
+}
+
+/// Protect a store still using the built-in default password with a passphrase
+/// (re-encrypts the existing data; use `change_master_password` once one is set)
+///
+/// # Arguments
+/// * `password` - New storage passphrase (at least 8 characters)
+/// * `storage` - Storage manager state
+///
+/// # Note
+/// The passphrase is wiped from memory when the command returns.
+#[tauri::command]
+pub async fn set_storage_passphrase(
+    password: String,
+    storage: State<'_, StorageManager>,
+) -> Result<(), String> {
+    let password = Zeroizing::new(password);
+    storage
+        .set_passphrase(&password)
+        .await
+        .map_err(|e| format!("Failed to set storage passphrase: {}", e))
+}
+
+/// Change the storage passphrase, re-encrypting the store with a fresh salt
+///
+/// # Arguments
+/// * `old_password` - Current storage passphrase
+/// * `new_password` - New storage passphrase (at least 8 characters)
+/// * `storage` - Storage manager state
+///
+/// # Note
+/// Both passphrases are wiped from memory when the command returns.
+#[tauri::command]
+pub async fn change_master_password(
+    old_password: String,
+    new_password: String,
+    storage: State<'_, StorageManager>,
+) -> Result<(), String> {
+    let old_password = Zeroizing::new(old_password);
+    let new_password = Zeroizing::new(new_password);
+    storage
+        .change_password(&old_password, &new_password)
+        .await
+        .map_err(|e| format!("Failed to change storage passphrase: {}", e))
+}
+
+/// Export every account and token as a password-encrypted storage backup
+///
+/// # Arguments
+/// * `password` - Password protecting the backup
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Backup JSON (contains tokens; unlike `export_backup` it is independent of the
+/// storage passphrase)
+#[tauri::command]
+pub async fn export_storage_backup(
+    password: String,
+    storage: State<'_, StorageManager>,
+) -> Result<String, String> {
+    let password = Zeroizing::new(password);
+    let backup = storage
+        .export_backup(&password)
+        .map_err(|e| format!("Failed to export storage backup: {}", e))?;
+
+    String::from_utf8(backup).map_err(|e| format!("Failed to export storage backup: {}", e))
+}
+
+/// Check a storage backup password without importing the backup
+///
+/// # Arguments
+/// * `data` - Backup JSON
+/// * `password` - Password to check
+///
+/// # Returns
+/// Whether the password opens the backup
+#[tauri::command]
+pub async fn verify_storage_backup_password(
+    data: String,
+    password: String,
+) -> Result<bool, String> {
+    let password = Zeroizing::new(password);
+    Ok(storage_backup::verify_backup_password(data.as_bytes(), &password))
+}
+
+/// Import the accounts of a backup produced by `export_storage_backup`
+///
+/// # Arguments
+/// * `data` - Backup JSON
+/// * `password` - Backup password
+/// * `mode` - "merge" (keep existing accounts) or "replace"
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Imported and skipped account IDs
+#[tauri::command]
+pub async fn import_storage_backup(
+    data: String,
+    password: String,
+    mode: ImportMode,
+    storage: State<'_, StorageManager>,
+) -> Result<BackupImport, String> {
+    let password = Zeroizing::new(password);
+    let report = storage
+        .import_backup(data.as_bytes(), &password, mode)
+        .await
+        .map_err(|e| format!("Failed to import storage backup: {}", e))?;
+
+    // Merged accounts may bring a second active flag (or none)
+    let _ = accounts::ensure_active_invariant(&storage).await;
+
+    Ok(report)
+}
+
+/// Get how long each startup phase took
+///
+/// # Arguments
+/// * `timings` - Startup timings state (recorded during setup)
+///
+/// # Returns
+/// Phase durations in execution order and their total
+#[tauri::command]
+pub async fn get_startup_timings(
+    timings: State<'_, StartupTimings>,
+) -> Result<StartupTimings, String> {
+    Ok(timings.inner().clone())
+}
+
+/// Attribute stored bytes (columns, cached avatars, encrypted store) to each account
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Per-account usage, largest first
+#[tauri::command]
+pub async fn storage_by_account(
+    app: AppHandle,
+    storage: State<'_, StorageManager>,
+) -> Result<Vec<AccountStorageUsage>, String> {
+    usage::storage_by_account(&storage, &app_data_dir(&app)?).await
+}
+
+/// Report the disk usage of the columns file and column snapshots
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+///
+/// # Returns
+/// Per-account, snapshot and total sizes in bytes
+#[tauri::command]
+pub async fn columns_storage_report(app: AppHandle) -> Result<ColumnsStorageReport, String> {
+    columns_footprint::columns_storage_report(&app_data_dir(&app)?)
+}
+
+/// Delete old column snapshots, keeping the newest ones
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `keep` - Number of snapshots to keep
+///
+/// # Returns
+/// File names of the deleted snapshots
+#[tauri::command]
+pub async fn prune_column_snapshots(app: AppHandle, keep: usize) -> Result<Vec<String>, String> {
+    columns_footprint::prune_column_snapshots(&app_data_dir(&app)?, keep)
+}
+
+/// Remove orphaned temp files and column snapshots beyond the keep count
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `keep_snapshots` - Number of column snapshots to keep (default: 10)
+///
+/// # Returns
+/// Removed temp files and snapshots
+#[tauri::command]
+pub async fn cleanup_temp_files(
+    app: AppHandle,
+    keep_snapshots: Option<usize>,
+) -> Result<CleanupReport, String> {
+    maintenance::cleanup_temp_files(
+        &app_data_dir(&app)?,
+        keep_snapshots.unwrap_or(maintenance::DEFAULT_SNAPSHOT_KEEP),
+        maintenance::STALE_TEMP_AGE,
+    )
+}
+
+/// Get a home timeline page without items a column already shows
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `account_id` - Account whose timeline is fetched
+/// * `column_id` - Column being filled; its `pageSize` setting sets the page size
+///   (optional; the server default is used when omitted)
+/// * `known_cids` - CIDs of posts already displayed in the column
+/// * `cursor` - Pagination cursor (optional)
+/// * `algorithm` - Home timeline algorithm hint (optional)
+/// * `hide_moderated` - Drop posts by blocked/muted authors (default: false)
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `cache` - Request cache state
+/// * `refresh_state` - Refresh state (nothing is fetched while refreshes are paused)
+///
+/// # Returns
+/// Timeline page with already-known posts removed, or `paused`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_timeline_deduped(
+    app: AppHandle,
+    account_id: String,
+    column_id: Option<String>,
+    known_cids: Vec<String>,
+    cursor: Option<String>,
+    algorithm: Option<String>,
+    hide_moderated: Option<bool>,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    cache: State<'_, RequestCache>,
+    refresh_state: State<'_, RefreshState>,
+) -> Result<TimelineRefresh, String> {
+    refresh::refresh_timeline(&refresh_state, || async {
+        let (_, client, token) =
+            authenticated_client(&storage, &client_pool, &account_id).await?;
+        let limit = match column_id {
+            Some(column_id) => {
+                Some(columns::page_size_for_column(&app_data_dir(&app)?, &column_id)?)
+            }
+            None => None,
+        };
+
+        let page = client
+            .get_timeline(&token.access_jwt, cursor.as_deref(), algorithm.as_deref(), limit)
+            .await
+            .map_err(|e| format!("Failed to get timeline: {}", e))?;
+
+        let mut feed = merge_feed(known_cids, page.feed);
+        if hide_moderated.unwrap_or(false) {
+            let lists = cache
+                .get_or_fetch(&account_id, MODERATION_LISTS_KEY, "", || {
+                    client.get_moderation_lists(&token.access_jwt)
+                })
+                .await
+                .map_err(|e| format!("Failed to get moderation lists: {}", e))?;
+            feed = filters::hide_moderated_authors(feed, &lists);
+        }
+
+        Ok(FeedPage {
+            feed,
+            cursor: page.cursor,
+        })
+    })
+    .await
+}
+
+/// Pause or resume all automatic refresh activity
+///
+/// # Arguments
+/// * `paused` - `true` to pause, `false` to resume
+/// * `refresh_state` - Refresh state
+#[tauri::command]
+pub async fn set_refresh_paused(
+    paused: bool,
+    refresh_state: State<'_, RefreshState>,
+) -> Result<(), String> {
+    refresh_state.set_paused(paused);
+    Ok(())
+}
+
+/// Check whether automatic refresh activity is paused
+///
+/// # Arguments
+/// * `refresh_state` - Refresh state
+#[tauri::command]
+pub async fn is_refresh_paused(refresh_state: State<'_, RefreshState>) -> Result<bool, String> {
+    Ok(refresh_state.is_paused())
+}
+
+/// Refresh expired access tokens of all accounts
+///
+/// # Arguments
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+/// * `refresh_state` - Refresh state
+///
+/// # Returns
+/// `paused` status without doing anything while refreshes are paused,
+/// otherwise the refreshed, failed and deferred (backing off) accounts
+#[tauri::command]
+pub async fn refresh_all_sessions(
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+    refresh_state: State<'_, RefreshState>,
+) -> Result<RefreshOutcome, String> {
+    refresh::refresh_all_sessions(&refresh_state, &storage, |account| {
+        client_pool.get_owned(&account.server_url)
+    })
+    .await
+    .map_err(|e| format!("Failed to refresh sessions: {}", e))
+}
+
+/// Reset the refresh backoff of an account so it can be refreshed immediately
+///
+/// # Arguments
+/// * `account_id` - Account to reset
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// The updated account
+#[tauri::command]
+pub async fn clear_refresh_backoff(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+) -> Result<Account, String> {
+    refresh::clear_refresh_backoff(&storage, &account_id)
+        .await
+        .map_err(|e| format!("Failed to clear refresh backoff: {}", e))
+}
+
+/// Refresh the display name, avatar and handle of all accounts in one batch
+///
+/// # Arguments
+/// * `app` - Tauri app handle (emits "account-updated" per changed account)
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Returns
+/// Updated accounts and per-account failures
+#[tauri::command]
+pub async fn refresh_all_profiles(
+    app: AppHandle,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<ProfileRefreshReport, String> {
+    accounts::refresh_all_profiles(
+        &storage,
+        |account| client_pool.get_owned(&account.server_url),
+        |account| {
+            let _ = app.emit("account-updated", account);
+        },
+    )
+    .await
+    .map_err(|e| format!("Failed to refresh profiles: {}", e))
+}
+
+/// Set or clear the private note of an account
+///
+/// # Arguments
+/// * `account_id` - Account to annotate
+/// * `note` - Note text (max 500 characters; empty or None clears it)
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// The updated account
+#[tauri::command]
+pub async fn set_account_note(
+    account_id: String,
+    note: Option<String>,
+    storage: State<'_, StorageManager>,
+) -> Result<Account, String> {
+    accounts::set_account_note(&storage, &account_id, note)
+        .await
+        .map_err(|e| format!("Failed to set account note: {}", e))
+}
+
+/// Edit the display name, avatar or note of an account
+///
+/// # Arguments
+/// * `account_id` - Account to edit
+/// * `patch` - Fields to change (omitted fields are left unchanged)
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// The updated account
+#[tauri::command]
+pub async fn update_account(
+    account_id: String,
+    patch: AccountPatch,
+    storage: State<'_, StorageManager>,
+) -> Result<Account, String> {
+    accounts::update_account(&storage, &account_id, patch)
+        .await
+        .map_err(|e| format!("Failed to update account: {}", e))
+}
+
+/// Check that an account's stored access token was issued for that account
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `account_id` - Account to check
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Whether the token DID matches the account DID
+///
+/// # Note
+/// A mismatch also emits a `token-account-mismatch` event (payload: account ID) so the
+/// UI can warn even when the caller ignores the result.
+#[tauri::command]
+pub async fn verify_token_account_match(
+    app: AppHandle,
+    account_id: String,
+    storage: State<'_, StorageManager>,
+) -> Result<bool, String> {
+    let matches = accounts::verify_token_account_match(&storage, &account_id)
+        .await
+        .map_err(|e| format!("Failed to verify token: {}", e))?;
+
+    if !matches {
+        let _ = app.emit("token-account-mismatch", &account_id);
+    }
+
+    Ok(matches)
+}
+
+/// Check with the PDS that an account's session is still live
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `account_id` - Account to check
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Returns
+/// Whether the token is accepted, the account status and any handle change
+///
+/// # Note
+/// A changed handle is saved and emitted as an `account-updated` event.
+#[tauri::command]
+pub async fn verify_session(
+    app: AppHandle,
+    account_id: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<SessionVerification, String> {
+    let account = storage
+        .get_account(&account_id)
+        .await
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+
+    let client = client_pool
+        .get(&account.server_url)
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+
+    let verification = accounts::verify_session(&storage, &client, &account)
+        .await
+        .map_err(|e| format!("Failed to verify session: {}", e))?;
+
+    if verification.previous_handle.is_some() {
+        let _ = app.emit("account-updated", &verification.account);
+    }
+
+    Ok(verification)
+}
+
+/// Export application settings as JSON
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+///
+/// # Returns
+/// Versioned settings JSON (contains no accounts, tokens or columns)
+#[tauri::command]
+pub async fn export_settings(app: AppHandle) -> Result<String, String> {
+    settings::export_settings(&app_data_dir(&app)?)
+}
+
+/// Import application settings from JSON produced by `export_settings`
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `json` - Settings JSON
+/// * `storage` - Storage manager state (receives the imported refresh skew)
+/// * `client_pool` - Shared client state (rebuilt with the imported configuration)
+///
+/// # Returns
+/// The imported settings
+#[tauri::command]
+pub async fn import_settings(
+    app: AppHandle,
+    json: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<AppSettings, String> {
+    let imported = settings::import_settings(&app_data_dir(&app)?, &json)?;
+    apply_settings(&imported, &storage, &client_pool)?;
+    Ok(imported)
+}
+
+/// Subscribe to realtime repository events of an account
+///
+/// # Arguments
+/// * `app` - Tauri app handle (events are emitted as `realtime-event`)
+/// * `account_id` - Account whose events are streamed
+/// * `collections` - Collections to subscribe to (e.g., "app.bsky.feed.post")
+/// * `storage` - Storage manager state
+/// * `registry` - Subscription registry state
+/// * `refresh_state` - Refresh state (the connection is dropped while refreshes are paused)
+///
+/// # Returns
+/// Subscription ID
+#[tauri::command]
+pub async fn subscribe_realtime(
+    app: AppHandle,
+    account_id: String,
+    collections: Vec<String>,
+    storage: State<'_, StorageManager>,
+    registry: State<'_, SubscriptionRegistry>,
+    refresh_state: State<'_, RefreshState>,
+) -> Result<String, String> {
+    let account = storage
+        .get_account(&account_id)
+        .await
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+
+    let paused = refresh_state.watch_paused();
+    registry.subscribe(DEFAULT_JETSTREAM_URL, &account.did, collections, paused, move |event| {
+        let _ = app.emit("realtime-event", event);
+    })
+}
+
+/// Stop a realtime subscription
+///
+/// # Arguments
+/// * `subscription_id` - ID returned by `subscribe_realtime`
+/// * `registry` - Subscription registry state
+#[tauri::command]
+pub async fn unsubscribe_realtime(
+    subscription_id: String,
+    registry: State<'_, SubscriptionRegistry>,
+) -> Result<(), String> {
+    registry.unsubscribe(&subscription_id)
+}
+
+/// List active realtime subscriptions and their health
+///
+/// # Arguments
+/// * `registry` - Subscription registry state
+#[tauri::command]
+pub async fn list_subscriptions(
+    registry: State<'_, SubscriptionRegistry>,
+) -> Result<Vec<SubscriptionInfo>, String> {
+    Ok(registry.list())
+}
+
+/// Describe what the current session of an account is allowed to do
+///
+/// # Arguments
+/// * `account_id` - Account whose session is inspected
+/// * `storage` - Storage manager state
+///
+/// # Returns
+/// Scopes decoded from the access token (unverified, for display only)
+#[tauri::command]
+pub async fn get_session_scopes(
+    account_id: String,
+    storage: State<'_, StorageManager>,
+) -> Result<SessionScopes, String> {
+    let token = storage
+        .get_auth_token(&account_id)
+        .await
+        .map_err(|e| format!("Failed to get token: {}", e))?;
+
+    SessionScopes::from_access_jwt(&token.access_jwt)
+        .map_err(|e| format!("Failed to decode session scopes: {}", e))
+}
+
+/// Fail-fast client for latency-sensitive calls
+fn no_retry_client(
+    app: &AppHandle,
+    client_config: &ClientConfig,
+    server_url: Option<String>,
+) -> Result<ATProtocolClient, String> {
+    let server_url = resolve_login_server_url(app, server_url, client_config)?;
+
+    ATProtocolClient::with_config(Some(server_url), client_config.no_retry())
+        .map_err(|e| format!("Failed to create client: {}", e))
+}
+
+/// Describe a PDS server (e.g., to validate a custom server URL before login)
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `server_url` - Server to describe
+/// * `client_pool` - Shared client state (supplies the current configuration)
+///
+/// # Returns
+/// Handle domains and whether sign-up needs an invite code (fails if the URL is not
+/// an AT Protocol PDS)
+///
+/// # Note
+/// Uses a single attempt without retries so the UI gets an answer quickly
+#[tauri::command]
+pub async fn describe_server(
+    app: AppHandle,
+    server_url: String,
+    client_pool: State<'_, ClientPool>,
+) -> Result<ServerDescription, String> {
+    let client = no_retry_client(&app, &client_pool.config(), Some(server_url))?;
+
+    client
+        .describe_server()
+        .await
+        .map_err(|e| format!("Failed to describe server: {}", e))
+}
+
+/// Check whether a PDS server is reachable
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `server_url` - Server to ping (defaults to the configured default server)
+/// * `client_pool` - Shared client state (supplies the current configuration)
+///
+/// # Returns
+/// Round-trip time in milliseconds
+///
+/// # Note
+/// Uses a single attempt without retries so the UI gets an answer quickly
+#[tauri::command]
+pub async fn ping_server(
+    app: AppHandle,
+    server_url: Option<String>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<u64, String> {
+    let client = no_retry_client(&app, &client_pool.config(), server_url)?;
+
+    let started = std::time::Instant::now();
+    client
+        .with_retry(|| client.ping())
+        .await
+        .map_err(|e| format!("Ping failed: {}", e))?;
+
+    Ok(started.elapsed().as_millis() as u64)
+}
+
+/// Compare the device clock with a PDS server's clock
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `server_url` - Server to compare with (defaults to the configured default server)
+/// * `client_pool` - Shared client state (supplies the current configuration)
+///
+/// # Returns
+/// Measured offset and whether it exceeds 5 minutes (UI should warn "your clock is wrong")
+#[tauri::command]
+pub async fn check_clock_skew(
+    app: AppHandle,
+    server_url: Option<String>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<ClockSkewReport, String> {
+    let client = no_retry_client(&app, &client_pool.config(), server_url)?;
+
+    clock::check_clock_skew(&client, CLOCK_SKEW_THRESHOLD_SECS)
+        .await
+        .map_err(|e| format!("Failed to check clock skew: {}", e))
+}
+
+/// Resolve a handle to its DID (e.g., to validate it before the password is entered)
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `handle` - Handle to resolve
+/// * `server_url` - Server to ask (defaults to the configured default server)
+/// * `client_pool` - Shared client state (supplies the current configuration)
+///
+/// # Returns
+/// The DID the handle points to (fails with "Account not found" for unknown handles)
+///
+/// # Note
+/// Uses a single attempt without retries so the UI gets an answer quickly
+#[tauri::command]
+pub async fn resolve_handle(
+    app: AppHandle,
+    handle: String,
+    server_url: Option<String>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<String, String> {
+    let client = no_retry_client(&app, &client_pool.config(), server_url)?;
+
+    client
+        .resolve_handle(&handles::normalize_handle(&handle))
+        .await
+        .map_err(|e| format!("Failed to resolve handle: {}", e))
+}
+
+/// Check whether a handle is free on a PDS server (e.g., for typeahead)
+///
+/// # Arguments
+/// * `app` - Tauri app handle
+/// * `handle` - Handle to check
+/// * `server_url` - Server to ask (defaults to the configured default server)
+/// * `client_pool` - Shared client state (supplies the current configuration)
+///
+/// # Returns
+/// `true` if the handle does not resolve to any account
+///
+/// # Note
+/// Uses a single attempt without retries so the UI gets an answer quickly
+#[tauri::command]
+pub async fn check_handle_availability(
+    app: AppHandle,
+    handle: String,
+    server_url: Option<String>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<bool, String> {
+    let client = no_retry_client(&app, &client_pool.config(), server_url)?;
+    let handle = handles::normalize_handle(&handle);
+
+    match client.with_retry(|| client.resolve_handle(&handle)).await {
+        Ok(_) => Ok(false),
+        Err(AuthError::AccountNotFound(_)) => Ok(true),
+        Err(e) => Err(format!("Failed to check handle: {}", e)),
+    }
+}
+
+/// Check whether an account can switch to a new handle
+///
+/// # Arguments
+/// * `account_id` - Account whose handle would change
+/// * `new_handle` - Requested handle
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state (supplies the current configuration)
+///
+/// # Returns
+/// Normalized handle, domain and availability checks, and the reason if invalid
+#[tauri::command]
+pub async fn validate_new_handle(
+    account_id: String,
+    new_handle: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<HandleValidation, String> {
+    let account = storage
+        .get_account(&account_id)
+        .await
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+
+    let client = ATProtocolClient::with_config(
+        Some(account.server_url.clone()),
+        client_pool.config().no_retry(),
+    )
+    .map_err(|e| format!("Failed to create client: {}", e))?;
+
+    handles::validate_new_handle(&client, &account, &new_handle)
+        .await
+        .map_err(|e| format!("Failed to validate handle: {}", e))
+}
+
+/// Change the handle of an account (validated first) and update the stored account
+///
+/// # Arguments
+/// * `account_id` - Account to update
+/// * `new_handle` - Requested handle
+/// * `storage` - Storage manager state
+/// * `client_pool` - Shared client state
+///
+/// # Returns
+/// The updated account
+#[tauri::command]
+pub async fn update_handle(
+    account_id: String,
+    new_handle: String,
+    storage: State<'_, StorageManager>,
+    client_pool: State<'_, ClientPool>,
+) -> Result<Account, String> {
+    let (_, client, token) = authenticated_client(&storage, &client_pool, &account_id).await?;
+
+    handles::update_handle(&storage, &client, &token.access_jwt, &account_id, &new_handle)
+        .await
+        .map_err(|e| format!("Failed to update handle: {}", e))
+}
+
+/// Show the client configuration in effect (settings merged with environment overrides)
+///
+/// # Arguments
+/// * `client_pool` - Shared client state (supplies the current configuration)
+///
+/// # Returns
+/// Timeout, retries, user agent and proxy host (credentials are never returned)
+///
+/// # Note
+/// The configuration is resolved at startup; saved settings apply after a restart
+#[tauri::command]
+pub async fn get_effective_client_config(
+    client_pool: State<'_, ClientPool>,
+) -> Result<EffectiveClientConfig, String> {
+    Ok(client_pool.config().effective())
+}
+
+/// Inspect the TLS certificate of a PDS server for the trust screen
+///
+/// # Arguments
+/// * `server_url` - PDS server URL
+///
+/// # Returns
+/// Leaf certificate subject, issuer, validity and SHA-256 fingerprint (nothing is stored)
+#[tauri::command]
+pub async fn inspect_tls(server_url: String) -> Result<TlsInfo, String> {
+    tls::inspect_tls(&server_url)
+        .await
+        .map_err(|e| format!("Failed to inspect certificate: {}", e))
 }
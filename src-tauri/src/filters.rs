@@ -0,0 +1,68 @@
+/**
+ * Column content filters
+ *
+ * Pure helpers deciding which feed items a column hides
+ */
+
+use crate::api::moderation::ModerationLists;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Whether content by an author should be hidden because the user blocks or mutes them
+///
+/// # Arguments
+/// * `did` - Author DID
+/// * `blocks` - DIDs the user blocks
+/// * `mutes` - DIDs the user mutes
+pub fn should_hide_author(did: &str, blocks: &HashSet<String>, mutes: &HashSet<String>) -> bool {
+    blocks.contains(did) || mutes.contains(did)
+}
+
+/// Drop feed items whose post author is blocked or muted
+///
+/// Items without an author DID are kept.
+pub fn hide_moderated_authors(feed: Vec<Value>, lists: &ModerationLists) -> Vec<Value> {
+    feed.into_iter()
+        .filter(|item| {
+            let author = item
+                .get("post")
+                .and_then(|post| post.get("author"))
+                .and_then(|author| author.get("did"))
+                .and_then(Value::as_str);
+
+            !author.is_some_and(|did| should_hide_author(did, &lists.blocks, &lists.mutes))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_hide_author() {
+        let blocks = HashSet::from(["did:plc:blocked".to_string()]);
+        let mutes = HashSet::from(["did:plc:muted".to_string()]);
+
+        assert!(should_hide_author("did:plc:blocked", &blocks, &mutes));
+        assert!(should_hide_author("did:plc:muted", &blocks, &mutes));
+        assert!(!should_hide_author("did:plc:friend", &blocks, &mutes));
+        assert!(!should_hide_author("did:plc:friend", &HashSet::new(), &HashSet::new()));
+    }
+
+    #[test]
+    fn test_hide_moderated_authors() {
+        let lists = ModerationLists {
+            blocks: HashSet::from(["did:plc:blocked".to_string()]),
+            mutes: HashSet::new(),
+        };
+        let item = |did: &str| serde_json::json!({ "post": { "author": { "did": did } } });
+
+        let feed = hide_moderated_authors(
+            vec![item("did:plc:blocked"), item("did:plc:friend"), serde_json::json!({})],
+            &lists,
+        );
+
+        assert_eq!(feed, vec![item("did:plc:friend"), serde_json::json!({})]);
+    }
+}
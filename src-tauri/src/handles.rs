@@ -0,0 +1,270 @@
+/**
+ * Handle changes
+ *
+ * Validates a new handle against the PDS before com.atproto.identity.updateHandle
+ * is called, so the user gets a precise reason instead of a server error
+ */
+
+use crate::auth::ATProtocolClient;
+use crate::storage::StorageManager;
+use crate::types::{Account, AuthError};
+use serde::Serialize;
+
+/// Maximum handle length (DNS name limit)
+const MAX_HANDLE_LEN: usize = 253;
+
+/// Outcome of `validate_new_handle`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandleValidation {
+    /// Normalized handle (lowercase, without "@")
+    pub handle: String,
+    /// Whether the handle can be applied
+    pub valid: bool,
+    /// Whether the handle ends with one of the server's user domains
+    pub domain_allowed: bool,
+    /// Whether no account currently uses the handle (None if not checked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available: Option<bool>,
+    /// Why the handle cannot be used (None when valid)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Lowercase a handle and strip surrounding whitespace and a leading "@"
+pub fn normalize_handle(handle: &str) -> String {
+    handle.trim().trim_start_matches('@').to_lowercase()
+}
+
+/// Check the handle syntax (DNS labels of letters, digits and inner hyphens)
+fn syntax_error(handle: &str) -> Option<String> {
+    if handle.len() > MAX_HANDLE_LEN {
+        return Some(format!("Handle is longer than {} characters", MAX_HANDLE_LEN));
+    }
+
+    let labels: Vec<&str> = handle.split('.').collect();
+    if labels.len() < 2 {
+        return Some("Handle must contain a domain (e.g., name.bsky.social)".to_string());
+    }
+
+    let label_ok = |label: &&str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if !labels.iter().all(label_ok) {
+        return Some("Handle contains an invalid segment".to_string());
+    }
+
+    None
+}
+
+/// Check that a new handle is well-formed, on an allowed domain and not taken
+///
+/// # Arguments
+/// * `client` - Client for the account's PDS
+/// * `account` - Account whose handle would change
+/// * `new_handle` - Requested handle (normalized before checking)
+pub async fn validate_new_handle(
+    client: &ATProtocolClient,
+    account: &Account,
+    new_handle: &str,
+) -> Result<HandleValidation, AuthError> {
+    let handle = normalize_handle(new_handle);
+    let mut validation = HandleValidation {
+        handle: handle.clone(),
+        valid: false,
+        domain_allowed: false,
+        available: None,
+        reason: None,
+    };
+
+    if let Some(reason) = syntax_error(&handle) {
+        validation.reason = Some(reason);
+        return Ok(validation);
+    }
+
+    if handle == account.handle.to_lowercase() {
+        validation.reason = Some("This is already the account's handle".to_string());
+        return Ok(validation);
+    }
+
+    let domains = client.describe_server_domains().await?;
+    validation.domain_allowed = domains.iter().any(|domain| {
+        let domain = domain.trim_start_matches('.').to_lowercase();
+        handle
+            .strip_suffix(&domain)
+            .is_some_and(|name| name.ends_with('.') && name.len() > 1)
+    });
+    if !validation.domain_allowed {
+        validation.reason = Some(format!(
+            "Handle must end with one of: {}",
+            domains.join(", ")
+        ));
+        return Ok(validation);
+    }
+
+    let available = match client.resolve_handle(&handle).await {
+        Ok(_) => false,
+        Err(AuthError::AccountNotFound(_)) => true,
+        Err(e) => return Err(e),
+    };
+    validation.available = Some(available);
+
+    if available {
+        validation.valid = true;
+    } else {
+        validation.reason = Some("Handle is already taken".to_string());
+    }
+
+    Ok(validation)
+}
+
+/// Validate and apply a handle change, then update the stored account
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `client` - Client for the account's PDS
+/// * `access_jwt` - Access token of the account
+/// * `account_id` - Account to update
+/// * `new_handle` - Requested handle
+///
+/// # Returns
+/// The updated account (`InvalidInput` with the reason if validation fails)
+pub async fn update_handle(
+    storage: &StorageManager,
+    client: &ATProtocolClient,
+    access_jwt: &str,
+    account_id: &str,
+    new_handle: &str,
+) -> Result<Account, AuthError> {
+    let account = storage.get_account(account_id).await?;
+
+    let validation = validate_new_handle(client, &account, new_handle).await?;
+    if !validation.valid {
+        return Err(AuthError::InvalidInput(
+            validation.reason.unwrap_or_else(|| "Invalid handle".to_string()),
+        ));
+    }
+
+    client.update_handle(access_jwt, &validation.handle).await?;
+
+    // Only the handle is written: the account may have changed during the network calls
+    storage
+        .modify_account(account_id, |account| {
+            let changed = account.handle != validation.handle;
+            account.handle = validation.handle;
+            changed
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use mockito::{Matcher, Server, ServerGuard};
+    use tempfile::TempDir;
+
+    fn alice() -> Account {
+        test_support::account("alice")
+    }
+
+    async fn pds_with_domains() -> ServerGuard {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/xrpc/com.atproto.server.describeServer")
+            .with_status(200)
+            .with_body(r#"{"did":"did:web:bsky.social","availableUserDomains":[".bsky.social"]}"#)
+            .create_async()
+            .await;
+        server
+    }
+
+    async fn mock_resolve(server: &mut ServerGuard, handle: &str, taken: bool) {
+        let mock = server
+            .mock("GET", "/xrpc/com.atproto.identity.resolveHandle")
+            .match_query(Matcher::UrlEncoded("handle".into(), handle.into()));
+        let mock = if taken {
+            mock.with_status(200).with_body(r#"{"did":"did:plc:someone"}"#)
+        } else {
+            mock.with_status(400)
+                .with_body(r#"{"error":"InvalidRequest","message":"Unable to resolve handle"}"#)
+        };
+        mock.create_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_valid_handle_is_applied() {
+        let mut server = pds_with_domains().await;
+        mock_resolve(&mut server, "alice2.bsky.social", false).await;
+        let update = server
+            .mock("POST", "/xrpc/com.atproto.identity.updateHandle")
+            .match_header("authorization", "Bearer token")
+            .match_body(Matcher::Json(serde_json::json!({ "handle": "alice2.bsky.social" })))
+            .with_status(200)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let validation = validate_new_handle(&client, &alice(), " @Alice2.bsky.social ")
+            .await
+            .unwrap();
+        assert!(validation.valid);
+        assert_eq!(validation.handle, "alice2.bsky.social");
+        assert_eq!(validation.available, Some(true));
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        storage.save_account(&alice()).await.unwrap();
+
+        let account = update_handle(&storage, &client, "token", "alice", "alice2.bsky.social")
+            .await
+            .unwrap();
+
+        update.assert_async().await;
+        assert_eq!(account.handle, "alice2.bsky.social");
+        assert_eq!(storage.get_account("alice").await.unwrap().handle, "alice2.bsky.social");
+    }
+
+    #[tokio::test]
+    async fn test_taken_handle_is_rejected() {
+        let mut server = pds_with_domains().await;
+        mock_resolve(&mut server, "bob.bsky.social", true).await;
+        let update = server
+            .mock("POST", "/xrpc/com.atproto.identity.updateHandle")
+            .expect(0)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let validation = validate_new_handle(&client, &alice(), "bob.bsky.social").await.unwrap();
+        assert!(!validation.valid);
+        assert!(validation.domain_allowed);
+        assert_eq!(validation.available, Some(false));
+
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        storage.save_account(&alice()).await.unwrap();
+        let result = update_handle(&storage, &client, "token", "alice", "bob.bsky.social").await;
+
+        assert!(matches!(result, Err(AuthError::InvalidInput(_))));
+        update.assert_async().await;
+        assert_eq!(storage.get_account("alice").await.unwrap().handle, "alice.bsky.social");
+    }
+
+    #[tokio::test]
+    async fn test_wrong_domain_is_rejected() {
+        let server = pds_with_domains().await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        for handle in ["alice.example.com", "alicebsky.social", "-x.bsky.social", "alice"] {
+            let validation = validate_new_handle(&client, &alice(), handle).await.unwrap();
+            assert!(!validation.valid, "{} should be rejected", handle);
+            assert!(!validation.domain_allowed);
+            assert!(validation.reason.is_some());
+        }
+    }
+}
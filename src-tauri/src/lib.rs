@@ -1,10 +1,27 @@
 // Module declarations
 mod types;
 mod auth;
+mod api;
+mod accounts;
+mod filters;
+mod handles;
+mod realtime;
+mod refresh;
+mod startup;
 mod storage;
 mod commands;
+#[cfg(test)]
+mod test_support;
 
-use storage::StorageManager;
+use api::avatars::{AvatarCache, DEFAULT_AVATAR_TTL};
+use api::cache::{RequestCache, DEFAULT_CACHE_TTL};
+use auth::oauth::OAuthFlows;
+use auth::pool::ClientPool;
+use auth::ClientConfig;
+use realtime::SubscriptionRegistry;
+use refresh::RefreshState;
+use startup::StartupTimings;
+use std::time::Duration;
 use tauri::Manager;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -17,17 +34,6 @@ fn greet(name: &str) -> String {
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .plugin(
-            tauri_plugin_stronghold::Builder::new(|password: &str| {
-                // Simple password hash function for Stronghold
-                // In production, this should use a more secure method
-                use std::hash::{Hash, Hasher};
-                let mut hasher = std::collections::hash_map::DefaultHasher::new();
-                password.hash(&mut hasher);
-                hasher.finish().to_le_bytes().to_vec()
-            })
-            .build(),
-        )
         .setup(|app| {
             // Get app data directory
             let data_dir = app
@@ -35,25 +41,141 @@ pub fn run() {
                 .app_data_dir()
                 .expect("Failed to get app data directory");
 
-            // Create storage manager with data directory
-            let storage = StorageManager::new(data_dir)
+            let mut timings = StartupTimings::default();
+
+            // Retire vaults keyed with the old DefaultHasher scheme, then register
+            // Stronghold with Argon2 and a per-install salt
+            timings.time("stronghold_migration", || {
+                let _ = storage::stronghold_migration::migrate_stronghold_vault(&data_dir);
+            });
+            let salt_path = data_dir.join(storage::stronghold_migration::STRONGHOLD_SALT_FILE);
+            timings.time("stronghold_plugin", || {
+                app.handle()
+                    .plugin(tauri_plugin_stronghold::Builder::with_argon2(&salt_path).build())
+            })?;
+
+            // Migrations, settings, storage (key derivation) and the active account
+            let (settings, storage) = startup::load_state(&data_dir, &mut timings)
                 .expect("Failed to initialize storage manager");
 
             app.manage(storage);
+            app.manage(timings);
+
+            // Short-lived cache for read-only XRPC responses
+            let cache_ttl = settings
+                .request_cache_ttl_secs
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_CACHE_TTL);
+            app.manage(RequestCache::new(cache_ttl));
+            app.manage(AvatarCache::new(&data_dir, DEFAULT_AVATAR_TTL));
+
+            // HTTP client behaviour; environment variables override the settings
+            let client_config = ClientConfig::resolve(&settings, |key| std::env::var(key).ok());
+            app.manage(ClientPool::new(client_config));
+            app.manage(RefreshState::default());
+            app.manage(SubscriptionRegistry::default());
+            app.manage(OAuthFlows::default());
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             greet,
             commands::login,
+            commands::oauth_start,
+            commands::oauth_complete,
             commands::logout,
             commands::refresh_session,
+            commands::selftest_refresh,
             commands::restore_sessions,
+            commands::startup_sequence,
             commands::add_account,
             commands::remove_account,
             commands::list_accounts,
+            commands::switch_active_account,
+            commands::get_access_token,
             commands::get_columns,
             commands::save_columns_command,
+            commands::ensure_default_columns,
+            commands::add_column,
+            commands::remove_column,
+            commands::update_column,
+            commands::reorder_columns,
+            commands::swap_columns,
+            commands::set_all_columns_refresh,
+            commands::accounts_with_columns,
+            commands::migrate_legacy_columns,
+            commands::prune_orphaned_columns,
+            commands::export_columns_preset,
+            commands::validate_columns_preset,
+            commands::import_columns_preset,
+            commands::get_settings,
+            commands::repair_settings,
+            commands::save_settings_command,
+            commands::request_email_confirmation,
+            commands::get_profile,
+            commands::get_avatar,
+            commands::get_preferences,
+            commands::get_unread_count,
+            commands::generate_starter_deck,
+            commands::get_dm_unread_count,
+            commands::get_moderation_lists,
+            commands::get_account_method_errors,
+            commands::remove_account_fully,
+            commands::reset_all_data,
+            commands::ensure_active_invariant,
+            commands::export_account,
+            commands::export_debug_snapshot,
+            commands::export_backup,
+            commands::export_portable,
+            commands::import_backup,
+            commands::resume_import,
+            commands::import_account,
+            commands::verify_bundle_password,
+            commands::generate_passphrase,
+            commands::check_schema_versions,
+            commands::check_storage_writable,
+            commands::get_persistence_metrics,
+            commands::verify_encryption_roundtrip,
+            commands::security_audit,
+            commands::is_storage_locked,
+            commands::get_storage_recovery,
+            commands::unlock,
+            commands::set_storage_passphrase,
+            commands::change_master_password,
+            commands::export_storage_backup,
+            commands::verify_storage_backup_password,
+            commands::import_storage_backup,
+            commands::get_startup_timings,
+            commands::columns_storage_report,
+            commands::storage_by_account,
+            commands::prune_column_snapshots,
+            commands::cleanup_temp_files,
+            commands::migrate_stronghold_vault,
+            commands::get_timeline_deduped,
+            commands::set_refresh_paused,
+            commands::is_refresh_paused,
+            commands::refresh_all_sessions,
+            commands::clear_refresh_backoff,
+            commands::refresh_all_profiles,
+            commands::set_account_note,
+            commands::update_account,
+            commands::verify_token_account_match,
+            commands::verify_session,
+            commands::export_settings,
+            commands::import_settings,
+            commands::subscribe_realtime,
+            commands::unsubscribe_realtime,
+            commands::list_subscriptions,
+            commands::get_session_scopes,
+            commands::describe_server,
+            commands::ping_server,
+            commands::check_clock_skew,
+            commands::resolve_handle,
+            commands::check_handle_availability,
+            commands::validate_new_handle,
+            commands::update_handle,
+            commands::get_effective_client_config,
+            commands::inspect_tls,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
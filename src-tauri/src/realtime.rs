@@ -0,0 +1,406 @@
+/**
+ * Realtime subscriptions
+ *
+ * Streams repository events from a Jetstream endpoint over WebSocket and keeps a
+ * registry of active subscriptions and their health
+ */
+
+use futures_util::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// Default public Jetstream endpoint
+pub const DEFAULT_JETSTREAM_URL: &str = "wss://jetstream2.us-east.bsky.network";
+
+/// Maximum delay between reconnection attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Connection state of a subscription
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionState {
+    /// Connected and receiving events
+    Connected,
+    /// Connecting or waiting to reconnect after a failure
+    Reconnecting,
+    /// Disconnected while automatic refreshes are paused
+    Paused,
+    /// Unsubscribed
+    Closed,
+}
+
+/// Health snapshot of a subscription (returned to the frontend)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionInfo {
+    /// Subscription ID
+    pub id: String,
+    /// Account DID whose events are streamed
+    pub did: String,
+    /// Subscribed collections (e.g., "app.bsky.feed.post")
+    pub collections: Vec<String>,
+    /// Current connection state
+    pub state: ConnectionState,
+    /// Number of events received since subscribing
+    pub events_received: u64,
+    /// Timestamp of the last received event (ISO 8601)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_event_at: Option<String>,
+}
+
+/// Live state of a subscription shared with its connection task
+struct Subscription {
+    id: String,
+    did: String,
+    collections: Vec<String>,
+    state: Mutex<ConnectionState>,
+    events_received: AtomicU64,
+    last_event_at: Mutex<Option<String>>,
+    /// Signals the connection task to stop
+    shutdown: Notify,
+}
+
+impl Subscription {
+    fn set_state(&self, state: ConnectionState) {
+        if let Ok(mut current) = self.state.lock() {
+            *current = state;
+        }
+    }
+
+    fn record_event(&self) {
+        self.events_received.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut last) = self.last_event_at.lock() {
+            *last = Some(chrono::Utc::now().to_rfc3339());
+        }
+    }
+
+    fn info(&self) -> SubscriptionInfo {
+        SubscriptionInfo {
+            id: self.id.clone(),
+            did: self.did.clone(),
+            collections: self.collections.clone(),
+            state: self
+                .state
+                .lock()
+                .map(|s| *s)
+                .unwrap_or(ConnectionState::Closed),
+            events_received: self.events_received.load(Ordering::SeqCst),
+            last_event_at: self.last_event_at.lock().ok().and_then(|l| l.clone()),
+        }
+    }
+}
+
+/// Registry of active subscriptions (managed by Tauri)
+#[derive(Default)]
+pub struct SubscriptionRegistry {
+    subscriptions: Mutex<HashMap<String, Arc<Subscription>>>,
+}
+
+impl SubscriptionRegistry {
+    /// Start streaming events for an account
+    ///
+    /// The connection runs in a background task that reconnects with exponential
+    /// backoff until `unsubscribe` is called. While `paused` is true the connection
+    /// is dropped; it is re-established on resume.
+    ///
+    /// # Arguments
+    /// * `endpoint` - Jetstream base URL (ws:// or wss://)
+    /// * `did` - Account DID whose repository events are streamed
+    /// * `collections` - Collections to subscribe to
+    /// * `paused` - Pause switch (`RefreshState::watch_paused`)
+    /// * `on_event` - Called with every received event
+    ///
+    /// # Returns
+    /// Subscription ID
+    pub fn subscribe<F>(
+        &self,
+        endpoint: &str,
+        did: &str,
+        collections: Vec<String>,
+        paused: watch::Receiver<bool>,
+        on_event: F,
+    ) -> Result<String, String>
+    where
+        F: Fn(Value) + Send + Sync + 'static,
+    {
+        let subscription = Arc::new(Subscription {
+            id: Uuid::new_v4().to_string(),
+            did: did.to_string(),
+            collections,
+            state: Mutex::new(ConnectionState::Reconnecting),
+            events_received: AtomicU64::new(0),
+            last_event_at: Mutex::new(None),
+            shutdown: Notify::new(),
+        });
+
+        let url = subscribe_url(endpoint, &subscription.did, &subscription.collections);
+
+        self.subscriptions
+            .lock()
+            .map_err(|e| format!("Subscription registry lock error: {}", e))?
+            .insert(subscription.id.clone(), subscription.clone());
+
+        let id = subscription.id.clone();
+        tokio::spawn(run_subscription(subscription, url, paused, on_event));
+
+        Ok(id)
+    }
+
+    /// Stop a subscription and remove it from the registry
+    pub fn unsubscribe(&self, id: &str) -> Result<(), String> {
+        let subscription = self
+            .subscriptions
+            .lock()
+            .map_err(|e| format!("Subscription registry lock error: {}", e))?
+            .remove(id)
+            .ok_or_else(|| format!("Subscription not found: {}", id))?;
+
+        subscription.set_state(ConnectionState::Closed);
+        subscription.shutdown.notify_one();
+
+        Ok(())
+    }
+
+    /// List active subscriptions and their health
+    pub fn list(&self) -> Vec<SubscriptionInfo> {
+        let Ok(subscriptions) = self.subscriptions.lock() else {
+            return vec![];
+        };
+
+        subscriptions.values().map(|s| s.info()).collect()
+    }
+}
+
+/// Build the Jetstream subscribe URL
+fn subscribe_url(endpoint: &str, did: &str, collections: &[String]) -> String {
+    let mut url = format!(
+        "{}/subscribe?wantedDids={}",
+        endpoint.trim_end_matches('/'),
+        did
+    );
+    for collection in collections {
+        url.push_str("&wantedCollections=");
+        url.push_str(collection);
+    }
+    url
+}
+
+/// Connection loop of a subscription
+async fn run_subscription<F>(
+    subscription: Arc<Subscription>,
+    url: String,
+    mut paused: watch::Receiver<bool>,
+    on_event: F,
+) where
+    F: Fn(Value) + Send + Sync + 'static,
+{
+    let mut attempt: u32 = 0;
+
+    loop {
+        // Stay disconnected until refreshes are resumed
+        if *paused.borrow_and_update() {
+            subscription.set_state(ConnectionState::Paused);
+            tokio::select! {
+                _ = subscription.shutdown.notified() => return,
+                _ = paused.wait_for(|paused| !*paused) => {}
+            }
+            attempt = 0;
+        }
+
+        subscription.set_state(ConnectionState::Reconnecting);
+
+        let connect = tokio::select! {
+            _ = subscription.shutdown.notified() => return,
+            result = tokio_tungstenite::connect_async(url.as_str()) => result,
+        };
+
+        if let Ok((mut stream, _)) = connect {
+            attempt = 0;
+            subscription.set_state(ConnectionState::Connected);
+
+            loop {
+                let message = tokio::select! {
+                    _ = subscription.shutdown.notified() => return,
+                    Ok(()) = paused.changed() => {
+                        if *paused.borrow_and_update() {
+                            break;
+                        }
+                        continue;
+                    }
+                    message = stream.next() => message,
+                };
+
+                match message {
+                    Some(Ok(Message::Text(text))) => {
+                        subscription.record_event();
+                        if let Ok(event) = serde_json::from_str::<Value>(&text) {
+                            on_event(event);
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+
+        // Paused while connected: reconnect right away on resume
+        if *paused.borrow() {
+            continue;
+        }
+
+        // Exponential backoff: 1s, 2s, 4s ... capped
+        let delay = Duration::from_secs(2u64.saturating_pow(attempt)).min(MAX_RECONNECT_DELAY);
+        attempt = attempt.saturating_add(1);
+        subscription.set_state(ConnectionState::Reconnecting);
+
+        tokio::select! {
+            _ = subscription.shutdown.notified() => return,
+            _ = tokio::time::sleep(delay) => {}
+            Ok(()) = paused.changed() => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::refresh::RefreshState;
+    use futures_util::SinkExt;
+    use tokio::net::TcpListener;
+
+    /// Mock Jetstream server that sends two events and then stays open
+    async fn mock_jetstream() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            for n in 0..2 {
+                let event = format!(r#"{{"did":"did:plc:alice","kind":"commit","seq":{}}}"#, n);
+                ws.send(Message::Text(event)).await.unwrap();
+            }
+            // Keep the connection open until the client goes away
+            while ws.next().await.is_some() {}
+        });
+
+        format!("ws://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_list_subscriptions_reports_connected_subscription() {
+        let endpoint = mock_jetstream().await;
+        let registry = SubscriptionRegistry::default();
+        let received = Arc::new(AtomicU64::new(0));
+
+        let counter = received.clone();
+        let id = registry
+            .subscribe(
+                &endpoint,
+                "did:plc:alice",
+                vec!["app.bsky.feed.post".to_string()],
+                watch::channel(false).1,
+                move |_| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                },
+            )
+            .unwrap();
+
+        // Wait for the events to arrive
+        for _ in 0..100 {
+            if received.load(Ordering::SeqCst) == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let subscriptions = registry.list();
+        assert_eq!(subscriptions.len(), 1);
+        let info = &subscriptions[0];
+        assert_eq!(info.id, id);
+        assert_eq!(info.did, "did:plc:alice");
+        assert_eq!(info.collections, vec!["app.bsky.feed.post".to_string()]);
+        assert_eq!(info.state, ConnectionState::Connected);
+        assert_eq!(info.events_received, 2);
+        assert!(info.last_event_at.is_some());
+
+        registry.unsubscribe(&id).unwrap();
+        assert!(registry.list().is_empty());
+        assert!(registry.unsubscribe(&id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscription_disconnects_while_paused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicU64::new(0));
+        let open = Arc::new(AtomicU64::new(0));
+
+        let (accepted, live) = (connections.clone(), open.clone());
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let live = live.clone();
+                live.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    while let Some(Ok(_)) = ws.next().await {}
+                    live.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        let registry = SubscriptionRegistry::default();
+        let refresh_state = RefreshState::default();
+        let id = registry
+            .subscribe(&endpoint, "did:plc:alice", vec![], refresh_state.watch_paused(), |_| {})
+            .unwrap();
+
+        async fn wait_until(condition: impl Fn() -> bool) {
+            for _ in 0..100 {
+                if condition() {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            panic!("condition not reached");
+        }
+        let state = |registry: &SubscriptionRegistry| registry.list()[0].state;
+
+        wait_until(|| state(&registry) == ConnectionState::Connected).await;
+        assert_eq!(open.load(Ordering::SeqCst), 1);
+
+        refresh_state.set_paused(true);
+        wait_until(|| open.load(Ordering::SeqCst) == 0).await;
+        assert_eq!(state(&registry), ConnectionState::Paused);
+
+        refresh_state.set_paused(false);
+        wait_until(|| state(&registry) == ConnectionState::Connected).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        registry.unsubscribe(&id).unwrap();
+    }
+
+    #[test]
+    fn test_subscribe_url() {
+        let url = subscribe_url(
+            "wss://jetstream.example/",
+            "did:plc:alice",
+            &["app.bsky.feed.post".to_string(), "app.bsky.feed.like".to_string()],
+        );
+
+        assert_eq!(
+            url,
+            "wss://jetstream.example/subscribe?wantedDids=did:plc:alice\
+             &wantedCollections=app.bsky.feed.post&wantedCollections=app.bsky.feed.like"
+        );
+    }
+}
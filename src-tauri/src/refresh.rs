@@ -0,0 +1,703 @@
+/**
+ * Background refresh coordination
+ *
+ * Central switch that lets the user pause all automatic refresh activity, plus
+ * per-account backoff after repeated refresh failures
+ */
+
+use crate::api::feed::FeedPage;
+use crate::auth::jwt::decode_claims;
+use crate::auth::ATProtocolClient;
+use crate::storage::{StorageManager, TokenRefreshFailure};
+use crate::types::{Account, AuthError};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::future::Future;
+use std::time::Instant;
+use tokio::sync::watch;
+
+/// Backoff after the first failed refresh
+const BASE_BACKOFF_SECS: i64 = 30;
+/// Upper bound for the refresh backoff
+const MAX_BACKOFF_SECS: i64 = 3600;
+
+/// Shared refresh state (managed by Tauri)
+pub struct RefreshState {
+    /// Whether automatic refreshes are paused (watched by realtime subscriptions)
+    paused: watch::Sender<bool>,
+}
+
+impl Default for RefreshState {
+    fn default() -> Self {
+        Self {
+            paused: watch::channel(false).0,
+        }
+    }
+}
+
+impl RefreshState {
+    /// Pause or resume automatic refreshes
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.send_replace(paused);
+    }
+
+    /// Whether automatic refreshes are paused
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Receiver that observes pausing and resuming
+    pub fn watch_paused(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+}
+
+/// Per-account failure of a batch refresh
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshFailure {
+    /// Account ID
+    pub account_id: String,
+    /// Error message
+    pub error: String,
+}
+
+/// Result of a batch refresh
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum RefreshOutcome {
+    /// Refreshes are paused; nothing was done
+    Paused,
+    /// Refresh ran for every account
+    #[serde(rename_all = "camelCase")]
+    Completed {
+        /// Accounts whose token is valid after the run
+        refreshed: Vec<String>,
+        /// Accounts that could not be refreshed
+        failed: Vec<RefreshFailure>,
+        /// Accounts skipped because they are still backing off
+        deferred: Vec<String>,
+        /// Accounts whose backoff state could not be saved
+        unrecorded: Vec<RefreshFailure>,
+    },
+}
+
+/// Result of a column timeline refresh
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum TimelineRefresh {
+    /// Refreshes are paused; nothing was fetched
+    Paused,
+    /// The fetched page
+    Fetched(FeedPage),
+}
+
+/// How often `selftest_refresh` tries to store a refreshed token before giving up
+const SELFTEST_SAVE_ATTEMPTS: u32 = 3;
+
+/// Result of `selftest_refresh`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshSelfTest {
+    /// Refresh, persistence and expiry checks all passed
+    pub success: bool,
+    /// The stored refresh token differs from the one before the test
+    pub token_changed: bool,
+    /// Access token expiry before the refresh (ISO 8601, None if unknown)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_expires_at: Option<String>,
+    /// Expiry decoded from the new access token (ISO 8601, None if undecodable)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_expires_at: Option<String>,
+    /// Duration of the refreshSession call in milliseconds
+    pub refresh_ms: u64,
+    /// Duration of the whole self-test in milliseconds
+    pub total_ms: u64,
+    /// Saves of the new token that were attempted (0 if the refresh itself failed)
+    pub save_attempts: u32,
+    /// Which step failed (None on success)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Expiry (`exp` claim) of a JWT
+fn jwt_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let exp = decode_claims(token).ok()?.get("exp")?.as_i64()?;
+    DateTime::from_timestamp(exp, 0)
+}
+
+/// Backoff to apply after the given number of consecutive failures
+fn backoff_for(failure_count: u32) -> Duration {
+    let exponent = failure_count.saturating_sub(1).min(16);
+    Duration::seconds((BASE_BACKOFF_SECS << exponent).min(MAX_BACKOFF_SECS))
+}
+
+/// Whether an account is still inside its backoff window
+pub(crate) fn is_backing_off(account: &Account, now: DateTime<Utc>) -> bool {
+    account
+        .next_refresh_not_before
+        .as_deref()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .is_some_and(|not_before| not_before > now)
+}
+
+/// Record the result of a refresh attempt on the account metadata
+///
+/// Only the failure counter and backoff fields are touched, on the stored account.
+pub(crate) async fn record_refresh_result(
+    storage: &StorageManager,
+    account_id: &str,
+    succeeded: bool,
+) -> Result<Account, AuthError> {
+    storage
+        .modify_account(account_id, |account| {
+            if succeeded {
+                let backing_off =
+                    account.refresh_failure_count > 0 || account.next_refresh_not_before.is_some();
+                account.refresh_failure_count = 0;
+                account.next_refresh_not_before = None;
+                return backing_off;
+            }
+
+            account.refresh_failure_count = account.refresh_failure_count.saturating_add(1);
+            let not_before = Utc::now() + backoff_for(account.refresh_failure_count);
+            account.next_refresh_not_before = Some(not_before.to_rfc3339());
+            true
+        })
+        .await
+}
+
+/// Reset the refresh backoff of an account so it is retried immediately
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `account_id` - Account to reset
+///
+/// # Returns
+/// The updated account (errors if the account does not exist)
+pub async fn clear_refresh_backoff(
+    storage: &StorageManager,
+    account_id: &str,
+) -> Result<Account, AuthError> {
+    record_refresh_result(storage, account_id, true).await
+}
+
+/// Make sure every account holds a valid access token, refreshing expired ones
+///
+/// # Arguments
+/// * `state` - Refresh state (short-circuits with `Paused` when paused)
+/// * `storage` - Storage manager
+/// * `client_for` - Builds the client for an account's PDS
+///
+/// # Note
+/// Accounts inside their failure backoff window are deferred, not retried.
+pub async fn refresh_all_sessions<F>(
+    state: &RefreshState,
+    storage: &StorageManager,
+    client_for: F,
+) -> Result<RefreshOutcome, AuthError>
+where
+    F: Fn(&Account) -> Result<ATProtocolClient, AuthError>,
+{
+    if state.is_paused() {
+        return Ok(RefreshOutcome::Paused);
+    }
+
+    let mut refreshed = Vec::new();
+    let mut failed = Vec::new();
+    let mut deferred = Vec::new();
+    let mut unrecorded = Vec::new();
+    let now = Utc::now();
+
+    for account in storage.list_accounts().await? {
+        if is_backing_off(&account, now) {
+            deferred.push(account.id);
+            continue;
+        }
+
+        let result = match client_for(&account) {
+            Ok(client) => storage.get_valid_token(&account.id, &client).await,
+            Err(e) => Err(e),
+        };
+
+        // A failed bookkeeping write must not abort the refresh of the other accounts
+        if let Err(e) = record_refresh_result(storage, &account.id, result.is_ok()).await {
+            unrecorded.push(RefreshFailure {
+                account_id: account.id.clone(),
+                error: e.to_string(),
+            });
+        }
+
+        match result {
+            Ok(_) => refreshed.push(account.id),
+            Err(e) => failed.push(RefreshFailure {
+                account_id: account.id,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    Ok(RefreshOutcome::Completed {
+        refreshed,
+        failed,
+        deferred,
+        unrecorded,
+    })
+}
+
+/// Fetch a column's timeline page unless refreshes are paused
+///
+/// # Arguments
+/// * `state` - Refresh state (short-circuits with `Paused` when paused)
+/// * `fetch` - Fetches the page, including any token refresh it needs
+///
+/// # Note
+/// `fetch` never runs while paused, so no token refresh happens either.
+pub async fn refresh_timeline<F, Fut>(
+    state: &RefreshState,
+    fetch: F,
+) -> Result<TimelineRefresh, String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<FeedPage, String>>,
+{
+    if state.is_paused() {
+        return Ok(TimelineRefresh::Paused);
+    }
+
+    fetch().await.map(TimelineRefresh::Fetched)
+}
+
+/// Refresh one account and verify that storage picked up the rotated token
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `client` - Client for the account's PDS
+/// * `account_id` - Account to test
+///
+/// # Note
+/// A failed refresh leaves the stored token untouched. After a successful refresh the
+/// server has already revoked the previous refresh token, so it is never restored: the
+/// new token is kept and saving it is retried up to `SELFTEST_SAVE_ATTEMPTS` times.
+/// Runs under the account's refresh lock (see `StorageManager::refresh_token_with_report`).
+pub async fn selftest_refresh(
+    storage: &StorageManager,
+    client: &ATProtocolClient,
+    account_id: &str,
+) -> Result<RefreshSelfTest, AuthError> {
+    let started = Instant::now();
+    let refresh = storage
+        .refresh_token_with_report(account_id, client, SELFTEST_SAVE_ATTEMPTS)
+        .await?;
+    let previous = refresh.previous;
+    let previous_expiry = jwt_expiry(&previous.access_jwt).or_else(|| {
+        DateTime::parse_from_rfc3339(&previous.access_expires_at)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    });
+
+    let mut report = RefreshSelfTest {
+        success: false,
+        token_changed: false,
+        previous_expires_at: previous_expiry.map(|t| t.to_rfc3339()),
+        new_expires_at: None,
+        refresh_ms: refresh.refresh_time.as_millis() as u64,
+        total_ms: 0,
+        save_attempts: refresh.save_attempts,
+        error: None,
+    };
+
+    report.error = match refresh.outcome {
+        Err(TokenRefreshFailure::Refresh(e)) => Some(format!("Refresh failed: {}", e)),
+        Err(TokenRefreshFailure::Save(e)) => {
+            Some(format!("Failed to persist refreshed token: {}", e))
+        }
+        Err(TokenRefreshFailure::Mismatch) => {
+            Some("Stored token does not match the refreshed one".to_string())
+        }
+        Ok(stored) => {
+            report.token_changed = stored.refresh_jwt != previous.refresh_jwt;
+            let new_expiry = jwt_expiry(&stored.access_jwt);
+            report.new_expires_at = new_expiry.map(|t| t.to_rfc3339());
+
+            if !report.token_changed {
+                Some("Server returned the same refresh token".to_string())
+            } else if !matches!(
+                (new_expiry, previous_expiry),
+                (Some(new), Some(old)) if new > old
+            ) {
+                Some("New access token does not expire later".to_string())
+            } else {
+                None
+            }
+        }
+    };
+
+    report.success = report.error.is_none();
+    report.total_ms = started.elapsed().as_millis() as u64;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::update_account;
+    use crate::auth::jwt::tests::make_jwt;
+    use crate::test_support;
+    use crate::types::{AccountPatch, AuthToken};
+    use chrono::Utc;
+    use mockito::Server;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    async fn storage_with_expired_token(data_dir: std::path::PathBuf) -> StorageManager {
+        let storage = StorageManager::new(data_dir).await.unwrap();
+        let now = Utc::now();
+
+        storage
+            .save_account(&test_support::account("alice"))
+            .await
+            .unwrap();
+        storage
+            .save_auth_token(&AuthToken {
+                account_id: "alice".to_string(),
+                access_jwt: "old-access".to_string(),
+                refresh_jwt: "old-refresh".to_string(),
+                issued_at: (now - chrono::Duration::hours(2)).to_rfc3339(),
+                access_expires_at: (now - chrono::Duration::minutes(30)).to_rfc3339(),
+                refresh_expires_at: (now + chrono::Duration::days(59)).to_rfc3339(),
+                session_string: None,
+                dpop_key: None,
+                oauth_issuer: None,
+                oauth_client_id: None,
+            })
+            .await
+            .unwrap();
+
+        storage
+    }
+
+    #[tokio::test]
+    async fn test_refresh_all_sessions_honors_pause() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_expired_token(temp_dir.path().to_path_buf()).await;
+
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .match_header("authorization", "Bearer old-refresh")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"accessJwt":"new-access","refreshJwt":"new-refresh","did":"did:plc:alice","handle":"alice.bsky.social"}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let url = server.url();
+        let client_for = |_: &Account| Ok(ATProtocolClient::with_base_url(&url));
+
+        let state = RefreshState::default();
+        state.set_paused(true);
+
+        let outcome = refresh_all_sessions(&state, &storage, client_for).await.unwrap();
+        assert!(matches!(outcome, RefreshOutcome::Paused));
+        assert_eq!(
+            storage.get_auth_token("alice").await.unwrap().access_jwt,
+            "old-access"
+        );
+
+        // Column refreshes are skipped with a distinct result, not an empty page
+        let timeline = refresh_timeline(&state, || async {
+            panic!("no timeline may be fetched while paused")
+        })
+        .await
+        .unwrap();
+        assert!(matches!(timeline, TimelineRefresh::Paused));
+        assert_eq!(serde_json::to_value(&timeline).unwrap(), json!({"status": "paused"}));
+
+        state.set_paused(false);
+
+        let timeline = refresh_timeline(&state, || async {
+            Ok(FeedPage {
+                feed: Vec::new(),
+                cursor: Some("next".to_string()),
+            })
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&timeline).unwrap(),
+            json!({"status": "fetched", "feed": [], "cursor": "next"})
+        );
+
+        let outcome = refresh_all_sessions(&state, &storage, client_for).await.unwrap();
+        match outcome {
+            RefreshOutcome::Completed {
+                refreshed, failed, ..
+            } => {
+                assert_eq!(refreshed, vec!["alice".to_string()]);
+                assert!(failed.is_empty());
+            }
+            RefreshOutcome::Paused => panic!("refresh should run after resume"),
+        }
+        assert_eq!(
+            storage.get_auth_token("alice").await.unwrap().access_jwt,
+            "new-access"
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_clear_refresh_backoff_allows_immediate_refresh() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_expired_token(temp_dir.path().to_path_buf()).await;
+        let state = RefreshState::default();
+
+        let mut server = Server::new_async().await;
+        let failing = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let url = server.url();
+        let client_for = |_: &Account| Ok(ATProtocolClient::with_base_url(&url));
+
+        // First attempt fails and starts the backoff
+        let outcome = refresh_all_sessions(&state, &storage, client_for).await.unwrap();
+        assert!(matches!(
+            outcome,
+            RefreshOutcome::Completed { ref failed, .. } if failed.len() == 1
+        ));
+
+        // Further attempts are deferred without hitting the server
+        for _ in 0..2 {
+            let outcome = refresh_all_sessions(&state, &storage, client_for).await.unwrap();
+            assert!(matches!(
+                outcome,
+                RefreshOutcome::Completed { ref deferred, .. } if deferred == &["alice"]
+            ));
+        }
+        failing.assert_async().await;
+
+        let account = storage.get_account("alice").await.unwrap();
+        assert_eq!(account.refresh_failure_count, 1);
+        assert!(account.next_refresh_not_before.is_some());
+
+        // Network is back: clearing the backoff lets the next run proceed
+        failing.remove_async().await;
+        let succeeding = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"accessJwt":"new-access","refreshJwt":"new-refresh","did":"did:plc:alice","handle":"alice.bsky.social"}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let account = clear_refresh_backoff(&storage, "alice").await.unwrap();
+        assert_eq!(account.refresh_failure_count, 0);
+        assert!(account.next_refresh_not_before.is_none());
+
+        let outcome = refresh_all_sessions(&state, &storage, client_for).await.unwrap();
+        assert!(matches!(
+            outcome,
+            RefreshOutcome::Completed { ref refreshed, .. } if refreshed == &["alice"]
+        ));
+        succeeding.assert_async().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_refresh_result_keeps_concurrent_account_edits() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_expired_token(temp_dir.path().to_path_buf()).await;
+        let state = RefreshState::default();
+
+        // The account is edited after the batch listed it, while its refresh runs
+        let client_for = |account: &Account| {
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let patch = AccountPatch {
+                        note: Some("edited".to_string()),
+                        ..Default::default()
+                    };
+                    update_account(&storage, &account.id, patch).await.unwrap();
+                })
+            });
+            Err(AuthError::NetworkError("offline".to_string()))
+        };
+
+        refresh_all_sessions(&state, &storage, client_for).await.unwrap();
+
+        let account = storage.get_account("alice").await.unwrap();
+        assert_eq!(account.note.as_deref(), Some("edited"));
+        assert_eq!(account.refresh_failure_count, 1);
+        assert!(account.next_refresh_not_before.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_clear_refresh_backoff_unknown_account() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+
+        assert!(clear_refresh_backoff(&storage, "missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_selftest_refresh_advances_stored_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_expired_token(temp_dir.path().to_path_buf()).await;
+        let new_access = make_jwt(&json!({ "exp": (Utc::now().timestamp() + 7200) }));
+
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .match_header("authorization", "Bearer old-refresh")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "accessJwt": new_access,
+                    "refreshJwt": "new-refresh",
+                    "did": "did:plc:alice",
+                    "handle": "alice.bsky.social"
+                })
+                .to_string(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let report = selftest_refresh(&storage, &client, "alice").await.unwrap();
+
+        mock.assert_async().await;
+        assert!(report.success, "{:?}", report.error);
+        assert!(report.token_changed);
+        assert_eq!(report.save_attempts, 1);
+        assert!(report.new_expires_at > report.previous_expires_at);
+        assert!(report.total_ms >= report.refresh_ms);
+        let stored = storage.get_auth_token("alice").await.unwrap();
+        assert_eq!(stored.refresh_jwt, "new-refresh");
+        assert_eq!(stored.access_jwt, new_access);
+    }
+
+    #[tokio::test]
+    async fn test_selftest_refresh_is_single_flight_with_get_valid_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_expired_token(temp_dir.path().to_path_buf()).await;
+
+        let mut server = Server::new_async().await;
+        let mut mocks = Vec::new();
+        for (old, new) in [("old-refresh", "new-refresh"), ("new-refresh", "newer-refresh")] {
+            let access = make_jwt(&json!({ "exp": (Utc::now().timestamp() + 7200) }));
+            let mock = server
+                .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+                .match_header("authorization", format!("Bearer {}", old).as_str())
+                .with_status(200)
+                .with_body(
+                    json!({
+                        "accessJwt": access,
+                        "refreshJwt": new,
+                        "did": "did:plc:alice",
+                        "handle": "alice.bsky.social"
+                    })
+                    .to_string(),
+                )
+                .expect_at_most(1)
+                .create_async()
+                .await;
+            mocks.push(mock);
+        }
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        // Whichever runs first, the other never replays the rotated refresh token
+        let (report, token) = tokio::join!(
+            selftest_refresh(&storage, &client, "alice"),
+            storage.get_valid_token("alice", &client)
+        );
+
+        for mock in &mocks {
+            mock.assert_async().await;
+        }
+        assert!(report.unwrap().success);
+        let stored = storage.get_auth_token("alice").await.unwrap();
+        assert_eq!(stored.refresh_jwt, token.unwrap().refresh_jwt);
+    }
+
+    #[tokio::test]
+    async fn test_selftest_refresh_failure_keeps_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_expired_token(temp_dir.path().to_path_buf()).await;
+
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .with_status(400)
+            .with_body(r#"{"error":"ExpiredToken","message":"Token has expired"}"#)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let report = selftest_refresh(&storage, &client, "alice").await.unwrap();
+
+        assert!(!report.success);
+        assert!(report.error.unwrap().contains("Refresh failed"));
+        assert_eq!(
+            storage.get_auth_token("alice").await.unwrap().refresh_jwt,
+            "old-refresh"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_selftest_refresh_keeps_rotated_token_when_saving_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_expired_token(temp_dir.path().to_path_buf()).await;
+
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .with_status(200)
+            .with_body(
+                r#"{"accessJwt":"new-access","refreshJwt":"new-refresh","did":"did:plc:alice",
+                    "handle":"alice.bsky.social"}"#,
+            )
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        // A directory in place of the temp file makes every write fail
+        let blocker = temp_dir.path().join("storage.enc.tmp");
+        std::fs::create_dir_all(blocker.join("blocked")).unwrap();
+
+        let report = selftest_refresh(&storage, &client, "alice").await.unwrap();
+
+        assert!(!report.success);
+        assert!(report.error.unwrap().contains("Failed to persist refreshed token"));
+        assert_eq!(report.save_attempts, SELFTEST_SAVE_ATTEMPTS);
+        // The old refresh token is revoked by now; it must not come back
+        assert_eq!(
+            storage.get_auth_token("alice").await.unwrap().refresh_jwt,
+            "new-refresh"
+        );
+
+        // Written to disk by the next successful save
+        std::fs::remove_dir_all(blocker).unwrap();
+        storage.save_account(&test_support::account("alice")).await.unwrap();
+        storage.reload().await.unwrap();
+        assert_eq!(
+            storage.get_auth_token("alice").await.unwrap().refresh_jwt,
+            "new-refresh"
+        );
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        assert_eq!(backoff_for(1), Duration::seconds(30));
+        assert_eq!(backoff_for(2), Duration::seconds(60));
+        assert_eq!(backoff_for(50), Duration::seconds(MAX_BACKOFF_SECS));
+    }
+}
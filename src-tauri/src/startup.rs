@@ -0,0 +1,715 @@
+/**
+ * Startup phase timing and sequencing
+ *
+ * Records how long each setup phase took (key derivation, storage load, migrations)
+ * so a slow launch can be traced to its cause, and runs the network-bound startup
+ * work in a prioritized order with bounded concurrency
+ */
+
+use crate::accounts::{self, refresh_profile};
+use crate::api::avatars::AvatarCache;
+use crate::auth::ATProtocolClient;
+use crate::refresh::{is_backing_off, record_refresh_result, RefreshState};
+use crate::storage::columns::load_columns;
+use crate::storage::maintenance::{cleanup_temp_files, DEFAULT_SNAPSHOT_KEEP, STALE_TEMP_AGE};
+use crate::storage::settings::load_settings;
+use crate::storage::StorageManager;
+use crate::types::{Account, AppSettings, AuthError, DeckColumnConfig};
+use chrono::Utc;
+use futures_util::{stream, StreamExt};
+use serde::Serialize;
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Maximum number of concurrent network tasks in `startup_sequence`
+pub const STARTUP_CONCURRENCY: usize = 2;
+
+/// Pause between two accounts of `background_refresh`
+pub const BACKGROUND_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Duration of one startup phase
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseTiming {
+    /// Phase name (e.g., "key_derivation")
+    pub phase: String,
+    /// Duration in milliseconds
+    pub duration_ms: f64,
+}
+
+/// Per-phase timings of the last startup (managed by Tauri)
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupTimings {
+    /// Phases in execution order
+    pub phases: Vec<PhaseTiming>,
+    /// Sum of all phases in milliseconds
+    pub total_ms: f64,
+}
+
+impl StartupTimings {
+    /// Record a phase that took `duration`
+    pub fn record(&mut self, phase: &str, duration: Duration) {
+        let duration_ms = duration.as_secs_f64() * 1000.0;
+        self.total_ms += duration_ms;
+        self.phases.push(PhaseTiming {
+            phase: phase.to_string(),
+            duration_ms,
+        });
+    }
+
+    /// Run `f` and record its duration as `phase`
+    pub fn time<T>(&mut self, phase: &str, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        self.record(phase, started.elapsed());
+        result
+    }
+}
+
+/// Run the setup phases that only need the data directory, timing each of them
+///
+/// Phases: temp file cleanup, key derivation, storage load (without key derivation),
+/// schema migrations, settings load and the active-account repair.
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `timings` - Receives the phase durations
+pub fn load_state(
+    data_dir: &Path,
+    timings: &mut StartupTimings,
+) -> Result<(AppSettings, StorageManager), AuthError> {
+    // Leftovers of writes interrupted by a crash
+    timings.time("temp_cleanup", || {
+        let _ = cleanup_temp_files(data_dir, DEFAULT_SNAPSHOT_KEEP, STALE_TEMP_AGE);
+    });
+
+    let started = Instant::now();
+    let storage =
+        tauri::async_runtime::block_on(StorageManager::open(data_dir.to_path_buf()))?;
+    let total = started.elapsed();
+    let key_derivation =
+        tauri::async_runtime::block_on(storage.key_derivation_time())?.min(total);
+    timings.record("key_derivation", key_derivation);
+    timings.record("storage_load", total - key_derivation);
+
+    // Bring persisted files up to the current schema before reading them (the columns
+    // conversion and the store rewrite need the open store)
+    timings.time("schema_migrations", || {
+        let _ = tauri::async_runtime::block_on(crate::storage::schema::check_schema_versions(
+            data_dir, &storage,
+        ));
+    });
+
+    // Settings are optional; fall back to defaults if unreadable
+    let settings = timings.time("settings_load", || load_settings(data_dir).unwrap_or_default());
+    storage.set_token_refresh_skew(settings.token_refresh_skew());
+
+    // Exactly one account must be active; repair drift from edits/merges
+    timings.time("active_invariant", || {
+        let _ = tauri::async_runtime::block_on(accounts::ensure_active_invariant(&storage));
+    });
+
+    Ok((settings, storage))
+}
+
+/// Phase of `startup_sequence`, in execution order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPhase {
+    /// Load the stored accounts
+    Restore,
+    /// Make sure every session holds a valid access token
+    Validate,
+    /// Columns, profile and avatar of the active account
+    ActiveAccount,
+    /// Profiles and avatars of the other accounts (`background_refresh`)
+    Background,
+}
+
+/// Payload of `startup-progress` events
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupProgress {
+    pub phase: StartupPhase,
+    /// Account of the finished step (None for account-independent steps)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    /// Steps of this phase finished so far
+    pub completed: usize,
+    /// Steps in this phase
+    pub total: usize,
+}
+
+/// Step of `startup_sequence` that failed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupFailure {
+    pub phase: StartupPhase,
+    pub account_id: String,
+    pub error: String,
+}
+
+/// Outcome of `startup_sequence`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupSequenceReport {
+    /// Restored accounts, active account first
+    pub accounts: Vec<Account>,
+    /// Stored columns of the active account, ordered by position
+    pub active_columns: Vec<DeckColumnConfig>,
+    /// Accounts whose handle, display name or avatar changed
+    pub updated: Vec<Account>,
+    /// Failed steps (a failure never aborts the sequence)
+    pub failed: Vec<StartupFailure>,
+    /// Accounts not validated because refreshes are paused or the account is backing off
+    pub deferred: Vec<String>,
+    /// Validated accounts left to `background_refresh`
+    pub background: Vec<String>,
+}
+
+/// Outcome of `background_refresh`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundRefreshReport {
+    /// Accounts whose handle, display name or avatar changed
+    pub updated: Vec<Account>,
+    /// Failed steps
+    pub failed: Vec<StartupFailure>,
+    /// Accounts not refreshed because refreshes were paused meanwhile
+    pub deferred: Vec<String>,
+}
+
+/// Run `task` for each account with at most `limit` tasks in flight
+///
+/// Reports progress as tasks finish and records results in `report`.
+///
+/// # Returns
+/// IDs of the accounts whose task succeeded
+async fn run_phase<F, Fut, P>(
+    phase: StartupPhase,
+    accounts: &[Account],
+    limit: usize,
+    task: F,
+    on_progress: &mut P,
+    report: &mut StartupSequenceReport,
+) -> Vec<String>
+where
+    F: Fn(Account) -> Fut,
+    Fut: Future<Output = Result<Option<Account>, String>>,
+    P: FnMut(&StartupProgress),
+{
+    let total = accounts.len();
+    let mut tasks = stream::iter(accounts.iter().cloned())
+        .map(|account| {
+            let account_id = account.id.clone();
+            let running = task(account);
+            async move { (account_id, running.await) }
+        })
+        .buffer_unordered(limit.max(1));
+
+    let mut succeeded = Vec::new();
+    let mut completed = 0;
+    while let Some((account_id, result)) = tasks.next().await {
+        completed += 1;
+        match result {
+            Ok(updated) => {
+                report.updated.extend(updated);
+                succeeded.push(account_id.clone());
+            }
+            Err(error) => report.failed.push(StartupFailure {
+                phase,
+                account_id: account_id.clone(),
+                error,
+            }),
+        }
+        on_progress(&StartupProgress {
+            phase,
+            account_id: Some(account_id),
+            completed,
+            total,
+        });
+    }
+
+    succeeded
+}
+
+/// Refresh an account's profile and prefetch its avatar
+async fn refresh_account(
+    storage: &StorageManager,
+    avatars: &AvatarCache,
+    client: Result<ATProtocolClient, AuthError>,
+    account: Account,
+) -> Result<Option<Account>, String> {
+    let updated = refresh_profile(storage, client, &account)
+        .await
+        .map_err(|e| format!("Failed to refresh profile: {}", e))?;
+
+    // Prefetch is best-effort: the UI falls back to the remote URL
+    if let Some(url) = updated.as_ref().unwrap_or(&account).avatar.as_deref() {
+        let _ = avatars.get(url).await;
+    }
+
+    Ok(updated)
+}
+
+/// Run the network-bound startup work in priority order
+///
+/// Phases: restore accounts, validate every session, then the active account's
+/// columns/profile/avatar. Each phase finishes before the next one starts, and at most
+/// `STARTUP_CONCURRENCY` requests run at a time. The other accounts' profiles and
+/// avatars are left to `background_refresh` (listed in `background`).
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory (columns file location)
+/// * `avatars` - Avatar cache to prefetch into
+/// * `refresh_state` - Refresh state (every account is deferred while paused)
+/// * `client_for` - Builds the client for an account's PDS
+/// * `on_progress` - Called after every finished step
+///
+/// # Note
+/// Accounts whose session could not be validated are skipped by the later phases,
+/// and so are deferred accounts (paused refreshes or inside their failure backoff).
+/// Validation results feed the failure backoff like `refresh_all_sessions`.
+pub async fn startup_sequence<F, P>(
+    storage: &StorageManager,
+    data_dir: &Path,
+    avatars: &AvatarCache,
+    refresh_state: &RefreshState,
+    client_for: F,
+    mut on_progress: P,
+) -> Result<StartupSequenceReport, AuthError>
+where
+    F: Fn(&Account) -> Result<ATProtocolClient, AuthError>,
+    P: FnMut(&StartupProgress),
+{
+    let mut report = StartupSequenceReport::default();
+
+    let mut accounts = storage.list_accounts().await?;
+    accounts.sort_by(|a, b| b.is_active.cmp(&a.is_active).then_with(|| a.id.cmp(&b.id)));
+    report.accounts = accounts.clone();
+    on_progress(&StartupProgress {
+        phase: StartupPhase::Restore,
+        account_id: None,
+        completed: 1,
+        total: 1,
+    });
+
+    // A refresh now would ignore the pause switch or hammer a failing server
+    let now = Utc::now();
+    let (deferred, to_validate): (Vec<Account>, Vec<Account>) = accounts
+        .iter()
+        .cloned()
+        .partition(|account| refresh_state.is_paused() || is_backing_off(account, now));
+    report.deferred = deferred.into_iter().map(|account| account.id).collect();
+
+    let valid = run_phase(
+        StartupPhase::Validate,
+        &to_validate,
+        STARTUP_CONCURRENCY,
+        |account| {
+            let client = client_for(&account);
+            async move {
+                let result = match client {
+                    Ok(client) => storage
+                        .get_valid_token(&account.id, &client)
+                        .await
+                        .map_err(|e| format!("Failed to validate session: {}", e)),
+                    Err(e) => Err(format!("Failed to create client: {}", e)),
+                };
+
+                // Bookkeeping is best-effort: a failed write must not fail a valid session
+                let _ = record_refresh_result(storage, &account.id, result.is_ok()).await;
+                result.map(|_| None)
+            }
+        },
+        &mut on_progress,
+        &mut report,
+    )
+    .await;
+
+    // Columns are local, so the deck can render even if the session is invalid
+    if let Some(active) = accounts.iter().find(|account| account.is_active) {
+        match load_columns(&data_dir.to_path_buf()) {
+            Ok(columns) => {
+                let mut own: Vec<DeckColumnConfig> =
+                    columns.into_iter().filter(|column| column.did == active.did).collect();
+                own.sort_by_key(|column| column.position);
+                report.active_columns = own;
+            }
+            Err(error) => report.failed.push(StartupFailure {
+                phase: StartupPhase::ActiveAccount,
+                account_id: active.id.clone(),
+                error,
+            }),
+        }
+    }
+
+    let (active, others): (Vec<Account>, Vec<Account>) = accounts
+        .into_iter()
+        .filter(|account| valid.contains(&account.id))
+        .partition(|account| account.is_active);
+    run_phase(
+        StartupPhase::ActiveAccount,
+        &active,
+        STARTUP_CONCURRENCY,
+        |account| refresh_account(storage, avatars, client_for(&account), account),
+        &mut on_progress,
+        &mut report,
+    )
+    .await;
+    report.background = others.into_iter().map(|account| account.id).collect();
+
+    Ok(report)
+}
+
+/// Refresh the profiles and avatars of the accounts `startup_sequence` left behind
+///
+/// Accounts are refreshed one at a time, `interval` apart, so the other accounts'
+/// requests never compete with the deck of the active one.
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `avatars` - Avatar cache to prefetch into
+/// * `refresh_state` - Refresh state (the remaining accounts are deferred once paused)
+/// * `account_ids` - Accounts to refresh (`StartupSequenceReport::background`)
+/// * `interval` - Pause between two accounts
+/// * `client_for` - Builds the client for an account's PDS
+/// * `on_progress` - Called after every finished step
+///
+/// # Note
+/// Accounts removed since the startup sequence are skipped.
+pub async fn background_refresh<F, P>(
+    storage: &StorageManager,
+    avatars: &AvatarCache,
+    refresh_state: &RefreshState,
+    account_ids: &[String],
+    interval: Duration,
+    client_for: F,
+    mut on_progress: P,
+) -> BackgroundRefreshReport
+where
+    F: Fn(&Account) -> Result<ATProtocolClient, AuthError>,
+    P: FnMut(&StartupProgress),
+{
+    let mut report = BackgroundRefreshReport::default();
+
+    for (completed, account_id) in account_ids.iter().enumerate() {
+        if completed > 0 {
+            tokio::time::sleep(interval).await;
+        }
+        if refresh_state.is_paused() {
+            report.deferred.extend(account_ids[completed..].iter().cloned());
+            break;
+        }
+        let Ok(account) = storage.get_account(account_id).await else {
+            continue;
+        };
+
+        match refresh_account(storage, avatars, client_for(&account), account).await {
+            Ok(updated) => report.updated.extend(updated),
+            Err(error) => report.failed.push(StartupFailure {
+                phase: StartupPhase::Background,
+                account_id: account_id.clone(),
+                error,
+            }),
+        }
+        on_progress(&StartupProgress {
+            phase: StartupPhase::Background,
+            account_id: Some(account_id.clone()),
+            completed: completed + 1,
+            total: account_ids.len(),
+        });
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::columns::{get_default_columns, save_columns};
+    use crate::test_support;
+    use crate::types::{AuthToken, SessionResponse};
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    fn account(id: &str, is_active: bool) -> Account {
+        Account {
+            is_active,
+            ..test_support::account(id)
+        }
+    }
+
+    fn token(account_id: &str) -> AuthToken {
+        AuthToken::from_session(
+            account_id,
+            SessionResponse {
+                access_jwt: format!("access-{}", account_id),
+                refresh_jwt: format!("refresh-{}", account_id),
+                did: String::new(),
+                handle: String::new(),
+                email: None,
+                display_name: None,
+                avatar: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_load_state_records_every_phase() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut timings = StartupTimings::default();
+
+        load_state(temp_dir.path(), &mut timings).unwrap();
+
+        let phases: Vec<&str> = timings.phases.iter().map(|p| p.phase.as_str()).collect();
+        assert_eq!(
+            phases,
+            vec![
+                "temp_cleanup",
+                "key_derivation",
+                "storage_load",
+                "schema_migrations",
+                "settings_load",
+                "active_invariant"
+            ]
+        );
+        assert!(timings.phases.iter().all(|p| p.duration_ms >= 0.0));
+        // A fresh store always derives its key
+        assert!(timings.phases[1].duration_ms > 0.0);
+        let sum: f64 = timings.phases.iter().map(|p| p.duration_ms).sum();
+        assert!((timings.total_ms - sum).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_run_phase_bounds_concurrency() {
+        let accounts: Vec<Account> = (0..6).map(|i| account(&format!("a{}", i), false)).collect();
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let mut report = StartupSequenceReport::default();
+        let mut events = Vec::new();
+
+        let succeeded = run_phase(
+            StartupPhase::Background,
+            &accounts,
+            STARTUP_CONCURRENCY,
+            |_| async {
+                let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(None)
+            },
+            &mut |progress: &StartupProgress| events.push(progress.completed),
+            &mut report,
+        )
+        .await;
+
+        assert_eq!(succeeded.len(), 6);
+        assert_eq!(peak.load(Ordering::SeqCst), STARTUP_CONCURRENCY);
+        assert_eq!(events, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_startup_sequence_runs_phases_in_priority_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let storage = StorageManager::new(data_dir.to_path_buf()).await.unwrap();
+        let avatars = AvatarCache::new(data_dir, Duration::from_secs(3600));
+
+        // Carol has no stored token, so her session fails validation
+        for (account, has_token) in [
+            (account("bob", false), true),
+            (account("alice", true), true),
+            (account("carol", false), false),
+        ] {
+            storage.save_account(&account).await.unwrap();
+            if has_token {
+                storage.save_auth_token(&token(&account.id)).await.unwrap();
+            }
+        }
+        let mut columns = get_default_columns("did:plc:bob");
+        columns.extend(get_default_columns("did:plc:alice"));
+        columns[1].position = 1;
+        save_columns(&data_dir.to_path_buf(), columns).unwrap();
+
+        let mut server = Server::new_async().await;
+        for id in ["alice", "bob"] {
+            server
+                .mock("GET", "/xrpc/app.bsky.actor.getProfile")
+                .match_query(Matcher::UrlEncoded("actor".into(), format!("did:plc:{}", id)))
+                .with_status(200)
+                .with_body(
+                    json!({
+                        "did": format!("did:plc:{}", id),
+                        "handle": format!("{}.bsky.social", id),
+                        "displayName": id.to_uppercase(),
+                        "avatar": format!("{}/{}.jpg", server.url(), id)
+                    })
+                    .to_string(),
+                )
+                .create_async()
+                .await;
+        }
+        let avatar_mock = server
+            .mock("GET", Matcher::Regex(r"^/(alice|bob)\.jpg$".to_string()))
+            .with_status(200)
+            .with_body("image")
+            .expect(2)
+            .create_async()
+            .await;
+        let url = server.url();
+
+        let mut events = Vec::new();
+        let report = startup_sequence(
+            &storage,
+            data_dir,
+            &avatars,
+            &RefreshState::default(),
+            |_| Ok(ATProtocolClient::with_base_url(&url)),
+            |progress| events.push((progress.phase, progress.account_id.clone())),
+        )
+        .await
+        .unwrap();
+
+        // The sequence returns once the active account is ready
+        let phases: Vec<StartupPhase> = events.iter().map(|(phase, _)| *phase).collect();
+        assert_eq!(
+            phases,
+            vec![
+                StartupPhase::Restore,
+                StartupPhase::Validate,
+                StartupPhase::Validate,
+                StartupPhase::Validate,
+                StartupPhase::ActiveAccount,
+            ]
+        );
+        assert_eq!(events[4].1.as_deref(), Some("alice"));
+
+        assert_eq!(report.accounts[0].id, "alice");
+        assert_eq!(report.active_columns.len(), 1);
+        assert_eq!(report.active_columns[0].did, "did:plc:alice");
+        let updated: Vec<&str> = report.updated.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(updated, vec!["alice"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].phase, StartupPhase::Validate);
+        assert_eq!(report.failed[0].account_id, "carol");
+        assert!(report.deferred.is_empty());
+        assert_eq!(report.background, vec!["bob"]);
+        assert!(storage.get_account("bob").await.unwrap().display_name.is_none());
+
+        // The failed validation starts carol's backoff
+        let carol = storage.get_account("carol").await.unwrap();
+        assert_eq!(carol.refresh_failure_count, 1);
+        assert!(is_backing_off(&carol, Utc::now()));
+
+        let mut events = Vec::new();
+        let background = background_refresh(
+            &storage,
+            &avatars,
+            &RefreshState::default(),
+            &report.background,
+            Duration::ZERO,
+            |_| Ok(ATProtocolClient::with_base_url(&url)),
+            |progress| events.push((progress.phase, progress.account_id.clone())),
+        )
+        .await;
+        avatar_mock.assert_async().await;
+
+        assert_eq!(events, vec![(StartupPhase::Background, Some("bob".to_string()))]);
+        let updated: Vec<&str> = background.updated.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(updated, vec!["bob"]);
+        assert!(background.failed.is_empty());
+        assert_eq!(storage.get_account("bob").await.unwrap().display_name.as_deref(), Some("BOB"));
+    }
+
+    #[tokio::test]
+    async fn test_startup_sequence_defers_paused_and_backing_off_accounts() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let storage = StorageManager::new(data_dir.to_path_buf()).await.unwrap();
+        let avatars = AvatarCache::new(data_dir, Duration::from_secs(3600));
+
+        let backing_off = Account {
+            next_refresh_not_before: Some((Utc::now() + chrono::Duration::hours(1)).to_rfc3339()),
+            ..account("bob", false)
+        };
+        for account in [account("alice", true), backing_off] {
+            storage.save_account(&account).await.unwrap();
+            storage.save_auth_token(&token(&account.id)).await.unwrap();
+        }
+
+        let mut server = Server::new_async().await;
+        let profile = server
+            .mock("GET", "/xrpc/app.bsky.actor.getProfile")
+            .match_query(Matcher::UrlEncoded("actor".into(), "did:plc:alice".into()))
+            .with_status(200)
+            .with_body(json!({"did": "did:plc:alice", "handle": "alice.bsky.social"}).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let url = server.url();
+        let client_for = |_: &Account| Ok(ATProtocolClient::with_base_url(&url));
+        let refresh_state = RefreshState::default();
+
+        // Bob is inside his backoff window
+        let mut validated = Vec::new();
+        let report = startup_sequence(
+            &storage,
+            data_dir,
+            &avatars,
+            &refresh_state,
+            client_for,
+            |progress| {
+                if progress.phase == StartupPhase::Validate {
+                    validated.push(progress.account_id.clone().unwrap());
+                }
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(validated, vec!["alice"]);
+        assert_eq!(report.deferred, vec!["bob"]);
+        assert!(report.failed.is_empty());
+
+        // Paused: nobody is validated and no requests are made
+        refresh_state.set_paused(true);
+        let mut validated = Vec::new();
+        let report = startup_sequence(
+            &storage,
+            data_dir,
+            &avatars,
+            &refresh_state,
+            client_for,
+            |progress| {
+                if progress.phase != StartupPhase::Restore {
+                    validated.push(progress.account_id.clone());
+                }
+            },
+        )
+        .await
+        .unwrap();
+        assert!(validated.is_empty());
+        assert_eq!(report.deferred, vec!["alice", "bob"]);
+        assert_eq!(report.accounts.len(), 2);
+
+        // The background refresh stops once refreshes are paused
+        let background = background_refresh(
+            &storage,
+            &avatars,
+            &refresh_state,
+            &["bob".to_string()],
+            Duration::ZERO,
+            client_for,
+            |_| panic!("no step may run while paused"),
+        )
+        .await;
+        assert_eq!(background.deferred, vec!["bob"]);
+        profile.assert_async().await;
+    }
+}
@@ -0,0 +1,424 @@
+/**
+ * Single-account export/import
+ *
+ * Moves one account (metadata, token and deck columns) between devices as a
+ * password-encrypted, versioned bundle
+ */
+
+use crate::accounts::ensure_active_invariant;
+use crate::auth::ATProtocolClient;
+use crate::storage::columns::{
+    account_dids, ensure_known_dids, load_columns, lock_columns, save_columns,
+};
+use crate::storage::crypto::{decrypt, derive_key_from_password, encrypt, generate_salt};
+use crate::storage::StorageManager;
+use crate::types::{Account, AuthError, AuthToken, DeckColumnConfig};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// Current account bundle format version
+pub const ACCOUNT_BUNDLE_VERSION: u32 = 1;
+
+/// Plaintext of the password verifier stored in the envelope
+const VERIFIER_PLAINTEXT: &[u8] = b"taurisky-account-bundle";
+
+/// Outer (unencrypted) bundle envelope
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleEnvelope {
+    /// Bundle format version
+    version: u32,
+    /// Key derivation salt (base64)
+    salt: String,
+    /// Encrypted `VERIFIER_PLAINTEXT`, checked before the payload is decrypted
+    /// (absent in bundles written before it was added)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    verifier: Option<String>,
+    /// Encrypted `BundlePayload` (base64 nonce + ciphertext)
+    data: String,
+}
+
+impl BundleEnvelope {
+    /// Parse and version-check a bundle
+    fn parse(data: &str) -> Result<Self, String> {
+        let envelope: BundleEnvelope =
+            serde_json::from_str(data).map_err(|e| format!("Invalid account bundle: {}", e))?;
+
+        if envelope.version != ACCOUNT_BUNDLE_VERSION {
+            return Err(format!(
+                "Unsupported account bundle version {} (expected {})",
+                envelope.version, ACCOUNT_BUNDLE_VERSION
+            ));
+        }
+
+        Ok(envelope)
+    }
+
+    /// Derive the bundle key from a password
+    fn derive_key(&self, password: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+        let salt = BASE64
+            .decode(&self.salt)
+            .map_err(|e| format!("Invalid account bundle salt: {}", e))?;
+        derive_key_from_password(password, &salt)
+    }
+
+    /// Whether the key opens the verifier (None for bundles without a verifier)
+    fn verifier_matches(&self, key: &[u8]) -> Option<bool> {
+        let verifier = self.verifier.as_ref()?;
+        Some(decrypt(verifier, key).is_ok_and(|plain| plain == VERIFIER_PLAINTEXT))
+    }
+}
+
+/// Encrypted bundle contents
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundlePayload {
+    account: Account,
+    token: AuthToken,
+    columns: Vec<DeckColumnConfig>,
+}
+
+/// Export one account as an encrypted bundle
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory (columns file location)
+/// * `account_id` - Account to export
+/// * `password` - Password protecting the bundle
+pub async fn export_account(
+    storage: &StorageManager,
+    data_dir: &Path,
+    account_id: &str,
+    password: &str,
+) -> Result<String, String> {
+    if password.is_empty() {
+        return Err("Bundle password must not be empty".to_string());
+    }
+
+    let account = storage
+        .get_account(account_id)
+        .await
+        .map_err(|e| format!("Failed to get account: {}", e))?;
+    let token = storage
+        .get_auth_token(account_id)
+        .await
+        .map_err(|e| format!("Failed to get token: {}", e))?;
+    let columns = load_columns(&data_dir.to_path_buf())?
+        .into_iter()
+        .filter(|c| c.did == account.did)
+        .collect();
+
+    let payload = serde_json::to_vec(&BundlePayload {
+        account,
+        token,
+        columns,
+    })
+    .map_err(|e| format!("Failed to serialize bundle: {}", e))?;
+
+    let salt = generate_salt();
+    let key = derive_key_from_password(password, &salt)?;
+
+    serde_json::to_string_pretty(&BundleEnvelope {
+        version: ACCOUNT_BUNDLE_VERSION,
+        salt: BASE64.encode(&salt),
+        verifier: Some(encrypt(VERIFIER_PLAINTEXT, &key)?),
+        data: encrypt(&payload, &key)?,
+    })
+    .map_err(|e| format!("Failed to serialize bundle: {}", e))
+}
+
+/// Check a bundle password without decrypting the payload
+///
+/// # Arguments
+/// * `data` - Bundle JSON
+/// * `password` - Password to check
+///
+/// # Note
+/// Only the small verifier is decrypted; bundles written before the verifier was
+/// added fall back to decrypting the payload.
+pub fn verify_bundle_password(data: &str, password: &str) -> Result<bool, String> {
+    let envelope = BundleEnvelope::parse(data)?;
+    let key = envelope.derive_key(password)?;
+
+    Ok(envelope
+        .verifier_matches(&key)
+        .unwrap_or_else(|| decrypt(&envelope.data, &key).is_ok()))
+}
+
+/// Check that a bundled token still works, refreshing it if only the access token died
+///
+/// The account handle is updated to the one reported by the server.
+async fn validate_bundled_token(
+    client: &ATProtocolClient,
+    account: &mut Account,
+    token: AuthToken,
+) -> Result<AuthToken, String> {
+    let token = match client.get_session(&token.access_jwt).await {
+        Ok(session) => {
+            if session.did != account.did {
+                return Err("Bundled token belongs to a different account".to_string());
+            }
+            account.handle = session.handle;
+            token
+        }
+        Err(AuthError::NetworkError(e)) => {
+            return Err(format!("Failed to validate bundled token: {}", e));
+        }
+        Err(_) => {
+            let session = client
+                .refresh_session(&token.refresh_jwt)
+                .await
+                .map_err(|e| format!("Bundled tokens are no longer valid: {}", e))?;
+            if session.did != account.did {
+                return Err("Bundled token belongs to a different account".to_string());
+            }
+            account.handle = session.handle.clone();
+            AuthToken::from_session(&token.account_id, session)
+        }
+    };
+
+    Ok(token)
+}
+
+/// Import an account bundle produced by `export_account`
+///
+/// Tokens are validated with getSession (and refreshed if needed) before anything is
+/// saved. An account with the same DID is updated in place instead of duplicated, and
+/// its columns are replaced by the bundled ones.
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory (columns file location)
+/// * `data` - Bundle JSON
+/// * `password` - Bundle password
+/// * `client_for` - Builds the client for the bundled account's PDS
+pub async fn import_account<F>(
+    storage: &StorageManager,
+    data_dir: &Path,
+    data: &str,
+    password: &str,
+    client_for: F,
+) -> Result<Account, String>
+where
+    F: Fn(&Account) -> Result<ATProtocolClient, AuthError>,
+{
+    let envelope = BundleEnvelope::parse(data)?;
+    let key = envelope.derive_key(password)?;
+    if envelope.verifier_matches(&key) == Some(false) {
+        return Err("Failed to unlock account bundle (wrong password)".to_string());
+    }
+    let payload = decrypt(&envelope.data, &key)
+        .map_err(|_| "Failed to decrypt account bundle (wrong password?)".to_string())?;
+    let BundlePayload {
+        mut account,
+        token,
+        columns,
+    } = serde_json::from_slice(&payload).map_err(|e| format!("Invalid account bundle: {}", e))?;
+
+    let client = client_for(&account).map_err(|e| format!("Failed to create client: {}", e))?;
+    let mut token = validate_bundled_token(&client, &mut account, token).await?;
+
+    // Dedup by DID: reuse the local account ID
+    let existing = storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?
+        .into_iter()
+        .find(|a| a.did == account.did);
+    match existing {
+        Some(existing) => {
+            account.id = existing.id;
+            account.is_active = existing.is_active;
+        }
+        None => {
+            account.id = Uuid::new_v4().to_string();
+            account.is_active = false;
+        }
+    }
+    token.account_id = account.id.clone();
+
+    // Bundled columns may only belong to the bundled account or an existing one
+    let mut dids = account_dids(storage).await?;
+    dids.push(account.did.clone());
+    ensure_known_dids(&dids, &columns)?;
+
+    storage
+        .save_account(&account)
+        .await
+        .map_err(|e| format!("Failed to save account: {}", e))?;
+    storage
+        .save_auth_token(&token)
+        .await
+        .map_err(|e| format!("Failed to save token: {}", e))?;
+
+    if !columns.is_empty() {
+        let data_dir = data_dir.to_path_buf();
+        let _guard = lock_columns()?;
+        let mut merged: Vec<DeckColumnConfig> = load_columns(&data_dir)?
+            .into_iter()
+            .filter(|c| c.did != account.did)
+            .collect();
+        merged.extend(columns.into_iter().map(|column| DeckColumnConfig {
+            id: Uuid::new_v4().to_string(),
+            ..column
+        }));
+        for (index, column) in merged.iter_mut().enumerate() {
+            column.position = index as u32;
+        }
+        save_columns(&data_dir, merged)?;
+    }
+
+    // Activate the imported account if it is the only one
+    let report = ensure_active_invariant(storage)
+        .await
+        .map_err(|e| format!("Failed to update active account: {}", e))?;
+    if report.activated.as_deref() == Some(account.id.as_str()) {
+        account.is_active = true;
+    }
+
+    Ok(account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::storage_with_account;
+    use mockito::Server;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let source_dir = TempDir::new().unwrap();
+        let source = storage_with_account(source_dir.path()).await;
+        let bundle = export_account(&source, source_dir.path(), "alice", "bundle-pw")
+            .await
+            .unwrap();
+        assert!(!bundle.contains("access"));
+
+        let mut server = Server::new_async().await;
+        let get_session = server
+            .mock("GET", "/xrpc/com.atproto.server.getSession")
+            .match_header("authorization", "Bearer secret-access")
+            .with_status(200)
+            .with_body(r#"{"did":"did:plc:alice","handle":"alice.bsky.social"}"#)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        let url = server.url();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = StorageManager::new(target_dir.path().to_path_buf()).await.unwrap();
+        let imported = import_account(&target, target_dir.path(), &bundle, "bundle-pw", |_| {
+            Ok(ATProtocolClient::with_base_url(&url))
+        })
+        .await
+        .unwrap();
+
+        get_session.assert_async().await;
+        assert_eq!(imported.did, "did:plc:alice");
+        assert!(imported.is_active);
+        assert_eq!(
+            target.get_auth_token(&imported.id).await.unwrap().access_jwt,
+            "secret-access"
+        );
+        let columns = load_columns(&target_dir.path().to_path_buf()).unwrap();
+        assert_eq!(columns.len(), 1);
+        assert_eq!(columns[0].did, "did:plc:alice");
+
+        // Importing again updates the same account instead of duplicating it
+        let again = import_account(&target, target_dir.path(), &bundle, "bundle-pw", |_| {
+            Ok(ATProtocolClient::with_base_url(&url))
+        })
+        .await
+        .unwrap();
+        assert_eq!(again.id, imported.id);
+        assert_eq!(target.list_accounts().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_dead_tokens() {
+        let source_dir = TempDir::new().unwrap();
+        let source = storage_with_account(source_dir.path()).await;
+        let bundle = export_account(&source, source_dir.path(), "alice", "bundle-pw")
+            .await
+            .unwrap();
+
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/xrpc/com.atproto.server.getSession")
+            .with_status(401)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .with_status(400)
+            .with_body(r#"{"error":"ExpiredToken","message":"Token has expired"}"#)
+            .create_async()
+            .await;
+        let url = server.url();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = StorageManager::new(target_dir.path().to_path_buf()).await.unwrap();
+        let result = import_account(&target, target_dir.path(), &bundle, "bundle-pw", |_| {
+            Ok(ATProtocolClient::with_base_url(&url))
+        })
+        .await;
+
+        assert!(result.unwrap_err().contains("no longer valid"));
+        assert!(target.list_accounts().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_version_mismatch_and_wrong_password() {
+        let source_dir = TempDir::new().unwrap();
+        let source = storage_with_account(source_dir.path()).await;
+        let bundle = export_account(&source, source_dir.path(), "alice", "bundle-pw")
+            .await
+            .unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let target = StorageManager::new(target_dir.path().to_path_buf()).await.unwrap();
+        let offline = |_: &Account| Ok(ATProtocolClient::with_base_url("http://127.0.0.1:9"));
+
+        let mut envelope: serde_json::Value = serde_json::from_str(&bundle).unwrap();
+        envelope["version"] = serde_json::json!(ACCOUNT_BUNDLE_VERSION + 1);
+        let result =
+            import_account(&target, target_dir.path(), &envelope.to_string(), "bundle-pw", offline)
+                .await;
+        assert!(result.unwrap_err().contains("Unsupported account bundle version"));
+
+        let result = import_account(&target, target_dir.path(), &bundle, "wrong", offline).await;
+        assert!(result.unwrap_err().contains("wrong password"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_bundle_password() {
+        let source_dir = TempDir::new().unwrap();
+        let source = storage_with_account(source_dir.path()).await;
+        let bundle = export_account(&source, source_dir.path(), "alice", "bundle-pw")
+            .await
+            .unwrap();
+
+        assert!(verify_bundle_password(&bundle, "bundle-pw").unwrap());
+        assert!(!verify_bundle_password(&bundle, "wrong").unwrap());
+
+        // The verifier is checked first: a wrong password never reaches the payload
+        let mut envelope: serde_json::Value = serde_json::from_str(&bundle).unwrap();
+        envelope["data"] = serde_json::json!("not-ciphertext");
+        let target_dir = TempDir::new().unwrap();
+        let target = StorageManager::new(target_dir.path().to_path_buf()).await.unwrap();
+        let offline = |_: &Account| Ok(ATProtocolClient::with_base_url("http://127.0.0.1:9"));
+        let result =
+            import_account(&target, target_dir.path(), &envelope.to_string(), "wrong", offline)
+                .await;
+        assert!(result.unwrap_err().contains("Failed to unlock"));
+
+        // Bundles without a verifier are checked against the payload
+        let mut legacy: serde_json::Value = serde_json::from_str(&bundle).unwrap();
+        legacy.as_object_mut().unwrap().remove("verifier");
+        assert!(verify_bundle_password(&legacy.to_string(), "bundle-pw").unwrap());
+        assert!(!verify_bundle_password(&legacy.to_string(), "wrong").unwrap());
+    }
+}
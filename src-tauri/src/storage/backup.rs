@@ -0,0 +1,480 @@
+/**
+ * Full backup export/import
+ *
+ * A backup holds the settings, columns and encrypted account storage files. Imports
+ * are staged and verified before anything in the data directory is touched, so an
+ * interrupted import leaves the existing data intact.
+ */
+
+use crate::storage::columns::{lock_columns, COLUMNS_FILE};
+use crate::storage::keyfile::KEY_FILE;
+use crate::storage::persistence::STORAGE_FILE;
+use crate::storage::settings::SETTINGS_FILE;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Current backup format version
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Files covered by a backup (restoring a backup replaces all of them)
+const BACKUP_FILES: [&str; 4] = [SETTINGS_FILE, COLUMNS_FILE, STORAGE_FILE, KEY_FILE];
+
+/// Directory (inside the data dir) holding staged imports
+const STAGING_DIR: &str = "backup-staging";
+
+/// Manifest written next to the staged files
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Marker written once every staged file matched its checksum
+const VALIDATED_MARKER: &str = "validated";
+
+/// Subdirectory of a staging set receiving the replaced files during the swap
+const PREVIOUS_DIR: &str = "previous";
+
+/// Journal written before the first rename of a swap
+const SWAP_JOURNAL: &str = "swap.json";
+
+/// Swap in progress (lets `resume_import` finish or undo an interrupted swap)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapJournal {
+    /// Live files that are moved into `previous/` before the staged files move in
+    displaced: Vec<String>,
+}
+
+/// One file of a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupFile {
+    /// File name inside the data directory
+    name: String,
+    /// SHA-256 of the contents (hex)
+    sha256: String,
+    /// File contents (base64; omitted in the staging manifest)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+}
+
+/// Backup document (also used, without file data, as the staging manifest)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Backup {
+    /// Backup format version
+    version: u32,
+    /// When the backup was taken (ISO 8601)
+    created_at: String,
+    /// Backed-up files
+    files: Vec<BackupFile>,
+}
+
+/// Hex SHA-256 of file contents
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Directory of a staging set
+fn staging_path(data_dir: &Path, staging_id: &str) -> Result<PathBuf, String> {
+    if Uuid::parse_str(staging_id).is_err() {
+        return Err(format!("Invalid staging ID '{}'", staging_id));
+    }
+
+    Ok(data_dir.join(STAGING_DIR).join(staging_id))
+}
+
+/// Export the settings, columns and account storage files as a backup document
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+///
+/// # Note
+/// Account data stays encrypted; the key file is included so the storage file can
+/// be decrypted after restoring.
+pub fn export_backup(data_dir: &Path) -> Result<String, String> {
+    let mut files = Vec::new();
+
+    for name in BACKUP_FILES {
+        let path = data_dir.join(name);
+        if !path.exists() {
+            continue;
+        }
+
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        files.push(BackupFile {
+            name: name.to_string(),
+            sha256: sha256_hex(&bytes),
+            data: Some(BASE64.encode(&bytes)),
+        });
+    }
+
+    serde_json::to_string_pretty(&Backup {
+        version: BACKUP_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        files,
+    })
+    .map_err(|e| format!("Failed to serialize backup: {}", e))
+}
+
+/// Write the backup files into a new staging set and verify them
+///
+/// # Returns
+/// ID of the validated staging set
+pub(crate) fn stage_backup(data_dir: &Path, data: &str) -> Result<String, String> {
+    let backup: Backup =
+        serde_json::from_str(data).map_err(|e| format!("Invalid backup: {}", e))?;
+
+    if backup.version != BACKUP_VERSION {
+        return Err(format!(
+            "Unsupported backup version {} (expected {})",
+            backup.version, BACKUP_VERSION
+        ));
+    }
+
+    let has = |name: &str| backup.files.iter().any(|f| f.name == name);
+    if has(STORAGE_FILE) != has(KEY_FILE) {
+        return Err("Incomplete backup: storage and key files must be restored together".into());
+    }
+    if let Some(unknown) = backup.files.iter().find(|f| !BACKUP_FILES.contains(&f.name.as_str())) {
+        return Err(format!("Unexpected file in backup: {}", unknown.name));
+    }
+
+    let staging_id = Uuid::new_v4().to_string();
+    let staging_dir = staging_path(data_dir, &staging_id)?;
+    fs::create_dir_all(&staging_dir)
+        .map_err(|e| format!("Failed to create staging dir: {}", e))?;
+
+    let staged = (|| {
+        let mut manifest = backup.clone();
+        for file in &mut manifest.files {
+            let bytes = BASE64
+                .decode(file.data.take().unwrap_or_default())
+                .map_err(|e| format!("Invalid data for {}: {}", file.name, e))?;
+            fs::write(staging_dir.join(&file.name), bytes)
+                .map_err(|e| format!("Failed to stage {}: {}", file.name, e))?;
+        }
+
+        let manifest = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        fs::write(staging_dir.join(MANIFEST_FILE), manifest)
+            .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+        validate_staging(&staging_dir)
+    })();
+
+    if let Err(e) = staged {
+        let _ = fs::remove_dir_all(&staging_dir);
+        return Err(e);
+    }
+
+    Ok(staging_id)
+}
+
+/// Read the manifest of a staging set
+fn read_manifest(staging_dir: &Path) -> Result<Backup, String> {
+    let manifest = fs::read_to_string(staging_dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+
+    serde_json::from_str(&manifest).map_err(|e| format!("Invalid manifest: {}", e))
+}
+
+/// Check every staged file against the manifest checksums and mark the set validated
+fn validate_staging(staging_dir: &Path) -> Result<Backup, String> {
+    let manifest = read_manifest(staging_dir)?;
+
+    for file in &manifest.files {
+        let bytes = fs::read(staging_dir.join(&file.name))
+            .map_err(|e| format!("Staged file {} is missing: {}", file.name, e))?;
+        if sha256_hex(&bytes) != file.sha256 {
+            return Err(format!("Checksum mismatch for {}", file.name));
+        }
+    }
+
+    fs::write(staging_dir.join(VALIDATED_MARKER), "")
+        .map_err(|e| format!("Failed to mark staging set validated: {}", e))?;
+
+    Ok(manifest)
+}
+
+/// Read the swap journal of a staging set (None if no swap was started)
+fn read_journal(staging_dir: &Path) -> Result<Option<SwapJournal>, String> {
+    let path = staging_dir.join(SWAP_JOURNAL);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let journal = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read swap journal: {}", e))?;
+    serde_json::from_str(&journal)
+        .map(Some)
+        .map_err(|e| format!("Invalid swap journal: {}", e))
+}
+
+/// Record the files a swap is about to displace (before anything is renamed)
+fn begin_swap(data_dir: &Path, staging_dir: &Path) -> Result<SwapJournal, String> {
+    let journal = SwapJournal {
+        displaced: BACKUP_FILES
+            .iter()
+            .filter(|name| data_dir.join(name).exists())
+            .map(|name| name.to_string())
+            .collect(),
+    };
+
+    let contents = serde_json::to_string_pretty(&journal)
+        .map_err(|e| format!("Failed to serialize swap journal: {}", e))?;
+    let temp_path = staging_dir.join(format!("{}.tmp", SWAP_JOURNAL));
+    fs::write(&temp_path, contents).map_err(|e| format!("Failed to write swap journal: {}", e))?;
+    fs::rename(&temp_path, staging_dir.join(SWAP_JOURNAL))
+        .map_err(|e| format!("Failed to write swap journal: {}", e))?;
+
+    Ok(journal)
+}
+
+/// Move the staged files into the data directory, restoring the old ones on failure
+///
+/// Every step checks where its file currently is, so a swap interrupted by a crash
+/// is finished by running it again with the same journal.
+fn swap_staging(
+    data_dir: &Path,
+    staging_dir: &Path,
+    manifest: &Backup,
+    journal: &SwapJournal,
+) -> Result<(), String> {
+    let previous_dir = staging_dir.join(PREVIOUS_DIR);
+    fs::create_dir_all(&previous_dir)
+        .map_err(|e| format!("Failed to create rollback dir: {}", e))?;
+
+    let result = (|| {
+        // Move every current backup-covered file aside first...
+        for name in &journal.displaced {
+            let aside = previous_dir.join(name);
+            if !aside.exists() {
+                fs::rename(data_dir.join(name), aside)
+                    .map_err(|e| format!("Failed to move aside {}: {}", name, e))?;
+            }
+        }
+
+        // ...then move the staged files in
+        for file in &manifest.files {
+            let staged = staging_dir.join(&file.name);
+            if staged.exists() {
+                fs::rename(staged, data_dir.join(&file.name))
+                    .map_err(|e| format!("Failed to restore {}: {}", file.name, e))?;
+            }
+        }
+
+        for file in &manifest.files {
+            let bytes = fs::read(data_dir.join(&file.name))
+                .map_err(|e| format!("Restored file {} is missing: {}", file.name, e))?;
+            if sha256_hex(&bytes) != file.sha256 {
+                return Err(format!("Checksum mismatch for restored {}", file.name));
+            }
+        }
+
+        Ok(())
+    })();
+
+    if result.is_err() {
+        for file in &manifest.files {
+            let staged = staging_dir.join(&file.name);
+            if !staged.exists() {
+                let _ = fs::rename(data_dir.join(&file.name), staged);
+            }
+        }
+        for name in &journal.displaced {
+            let aside = previous_dir.join(name);
+            if aside.exists() {
+                let _ = fs::rename(aside, data_dir.join(name));
+            }
+        }
+        let _ = fs::remove_file(staging_dir.join(SWAP_JOURNAL));
+    }
+
+    result
+}
+
+/// Complete a validated staging set and delete it
+///
+/// A set with a swap journal was interrupted mid-swap; its swap is finished (or
+/// undone if that fails) instead of re-validating the partly moved staged files.
+/// The columns lock is held during the swap, so no columns write races the rename.
+fn complete_staging(data_dir: &Path, staging_id: &str) -> Result<(), String> {
+    let staging_dir = staging_path(data_dir, staging_id)?;
+
+    if !staging_dir.join(VALIDATED_MARKER).exists() {
+        return Err(format!("Staging set {} is missing or was never validated", staging_id));
+    }
+
+    let (manifest, journal) = match read_journal(&staging_dir)? {
+        Some(journal) => (read_manifest(&staging_dir)?, journal),
+        None => {
+            let manifest = validate_staging(&staging_dir)?;
+            (manifest, begin_swap(data_dir, &staging_dir)?)
+        }
+    };
+    {
+        let _columns = lock_columns()?;
+        swap_staging(data_dir, &staging_dir, &manifest, &journal).map_err(|e| {
+            format!("{} (data left unchanged; retry with resume_import {})", e, staging_id)
+        })?;
+    }
+
+    let _ = fs::remove_dir_all(&staging_dir);
+    Ok(())
+}
+
+/// Restore a backup produced by `export_backup`
+///
+/// Files are staged and checksum-verified first and only then swapped into place.
+/// A failure at any point leaves the existing data intact.
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `data` - Backup JSON
+///
+/// # Note
+/// Run it through `StorageManager::restore_files`, which reloads the restored accounts
+/// without letting a save in between.
+pub fn import_backup(data_dir: &Path, data: &str) -> Result<(), String> {
+    let staging_id = stage_backup(data_dir, data)?;
+    complete_staging(data_dir, &staging_id)
+}
+
+/// Finish an import whose staging set was validated but never (fully) swapped into place
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `staging_id` - ID of the staging set (as reported by the failed import)
+pub fn resume_import(data_dir: &Path, staging_id: &str) -> Result<(), String> {
+    complete_staging(data_dir, staging_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn seed_data_dir(data_dir: &Path, marker: &str) {
+        fs::write(data_dir.join(SETTINGS_FILE), format!(r#"{{"version":1,"m":"{}"}}"#, marker))
+            .unwrap();
+        fs::write(data_dir.join(COLUMNS_FILE), format!(r#"[{{"m":"{}"}}]"#, marker)).unwrap();
+        fs::write(data_dir.join(STORAGE_FILE), format!("storage-{}", marker)).unwrap();
+        fs::write(data_dir.join(KEY_FILE), format!("salt-{}", marker)).unwrap();
+    }
+
+    fn read_all(data_dir: &Path) -> Vec<String> {
+        BACKUP_FILES
+            .iter()
+            .map(|name| fs::read_to_string(data_dir.join(name)).unwrap())
+            .collect()
+    }
+
+    /// A backup of a data dir seeded with `marker`
+    fn backup_with_marker(marker: &str) -> String {
+        let source = TempDir::new().unwrap();
+        seed_data_dir(source.path(), marker);
+        export_backup(source.path()).unwrap()
+    }
+
+    #[test]
+    fn test_import_backup_replaces_files() {
+        let target = TempDir::new().unwrap();
+        seed_data_dir(target.path(), "old");
+        let expected = {
+            let source = TempDir::new().unwrap();
+            seed_data_dir(source.path(), "new");
+            read_all(source.path())
+        };
+
+        import_backup(target.path(), &backup_with_marker("new")).unwrap();
+
+        assert_eq!(read_all(target.path()), expected);
+        assert_eq!(fs::read_dir(target.path().join(STAGING_DIR)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_corrupted_backup_leaves_data_intact() {
+        let target = TempDir::new().unwrap();
+        seed_data_dir(target.path(), "old");
+        let original = read_all(target.path());
+
+        let mut backup: serde_json::Value =
+            serde_json::from_str(&backup_with_marker("new")).unwrap();
+        backup["files"][2]["data"] = serde_json::json!(BASE64.encode("tampered"));
+
+        let result = import_backup(target.path(), &backup.to_string());
+
+        assert!(result.unwrap_err().contains("Checksum mismatch"));
+        assert_eq!(read_all(target.path()), original);
+        assert_eq!(fs::read_dir(target.path().join(STAGING_DIR)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_interrupted_import_can_be_resumed() {
+        let target = TempDir::new().unwrap();
+        seed_data_dir(target.path(), "old");
+        let original = read_all(target.path());
+
+        // The import stops after staging (e.g., the app was closed)
+        let staging_id = stage_backup(target.path(), &backup_with_marker("new")).unwrap();
+        assert_eq!(read_all(target.path()), original);
+
+        resume_import(target.path(), &staging_id).unwrap();
+
+        assert!(read_all(target.path()).iter().all(|contents| contents.contains("new")));
+        assert!(!target.path().join(STAGING_DIR).join(&staging_id).exists());
+        // A completed staging set cannot be resumed again
+        assert!(resume_import(target.path(), &staging_id).is_err());
+    }
+
+    #[test]
+    fn test_resume_finishes_swap_interrupted_by_crash() {
+        // Crash after the first move-aside, and after the first staged file moved in
+        for renames in [1, BACKUP_FILES.len() + 1] {
+            let target = TempDir::new().unwrap();
+            let data_dir = target.path();
+            seed_data_dir(data_dir, "old");
+            let staging_id = stage_backup(data_dir, &backup_with_marker("new")).unwrap();
+
+            let staging_dir = staging_path(data_dir, &staging_id).unwrap();
+            let journal = begin_swap(data_dir, &staging_dir).unwrap();
+            let previous_dir = staging_dir.join(PREVIOUS_DIR);
+            fs::create_dir_all(&previous_dir).unwrap();
+            let moves_aside = journal
+                .displaced
+                .iter()
+                .map(|name| (data_dir.join(name), previous_dir.join(name)));
+            let moves_in = BACKUP_FILES
+                .iter()
+                .map(|name| (staging_dir.join(name), data_dir.join(name)));
+            for (from, to) in moves_aside.chain(moves_in).take(renames) {
+                fs::rename(from, to).unwrap();
+            }
+
+            resume_import(data_dir, &staging_id).unwrap();
+
+            assert!(read_all(data_dir).iter().all(|contents| contents.contains("new")));
+            assert!(!staging_dir.exists());
+        }
+    }
+
+    #[test]
+    fn test_incomplete_backup_is_rejected() {
+        let target = TempDir::new().unwrap();
+        seed_data_dir(target.path(), "old");
+
+        let mut backup: serde_json::Value =
+            serde_json::from_str(&backup_with_marker("new")).unwrap();
+        backup["files"].as_array_mut().unwrap().retain(|f| f["name"] != KEY_FILE);
+
+        let result = import_backup(target.path(), &backup.to_string());
+
+        assert!(result.unwrap_err().contains("Incomplete backup"));
+        assert!(read_all(target.path()).iter().all(|contents| contents.contains("old")));
+    }
+}
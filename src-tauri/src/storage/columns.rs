@@ -4,13 +4,119 @@
  * Handles reading and writing deck column configurations to/from JSON file
  */
 
-use crate::types::{ColumnType, ColumnWidth, DeckColumnConfig};
+use crate::auth::ATProtocolClient;
+use crate::storage::columns_footprint::{prune_column_snapshots, COLUMN_SNAPSHOTS_DIR};
+use crate::storage::maintenance::DEFAULT_SNAPSHOT_KEEP;
+use crate::storage::presets::MAX_PRESET_COLUMNS;
+use crate::storage::schema::COLUMNS_VERSION;
+use crate::storage::StorageManager;
+use crate::types::{ColumnPatch, ColumnType, ColumnWidth, DeckColumnConfig};
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
 use uuid::Uuid;
 
-const COLUMNS_FILE: &str = "columns.json";
+pub(crate) const COLUMNS_FILE: &str = "columns.json";
+
+/// Archive of a legacy flat columns file (written by `migrate_legacy_columns`)
+pub(crate) const LEGACY_COLUMNS_FILE: &str = "columns.legacy.json";
+
+/// Per-account layout of the columns file
+///
+/// Files written before account-scoped storage are a bare array of columns; they are
+/// still read, and converted by `migrate_legacy_columns`.
+#[derive(Debug, Serialize, Deserialize)]
+struct ColumnsFile {
+    version: u32,
+    /// Columns keyed by account DID
+    accounts: BTreeMap<String, Vec<DeckColumnConfig>>,
+}
+
+/// Serializes read-modify-write cycles of the columns file (see `lock_columns`)
+static COLUMNS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Take the columns lock
+///
+/// Every writer of the columns file holds it from load to save, so concurrent
+/// changes are applied one after the other and none of them is lost.
+///
+/// # Note
+/// Not reentrant: functions of this module that take it must not call each other.
+pub(crate) fn lock_columns() -> Result<MutexGuard<'static, ()>, String> {
+    COLUMNS_LOCK
+        .lock()
+        .map_err(|e| format!("Columns lock error: {}", e))
+}
+
+/// Column setting holding the fetch page size (XRPC `limit`)
+pub const PAGE_SIZE_SETTING: &str = "pageSize";
+
+/// Largest page size accepted by the API for a column type
+///
+/// getTimeline, listNotifications and getFeed all cap `limit` at 100.
+pub fn max_page_size(column_type: &ColumnType) -> u32 {
+    match column_type {
+        ColumnType::Timeline | ColumnType::Notifications | ColumnType::Feed => 100,
+    }
+}
+
+/// Page size used when a column has no `pageSize` setting
+pub fn default_page_size(column_type: &ColumnType) -> u32 {
+    match column_type {
+        // Notifications are small rows; fetch more per page
+        ColumnType::Notifications => 50,
+        ColumnType::Timeline | ColumnType::Feed => 30,
+    }
+}
+
+/// Validate the `pageSize` setting of a column
+///
+/// The setting is optional; when present it must be an integer between 1 and the
+/// API maximum of the column type.
+pub fn validate_page_size(column: &DeckColumnConfig) -> Result<(), String> {
+    let Some(value) = column.settings.as_ref().and_then(|s| s.get(PAGE_SIZE_SETTING)) else {
+        return Ok(());
+    };
+
+    let max = max_page_size(&column.column_type);
+    match value.as_u64() {
+        Some(size) if (1..=max as u64).contains(&size) => Ok(()),
+        _ => Err(format!(
+            "Column {}: pageSize must be an integer between 1 and {} (got {})",
+            column.id, max, value
+        )),
+    }
+}
+
+/// Page size to fetch for a column (configured value clamped to the API maximum)
+pub fn column_page_size(column: &DeckColumnConfig) -> u32 {
+    let max = max_page_size(&column.column_type);
+
+    column
+        .settings
+        .as_ref()
+        .and_then(|settings| settings.get(PAGE_SIZE_SETTING))
+        .and_then(|value| value.as_u64())
+        .map(|size| size.clamp(1, max as u64) as u32)
+        .unwrap_or_else(|| default_page_size(&column.column_type))
+}
+
+/// Look up the page size of a stored column
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `column_id` - Column being fetched
+pub fn page_size_for_column(data_dir: &PathBuf, column_id: &str) -> Result<u32, String> {
+    load_columns(data_dir)?
+        .iter()
+        .find(|column| column.id == column_id)
+        .map(column_page_size)
+        .ok_or_else(|| format!("Column not found: {}", column_id))
+}
 
 /// Load column configurations from file
 ///
@@ -26,8 +132,16 @@ pub fn load_columns(data_dir: &PathBuf) -> Result<Vec<DeckColumnConfig>, String>
     let content = fs::read_to_string(&columns_path)
         .map_err(|e| format!("Failed to read columns file: {}", e))?;
 
-    let mut columns: Vec<DeckColumnConfig> = serde_json::from_str(&content)
+    let value: Value = serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse columns JSON: {}", e))?;
+    let parsed = if value.is_array() {
+        serde_json::from_value(value)
+    } else {
+        serde_json::from_value::<ColumnsFile>(value)
+            .map(|file| file.accounts.into_values().flatten().collect())
+    };
+    let mut columns: Vec<DeckColumnConfig> =
+        parsed.map_err(|e| format!("Failed to parse columns JSON: {}", e))?;
 
     // Sort by position
     columns.sort_by_key(|c| c.position);
@@ -35,9 +149,29 @@ pub fn load_columns(data_dir: &PathBuf) -> Result<Vec<DeckColumnConfig>, String>
     Ok(columns)
 }
 
+/// Copy the current columns file into the snapshots directory
+///
+/// Only the newest `DEFAULT_SNAPSHOT_KEEP` snapshots are kept.
+fn snapshot_columns(data_dir: &Path) -> Result<(), String> {
+    let columns_path = data_dir.join(COLUMNS_FILE);
+    if !columns_path.exists() {
+        return Ok(());
+    }
+
+    let snapshots_dir = data_dir.join(COLUMN_SNAPSHOTS_DIR);
+    fs::create_dir_all(&snapshots_dir)
+        .map_err(|e| format!("Failed to create snapshots dir: {}", e))?;
+    let name = format!("columns-{}.json", Utc::now().format("%Y%m%dT%H%M%S%.6fZ"));
+    fs::copy(&columns_path, snapshots_dir.join(name))
+        .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+
+    prune_column_snapshots(data_dir, DEFAULT_SNAPSHOT_KEEP).map(|_| ())
+}
+
 /// Save column configurations to file
 ///
-/// Uses atomic write (temp file + rename) to prevent corruption
+/// Uses atomic write (temp file + rename) to prevent corruption. The file being
+/// replaced is kept as a snapshot (see `snapshot_columns`).
 pub fn save_columns(
     data_dir: &PathBuf,
     mut columns: Vec<DeckColumnConfig>,
@@ -46,6 +180,9 @@ pub fn save_columns(
     if columns.is_empty() {
         return Err("At least one column is required".to_string());
     }
+    for column in &columns {
+        validate_page_size(column)?;
+    }
 
     // Ensure data directory exists
     fs::create_dir_all(data_dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
@@ -59,14 +196,25 @@ pub fn save_columns(
     let columns_path = data_dir.join(COLUMNS_FILE);
     let temp_path = data_dir.join(format!("{}.tmp", COLUMNS_FILE));
 
+    let mut file = ColumnsFile {
+        version: COLUMNS_VERSION,
+        accounts: BTreeMap::new(),
+    };
+    for column in columns {
+        file.accounts.entry(column.did.clone()).or_default().push(column);
+    }
+
     // Serialize to JSON
-    let json = serde_json::to_string_pretty(&columns)
+    let json = serde_json::to_string_pretty(&file)
         .map_err(|e| format!("Failed to serialize columns: {}", e))?;
 
     // Write to temp file
     fs::write(&temp_path, json)
         .map_err(|e| format!("Failed to write temp file: {}", e))?;
 
+    // Snapshots are a recovery aid; a failure doesn't block the save
+    let _ = snapshot_columns(data_dir);
+
     // Atomic rename
     fs::rename(&temp_path, &columns_path)
         .map_err(|e| format!("Failed to rename temp file: {}", e))?;
@@ -74,6 +222,333 @@ pub fn save_columns(
     Ok(())
 }
 
+/// Delete the columns file, so the next load falls back to the default configuration
+///
+/// # Returns
+/// Whether a columns file existed
+pub fn delete_columns(data_dir: &Path) -> Result<bool, String> {
+    let path = data_dir.join(COLUMNS_FILE);
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(path).map_err(|e| format!("Failed to delete columns file: {}", e))?;
+    Ok(true)
+}
+
+/// Apply one change to the stored columns and save them (under the columns lock)
+///
+/// The columns are reindexed (positions 0..n in list order) before saving.
+///
+/// # Returns
+/// The stored columns after the change
+fn modify_columns<F>(data_dir: &PathBuf, change: F) -> Result<Vec<DeckColumnConfig>, String>
+where
+    F: FnOnce(&mut Vec<DeckColumnConfig>) -> Result<(), String>,
+{
+    let _guard = lock_columns()?;
+
+    let mut columns = load_columns(data_dir)?;
+    change(&mut columns)?;
+    for (index, column) in columns.iter_mut().enumerate() {
+        column.position = index as u32;
+    }
+    save_columns(data_dir, columns)?;
+
+    load_columns(data_dir)
+}
+
+/// Append a column to the deck
+///
+/// # Arguments
+/// * `storage` - Storage manager (the column's DID must belong to a stored account)
+/// * `data_dir` - App data directory
+/// * `column` - New column (its position is ignored; it is placed last)
+///
+/// # Returns
+/// The updated column list
+pub async fn add_column(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+    mut column: DeckColumnConfig,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    ensure_known_dids(&account_dids(storage).await?, std::slice::from_ref(&column))?;
+
+    modify_columns(data_dir, |columns| {
+        if columns.iter().any(|c| c.id == column.id) {
+            return Err(format!("Column already exists: {}", column.id));
+        }
+        column.created_at = Utc::now().to_rfc3339();
+        columns.push(column);
+        Ok(())
+    })
+}
+
+/// Remove a column from the deck
+///
+/// # Returns
+/// The updated column list
+///
+/// # Note
+/// The last remaining column cannot be removed (at least one column is required).
+pub fn remove_column(data_dir: &PathBuf, id: &str) -> Result<Vec<DeckColumnConfig>, String> {
+    modify_columns(data_dir, |columns| {
+        let index = columns
+            .iter()
+            .position(|c| c.id == id)
+            .ok_or_else(|| format!("Column not found: {}", id))?;
+        if columns.len() == 1 {
+            return Err("Cannot remove the last column".to_string());
+        }
+        columns.remove(index);
+        Ok(())
+    })
+}
+
+/// Change the title, width or settings of a column
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `id` - Column to change
+/// * `patch` - Fields to change (None leaves a field as is)
+///
+/// # Returns
+/// The updated column list
+pub fn update_column(
+    data_dir: &PathBuf,
+    id: &str,
+    patch: ColumnPatch,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    modify_columns(data_dir, |columns| {
+        let column = columns
+            .iter_mut()
+            .find(|c| c.id == id)
+            .ok_or_else(|| format!("Column not found: {}", id))?;
+
+        if let Some(title) = patch.title {
+            let title = title.trim().to_string();
+            column.title = (!title.is_empty()).then_some(title);
+        }
+        if let Some(width) = patch.width {
+            column.width = Some(width);
+        }
+        if let Some(settings) = patch.settings {
+            column.settings = Some(settings);
+        }
+        Ok(())
+    })
+}
+
+/// Put the columns in a new order (e.g., after a drag and drop)
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `ordered_ids` - Every stored column ID, exactly once, in the new order
+///
+/// # Returns
+/// The updated column list (positions 0..n in the given order)
+///
+/// # Note
+/// Unknown, missing or repeated IDs reject the whole reorder; nothing is saved.
+pub fn reorder_columns(
+    data_dir: &PathBuf,
+    ordered_ids: &[String],
+) -> Result<Vec<DeckColumnConfig>, String> {
+    modify_columns(data_dir, |columns| {
+        let mut by_id: HashMap<String, DeckColumnConfig> =
+            columns.drain(..).map(|c| (c.id.clone(), c)).collect();
+
+        let mut problems = Vec::new();
+        let mut reordered = Vec::with_capacity(by_id.len());
+        for id in ordered_ids {
+            match by_id.remove(id) {
+                Some(column) => reordered.push(column),
+                None if reordered.iter().any(|c: &DeckColumnConfig| &c.id == id) => {
+                    problems.push(format!("duplicate id {}", id))
+                }
+                None => problems.push(format!("unknown id {}", id)),
+            }
+        }
+        let mut missing: Vec<&String> = by_id.keys().collect();
+        missing.sort();
+        problems.extend(missing.into_iter().map(|id| format!("missing id {}", id)));
+
+        if !problems.is_empty() {
+            return Err(format!(
+                "Column order does not match the stored columns: {}",
+                problems.join(", ")
+            ));
+        }
+        *columns = reordered;
+        Ok(())
+    })
+}
+
+/// Remove every column belonging to an account DID
+///
+/// Remaining columns are reindexed. If no column is left the columns file is deleted,
+/// so the next load falls back to the default configuration.
+///
+/// # Returns
+/// Number of removed columns
+pub fn remove_columns_for_did(data_dir: &PathBuf, did: &str) -> Result<usize, String> {
+    let _guard = lock_columns()?;
+
+    let columns = load_columns(data_dir)?;
+    let before = columns.len();
+
+    let mut remaining: Vec<DeckColumnConfig> =
+        columns.into_iter().filter(|c| c.did != did).collect();
+    let removed = before - remaining.len();
+
+    if removed == 0 {
+        return Ok(0);
+    }
+
+    if remaining.is_empty() {
+        delete_columns(data_dir)?;
+        return Ok(removed);
+    }
+
+    for (index, column) in remaining.iter_mut().enumerate() {
+        column.position = index as u32;
+    }
+    save_columns(data_dir, remaining)?;
+
+    Ok(removed)
+}
+
+/// Column setting holding the auto-refresh configuration (`{interval, scrollToTop}`)
+pub const AUTO_REFRESH_SETTING: &str = "autoRefresh";
+
+/// Auto-refresh interval meaning "realtime" (WebSocket stream)
+pub const REALTIME_INTERVAL: i64 = -1;
+
+/// Auto-refresh intervals offered by the column settings (seconds; 0 = off)
+pub const AUTO_REFRESH_INTERVALS: [i64; 8] = [0, 10, 30, 60, 300, 600, 1800, REALTIME_INTERVAL];
+
+/// Column left unchanged by `set_all_columns_refresh`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedColumn {
+    pub id: String,
+    pub reason: String,
+}
+
+/// Outcome of `set_all_columns_refresh`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkRefreshReport {
+    /// IDs of the columns that received the interval
+    pub updated: Vec<String>,
+    /// Columns whose type does not support the interval
+    pub skipped: Vec<SkippedColumn>,
+}
+
+/// Whether a column type can auto-refresh at the given interval
+///
+/// Realtime streams only carry the home timeline; notifications and custom feeds
+/// have to be polled.
+fn supports_refresh_interval(column_type: &ColumnType, interval: i64) -> bool {
+    interval != REALTIME_INTERVAL || *column_type == ColumnType::Timeline
+}
+
+/// Apply one auto-refresh setting to every column of an account
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `did` - Account whose columns are updated
+/// * `interval` - Refresh interval in seconds (one of `AUTO_REFRESH_INTERVALS`)
+/// * `scroll_to_top` - Scroll to the top on refresh
+///
+/// # Note
+/// Other column settings are preserved. Columns that cannot use the interval are
+/// reported in `skipped` and left unchanged.
+pub fn set_all_columns_refresh(
+    data_dir: &PathBuf,
+    did: &str,
+    interval: i64,
+    scroll_to_top: bool,
+) -> Result<BulkRefreshReport, String> {
+    if !AUTO_REFRESH_INTERVALS.contains(&interval) {
+        return Err(format!("Unsupported auto-refresh interval: {}", interval));
+    }
+
+    let _guard = lock_columns()?;
+    let mut columns = load_columns(data_dir)?;
+    if !columns.iter().any(|c| c.did == did) {
+        return Err(format!("No columns for account {}", did));
+    }
+
+    let mut report = BulkRefreshReport::default();
+    for column in columns.iter_mut().filter(|c| c.did == did) {
+        if !supports_refresh_interval(&column.column_type, interval) {
+            report.skipped.push(SkippedColumn {
+                id: column.id.clone(),
+                reason: format!("{:?} columns do not support realtime refresh", column.column_type),
+            });
+            continue;
+        }
+
+        column.settings.get_or_insert_with(HashMap::new).insert(
+            AUTO_REFRESH_SETTING.to_string(),
+            json!({ "interval": interval, "scrollToTop": scroll_to_top }),
+        );
+        report.updated.push(column.id.clone());
+    }
+
+    if !report.updated.is_empty() {
+        save_columns(data_dir, columns)?;
+    }
+
+    Ok(report)
+}
+
+/// Swap the positions of two columns of the same account
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `id_a` - First column
+/// * `id_b` - Second column
+///
+/// # Returns
+/// The two updated columns (in argument order)
+pub fn swap_columns(
+    data_dir: &PathBuf,
+    id_a: &str,
+    id_b: &str,
+) -> Result<(DeckColumnConfig, DeckColumnConfig), String> {
+    let _guard = lock_columns()?;
+    let mut columns = load_columns(data_dir)?;
+
+    let find = |id: &str| {
+        columns
+            .iter()
+            .position(|c| c.id == id)
+            .ok_or_else(|| format!("Column not found: {}", id))
+    };
+    let (index_a, index_b) = (find(id_a)?, find(id_b)?);
+
+    if index_a == index_b {
+        return Err("Cannot swap a column with itself".to_string());
+    }
+    if columns[index_a].did != columns[index_b].did {
+        return Err("Columns belong to different accounts".to_string());
+    }
+
+    let position_a = columns[index_a].position;
+    columns[index_a].position = columns[index_b].position;
+    columns[index_b].position = position_a;
+
+    save_columns(data_dir, columns)?;
+
+    let columns = load_columns(data_dir)?;
+    let updated = |id: &str| columns.iter().find(|c| c.id == id).cloned();
+    match (updated(id_a), updated(id_b)) {
+        (Some(a), Some(b)) => Ok((a, b)),
+        _ => Err("Swapped columns missing after save".to_string()),
+    }
+}
+
 /// Generate default column configuration
 ///
 /// Creates a single timeline column for the given account DID with medium width (400px)
@@ -93,12 +568,372 @@ pub fn get_default_columns(did: &str) -> Vec<DeckColumnConfig> {
     }]
 }
 
+/// Create the default timeline column if no columns are stored yet
+///
+/// # Arguments
+/// * `storage` - Storage manager (`did` must belong to a stored account)
+/// * `data_dir` - App data directory
+/// * `did` - Account DID for the default column
+///
+/// # Returns
+/// The stored columns (the new default column if the file was missing or empty)
+///
+/// # Note
+/// An unreadable columns file is reported, not replaced.
+pub async fn ensure_default_columns(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+    did: &str,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    let defaults = get_default_columns(did);
+    ensure_known_dids(&account_dids(storage).await?, &defaults)?;
+
+    let _guard = lock_columns()?;
+
+    let columns = load_columns(data_dir)?;
+    if !columns.is_empty() {
+        return Ok(columns);
+    }
+
+    save_columns(data_dir, defaults.clone())?;
+    Ok(defaults)
+}
+
+/// Pinned custom feed URIs from app.bsky.actor.getPreferences, in pin order
+///
+/// Reads `savedFeedsPrefV2` (pinned items of type "feed") and falls back to the
+/// legacy `savedFeedsPref` pinned list.
+fn pinned_feed_uris(preferences: &[Value]) -> Vec<String> {
+    let pref_of = |pref_type: &str| {
+        preferences
+            .iter()
+            .find(|pref| pref.get("$type").and_then(Value::as_str) == Some(pref_type))
+    };
+
+    let v2 = pref_of("app.bsky.actor.defs#savedFeedsPrefV2");
+    let uris: Vec<String> = if let Some(pref) = v2 {
+        pref.get("items")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|item| item.get("pinned").and_then(Value::as_bool) == Some(true))
+            .filter(|item| item.get("type").and_then(Value::as_str) == Some("feed"))
+            .filter_map(|item| item.get("value")?.as_str())
+            .map(str::to_string)
+            .collect()
+    } else if let Some(pref) = pref_of("app.bsky.actor.defs#savedFeedsPref") {
+        pref.get("pinned")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|uri| uri.contains("/app.bsky.feed.generator/"))
+            .map(str::to_string)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    uris.into_iter().filter(|uri| seen.insert(uri.clone())).collect()
+}
+
+/// Build a first-run deck: timeline, notifications and one column per pinned feed
+///
+/// # Arguments
+/// * `did` - Account DID
+/// * `preferences` - The account's preferences (None falls back to a lone timeline)
+///
+/// # Note
+/// The deck is capped at the preset column limit.
+pub fn build_starter_deck(did: &str, preferences: Option<&[Value]>) -> Vec<DeckColumnConfig> {
+    let Some(preferences) = preferences else {
+        return get_default_columns(did);
+    };
+
+    let now = Utc::now().to_rfc3339();
+    let column = |column_type: ColumnType, width: ColumnWidth, settings| DeckColumnConfig {
+        id: Uuid::new_v4().to_string(),
+        did: did.to_string(),
+        column_type,
+        title: None,
+        position: 0,
+        width: Some(width),
+        settings,
+        created_at: now.clone(),
+        updated_at: now.clone(),
+    };
+
+    let mut columns = vec![
+        column(ColumnType::Timeline, ColumnWidth::Medium, None),
+        column(ColumnType::Notifications, ColumnWidth::Xxs, None),
+    ];
+    columns.extend(pinned_feed_uris(preferences).into_iter().map(|uri| {
+        let settings = HashMap::from([("feedUri".to_string(), Value::String(uri))]);
+        column(ColumnType::Feed, ColumnWidth::Medium, Some(settings))
+    }));
+    columns.truncate(MAX_PRESET_COLUMNS);
+
+    for (index, column) in columns.iter_mut().enumerate() {
+        column.position = index as u32;
+    }
+
+    columns
+}
+
+/// Fetch the account preferences and build a starter deck from them (nothing is saved)
+///
+/// # Arguments
+/// * `client` - Client for the account's PDS
+/// * `access_jwt` - Access token
+/// * `did` - Account DID
+///
+/// # Note
+/// If the preferences cannot be fetched the deck is just a timeline column.
+pub async fn generate_starter_deck(
+    client: &ATProtocolClient,
+    access_jwt: &str,
+    did: &str,
+) -> Vec<DeckColumnConfig> {
+    let preferences = client.get_preferences(access_jwt).await.ok();
+
+    build_starter_deck(did, preferences.as_deref())
+}
+
+/// Report which stored accounts already have configured columns
+///
+/// Returns a map keyed by account DID. A missing, empty or unreadable columns file
+/// yields `false` for every account (the account will receive default columns).
+pub async fn accounts_with_columns(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+) -> Result<HashMap<String, bool>, String> {
+    let accounts = storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?;
+
+    let columns = load_columns(data_dir).unwrap_or_else(|_| vec![]);
+
+    Ok(accounts
+        .into_iter()
+        .map(|account| {
+            let has_columns = columns.iter().any(|c| c.did == account.did);
+            (account.did, has_columns)
+        })
+        .collect())
+}
+
+/// DIDs of the stored accounts
+pub(crate) async fn account_dids(storage: &StorageManager) -> Result<Vec<String>, String> {
+    Ok(storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?
+        .into_iter()
+        .map(|account| account.did)
+        .collect())
+}
+
+/// Check that every column belongs to one of `dids`
+///
+/// Orphaned columns are listed in the error as "id (did)".
+pub(crate) fn ensure_known_dids(
+    dids: &[String],
+    columns: &[DeckColumnConfig],
+) -> Result<(), String> {
+    let orphans: Vec<String> = columns
+        .iter()
+        .filter(|column| !dids.contains(&column.did))
+        .map(|column| format!("{} ({})", column.id, column.did))
+        .collect();
+    if !orphans.is_empty() {
+        return Err(format!(
+            "Columns reference accounts that are not logged in: {}",
+            orphans.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Save column configurations after checking every column belongs to a stored account
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory
+/// * `columns` - Columns to save
+///
+/// # Note
+/// Columns whose DID matches no stored account are listed in the error and nothing is
+/// saved (they would show up empty once their account is removed).
+pub async fn save_account_columns(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+    columns: Vec<DeckColumnConfig>,
+) -> Result<(), String> {
+    ensure_known_dids(&account_dids(storage).await?, &columns)?;
+
+    let _guard = lock_columns()?;
+    save_columns(data_dir, columns)
+}
+
+/// Remove columns whose DID matches no stored account
+///
+/// Remaining columns are reindexed. If no column is left the columns file is deleted,
+/// so the next load falls back to the default configuration.
+///
+/// # Returns
+/// Number of removed columns
+pub async fn prune_orphaned_columns(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+) -> Result<usize, String> {
+    let dids = account_dids(storage).await?;
+
+    let _guard = lock_columns()?;
+
+    let columns = load_columns(data_dir)?;
+    let before = columns.len();
+    let mut remaining: Vec<DeckColumnConfig> =
+        columns.into_iter().filter(|c| dids.contains(&c.did)).collect();
+    let pruned = before - remaining.len();
+
+    if pruned == 0 {
+        return Ok(0);
+    }
+    if remaining.is_empty() {
+        delete_columns(data_dir)?;
+        return Ok(pruned);
+    }
+
+    for (index, column) in remaining.iter_mut().enumerate() {
+        column.position = index as u32;
+    }
+    save_columns(data_dir, remaining)?;
+
+    Ok(pruned)
+}
+
+/// Result of `migrate_legacy_columns`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnsMigrationReport {
+    /// Whether a legacy flat columns file was found and converted
+    pub migrated: bool,
+    /// Number of migrated columns per account DID (including orphaned DIDs)
+    pub by_account: BTreeMap<String, usize>,
+    /// Columns whose DID matches no stored account, as "id (did)"
+    pub orphans: Vec<String>,
+    /// Archive of the legacy file (None if nothing was migrated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_to: Option<String>,
+}
+
+/// Convert a legacy flat columns file into the per-account layout
+///
+/// The legacy file is copied to `columns.legacy.json` before the converted file
+/// replaces it, so a failed save leaves both intact.
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory
+///
+/// # Note
+/// Orphaned columns are kept and flagged; `prune_orphaned_columns` removes them.
+/// A file that is already in the per-account layout is left alone.
+pub async fn migrate_legacy_columns(
+    storage: &StorageManager,
+    data_dir: &PathBuf,
+) -> Result<ColumnsMigrationReport, String> {
+    let dids = account_dids(storage).await?;
+
+    let _guard = lock_columns()?;
+
+    let columns_path = data_dir.join(COLUMNS_FILE);
+    let mut report = ColumnsMigrationReport::default();
+    if !columns_path.exists() {
+        return Ok(report);
+    }
+
+    let content = fs::read_to_string(&columns_path)
+        .map_err(|e| format!("Failed to read columns file: {}", e))?;
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse columns JSON: {}", e))?;
+    if !value.is_array() {
+        return Ok(report);
+    }
+    let columns: Vec<DeckColumnConfig> = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse legacy columns: {}", e))?;
+
+    for column in &columns {
+        *report.by_account.entry(column.did.clone()).or_default() += 1;
+        if !dids.contains(&column.did) {
+            report.orphans.push(format!("{} ({})", column.id, column.did));
+        }
+    }
+
+    let legacy_path = data_dir.join(LEGACY_COLUMNS_FILE);
+    fs::copy(&columns_path, &legacy_path)
+        .map_err(|e| format!("Failed to archive legacy columns file: {}", e))?;
+    if columns.is_empty() {
+        delete_columns(data_dir)?;
+    } else {
+        save_columns(data_dir, columns)?;
+    }
+
+    report.migrated = true;
+    report.archived_to = Some(LEGACY_COLUMNS_FILE.to_string());
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support;
+    use crate::types::Account;
     use std::fs;
+    use std::sync::Arc;
     use tempfile::TempDir;
 
+    fn test_account(did: &str, handle: &str) -> Account {
+        Account {
+            did: did.to_string(),
+            handle: handle.to_string(),
+            ..test_support::account(&Uuid::new_v4().to_string())
+        }
+    }
+
+    /// Store holding one account per DID
+    async fn storage_with_dids(data_dir: &Path, dids: &[&str]) -> StorageManager {
+        let storage = StorageManager::new(data_dir.to_path_buf()).await.unwrap();
+        for did in dids {
+            storage.save_account(&test_account(did, "handle.test")).await.unwrap();
+        }
+        storage
+    }
+
+    #[test]
+    fn test_save_keeps_bounded_snapshots_of_replaced_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let snapshots_dir = data_dir.join(COLUMN_SNAPSHOTS_DIR);
+
+        save_columns(&data_dir, get_default_columns("did:plc:alice")).unwrap();
+        assert!(!snapshots_dir.exists());
+
+        let first = fs::read_to_string(data_dir.join(COLUMNS_FILE)).unwrap();
+        save_columns(&data_dir, get_default_columns("did:plc:bob")).unwrap();
+        let snapshots: Vec<_> = fs::read_dir(&snapshots_dir).unwrap().flatten().collect();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(fs::read_to_string(snapshots[0].path()).unwrap(), first);
+
+        for _ in 0..DEFAULT_SNAPSHOT_KEEP + 2 {
+            save_columns(&data_dir, get_default_columns("did:plc:bob")).unwrap();
+        }
+        assert_eq!(fs::read_dir(&snapshots_dir).unwrap().count(), DEFAULT_SNAPSHOT_KEEP);
+    }
+
     #[test]
     fn test_save_and_load_columns() {
         let temp_dir = TempDir::new().unwrap();
@@ -151,4 +986,516 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_ensure_default_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = storage_with_dids(&data_dir, &["did:plc:alice", "did:plc:bob"]).await;
+
+        let unknown = ensure_default_columns(&storage, &data_dir, "did:plc:gone").await;
+        assert!(unknown.unwrap_err().contains("not logged in"));
+        assert!(!data_dir.join(COLUMNS_FILE).exists());
+
+        let created = ensure_default_columns(&storage, &data_dir, "did:plc:alice").await.unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].did, "did:plc:alice");
+        assert_eq!(created[0].column_type, ColumnType::Timeline);
+        assert_eq!(load_columns(&data_dir).unwrap()[0].id, created[0].id);
+
+        // Existing columns are kept as they are
+        let existing = ensure_default_columns(&storage, &data_dir, "did:plc:bob").await.unwrap();
+        assert_eq!(existing.len(), 1);
+        assert_eq!(existing[0].id, created[0].id);
+
+        fs::write(data_dir.join(COLUMNS_FILE), "invalid json").unwrap();
+        assert!(ensure_default_columns(&storage, &data_dir, "did:plc:alice").await.is_err());
+        let content = fs::read_to_string(data_dir.join(COLUMNS_FILE)).unwrap();
+        assert_eq!(content, "invalid json");
+    }
+
+    #[tokio::test]
+    async fn test_accounts_with_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+
+        storage
+            .save_account(&test_account("did:plc:alice", "alice.bsky.social"))
+            .await
+            .unwrap();
+        storage
+            .save_account(&test_account("did:plc:bob", "bob.bsky.social"))
+            .await
+            .unwrap();
+
+        // No columns file yet: nobody has columns
+        let map = accounts_with_columns(&storage, &data_dir).await.unwrap();
+        assert_eq!(map.get("did:plc:alice"), Some(&false));
+        assert_eq!(map.get("did:plc:bob"), Some(&false));
+
+        save_columns(&data_dir, get_default_columns("did:plc:alice")).unwrap();
+
+        let map = accounts_with_columns(&storage, &data_dir).await.unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("did:plc:alice"), Some(&true));
+        assert_eq!(map.get("did:plc:bob"), Some(&false));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_columns_splits_by_account() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage
+            .save_account(&test_account("did:plc:alice", "alice.bsky.social"))
+            .await
+            .unwrap();
+        storage
+            .save_account(&test_account("did:plc:bob", "bob.bsky.social"))
+            .await
+            .unwrap();
+
+        // Flat file from before account-scoped storage
+        let mut columns = Vec::new();
+        for did in ["did:plc:alice", "did:plc:bob", "did:plc:alice", "did:plc:gone"] {
+            let mut column = get_default_columns(did).remove(0);
+            column.position = columns.len() as u32;
+            columns.push(column);
+        }
+        let ids: Vec<String> = columns.iter().map(|column| column.id.clone()).collect();
+        let legacy = serde_json::to_string_pretty(&columns).unwrap();
+        fs::write(data_dir.join(COLUMNS_FILE), &legacy).unwrap();
+        assert_eq!(load_columns(&data_dir).unwrap().len(), 4);
+
+        let report = migrate_legacy_columns(&storage, &data_dir).await.unwrap();
+
+        assert!(report.migrated);
+        assert_eq!(report.by_account["did:plc:alice"], 2);
+        assert_eq!(report.by_account["did:plc:bob"], 1);
+        assert_eq!(report.by_account["did:plc:gone"], 1);
+        assert_eq!(report.orphans, vec![format!("{} (did:plc:gone)", ids[3])]);
+        assert_eq!(report.archived_to.as_deref(), Some(LEGACY_COLUMNS_FILE));
+        assert_eq!(fs::read_to_string(data_dir.join(LEGACY_COLUMNS_FILE)).unwrap(), legacy);
+
+        let file: Value =
+            serde_json::from_str(&fs::read_to_string(data_dir.join(COLUMNS_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(file["version"], COLUMNS_VERSION);
+        let split = |did: &str| -> Vec<String> {
+            file["accounts"][did]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|column| column["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(split("did:plc:alice"), vec![ids[0].clone(), ids[2].clone()]);
+        assert_eq!(split("did:plc:bob"), vec![ids[1].clone()]);
+        assert_eq!(split("did:plc:gone"), vec![ids[3].clone()]);
+        let loaded: Vec<String> =
+            load_columns(&data_dir).unwrap().into_iter().map(|c| c.id).collect();
+        assert_eq!(loaded, ids);
+
+        // Already migrated
+        let again = migrate_legacy_columns(&storage, &data_dir).await.unwrap();
+        assert!(!again.migrated);
+        assert_eq!(fs::read_to_string(data_dir.join(LEGACY_COLUMNS_FILE)).unwrap(), legacy);
+    }
+
+    #[tokio::test]
+    async fn test_save_account_columns_rejects_orphans() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account("did:plc:alice", "alice.test")).await.unwrap();
+
+        let mut columns = get_default_columns("did:plc:alice");
+        save_account_columns(&storage, &data_dir, columns.clone()).await.unwrap();
+
+        let mut orphan = get_default_columns("did:plc:gone").remove(0);
+        orphan.position = 1;
+        columns.push(orphan.clone());
+        let error = save_account_columns(&storage, &data_dir, columns).await.unwrap_err();
+
+        assert!(error.contains(&format!("{} (did:plc:gone)", orphan.id)));
+        assert_eq!(load_columns(&data_dir).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_prune_orphaned_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account("did:plc:alice", "alice.test")).await.unwrap();
+
+        let mut columns = get_default_columns("did:plc:gone");
+        let mut alice = get_default_columns("did:plc:alice").remove(0);
+        alice.position = 1;
+        columns.push(alice.clone());
+        save_columns(&data_dir, columns).unwrap();
+
+        assert_eq!(prune_orphaned_columns(&storage, &data_dir).await.unwrap(), 1);
+        let stored = load_columns(&data_dir).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!((stored[0].id.as_str(), stored[0].position), (alice.id.as_str(), 0));
+        assert_eq!(prune_orphaned_columns(&storage, &data_dir).await.unwrap(), 0);
+
+        // Only orphans: the file is removed
+        save_columns(&data_dir, get_default_columns("did:plc:gone")).unwrap();
+        assert_eq!(prune_orphaned_columns(&storage, &data_dir).await.unwrap(), 1);
+        assert!(!data_dir.join(COLUMNS_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn test_generate_starter_deck_with_pinned_feeds() {
+        let mut server = mockito::Server::new_async().await;
+        let feed = "at://did:plc:creator/app.bsky.feed.generator/cats";
+        server
+            .mock("GET", "/xrpc/app.bsky.actor.getPreferences")
+            .match_header("authorization", "Bearer token")
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "preferences": [{
+                        "$type": "app.bsky.actor.defs#savedFeedsPrefV2",
+                        "items": [
+                            { "type": "timeline", "value": "following", "pinned": true },
+                            { "type": "feed", "value": feed, "pinned": true },
+                            { "type": "feed", "value": "at://x/app.bsky.feed.generator/y",
+                              "pinned": false }
+                        ]
+                    }]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let deck = generate_starter_deck(&client, "token", "did:plc:alice").await;
+
+        let types: Vec<ColumnType> = deck.iter().map(|c| c.column_type.clone()).collect();
+        assert_eq!(
+            types,
+            vec![ColumnType::Timeline, ColumnType::Notifications, ColumnType::Feed]
+        );
+        assert_eq!(deck[2].settings.as_ref().unwrap()["feedUri"], feed);
+        assert!(deck.iter().enumerate().all(|(i, c)| c.position == i as u32));
+        assert!(deck.iter().all(|c| c.did == "did:plc:alice"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_starter_deck_degrades_to_timeline() {
+        let client = ATProtocolClient::with_base_url("http://127.0.0.1:9");
+
+        let deck = generate_starter_deck(&client, "token", "did:plc:alice").await;
+
+        assert_eq!(deck.len(), 1);
+        assert_eq!(deck[0].column_type, ColumnType::Timeline);
+    }
+
+    #[test]
+    fn test_starter_deck_is_capped() {
+        let pinned: Vec<String> = (0..MAX_PRESET_COLUMNS + 5)
+            .map(|i| format!("at://did:plc:c/app.bsky.feed.generator/f{}", i))
+            .collect();
+        let preferences = vec![serde_json::json!({
+            "$type": "app.bsky.actor.defs#savedFeedsPref",
+            "pinned": pinned
+        })];
+
+        let deck = build_starter_deck("did:plc:alice", Some(&preferences));
+
+        assert_eq!(deck.len(), MAX_PRESET_COLUMNS);
+    }
+
+    fn column_with_page_size(
+        column_type: ColumnType,
+        page_size: Option<Value>,
+    ) -> DeckColumnConfig {
+        let mut column = get_default_columns("did:plc:test").remove(0);
+        column.column_type = column_type;
+        column.settings =
+            page_size.map(|size| HashMap::from([(PAGE_SIZE_SETTING.to_string(), size)]));
+        column
+    }
+
+    #[test]
+    fn test_page_size_defaults_and_validation() {
+        let notifications = column_with_page_size(ColumnType::Notifications, None);
+        assert_eq!(column_page_size(&notifications), 50);
+        assert_eq!(column_page_size(&column_with_page_size(ColumnType::Feed, None)), 30);
+
+        assert!(validate_page_size(&column_with_page_size(ColumnType::Feed, Some(100.into())))
+            .is_ok());
+        for invalid in [Value::from(0), Value::from(101), Value::from("20")] {
+            let column = column_with_page_size(ColumnType::Timeline, Some(invalid));
+            assert!(validate_page_size(&column).is_err());
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let column = column_with_page_size(ColumnType::Timeline, Some(500.into()));
+        assert!(save_columns(&temp_dir.path().to_path_buf(), vec![column]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_column_page_size_flows_into_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let configured = column_with_page_size(ColumnType::Timeline, Some(20.into()));
+        // Written by an older version that did not validate the setting
+        let mut oversized = column_with_page_size(ColumnType::Timeline, Some(500.into()));
+        oversized.id = "oversized".to_string();
+        let json = serde_json::to_string(&vec![configured.clone(), oversized]).unwrap();
+        fs::write(data_dir.join(COLUMNS_FILE), json).unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let mocks = [
+            server
+                .mock("GET", "/xrpc/app.bsky.feed.getTimeline")
+                .match_query(mockito::Matcher::UrlEncoded("limit".into(), "20".into()))
+                .with_status(200)
+                .with_body(r#"{"feed":[]}"#)
+                .create_async()
+                .await,
+            server
+                .mock("GET", "/xrpc/app.bsky.feed.getTimeline")
+                .match_query(mockito::Matcher::UrlEncoded("limit".into(), "100".into()))
+                .with_status(200)
+                .with_body(r#"{"feed":[]}"#)
+                .create_async()
+                .await,
+        ];
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        for column_id in [configured.id.as_str(), "oversized"] {
+            let limit = page_size_for_column(&data_dir, column_id).unwrap();
+            client.get_timeline("token", None, None, Some(limit)).await.unwrap();
+        }
+
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+        assert!(page_size_for_column(&data_dir, "missing").is_err());
+    }
+
+    #[test]
+    fn test_swap_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let mut columns = get_default_columns("did:plc:alice");
+        let mut second = columns[0].clone();
+        second.id = Uuid::new_v4().to_string();
+        second.column_type = ColumnType::Notifications;
+        second.position = 1;
+        columns.push(second);
+        let mut bob = get_default_columns("did:plc:bob");
+        bob[0].position = 2;
+        columns.extend(bob.clone());
+        save_columns(&data_dir, columns.clone()).unwrap();
+        let (first, second) = (&columns[0], &columns[1]);
+
+        let (a, b) = swap_columns(&data_dir, &first.id, &second.id).unwrap();
+
+        assert_eq!((a.id.as_str(), a.position), (first.id.as_str(), second.position));
+        assert_eq!((b.id.as_str(), b.position), (second.id.as_str(), first.position));
+        let stored = load_columns(&data_dir).unwrap();
+        assert_eq!(stored[0].id, second.id);
+
+        assert!(swap_columns(&data_dir, &first.id, "missing").is_err());
+        assert!(swap_columns(&data_dir, &first.id, &bob[0].id).is_err());
+        assert_eq!(load_columns(&data_dir).unwrap()[0].id, second.id);
+    }
+
+    fn two_column_deck(data_dir: &PathBuf) -> Vec<DeckColumnConfig> {
+        let mut columns = get_default_columns("did:plc:alice");
+        let mut second = columns[0].clone();
+        second.id = Uuid::new_v4().to_string();
+        second.column_type = ColumnType::Notifications;
+        second.position = 1;
+        columns.push(second);
+        save_columns(data_dir, columns.clone()).unwrap();
+        columns
+    }
+
+    #[tokio::test]
+    async fn test_add_column() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = storage_with_dids(&data_dir, &["did:plc:alice", "did:plc:bob"]).await;
+        let columns = two_column_deck(&data_dir);
+
+        let mut new = get_default_columns("did:plc:bob").remove(0);
+        new.position = 0;
+        let updated = add_column(&storage, &data_dir, new.clone()).await.unwrap();
+
+        assert_eq!(updated.len(), 3);
+        assert_eq!((updated[2].id.as_str(), updated[2].position), (new.id.as_str(), 2));
+        assert_eq!(updated[0].id, columns[0].id);
+        assert_eq!(load_columns(&data_dir).unwrap().len(), 3);
+
+        let result = add_column(&storage, &data_dir, new).await;
+        assert!(result.unwrap_err().starts_with("Column already exists"));
+    }
+
+    #[tokio::test]
+    async fn test_add_column_rejects_unknown_did() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = storage_with_dids(&data_dir, &["did:plc:alice"]).await;
+        two_column_deck(&data_dir);
+
+        let orphan = get_default_columns("did:plc:gone").remove(0);
+        let error = add_column(&storage, &data_dir, orphan.clone()).await.unwrap_err();
+
+        assert!(error.contains(&format!("{} (did:plc:gone)", orphan.id)));
+        assert_eq!(load_columns(&data_dir).unwrap().len(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_writers_keep_every_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = Arc::new(storage_with_dids(&data_dir, &["did:plc:alice"]).await);
+        let columns = two_column_deck(&data_dir);
+
+        let mut tasks = Vec::new();
+        for _ in 0..4 {
+            let (storage, dir) = (storage.clone(), data_dir.clone());
+            tasks.push(tokio::spawn(async move {
+                let column = get_default_columns("did:plc:alice").remove(0);
+                add_column(&storage, &dir, column).await.unwrap();
+            }));
+            let (dir, a, b) = (data_dir.clone(), columns[0].id.clone(), columns[1].id.clone());
+            tasks.push(tokio::task::spawn_blocking(move || {
+                swap_columns(&dir, &a, &b).unwrap();
+                set_all_columns_refresh(&dir, "did:plc:alice", 60, false).unwrap();
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(load_columns(&data_dir).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_remove_column_keeps_the_last_one() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let columns = two_column_deck(&data_dir);
+
+        let updated = remove_column(&data_dir, &columns[0].id).unwrap();
+        assert_eq!(updated.len(), 1);
+        assert_eq!((updated[0].id.as_str(), updated[0].position), (columns[1].id.as_str(), 0));
+
+        assert!(remove_column(&data_dir, "missing").is_err());
+        let result = remove_column(&data_dir, &columns[1].id);
+        assert_eq!(result.unwrap_err(), "Cannot remove the last column");
+        assert_eq!(load_columns(&data_dir).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_update_column() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let columns = two_column_deck(&data_dir);
+
+        let patch = ColumnPatch {
+            title: Some(" Mentions ".to_string()),
+            width: Some(ColumnWidth::Xs),
+            ..Default::default()
+        };
+        let updated = update_column(&data_dir, &columns[1].id, patch).unwrap();
+
+        assert_eq!(updated[1].title.as_deref(), Some("Mentions"));
+        assert_eq!(updated[1].width, Some(ColumnWidth::Xs));
+        assert_eq!(updated[1].column_type, ColumnType::Notifications);
+        assert_eq!(updated[0].title, None);
+
+        // Settings are validated like a bulk save
+        let patch = ColumnPatch {
+            settings: Some(HashMap::from([(PAGE_SIZE_SETTING.to_string(), json!(500))])),
+            ..Default::default()
+        };
+        assert!(update_column(&data_dir, &columns[1].id, patch).is_err());
+        assert!(update_column(&data_dir, "missing", ColumnPatch::default()).is_err());
+        assert_eq!(load_columns(&data_dir).unwrap()[1].settings, None);
+    }
+
+    #[tokio::test]
+    async fn test_reorder_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = storage_with_dids(&data_dir, &["did:plc:alice", "did:plc:bob"]).await;
+        two_column_deck(&data_dir);
+        let bob = get_default_columns("did:plc:bob").remove(0);
+        let columns = add_column(&storage, &data_dir, bob).await.unwrap();
+        let order = [2, 0, 1].map(|i: usize| columns[i].id.clone());
+
+        let updated = reorder_columns(&data_dir, &order).unwrap();
+        let stored: Vec<(String, u32)> = load_columns(&data_dir)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.id, c.position))
+            .collect();
+        assert_eq!(stored[0], (columns[2].id.clone(), 0));
+        assert_eq!(stored[1], (columns[0].id.clone(), 1));
+        assert_eq!(stored[2], (columns[1].id.clone(), 2));
+        assert_eq!(updated.len(), 3);
+    }
+
+    #[test]
+    fn test_reorder_columns_rejects_mismatched_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let columns = two_column_deck(&data_dir);
+        let (a, b) = (columns[0].id.clone(), columns[1].id.clone());
+
+        let incomplete = reorder_columns(&data_dir, std::slice::from_ref(&b)).unwrap_err();
+        assert!(incomplete.contains(&format!("missing id {}", a)));
+        let unknown = reorder_columns(&data_dir, &[b.clone(), a.clone(), "x".into()]);
+        assert!(unknown.unwrap_err().contains("unknown id x"));
+        let repeated = reorder_columns(&data_dir, &[b.clone(), b.clone(), a.clone()]);
+        assert!(repeated.unwrap_err().contains(&format!("duplicate id {}", b)));
+
+        // Nothing was saved
+        assert_eq!(load_columns(&data_dir).unwrap()[0].id, a);
+    }
+
+    #[test]
+    fn test_set_all_columns_refresh() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let mut columns = build_starter_deck("did:plc:alice", Some(&[]));
+        columns[1].settings = Some(HashMap::from([(PAGE_SIZE_SETTING.to_string(), json!(40))]));
+        columns.extend(get_default_columns("did:plc:bob").into_iter().map(|mut c| {
+            c.position = 2;
+            c
+        }));
+        save_columns(&data_dir, columns.clone()).unwrap();
+        let (timeline, notifications) = (&columns[0].id, &columns[1].id);
+
+        let report = set_all_columns_refresh(&data_dir, "did:plc:alice", 30, false).unwrap();
+
+        assert_eq!(report.updated, vec![timeline.clone(), notifications.clone()]);
+        assert!(report.skipped.is_empty());
+        let stored = load_columns(&data_dir).unwrap();
+        let settings = stored[1].settings.as_ref().unwrap();
+        assert_eq!(settings[AUTO_REFRESH_SETTING], json!({ "interval": 30, "scrollToTop": false }));
+        assert_eq!(settings[PAGE_SIZE_SETTING], json!(40));
+        assert!(stored[2].settings.is_none());
+
+        let report =
+            set_all_columns_refresh(&data_dir, "did:plc:alice", REALTIME_INTERVAL, true).unwrap();
+
+        assert_eq!(report.updated, vec![timeline.clone()]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(&report.skipped[0].id, notifications);
+        let stored = load_columns(&data_dir).unwrap();
+        assert_eq!(stored[1].settings.as_ref().unwrap()[AUTO_REFRESH_SETTING]["interval"], 30);
+
+        assert!(set_all_columns_refresh(&data_dir, "did:plc:alice", 45, true).is_err());
+    }
 }
@@ -0,0 +1,202 @@
+/**
+ * Columns on-disk footprint
+ *
+ * Reports how much space the deck column files take so the settings screen can
+ * surface growth and offer to prune old column snapshots.
+ */
+
+use crate::storage::columns::{load_columns, COLUMNS_FILE};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Directory (inside the data dir) holding column snapshots
+pub(crate) const COLUMN_SNAPSHOTS_DIR: &str = "column-snapshots";
+
+/// One column snapshot file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnSnapshotInfo {
+    /// File name inside the snapshots directory
+    pub file_name: String,
+    /// Size in bytes
+    pub bytes: u64,
+}
+
+/// Disk usage of the columns-related files
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnsStorageReport {
+    /// Size of the shared columns file in bytes (0 if missing)
+    pub columns_file_bytes: u64,
+    /// Bytes of the columns file taken by each account, keyed by DID
+    pub per_account_bytes: BTreeMap<String, u64>,
+    /// Column snapshots, newest first
+    pub snapshots: Vec<ColumnSnapshotInfo>,
+    /// Total size of all snapshots in bytes
+    pub snapshot_bytes: u64,
+    /// Columns file plus snapshots, in bytes
+    pub total_bytes: u64,
+}
+
+/// Snapshot files with their size and modification time, newest first
+fn list_snapshots(data_dir: &Path) -> Result<Vec<(ColumnSnapshotInfo, SystemTime)>, String> {
+    let snapshots_dir = data_dir.join(COLUMN_SNAPSHOTS_DIR);
+    if !snapshots_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&snapshots_dir)
+        .map_err(|e| format!("Failed to read snapshots dir: {}", e))?;
+
+    let mut snapshots: Vec<(ColumnSnapshotInfo, SystemTime)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((
+                ColumnSnapshotInfo {
+                    file_name: entry.file_name().to_string_lossy().into_owned(),
+                    bytes: metadata.len(),
+                },
+                modified,
+            ))
+        })
+        .collect();
+
+    // Newest first; the name breaks ties (snapshot names embed a timestamp)
+    snapshots.sort_by(|(a, a_time), (b, b_time)| {
+        b_time.cmp(a_time).then_with(|| b.file_name.cmp(&a.file_name))
+    });
+
+    Ok(snapshots)
+}
+
+/// Compute the on-disk footprint of the columns file and its snapshots
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+///
+/// # Note
+/// All accounts share one columns file, so the per-account figure is the size of
+/// each account's columns as serialized in that file.
+pub fn columns_storage_report(data_dir: &Path) -> Result<ColumnsStorageReport, String> {
+    let columns_path = data_dir.join(COLUMNS_FILE);
+    let columns_file_bytes = fs::metadata(&columns_path).map(|m| m.len()).unwrap_or(0);
+
+    let mut per_account_bytes = BTreeMap::new();
+    if columns_file_bytes > 0 {
+        for column in &load_columns(&data_dir.to_path_buf())? {
+            let bytes = serde_json::to_string_pretty(column)
+                .map_err(|e| format!("Failed to serialize column: {}", e))?
+                .len() as u64;
+            *per_account_bytes.entry(column.did.clone()).or_insert(0) += bytes;
+        }
+    }
+
+    let snapshots: Vec<ColumnSnapshotInfo> =
+        list_snapshots(data_dir)?.into_iter().map(|(info, _)| info).collect();
+    let snapshot_bytes = snapshots.iter().map(|s| s.bytes).sum();
+
+    Ok(ColumnsStorageReport {
+        columns_file_bytes,
+        per_account_bytes,
+        snapshots,
+        snapshot_bytes,
+        total_bytes: columns_file_bytes + snapshot_bytes,
+    })
+}
+
+/// Delete all but the newest `keep` column snapshots
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `keep` - Number of snapshots to keep
+///
+/// # Returns
+/// File names of the deleted snapshots
+pub fn prune_column_snapshots(data_dir: &Path, keep: usize) -> Result<Vec<String>, String> {
+    let snapshots_dir = data_dir.join(COLUMN_SNAPSHOTS_DIR);
+    let mut removed = Vec::new();
+
+    for (info, _) in list_snapshots(data_dir)?.into_iter().skip(keep) {
+        fs::remove_file(snapshots_dir.join(&info.file_name))
+            .map_err(|e| format!("Failed to delete snapshot {}: {}", info.file_name, e))?;
+        removed.push(info.file_name);
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::columns::{get_default_columns, save_columns};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    /// Write snapshots named snapshot-0..n, each one second newer than the previous
+    fn seed_snapshots(data_dir: &Path, count: usize) {
+        let snapshots_dir = data_dir.join(COLUMN_SNAPSHOTS_DIR);
+        fs::create_dir_all(&snapshots_dir).unwrap();
+        let base = SystemTime::now() - Duration::from_secs(3600);
+
+        for index in 0..count {
+            let path = snapshots_dir.join(format!("snapshot-{}.json", index));
+            fs::write(&path, vec![b'x'; 100 * (index + 1)]).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(base + Duration::from_secs(index as u64))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_report_for_seeded_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let mut columns = get_default_columns("did:plc:alice");
+        columns.extend(get_default_columns("did:plc:alice"));
+        columns.extend(get_default_columns("did:plc:bob"));
+        save_columns(&data_dir.to_path_buf(), columns).unwrap();
+        seed_snapshots(data_dir, 3);
+
+        let report = columns_storage_report(data_dir).unwrap();
+
+        assert_eq!(
+            report.columns_file_bytes,
+            fs::metadata(data_dir.join(COLUMNS_FILE)).unwrap().len()
+        );
+        assert_eq!(report.per_account_bytes.len(), 2);
+        let per_account = &report.per_account_bytes;
+        assert!(per_account["did:plc:alice"] > per_account["did:plc:bob"]);
+        assert!(per_account.values().sum::<u64>() <= report.columns_file_bytes);
+        assert_eq!(report.snapshots.len(), 3);
+        assert_eq!(report.snapshots[0].file_name, "snapshot-2.json");
+        assert_eq!(report.snapshot_bytes, 600);
+        assert_eq!(report.total_bytes, report.columns_file_bytes + 600);
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_snapshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        seed_snapshots(data_dir, 5);
+
+        let mut removed = prune_column_snapshots(data_dir, 2).unwrap();
+        removed.sort();
+
+        assert_eq!(removed, vec!["snapshot-0.json", "snapshot-1.json", "snapshot-2.json"]);
+        let report = columns_storage_report(data_dir).unwrap();
+        let kept: Vec<&str> = report.snapshots.iter().map(|s| s.file_name.as_str()).collect();
+        assert_eq!(kept, vec!["snapshot-4.json", "snapshot-3.json"]);
+        assert_eq!(report.total_bytes, 900);
+
+        // Nothing to prune when fewer than `keep` exist
+        assert!(prune_column_snapshots(data_dir, 10).unwrap().is_empty());
+    }
+}
@@ -1,22 +1,98 @@
 /**
  * Cryptographic utilities for secure data storage
  *
- * Provides AES-256-GCM encryption for sensitive authentication data
+ * Provides AES-256-GCM (default) and XChaCha20-Poly1305 encryption for sensitive
+ * authentication data
  */
 
+use crate::storage::keyfile::KdfParams;
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256Gcm, Key, Nonce,
 };
 use argon2::{
     password_hash::{rand_core::RngCore, PasswordHasher, SaltString},
-    Argon2,
+    Algorithm, Argon2, Version,
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Cipher used for encrypted storage unless another one is chosen
+pub const CIPHER_ALGORITHM: &str = "AES-256-GCM";
+
+/// Leading format byte of AES-256-GCM ciphertexts (`version || nonce || ciphertext`)
+pub const CIPHER_VERSION_AES_256_GCM: u8 = 0x01;
+
+/// Leading format byte of XChaCha20-Poly1305 ciphertexts
+pub const CIPHER_VERSION_XCHACHA20_POLY1305: u8 = 0x02;
+
+/// AES-GCM nonce length
+const AES_GCM_NONCE_LEN: usize = 12;
+
+/// XChaCha20 nonce length
+const XCHACHA_NONCE_LEN: usize = 24;
+
+/// Cipher new data is encrypted with (decryption follows each ciphertext's version byte)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CipherAlgorithm {
+    /// AES-256-GCM (fastest with AES hardware acceleration)
+    #[default]
+    #[serde(rename = "AES-256-GCM")]
+    Aes256Gcm,
+    /// XChaCha20-Poly1305 (fast without AES acceleration, 192-bit random nonces)
+    #[serde(rename = "XChaCha20-Poly1305")]
+    XChaCha20Poly1305,
+}
+
+impl CipherAlgorithm {
+    /// Display name (e.g., "AES-256-GCM")
+    pub fn name(self) -> &'static str {
+        match self {
+            CipherAlgorithm::Aes256Gcm => CIPHER_ALGORITHM,
+            CipherAlgorithm::XChaCha20Poly1305 => "XChaCha20-Poly1305",
+        }
+    }
+
+    /// Encrypt data with this cipher
+    pub fn encrypt(self, data: &[u8], key: &[u8]) -> Result<String, String> {
+        match self {
+            CipherAlgorithm::Aes256Gcm => encrypt(data, key),
+            CipherAlgorithm::XChaCha20Poly1305 => encrypt_xchacha(data, key),
+        }
+    }
+}
+
+/// Known plaintext used by `verify_roundtrip`
+const ROUNDTRIP_TEST_VECTOR: &[u8] = b"taurisky encryption round-trip test vector";
 
 /// Derive encryption key from password using Argon2
-pub fn derive_key_from_password(password: &str, salt: &[u8]) -> Result<Vec<u8>, String> {
-    let argon2 = Argon2::default();
+///
+/// # Note
+/// Uses the `Argon2::default()` parameters (`KdfParams::legacy`), which files that
+/// don't record their parameters (account bundles, backups) are derived with. The key
+/// is wiped from memory when dropped; the password stays owned (and should be wiped)
+/// by the caller.
+pub fn derive_key_from_password(
+    password: &str,
+    salt: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, String> {
+    derive_key_with_params(password, salt, &KdfParams::legacy())
+}
+
+/// Derive encryption key from password using Argon2 with explicit parameters
+///
+/// # Arguments
+/// * `password` - Password to derive the key from
+/// * `salt` - Key derivation salt
+/// * `params` - Argon2 parameters (stored next to the salt, see `KeyFile`)
+pub fn derive_key_with_params(
+    password: &str,
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<Zeroizing<Vec<u8>>, String> {
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.argon2_params()?);
     let salt_string = SaltString::encode_b64(salt).map_err(|e| format!("Salt encoding error: {}", e))?;
 
     let password_hash = argon2
@@ -28,7 +104,7 @@ pub fn derive_key_from_password(password: &str, salt: &[u8]) -> Result<Vec<u8>,
         .ok_or_else(|| "Password hash extraction failed".to_string())?;
 
     // Take first 32 bytes for AES-256
-    Ok(hash.as_bytes()[..32].to_vec())
+    Ok(Zeroizing::new(hash.as_bytes()[..32].to_vec()))
 }
 
 /// Generate a random salt
@@ -38,8 +114,19 @@ pub fn generate_salt() -> Vec<u8> {
     salt
 }
 
-/// Encrypt data using AES-256-GCM
+/// Encrypt data using AES-256-GCM (no associated data; used for the whole-store file)
 pub fn encrypt(data: &[u8], key: &[u8]) -> Result<String, String> {
+    encrypt_with_aad(data, key, &[])
+}
+
+/// Encrypt data using AES-256-GCM, binding it to associated data
+///
+/// # Arguments
+/// * `data` - Plaintext
+/// * `key` - 32-byte key
+/// * `aad` - Associated data (e.g., an account ID or file-purpose tag); not stored,
+///   so `decrypt_with_aad` must be given the same bytes
+pub fn encrypt_with_aad(data: &[u8], key: &[u8], aad: &[u8]) -> Result<String, String> {
     if key.len() != 32 {
         return Err("Key must be 32 bytes for AES-256".to_string());
     }
@@ -54,44 +141,148 @@ pub fn encrypt(data: &[u8], key: &[u8]) -> Result<String, String> {
 
     // Encrypt the data
     let ciphertext = cipher
-        .encrypt(nonce, data)
+        .encrypt(nonce, Payload { msg: data, aad })
         .map_err(|e| format!("Encryption failed: {}", e))?;
 
-    // Combine nonce + ciphertext and encode as base64
-    let mut combined = nonce_bytes.to_vec();
+    // Combine version + nonce + ciphertext and encode as base64
+    let mut combined = vec![CIPHER_VERSION_AES_256_GCM];
+    combined.extend_from_slice(&nonce_bytes);
     combined.extend_from_slice(&ciphertext);
 
     Ok(BASE64.encode(&combined))
 }
 
-/// Decrypt data using AES-256-GCM
+/// Encrypt data using XChaCha20-Poly1305
+pub fn encrypt_xchacha(data: &[u8], key: &[u8]) -> Result<String, String> {
+    if key.len() != 32 {
+        return Err("Key must be 32 bytes for XChaCha20-Poly1305".to_string());
+    }
+
+    let cipher = XChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(key));
+
+    // Generate random nonce (24 bytes, safe to choose at random)
+    let mut nonce_bytes = [0u8; XCHACHA_NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), Payload { msg: data, aad: &[] })
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+
+    // Combine version + nonce + ciphertext and encode as base64
+    let mut combined = vec![CIPHER_VERSION_XCHACHA20_POLY1305];
+    combined.extend_from_slice(&nonce_bytes);
+    combined.extend_from_slice(&ciphertext);
+
+    Ok(BASE64.encode(&combined))
+}
+
+/// Decrypt data produced by `encrypt_xchacha` (other ciphertexts are rejected)
+#[allow(dead_code)]
+pub fn decrypt_xchacha(encrypted_data: &str, key: &[u8]) -> Result<Vec<u8>, String> {
+    if key.len() != 32 {
+        return Err("Key must be 32 bytes for XChaCha20-Poly1305".to_string());
+    }
+
+    let combined = BASE64
+        .decode(encrypted_data)
+        .map_err(|e| format!("Base64 decode failed: {}", e))?;
+
+    match combined.split_first() {
+        Some((&CIPHER_VERSION_XCHACHA20_POLY1305, sealed)) => {
+            decrypt_xchacha_sealed(sealed, key, &[])
+        }
+        _ => Err("Not XChaCha20-Poly1305 encrypted data".to_string()),
+    }
+}
+
+/// Decrypt data produced by `encrypt` or `encrypt_xchacha`, selecting the cipher by
+/// its version byte
+///
+/// # Note
+/// Data written before the version byte was added (AES-256-GCM `nonce || ciphertext`)
+/// is still accepted: its first byte is a random nonce byte, so unversioned data is
+/// tried whenever the versioned reading fails. Files are rewritten in the versioned
+/// format on their next save.
 pub fn decrypt(encrypted_data: &str, key: &[u8]) -> Result<Vec<u8>, String> {
+    decrypt_with_aad(encrypted_data, key, &[])
+}
+
+/// Decrypt data produced by `encrypt_with_aad`
+///
+/// # Arguments
+/// * `encrypted_data` - Ciphertext
+/// * `key` - 32-byte key
+/// * `aad` - Associated data used for encryption; any other value fails decryption
+pub fn decrypt_with_aad(encrypted_data: &str, key: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
     if key.len() != 32 {
         return Err("Key must be 32 bytes for AES-256".to_string());
     }
 
-    let key = Key::<Aes256Gcm>::from_slice(key);
-    let cipher = Aes256Gcm::new(key);
-
     // Decode base64
     let combined = BASE64
         .decode(encrypted_data)
         .map_err(|e| format!("Base64 decode failed: {}", e))?;
 
-    if combined.len() < 12 {
+    let versioned = match combined.split_first() {
+        Some((&CIPHER_VERSION_AES_256_GCM, sealed)) => decrypt_aes_gcm(sealed, key, aad),
+        Some((&CIPHER_VERSION_XCHACHA20_POLY1305, sealed)) => {
+            decrypt_xchacha_sealed(sealed, key, aad)
+        }
+        Some((version, _)) => Err(format!(
+            "Decryption failed: unsupported ciphertext version 0x{:02x} (or unversioned \
+             data encrypted with another key)",
+            version
+        )),
+        None => Err("Invalid encrypted data: too short".to_string()),
+    };
+
+    versioned.or_else(|e| decrypt_aes_gcm(&combined, key, aad).map_err(|_| e))
+}
+
+/// Decrypt `nonce || ciphertext` with XChaCha20-Poly1305
+fn decrypt_xchacha_sealed(sealed: &[u8], key: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < XCHACHA_NONCE_LEN {
         return Err("Invalid encrypted data: too short".to_string());
     }
 
+    let cipher = XChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(key));
+    let (nonce_bytes, ciphertext) = sealed.split_at(XCHACHA_NONCE_LEN);
+
+    cipher
+        .decrypt(XNonce::from_slice(nonce_bytes), Payload { msg: ciphertext, aad })
+        .map_err(|e| format!("Decryption failed: {}", e))
+}
+
+/// Decrypt `nonce || ciphertext` with AES-256-GCM
+fn decrypt_aes_gcm(sealed: &[u8], key: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < AES_GCM_NONCE_LEN {
+        return Err("Invalid encrypted data: too short".to_string());
+    }
+
+    let key = Key::<Aes256Gcm>::from_slice(key);
+    let cipher = Aes256Gcm::new(key);
+
     // Split nonce and ciphertext
-    let (nonce_bytes, ciphertext) = combined.split_at(12);
+    let (nonce_bytes, ciphertext) = sealed.split_at(AES_GCM_NONCE_LEN);
     let nonce = Nonce::from_slice(nonce_bytes);
 
     // Decrypt
     cipher
-        .decrypt(nonce, ciphertext)
+        .decrypt(nonce, Payload { msg: ciphertext, aad })
         .map_err(|e| format!("Decryption failed: {}", e))
 }
 
+/// Encrypt and decrypt a known test vector with a key and compare the result
+pub fn verify_roundtrip(algorithm: CipherAlgorithm, key: &[u8]) -> Result<(), String> {
+    let encrypted = algorithm.encrypt(ROUNDTRIP_TEST_VECTOR, key)?;
+    let decrypted = decrypt(&encrypted, key)?;
+
+    if decrypted != ROUNDTRIP_TEST_VECTOR {
+        return Err("Round-trip produced different data".to_string());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,6 +298,87 @@ mod tests {
         assert_eq!(data.to_vec(), decrypted);
     }
 
+    /// Encrypt in the format used before the version byte (`nonce || ciphertext`)
+    fn encrypt_unversioned(data: &[u8], key: &[u8], nonce_bytes: [u8; 12]) -> String {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce_bytes), data).unwrap();
+        BASE64.encode([nonce_bytes.as_slice(), &ciphertext].concat())
+    }
+
+    #[test]
+    fn test_ciphertext_is_versioned_and_unversioned_data_still_decrypts() {
+        let key = vec![3u8; 32];
+
+        let encrypted = BASE64.decode(encrypt(b"data", &key).unwrap()).unwrap();
+        assert_eq!(encrypted[0], CIPHER_VERSION_AES_256_GCM);
+        assert_eq!(encrypted.len(), 1 + 12 + b"data".len() + 16);
+
+        // Including unversioned data whose nonce happens to start with the version byte
+        for first in [0x00, CIPHER_VERSION_AES_256_GCM, 0xff] {
+            let mut nonce = [7u8; 12];
+            nonce[0] = first;
+            let legacy = encrypt_unversioned(b"legacy", &key, nonce);
+            assert_eq!(decrypt(&legacy, &key).unwrap(), b"legacy");
+        }
+
+        let mut unknown = encrypted.clone();
+        unknown[0] = 0x7f;
+        let error = decrypt(&BASE64.encode(&unknown), &key).unwrap_err();
+        assert!(error.contains("unsupported ciphertext version 0x7f"));
+        assert!(decrypt(&BASE64.encode(&encrypted), &[4u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_xchacha_round_trip() {
+        let key = vec![5u8; 32];
+
+        let encrypted = encrypt_xchacha(b"Hello, World!", &key).unwrap();
+        let raw = BASE64.decode(&encrypted).unwrap();
+        assert_eq!(raw[0], CIPHER_VERSION_XCHACHA20_POLY1305);
+        assert_eq!(raw.len(), 1 + 24 + 13 + 16);
+
+        assert_eq!(decrypt_xchacha(&encrypted, &key).unwrap(), b"Hello, World!");
+        assert_eq!(decrypt(&encrypted, &key).unwrap(), b"Hello, World!");
+        assert_ne!(encrypt_xchacha(b"Hello, World!", &key).unwrap(), encrypted);
+        assert!(decrypt_xchacha(&encrypted, &[6u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_ciphers_do_not_misread_each_other() {
+        let key = vec![5u8; 32];
+        let aes = BASE64.decode(encrypt(b"data", &key).unwrap()).unwrap();
+        let xchacha = BASE64.decode(encrypt_xchacha(b"data", &key).unwrap()).unwrap();
+
+        // Relabelled ciphertexts fail authentication instead of producing garbage
+        let mut aes_as_xchacha = aes.clone();
+        aes_as_xchacha[0] = CIPHER_VERSION_XCHACHA20_POLY1305;
+        assert!(decrypt(&BASE64.encode(&aes_as_xchacha), &key).is_err());
+        let mut xchacha_as_aes = xchacha.clone();
+        xchacha_as_aes[0] = CIPHER_VERSION_AES_256_GCM;
+        assert!(decrypt(&BASE64.encode(&xchacha_as_aes), &key).is_err());
+
+        assert!(decrypt_xchacha(&BASE64.encode(&aes), &key).is_err());
+        for algorithm in [CipherAlgorithm::Aes256Gcm, CipherAlgorithm::XChaCha20Poly1305] {
+            let encrypted = algorithm.encrypt(b"data", &key).unwrap();
+            assert_eq!(decrypt(&encrypted, &key).unwrap(), b"data");
+        }
+    }
+
+    #[test]
+    fn test_associated_data_must_match() {
+        let key = vec![8u8; 32];
+        let encrypted = encrypt_with_aad(b"token", &key, b"account:alice").unwrap();
+
+        assert_eq!(decrypt_with_aad(&encrypted, &key, b"account:alice").unwrap(), b"token");
+        assert!(decrypt_with_aad(&encrypted, &key, b"account:bob").is_err());
+        assert!(decrypt(&encrypted, &key).is_err());
+
+        // No associated data is the same as empty associated data
+        let plain = encrypt(b"token", &key).unwrap();
+        assert_eq!(decrypt_with_aad(&plain, &key, &[]).unwrap(), b"token");
+        assert!(decrypt_with_aad(&plain, &key, b"account:alice").is_err());
+    }
+
     #[test]
     fn test_key_derivation() {
         let password = "test_password";
@@ -118,4 +390,41 @@ mod tests {
         assert_eq!(key1, key2);
         assert_eq!(key1.len(), 32);
     }
+
+    #[test]
+    fn test_stored_params_reproduce_the_key() {
+        let salt = generate_salt();
+        let params = KdfParams { memory_kib: 8 * 1024, iterations: 1, ..KdfParams::current() };
+
+        let key = derive_key_with_params("password", &salt, &params).unwrap();
+        assert_eq!(key, derive_key_with_params("password", &salt, &params.clone()).unwrap());
+        assert_eq!(
+            derive_key_from_password("password", &salt).unwrap(),
+            derive_key_with_params("password", &salt, &KdfParams::legacy()).unwrap()
+        );
+
+        for changed in [
+            KdfParams { memory_kib: 16 * 1024, ..params.clone() },
+            KdfParams { iterations: 2, ..params.clone() },
+            KdfParams { parallelism: 2, ..params.clone() },
+        ] {
+            assert_ne!(key, derive_key_with_params("password", &salt, &changed).unwrap());
+        }
+        let unsupported = KdfParams { algorithm: "scrypt".to_string(), ..params };
+        assert!(derive_key_with_params("password", &salt, &unsupported).is_err());
+    }
+
+    #[test]
+    fn test_zeroizing_key_encrypts_until_wiped() {
+        use zeroize::Zeroize;
+
+        let mut key = derive_key_from_password("test_password", &generate_salt()).unwrap();
+        let encrypted = encrypt(b"secret", &key).unwrap();
+        assert_eq!(decrypt(&encrypted, &key).unwrap(), b"secret");
+
+        // What Drop does: the bytes are overwritten (and the buffer cleared)
+        key.zeroize();
+        assert!(key.is_empty());
+        assert!(decrypt(&encrypted, &key).is_err());
+    }
 }
@@ -0,0 +1,153 @@
+/**
+ * Full-state debug snapshot
+ *
+ * Nothing is redacted: the dump contains every account, token, column and setting
+ * so maintainers can reproduce a bug. It is only produced with the user's explicit
+ * consent and always password-encrypted.
+ */
+
+use crate::storage::columns::load_columns;
+use crate::storage::crypto::{derive_key_from_password, encrypt, generate_salt};
+use crate::storage::settings::load_settings;
+use crate::storage::StorageManager;
+use crate::types::{Account, AppSettings, AuthToken, DeckColumnConfig};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Current debug snapshot format version
+pub const DEBUG_SNAPSHOT_VERSION: u32 = 1;
+
+/// Label stored in the clear so nobody mistakes the file for a harmless log
+pub const DEBUG_SNAPSHOT_WARNING: &str =
+    "CONTAINS SECRETS: full app state including session tokens. Share only with maintainers.";
+
+/// Outer (unencrypted) snapshot envelope
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotEnvelope {
+    /// Snapshot format version
+    version: u32,
+    /// Always true; the payload holds credentials
+    contains_secrets: bool,
+    /// Human-readable warning (`DEBUG_SNAPSHOT_WARNING`)
+    warning: String,
+    /// When the snapshot was taken (ISO 8601)
+    created_at: String,
+    /// Key derivation salt (base64)
+    salt: String,
+    /// Encrypted `SnapshotPayload` (base64 nonce + ciphertext)
+    data: String,
+}
+
+/// Encrypted snapshot contents
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotPayload {
+    accounts: Vec<Account>,
+    tokens: Vec<AuthToken>,
+    columns: Vec<DeckColumnConfig>,
+    settings: AppSettings,
+}
+
+/// Bundle the decrypted storage, columns and settings into an encrypted snapshot
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory (columns and settings location)
+/// * `password` - Password protecting the snapshot
+/// * `consent` - The user explicitly agreed to export secrets
+///
+/// # Returns
+/// Versioned, encrypted snapshot JSON, labeled as containing secrets
+pub async fn export_debug_snapshot(
+    storage: &StorageManager,
+    data_dir: &Path,
+    password: &str,
+    consent: bool,
+) -> Result<String, String> {
+    if !consent {
+        return Err("Exporting a debug snapshot requires explicit consent".to_string());
+    }
+    if password.is_empty() {
+        return Err("Snapshot password must not be empty".to_string());
+    }
+
+    let accounts = storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?;
+    let mut tokens = Vec::new();
+    for account in &accounts {
+        // Accounts without a stored token are still included
+        if let Ok(token) = storage.get_auth_token(&account.id).await {
+            tokens.push(token);
+        }
+    }
+
+    let payload = serde_json::to_vec(&SnapshotPayload {
+        accounts,
+        tokens,
+        columns: load_columns(&data_dir.to_path_buf())?,
+        settings: load_settings(data_dir)?,
+    })
+    .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+
+    let salt = generate_salt();
+    let key = derive_key_from_password(password, &salt)?;
+
+    serde_json::to_string_pretty(&SnapshotEnvelope {
+        version: DEBUG_SNAPSHOT_VERSION,
+        contains_secrets: true,
+        warning: DEBUG_SNAPSHOT_WARNING.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        salt: BASE64.encode(&salt),
+        data: encrypt(&payload, &key)?,
+    })
+    .map_err(|e| format!("Failed to serialize snapshot: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::crypto::decrypt;
+    use crate::test_support::storage_with_account;
+    use serde_json::Value;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_snapshot_requires_consent() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_account(temp_dir.path()).await;
+
+        let result = export_debug_snapshot(&storage, temp_dir.path(), "snapshot-pw", false).await;
+
+        assert!(result.unwrap_err().contains("consent"));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_is_decryptable_with_all_sections() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = storage_with_account(temp_dir.path()).await;
+
+        let snapshot = export_debug_snapshot(&storage, temp_dir.path(), "snapshot-pw", true)
+            .await
+            .unwrap();
+        assert!(!snapshot.contains("secret-access"));
+
+        let envelope: SnapshotEnvelope = serde_json::from_str(&snapshot).unwrap();
+        assert_eq!(envelope.version, DEBUG_SNAPSHOT_VERSION);
+        assert!(envelope.contains_secrets);
+        assert_eq!(envelope.warning, DEBUG_SNAPSHOT_WARNING);
+
+        let salt = BASE64.decode(&envelope.salt).unwrap();
+        let key = derive_key_from_password("snapshot-pw", &salt).unwrap();
+        let payload: Value = serde_json::from_slice(&decrypt(&envelope.data, &key).unwrap())
+            .unwrap();
+
+        assert_eq!(payload["accounts"][0]["did"], "did:plc:alice");
+        assert_eq!(payload["tokens"][0]["accessJwt"], "secret-access");
+        assert_eq!(payload["columns"].as_array().unwrap().len(), 1);
+        assert!(payload["settings"].is_object());
+    }
+}
@@ -0,0 +1,137 @@
+/**
+ * Storage keys held outside the app
+ *
+ * A key provider supplies the storage encryption key directly (e.g., from the OS
+ * keychain), so no key has to be derived from a password. Without a provider the
+ * password path stays in use.
+ */
+
+use zeroize::Zeroizing;
+
+/// Supplies the 32-byte storage encryption key
+pub trait KeyProvider: Send + Sync {
+    /// Human-readable provider name (for reports)
+    fn name(&self) -> &'static str;
+
+    /// Return the stored key, generating and storing a random one on first use
+    fn load_or_create_key(&self) -> Result<Zeroizing<Vec<u8>>, String>;
+}
+
+/// Key provider of the current platform, if one is compiled in
+///
+/// Only available with the `keychain` feature; otherwise the key is always derived
+/// from the password.
+pub fn platform_key_provider() -> Option<Box<dyn KeyProvider>> {
+    #[cfg(feature = "keychain")]
+    {
+        Some(Box::new(keychain::KeychainKeyProvider::default()))
+    }
+    #[cfg(not(feature = "keychain"))]
+    {
+        None
+    }
+}
+
+#[cfg(feature = "keychain")]
+pub mod keychain {
+    use super::KeyProvider;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+    use zeroize::Zeroizing;
+
+    /// Keychain service name of the storage key
+    pub const KEYCHAIN_SERVICE: &str = "taurisky";
+    /// Keychain account name of the storage key
+    pub const KEYCHAIN_ACCOUNT: &str = "storage-key";
+
+    /// Storage key kept in the OS keychain (macOS Keychain, Windows Credential
+    /// Manager, Linux Secret Service)
+    pub struct KeychainKeyProvider {
+        service: String,
+        account: String,
+    }
+
+    impl KeychainKeyProvider {
+        /// Provider for a keychain entry
+        ///
+        /// # Arguments
+        /// * `service` - Keychain service name
+        /// * `account` - Keychain account name
+        pub fn new(service: &str, account: &str) -> Self {
+            Self {
+                service: service.to_string(),
+                account: account.to_string(),
+            }
+        }
+
+        fn entry(&self) -> Result<keyring::Entry, String> {
+            keyring::Entry::new(&self.service, &self.account)
+                .map_err(|e| format!("Failed to open keychain entry: {}", e))
+        }
+    }
+
+    impl Default for KeychainKeyProvider {
+        fn default() -> Self {
+            Self::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        }
+    }
+
+    impl KeyProvider for KeychainKeyProvider {
+        fn name(&self) -> &'static str {
+            "keychain"
+        }
+
+        fn load_or_create_key(&self) -> Result<Zeroizing<Vec<u8>>, String> {
+            let entry = self.entry()?;
+
+            match entry.get_password() {
+                Ok(encoded) => {
+                    let encoded = Zeroizing::new(encoded);
+                    let key = Zeroizing::new(
+                        BASE64
+                            .decode(encoded.as_str())
+                            .map_err(|e| format!("Invalid keychain key: {}", e))?,
+                    );
+                    if key.len() != 32 {
+                        return Err(format!("Invalid keychain key length: {}", key.len()));
+                    }
+                    Ok(key)
+                }
+                Err(keyring::Error::NoEntry) => {
+                    let mut key = Zeroizing::new(vec![0u8; 32]);
+                    OsRng.fill_bytes(&mut key);
+                    let encoded = Zeroizing::new(BASE64.encode(&key));
+                    entry
+                        .set_password(&encoded)
+                        .map_err(|e| format!("Failed to store keychain key: {}", e))?;
+                    Ok(key)
+                }
+                Err(e) => Err(format!("Failed to read keychain key: {}", e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Test provider keeping its key in memory
+    #[derive(Default)]
+    pub(crate) struct MemoryKeyProvider {
+        pub(crate) key: Mutex<Option<Vec<u8>>>,
+    }
+
+    impl KeyProvider for MemoryKeyProvider {
+        fn name(&self) -> &'static str {
+            "memory"
+        }
+
+        fn load_or_create_key(&self) -> Result<Zeroizing<Vec<u8>>, String> {
+            let mut key = self.key.lock().map_err(|e| e.to_string())?;
+            Ok(Zeroizing::new(key.get_or_insert_with(|| vec![9u8; 32]).clone()))
+        }
+    }
+}
@@ -0,0 +1,437 @@
+/**
+ * Key derivation file
+ *
+ * Stores the salt and KDF parameters of the storage file as one atomically written
+ * file with a checksum, so a partial write is detected instead of silently deriving
+ * a wrong key.
+ */
+
+use crate::storage::crypto::generate_salt;
+use crate::types::AuthError;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Salt + KDF parameters of the storage file
+pub(crate) const KEY_FILE: &str = "keyfile.json";
+
+/// Suffix of files staged by a key rotation (`PersistentStorage::rekey`)
+pub(crate) const REKEY_SUFFIX: &str = "rekey";
+
+/// Salt file written before KDF parameters were stored (migrated on open)
+const LEGACY_SALT_FILE: &str = "salt.bin";
+
+/// Directory (inside the data dir) receiving storage files whose key file is broken
+const QUARANTINE_DIR: &str = "quarantine";
+
+/// Current key file format
+const KEY_FILE_VERSION: u32 = 1;
+
+/// Salt length produced by `generate_salt`
+const SALT_LEN: usize = 16;
+
+/// KDF (Argon2) parameters used to derive the storage key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    /// KDF algorithm
+    pub algorithm: String,
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of iterations
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Parameters new key files are written with (argon2id, 64 MiB, 3 iterations,
+    /// 4 lanes: the second recommended option of RFC 9106)
+    pub fn current() -> Self {
+        Self {
+            algorithm: "argon2id".to_string(),
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 4,
+        }
+    }
+
+    /// Parameters of `Argon2::default()`, used by stores created before the parameters
+    /// were configurable and by `derive_key_from_password`
+    pub fn legacy() -> Self {
+        Self {
+            algorithm: "argon2id".to_string(),
+            memory_kib: argon2::Params::DEFAULT_M_COST,
+            iterations: argon2::Params::DEFAULT_T_COST,
+            parallelism: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+
+    /// Argon2 parameters, if the algorithm is supported and the costs are in range
+    pub fn argon2_params(&self) -> Result<argon2::Params, String> {
+        if self.algorithm != "argon2id" {
+            return Err(format!("Unsupported key derivation algorithm: {}", self.algorithm));
+        }
+        argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| format!("Invalid key derivation parameters: {}", e))
+    }
+}
+
+/// On-disk key file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyFile {
+    version: u32,
+    /// Base64-encoded salt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+    /// SHA-256 over version, salt and KDF parameters
+    checksum: String,
+}
+
+impl KeyFile {
+    fn new(salt: &[u8], kdf: &KdfParams) -> Self {
+        let salt = BASE64.encode(salt);
+        let checksum = Self::checksum(KEY_FILE_VERSION, &salt, kdf);
+        Self {
+            version: KEY_FILE_VERSION,
+            salt: Some(salt),
+            kdf: Some(kdf.clone()),
+            checksum,
+        }
+    }
+
+    fn checksum(version: u32, salt: &str, kdf: &KdfParams) -> String {
+        let input = format!(
+            "{}|{}|{}|{}|{}|{}",
+            version, salt, kdf.algorithm, kdf.memory_kib, kdf.iterations, kdf.parallelism
+        );
+        Sha256::digest(input.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Salt and parameters, if both are present and match the checksum
+    fn verified(&self) -> Option<(Vec<u8>, KdfParams)> {
+        let (salt, kdf) = (self.salt.as_ref()?, self.kdf.as_ref()?);
+        if self.version != KEY_FILE_VERSION
+            || Self::checksum(self.version, salt, kdf) != self.checksum
+        {
+            return None;
+        }
+        let salt = BASE64.decode(salt).ok()?;
+        (salt.len() == SALT_LEN).then(|| (salt, kdf.clone()))
+    }
+}
+
+/// State of the key file on disk
+enum KeyFileState {
+    /// Salt and parameters are complete and consistent
+    Valid(Vec<u8>, KdfParams),
+    /// Neither a key file nor a legacy salt exists
+    Missing,
+    /// Partially written or corrupt key material
+    Broken,
+}
+
+fn read_key_file(data_dir: &Path) -> KeyFileState {
+    let key_path = data_dir.join(KEY_FILE);
+    if key_path.exists() {
+        return fs::read_to_string(&key_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<KeyFile>(&contents).ok())
+            .and_then(|key_file| key_file.verified())
+            .map_or(KeyFileState::Broken, |(salt, kdf)| KeyFileState::Valid(salt, kdf));
+    }
+
+    // Stores created before the key file only have the raw salt
+    let legacy_path = data_dir.join(LEGACY_SALT_FILE);
+    if legacy_path.exists() {
+        return match fs::read(&legacy_path) {
+            Ok(salt) if salt.len() == SALT_LEN => KeyFileState::Valid(salt, KdfParams::legacy()),
+            _ => KeyFileState::Broken,
+        };
+    }
+
+    KeyFileState::Missing
+}
+
+/// Write the key file via a temporary file + rename
+fn write_key_file(data_dir: &Path, salt: &[u8], kdf: &KdfParams) -> Result<(), AuthError> {
+    let contents = serde_json::to_vec_pretty(&KeyFile::new(salt, kdf)).map_err(|e| {
+        AuthError::StorageError(format!("Failed to serialize key file: {}", e))
+    })?;
+    let temp_path = data_dir.join(format!("{}.tmp", KEY_FILE));
+    fs::write(&temp_path, contents)
+        .and_then(|_| fs::rename(&temp_path, data_dir.join(KEY_FILE)))
+        .map_err(|e| AuthError::StorageError(format!("Failed to write key file: {}", e)))
+}
+
+/// Path of the key file staged by a key rotation
+pub(crate) fn staged_key_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(format!("{}.{}", KEY_FILE, REKEY_SUFFIX))
+}
+
+/// Write the key file for a new salt and parameters next to the live one (flushed to
+/// disk)
+pub(crate) fn stage_key_file(
+    data_dir: &Path,
+    salt: &[u8],
+    kdf: &KdfParams,
+) -> Result<(), AuthError> {
+    let contents = serde_json::to_vec_pretty(&KeyFile::new(salt, kdf)).map_err(|e| {
+        AuthError::StorageError(format!("Failed to serialize key file: {}", e))
+    })?;
+    fs::File::create(staged_key_file(data_dir))
+        .and_then(|mut file| file.write_all(&contents).and_then(|_| file.sync_all()))
+        .map_err(|e| AuthError::StorageError(format!("Failed to stage key file: {}", e)))
+}
+
+/// Replace the live key file with the staged one
+pub(crate) fn commit_staged_key_file(data_dir: &Path) -> Result<(), AuthError> {
+    fs::rename(staged_key_file(data_dir), data_dir.join(KEY_FILE))
+        .map_err(|e| AuthError::StorageError(format!("Failed to install key file: {}", e)))?;
+    let _ = fs::remove_file(data_dir.join(LEGACY_SALT_FILE));
+    Ok(())
+}
+
+/// Move the given files (those that exist) into `quarantine/<timestamp>/`
+fn quarantine(data_dir: &Path, names: &[&str]) -> Result<(), AuthError> {
+    let target = data_dir
+        .join(QUARANTINE_DIR)
+        .join(chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string());
+    fs::create_dir_all(&target).map_err(|e| {
+        AuthError::StorageError(format!("Failed to create quarantine dir: {}", e))
+    })?;
+
+    for name in names {
+        let path = data_dir.join(name);
+        if path.exists() {
+            fs::rename(&path, target.join(name)).map_err(|e| {
+                AuthError::StorageError(format!("Failed to quarantine {}: {}", name, e))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Load the salt and KDF parameters of the storage file, creating (or repairing) the
+/// key file as needed
+///
+/// # Arguments
+/// * `data_dir` - Directory holding the storage files
+/// * `data_files` - Files encrypted with the derived key (quarantined with a broken key file)
+///
+/// # Returns
+/// The salt and the parameters to derive the key with: the stored ones, so a key is
+/// re-derived identically after `KdfParams::current` changes
+///
+/// # Note
+/// A broken key file without data is treated as "no key file yet" and regenerated.
+/// With existing data the key file and `data_files` are moved into `quarantine/`
+/// (nothing is deleted) and a fresh store is started, since deriving with the
+/// damaged salt would only produce a wrong key. Parameters this build cannot derive
+/// with (unknown algorithm, out-of-range costs) are rejected.
+pub(crate) fn load_or_create_key_params(
+    data_dir: &Path,
+    data_files: &[&str],
+) -> Result<(Vec<u8>, KdfParams), AuthError> {
+    let has_data = data_files.iter().any(|name| data_dir.join(name).exists());
+
+    match read_key_file(data_dir) {
+        KeyFileState::Valid(salt, kdf) => {
+            kdf.argon2_params().map_err(|e| {
+                AuthError::StorageError(format!(
+                    "Unsupported key derivation parameters {:?}: {}",
+                    kdf, e
+                ))
+            })?;
+            if !data_dir.join(KEY_FILE).exists() {
+                write_key_file(data_dir, &salt, &kdf)?;
+                let _ = fs::remove_file(data_dir.join(LEGACY_SALT_FILE));
+            }
+            Ok((salt, kdf))
+        }
+        KeyFileState::Missing | KeyFileState::Broken => {
+            if has_data {
+                let mut names = vec![KEY_FILE, LEGACY_SALT_FILE];
+                names.extend_from_slice(data_files);
+                quarantine(data_dir, &names)?;
+            }
+            let salt = generate_salt();
+            let kdf = KdfParams::current();
+            write_key_file(data_dir, &salt, &kdf)?;
+            let _ = fs::remove_file(data_dir.join(LEGACY_SALT_FILE));
+            Ok((salt, kdf))
+        }
+    }
+}
+
+/// Remove the key file (and a leftover legacy salt)
+pub(crate) fn remove_key_file(data_dir: &Path) -> Result<(), AuthError> {
+    for name in [KEY_FILE, LEGACY_SALT_FILE] {
+        let path = data_dir.join(name);
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| {
+                AuthError::StorageError(format!("Failed to delete key file: {}", e))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tempfile::TempDir;
+
+    const DATA: &str = "storage.enc";
+
+    fn key_file_json(data_dir: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(data_dir.join(KEY_FILE)).unwrap()).unwrap()
+    }
+
+    /// Rewrite the key file with one field removed (simulating a partial write)
+    fn drop_field(data_dir: &Path, field: &str) {
+        let mut json = key_file_json(data_dir);
+        json.as_object_mut().unwrap().remove(field);
+        fs::write(data_dir.join(KEY_FILE), json.to_string()).unwrap();
+    }
+
+    fn quarantined_files(data_dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(data_dir.join(QUARANTINE_DIR))
+            .unwrap()
+            .flatten()
+            .flat_map(|dir| fs::read_dir(dir.path()).unwrap().flatten())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_key_file_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+
+        let salt = load_or_create_key_params(data_dir, &[DATA]).unwrap().0;
+
+        assert_eq!(load_or_create_key_params(data_dir, &[DATA]).unwrap().0, salt);
+        assert!(!data_dir.join(format!("{}.tmp", KEY_FILE)).exists());
+        assert_eq!(key_file_json(data_dir)["kdf"]["algorithm"], "argon2id");
+    }
+
+    #[test]
+    fn test_params_without_salt_regenerates_new_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let salt = load_or_create_key_params(data_dir, &[DATA]).unwrap().0;
+        drop_field(data_dir, "salt");
+
+        let regenerated = load_or_create_key_params(data_dir, &[DATA]).unwrap().0;
+
+        assert_ne!(regenerated, salt);
+        assert!(!data_dir.join(QUARANTINE_DIR).exists());
+        assert!(key_file_json(data_dir)["salt"].is_string());
+    }
+
+    #[test]
+    fn test_salt_without_params_quarantines_existing_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let salt = load_or_create_key_params(data_dir, &[DATA]).unwrap().0;
+        fs::write(data_dir.join(DATA), "ciphertext").unwrap();
+        drop_field(data_dir, "kdf");
+
+        let regenerated = load_or_create_key_params(data_dir, &[DATA]).unwrap().0;
+
+        assert_ne!(regenerated, salt);
+        assert!(!data_dir.join(DATA).exists());
+        assert_eq!(quarantined_files(data_dir), vec![KEY_FILE.to_string(), DATA.to_string()]);
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_detected() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        load_or_create_key_params(data_dir, &[DATA]).unwrap();
+        fs::write(data_dir.join(DATA), "ciphertext").unwrap();
+        let mut json = key_file_json(data_dir);
+        json["salt"] = Value::String(BASE64.encode([7u8; SALT_LEN]));
+        fs::write(data_dir.join(KEY_FILE), json.to_string()).unwrap();
+
+        load_or_create_key_params(data_dir, &[DATA]).unwrap();
+
+        assert!(quarantined_files(data_dir).contains(&DATA.to_string()));
+    }
+
+    #[test]
+    fn test_data_without_key_file_is_quarantined() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        fs::write(data_dir.join(DATA), "ciphertext").unwrap();
+
+        load_or_create_key_params(data_dir, &[DATA]).unwrap();
+
+        assert_eq!(quarantined_files(data_dir), vec![DATA.to_string()]);
+        assert!(data_dir.join(KEY_FILE).exists());
+    }
+
+    #[test]
+    fn test_legacy_salt_is_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let legacy = generate_salt();
+        fs::write(data_dir.join(LEGACY_SALT_FILE), &legacy).unwrap();
+        fs::write(data_dir.join(DATA), "ciphertext").unwrap();
+
+        assert_eq!(load_or_create_key_params(data_dir, &[DATA]).unwrap().0, legacy);
+        assert!(!data_dir.join(LEGACY_SALT_FILE).exists());
+        assert!(data_dir.join(DATA).exists());
+        let (salt, kdf) = load_or_create_key_params(data_dir, &[DATA]).unwrap();
+        assert_eq!((salt, kdf), (legacy, KdfParams::legacy()));
+    }
+
+    #[test]
+    fn test_truncated_legacy_salt_is_quarantined() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        fs::write(data_dir.join(LEGACY_SALT_FILE), [1u8; 5]).unwrap();
+        fs::write(data_dir.join(DATA), "ciphertext").unwrap();
+
+        load_or_create_key_params(data_dir, &[DATA]).unwrap();
+
+        assert_eq!(
+            quarantined_files(data_dir),
+            vec![LEGACY_SALT_FILE.to_string(), DATA.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_stored_params_are_kept_and_unsupported_ones_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let write = |kdf: &KdfParams| {
+            let key_file = KeyFile::new(&generate_salt(), kdf);
+            fs::write(data_dir.join(KEY_FILE), serde_json::to_string(&key_file).unwrap())
+                .unwrap();
+        };
+
+        // Parameters other than the current ones (e.g., an older default) are used as is
+        let older = KdfParams { iterations: 9, ..KdfParams::current() };
+        write(&older);
+        assert_eq!(load_or_create_key_params(data_dir, &[DATA]).unwrap().1, older);
+
+        write(&KdfParams { algorithm: "scrypt".to_string(), ..KdfParams::current() });
+        assert!(load_or_create_key_params(data_dir, &[DATA]).is_err());
+        write(&KdfParams { parallelism: 0, ..KdfParams::current() });
+        assert!(load_or_create_key_params(data_dir, &[DATA]).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+/**
+ * Data directory maintenance
+ *
+ * Removes leftovers of interrupted atomic writes (`*.tmp`) and snapshots beyond the
+ * keep count. Runs on startup and from the maintenance screen.
+ */
+
+use crate::api::avatars::AVATAR_CACHE_DIR;
+use crate::storage::columns_footprint::prune_column_snapshots;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Minimum age of a `.tmp` file before it is considered orphaned
+///
+/// Atomic writes rename their temp file within milliseconds; anything older than
+/// this is not in use by a running write.
+pub const STALE_TEMP_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Number of column snapshots kept by the startup cleanup
+pub const DEFAULT_SNAPSHOT_KEEP: usize = 10;
+
+/// Directories (relative to the data dir) scanned for temp files
+const TEMP_FILE_DIRS: [&str; 2] = ["", AVATAR_CACHE_DIR];
+
+/// What `cleanup_temp_files` removed
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    /// Removed temp files (paths relative to the data dir)
+    pub removed_temp_files: Vec<String>,
+    /// Removed column snapshot file names
+    pub removed_snapshots: Vec<String>,
+}
+
+/// Remove stale temp files and prune column snapshots
+///
+/// # Arguments
+/// * `data_dir` - App data directory
+/// * `keep_snapshots` - Number of newest column snapshots to keep
+/// * `min_age` - Temp files modified more recently than this are left alone
+///
+/// # Note
+/// Temp files are matched by the `.tmp` suffix in the data dir and the avatar
+/// cache. Backup staging directories are left to `resume_import`.
+pub fn cleanup_temp_files(
+    data_dir: &Path,
+    keep_snapshots: usize,
+    min_age: Duration,
+) -> Result<CleanupReport, String> {
+    let now = SystemTime::now();
+    let mut report = CleanupReport::default();
+
+    for dir in TEMP_FILE_DIRS {
+        let Ok(entries) = fs::read_dir(data_dir.join(dir)) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_temp = path.is_file()
+                && path.extension().and_then(|ext| ext.to_str()) == Some("tmp");
+            if !is_temp {
+                continue;
+            }
+
+            let age = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age < min_age {
+                continue;
+            }
+
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+            let relative = Path::new(dir).join(entry.file_name());
+            report.removed_temp_files.push(relative.to_string_lossy().into_owned());
+        }
+    }
+    report.removed_temp_files.sort();
+
+    report.removed_snapshots = prune_column_snapshots(data_dir, keep_snapshots)?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::columns_footprint::COLUMN_SNAPSHOTS_DIR;
+    use tempfile::TempDir;
+
+    fn write_aged(path: &Path, age: Duration) {
+        fs::write(path, b"partial").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_stale_temp_files_are_removed_and_fresh_ones_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let hour = Duration::from_secs(3600);
+        fs::create_dir_all(data_dir.join(AVATAR_CACHE_DIR)).unwrap();
+        fs::create_dir_all(data_dir.join(COLUMN_SNAPSHOTS_DIR)).unwrap();
+
+        write_aged(&data_dir.join("columns.json.tmp"), hour);
+        write_aged(&data_dir.join(AVATAR_CACHE_DIR).join("abc.img.tmp"), hour);
+        write_aged(&data_dir.join("storage.enc.tmp"), Duration::ZERO);
+        write_aged(&data_dir.join("columns.json"), hour);
+        for index in 0..3 {
+            let name = format!("snapshot-{}.json", index);
+            write_aged(&data_dir.join(COLUMN_SNAPSHOTS_DIR).join(name), hour);
+        }
+
+        let report = cleanup_temp_files(data_dir, 2, STALE_TEMP_AGE).unwrap();
+
+        let avatar_temp = Path::new(AVATAR_CACHE_DIR).join("abc.img.tmp");
+        assert_eq!(
+            report.removed_temp_files,
+            vec![avatar_temp.to_string_lossy().into_owned(), "columns.json.tmp".to_string()]
+        );
+        assert!(!data_dir.join("columns.json.tmp").exists());
+        // In use by a running write
+        assert!(data_dir.join("storage.enc.tmp").exists());
+        assert!(data_dir.join("columns.json").exists());
+        assert_eq!(report.removed_snapshots.len(), 1);
+    }
+}
@@ -4,71 +4,456 @@
  * Provides encrypted file-based storage for accounts and authentication tokens
  */
 
+pub mod account_bundle;
+pub mod backup;
 pub mod columns;
+pub mod columns_footprint;
 mod crypto;
+pub mod debug_snapshot;
+pub mod key_provider;
+pub(crate) mod keyfile;
+pub mod maintenance;
+pub mod passphrase;
 mod persistence;
+pub mod portable;
+pub mod presets;
+pub mod schema;
+pub mod security;
+pub mod settings;
+pub mod storage_backup;
+pub mod stronghold_migration;
+pub mod usage;
+pub mod writability;
 
+use crate::auth::ATProtocolClient;
 use crate::types::{Account, AuthError, AuthToken};
-use persistence::{PersistentStorage, StorageData};
+pub use persistence::{EncryptionCheck, PersistenceMetrics};
+use persistence::{LoadSource, PersistentStorage, StorageData, DEFAULT_STORAGE_PASSWORD};
+use crypto::CipherAlgorithm;
+use key_provider::{platform_key_provider, KeyProvider};
+use storage_backup::{BackupImport, ImportMode};
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long before its expiry `get_valid_token` refreshes an access token by default
+/// (`AppSettings::token_refresh_skew_secs` overrides it)
+pub const TOKEN_REFRESH_SKEW: Duration = Duration::from_secs(60);
+
+/// Shortest interval between two `last_used_at` updates of an account (saves disk writes)
+pub const LAST_USED_DEBOUNCE: Duration = Duration::from_secs(60);
+
+/// Shortest storage passphrase accepted by `set_passphrase`
+pub const MIN_STORAGE_PASSPHRASE_CHARS: usize = 8;
+
+/// Why `refresh_token_with_report` has no stored new token
+pub enum TokenRefreshFailure {
+    /// The refresh call failed (the stored token is untouched)
+    Refresh(AuthError),
+    /// The token was refreshed but every save failed
+    Save(AuthError),
+    /// Saving succeeded but a different token was read back every time
+    Mismatch,
+}
+
+/// What `refresh_token_with_report` did
+pub struct TokenRefreshReport {
+    /// Token before the refresh
+    pub previous: AuthToken,
+    /// Time the refresh call took
+    pub refresh_time: Duration,
+    /// Save attempts made (0 if the refresh call failed)
+    pub save_attempts: u32,
+    /// The new token as read back from storage
+    pub outcome: Result<AuthToken, TokenRefreshFailure>,
+}
 
 /// Storage manager for authentication data
 /// Uses encrypted file-based storage for persistence
 pub struct StorageManager {
-    /// Persistent storage backend
-    persistence: Mutex<PersistentStorage>,
+    /// Persistent storage backend (async lock: held across file I/O, which also orders
+    /// concurrent saves)
+    persistence: tokio::sync::Mutex<PersistentStorage>,
     /// In-memory cache (synchronized with disk)
     cache: Mutex<StorageData>,
+    /// Store is protected by a passphrase that hasn't been entered yet (writes are refused)
+    locked: AtomicBool,
+    /// Why the data was loaded from `storage.enc.bak` instead of `storage.enc`, if it was
+    recovery: Mutex<Option<String>>,
+    /// Per-account locks making token refreshes single-flight (see `get_valid_token`)
+    refresh_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// Early-refresh window of `get_valid_token` in seconds (from the settings)
+    refresh_skew_secs: AtomicU64,
 }
 
 impl StorageManager {
-    /// Create a new storage manager
+    /// Create a new storage manager using the built-in default password
     ///
     /// # Arguments
     /// * `data_dir` - Directory to store encrypted files
-    pub fn new(data_dir: PathBuf) -> Result<Self, AuthError> {
-        // Use a default password for now
-        // In production, this should be derived from device-specific or user-specific credentials
-        let password = "taurisky_default_password_v1";
+    #[allow(dead_code)]
+    pub async fn new(data_dir: PathBuf) -> Result<Self, AuthError> {
+        Self::new_with_password(data_dir, DEFAULT_STORAGE_PASSWORD).await
+    }
 
-        let persistence = PersistentStorage::new(data_dir, password)?;
+    /// Create a new storage manager with the key derived from a passphrase
+    ///
+    /// # Arguments
+    /// * `data_dir` - Directory to store encrypted files
+    /// * `password` - Storage passphrase (only used for key derivation, not kept)
+    #[allow(dead_code)]
+    pub async fn new_with_password(
+        data_dir: PathBuf,
+        password: &str,
+    ) -> Result<Self, AuthError> {
+        let persistence = PersistentStorage::new(data_dir, password, CipherAlgorithm::default())?;
 
         // Load existing data or create new
-        let cache = persistence.load()?;
+        let (cache, source) = persistence.load_with_source().await?;
 
         Ok(Self {
-            persistence: Mutex::new(persistence),
+            persistence: tokio::sync::Mutex::new(persistence),
             cache: Mutex::new(cache),
+            locked: AtomicBool::new(false),
+            recovery: Mutex::new(Self::recovery_message(source)),
+            refresh_locks: Mutex::new(HashMap::new()),
+            refresh_skew_secs: AtomicU64::new(TOKEN_REFRESH_SKEW.as_secs()),
         })
     }
 
-    /// Save current cache to disk
-    fn persist(&self) -> Result<(), AuthError> {
-        let cache = self.cache.lock().map_err(|e| {
-            AuthError::StorageError(format!("Cache lock error: {}", e))
+    /// Open the store at startup
+    ///
+    /// # Arguments
+    /// * `data_dir` - Directory to store encrypted files
+    ///
+    /// # Note
+    /// With a platform key provider (`keychain` feature) the provider's key is used if
+    /// it opens the store (always the case for a new store). Otherwise a store the
+    /// built-in password does not decrypt is assumed to be protected by a passphrase:
+    /// it opens locked (no accounts, writes refused) until `unlock`.
+    pub async fn open(data_dir: PathBuf) -> Result<Self, AuthError> {
+        Self::open_with_key_provider(data_dir, platform_key_provider().as_deref()).await
+    }
+
+    /// Open the store, preferring a key provider's key over the built-in password
+    ///
+    /// # Arguments
+    /// * `data_dir` - Directory to store encrypted files
+    /// * `provider` - Key provider (None: password path only)
+    pub(crate) async fn open_with_key_provider(
+        data_dir: PathBuf,
+        provider: Option<&dyn KeyProvider>,
+    ) -> Result<Self, AuthError> {
+        Self::open_preferring(
+            provider,
+            |provider| PersistentStorage::with_key_provider(data_dir.clone(), provider),
+            || {
+                let cipher = CipherAlgorithm::default();
+                std::future::ready(PersistentStorage::new(
+                    data_dir.clone(),
+                    DEFAULT_STORAGE_PASSWORD,
+                    cipher,
+                ))
+            },
+        )
+        .await
+    }
+
+    /// Open with the provider's key if it decrypts the store, else with the password
+    async fn open_preferring<P>(
+        provider: Option<&dyn KeyProvider>,
+        with_provider: impl FnOnce(&dyn KeyProvider) -> Result<PersistentStorage, AuthError>,
+        with_password: impl FnOnce() -> P,
+    ) -> Result<Self, AuthError>
+    where
+        P: Future<Output = Result<PersistentStorage, AuthError>>,
+    {
+        if let Some(provider) = provider {
+            // A failing provider (e.g., no keychain service) falls back to the password
+            if let Ok(persistence) = with_provider(provider) {
+                if persistence.verify_encryption().ok || persistence.load().await.is_ok() {
+                    return Self::from_persistence(persistence).await;
+                }
+            }
+        }
+
+        Self::from_persistence(with_password().await?).await
+    }
+
+    /// Wrap an opened store (locked if its key decrypts neither the data nor its backup)
+    async fn from_persistence(persistence: PersistentStorage) -> Result<Self, AuthError> {
+        let check = persistence.verify_encryption();
+        let (cache, source, locked) = match persistence.load_with_source().await {
+            Ok((cache, source)) => (cache, source, false),
+            Err(_) if !check.ok && check.data_file_checked => {
+                (StorageData::new(), LoadSource::Primary, true)
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            persistence: tokio::sync::Mutex::new(persistence),
+            cache: Mutex::new(cache),
+            locked: AtomicBool::new(locked),
+            recovery: Mutex::new(Self::recovery_message(source)),
+            refresh_locks: Mutex::new(HashMap::new()),
+            refresh_skew_secs: AtomicU64::new(TOKEN_REFRESH_SKEW.as_secs()),
+        })
+    }
+
+    /// Recovery message of a load (None when the primary file was used)
+    fn recovery_message(source: LoadSource) -> Option<String> {
+        match source {
+            LoadSource::Primary => None,
+            LoadSource::Backup(error) => Some(error.to_string()),
+        }
+    }
+
+    /// Why the accounts were recovered from `storage.enc.bak` on the last open, unlock
+    /// or reload
+    ///
+    /// # Returns
+    /// The "primary corrupt, recovered from backup" storage error message, or None if
+    /// `storage.enc` loaded normally. The next save replaces the damaged file.
+    pub fn recovery(&self) -> Result<Option<String>, AuthError> {
+        let recovery = self.recovery.lock().map_err(|e| {
+            AuthError::StorageError(format!("Recovery lock error: {}", e))
         })?;
+        Ok(recovery.clone())
+    }
+
+    /// Whether the store is waiting for its passphrase
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    /// Unlock a passphrase-protected store
+    ///
+    /// # Arguments
+    /// * `password` - Storage passphrase
+    ///
+    /// # Note
+    /// The passphrase is only borrowed for key derivation and never stored; callers
+    /// should wipe their copy afterwards. The derived key stays in memory until the
+    /// store is dropped. Unlocking an unlocked store does nothing.
+    pub async fn unlock(&self, password: &str) -> Result<(), AuthError> {
+        if !self.is_locked() {
+            return Ok(());
+        }
+
+        // Same lock order as `persist`
+        let mut persistence = self.persistence.lock().await;
 
-        let persistence = self.persistence.lock().map_err(|e| {
-            AuthError::StorageError(format!("Persistence lock error: {}", e))
+        let unlocked = persistence.reopen(password).await?;
+        // A store whose primary file is damaged may still unlock from its backup
+        let (data, source) = unlocked.load_with_source().await.map_err(|e| {
+            if unlocked.verify_encryption().ok {
+                e
+            } else {
+                AuthError::InvalidCredentials("Wrong storage passphrase".to_string())
+            }
         })?;
+        *self.cache.lock().map_err(|e| {
+            AuthError::StorageError(format!("Cache lock error: {}", e))
+        })? = data;
+        *persistence = unlocked;
+        *self.recovery.lock().map_err(|e| {
+            AuthError::StorageError(format!("Recovery lock error: {}", e))
+        })? = Self::recovery_message(source);
+        self.locked.store(false, Ordering::SeqCst);
 
-        persistence.save(&cache)
+        Ok(())
     }
 
-    /// Save an authentication token (encrypted and persisted to disk)
-    pub async fn save_auth_token(&self, token: &AuthToken) -> Result<(), AuthError> {
-        let mut cache = self.cache.lock().map_err(|e| {
+    /// Protect a store created with the built-in default password with a passphrase
+    ///
+    /// # Arguments
+    /// * `password` - New storage passphrase (at least `MIN_STORAGE_PASSPHRASE_CHARS`)
+    ///
+    /// # Note
+    /// Once a passphrase is set it can only be replaced with `change_password`. As with
+    /// `unlock`, the passphrase is not kept after key derivation.
+    pub async fn set_passphrase(&self, password: &str) -> Result<(), AuthError> {
+        self.change_password(DEFAULT_STORAGE_PASSWORD, password)
+            .await
+            .map_err(|e| match e {
+                AuthError::InvalidCredentials(_) => AuthError::InvalidInput(
+                    "A storage passphrase is already set; change it instead".to_string(),
+                ),
+                e => e,
+            })
+    }
+
+    /// Change the storage passphrase, re-encrypting the data with a fresh salt
+    ///
+    /// # Arguments
+    /// * `old` - Current passphrase (must decrypt the storage file)
+    /// * `new` - New passphrase (at least `MIN_STORAGE_PASSPHRASE_CHARS`)
+    ///
+    /// # Note
+    /// The rewrite is crash-safe: a rotation interrupted midway is completed or rolled
+    /// back on the next start, never leaving a store neither passphrase opens.
+    pub async fn change_password(&self, old: &str, new: &str) -> Result<(), AuthError> {
+        if new.chars().count() < MIN_STORAGE_PASSPHRASE_CHARS {
+            return Err(AuthError::InvalidInput(format!(
+                "Storage passphrase must be at least {} characters",
+                MIN_STORAGE_PASSPHRASE_CHARS
+            )));
+        }
+        if self.is_locked() {
+            return Err(AuthError::StorageError("Storage is locked".to_string()));
+        }
+
+        // Holding the persistence lock keeps `persist` from writing with the old key;
+        // like there, the cache lock is not held across the (slow) key derivation
+        let mut persistence = self.persistence.lock().await;
+
+        if !persistence.verify_password(old).await? {
+            return Err(AuthError::InvalidCredentials("Wrong storage passphrase".to_string()));
+        }
+        let snapshot = self.cache.lock().map_err(|e| {
+            AuthError::StorageError(format!("Cache lock error: {}", e))
+        })?.clone();
+        persistence.rekey(new, &snapshot).await
+    }
+
+    /// Save current cache to disk
+    ///
+    /// Fails while the store is locked, so the passphrase-protected file is never
+    /// overwritten with the empty locked view.
+    ///
+    /// The persistence lock is taken before the cache is read, so concurrent saves
+    /// write their snapshots in order and the newest one always lands last.
+    async fn persist(&self) -> Result<(), AuthError> {
+        if self.is_locked() {
+            return Err(AuthError::StorageError("Storage is locked".to_string()));
+        }
+
+        let persistence = self.persistence.lock().await;
+
+        // The cache lock is not held across the write
+        let snapshot = self.cache.lock().map_err(|e| {
+            AuthError::StorageError(format!("Cache lock error: {}", e))
+        })?.clone();
+
+        persistence.save(&snapshot).await
+    }
+
+    /// Re-read the storage files from disk (e.g., after a backup was restored)
+    ///
+    /// Unsaved in-memory changes are discarded. A passphrase-protected store is locked
+    /// again, like at startup (see `open`).
+    pub async fn reload(&self) -> Result<(), AuthError> {
+        // Same lock order as `persist`
+        let mut persistence = self.persistence.lock().await;
+
+        self.reload_locked(&mut persistence).await
+    }
+
+    /// Replace storage files on disk and reload them, with no save in between
+    ///
+    /// # Arguments
+    /// * `restore` - Blocking file work (e.g., swapping a restored backup into place)
+    ///
+    /// # Note
+    /// The persistence lock is held from before `restore` runs until the reload is
+    /// done, so a concurrent save can neither overwrite the restored file nor be lost
+    /// in the swap. Nothing is reloaded when `restore` fails.
+    pub async fn restore_files<F>(&self, restore: F) -> Result<(), AuthError>
+    where
+        F: FnOnce() -> Result<(), AuthError> + Send + 'static,
+    {
+        let mut persistence = self.persistence.lock().await;
+
+        tokio::task::spawn_blocking(restore)
+            .await
+            .map_err(|e| AuthError::StorageError(format!("Storage task failed: {}", e)))??;
+
+        self.reload_locked(&mut persistence).await
+    }
+
+    /// Re-read the storage files while the caller holds the persistence lock
+    async fn reload_locked(&self, persistence: &mut PersistentStorage) -> Result<(), AuthError> {
+        let reopened = Self::open_preferring(
+            platform_key_provider().as_deref(),
+            |provider| persistence.reopen_with_key_provider(provider),
+            || persistence.reopen(DEFAULT_STORAGE_PASSWORD),
+        )
+        .await?;
+        self.locked.store(reopened.is_locked(), Ordering::SeqCst);
+        *persistence = reopened.persistence.into_inner();
+        *self.cache.lock().map_err(|e| {
+            AuthError::StorageError(format!("Cache lock error: {}", e))
+        })? = reopened.cache.into_inner().map_err(|e| {
             AuthError::StorageError(format!("Cache lock error: {}", e))
         })?;
+        *self.recovery.lock().map_err(|e| {
+            AuthError::StorageError(format!("Recovery lock error: {}", e))
+        })? = reopened.recovery.into_inner().map_err(|e| {
+            AuthError::StorageError(format!("Recovery lock error: {}", e))
+        })?;
+
+        Ok(())
+    }
 
-        cache.tokens.insert(token.account_id.clone(), token.clone());
+    /// Time the key derivation took when the storage was opened
+    pub async fn key_derivation_time(&self) -> Result<Duration, AuthError> {
+        let persistence = self.persistence.lock().await;
+
+        Ok(persistence.key_derivation_time())
+    }
 
-        // Release lock before persisting
-        drop(cache);
+    /// Write latency of the storage file since start
+    pub async fn persistence_metrics(&self) -> Result<PersistenceMetrics, AuthError> {
+        let persistence = self.persistence.lock().await;
+
+        Ok(persistence.metrics())
+    }
+
+    /// Verify that the live encryption key round-trips and decrypts the storage file
+    pub async fn verify_encryption(&self) -> Result<EncryptionCheck, AuthError> {
+        let persistence = self.persistence.lock().await;
+
+        Ok(persistence.verify_encryption())
+    }
+
+    /// Schema version of the encrypted store as written (None: no storage file yet)
+    pub async fn stored_schema_version(&self) -> Result<Option<u32>, AuthError> {
+        self.persistence.lock().await.stored_version().await
+    }
+
+    /// Write the store back in the current schema version
+    ///
+    /// # Note
+    /// `load` migrates older data in memory only; after this `stored_schema_version`
+    /// reports `STORAGE_VERSION` as well. Fails while the store is locked.
+    pub async fn rewrite_store(&self) -> Result<(), AuthError> {
+        self.persist().await
+    }
+
+    /// Audit the encryption settings of the store
+    pub async fn security_audit(&self) -> Result<security::SecurityAudit, AuthError> {
+        let persistence = self.persistence.lock().await;
+
+        Ok(security::audit_storage(&persistence))
+    }
+
+    /// Save an authentication token (encrypted and persisted to disk)
+    pub async fn save_auth_token(&self, token: &AuthToken) -> Result<(), AuthError> {
+        // Release lock before persisting (the guard can't be held across an await)
+        {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
+
+            cache.tokens.insert(token.account_id.clone(), token.clone());
+        }
 
         // Persist to disk
-        self.persist()
+        self.persist().await
     }
 
     /// Get an authentication token from storage
@@ -84,34 +469,228 @@ impl StorageManager {
             .ok_or_else(|| AuthError::AccountNotFound(account_id.to_string()))
     }
 
-    /// Delete an authentication token from storage
-    pub async fn delete_auth_token(&self, account_id: &str) -> Result<(), AuthError> {
-        let mut cache = self.cache.lock().map_err(|e| {
-            AuthError::StorageError(format!("Cache lock error: {}", e))
-        })?;
+    /// Get an authentication token, refreshing it first if the access token has expired
+    ///
+    /// # Arguments
+    /// * `account_id` - Account whose token is needed
+    /// * `client` - Client for the account's PDS (used for the refresh call)
+    ///
+    /// # Note
+    /// Tokens expiring within the refresh skew (`TOKEN_REFRESH_SKEW` unless set with
+    /// `set_token_refresh_skew`) are refreshed early so they don't
+    /// lapse in flight. An expired refresh token fails with `AuthError::TokenExpired`
+    /// (the user has to log in again). Refreshes are single-flight per account: the
+    /// PDS rotates the refresh token, so concurrent callers wait for the first refresh
+    /// and reuse its token instead of replaying the old refresh JWT.
+    pub async fn get_valid_token(
+        &self,
+        account_id: &str,
+        client: &ATProtocolClient,
+    ) -> Result<AuthToken, AuthError> {
+        let skew = self.token_refresh_skew();
+        let token = self.get_auth_token(account_id).await?;
 
-        cache.tokens.remove(account_id);
+        if !token.is_access_expiring(skew) {
+            return Ok(token);
+        }
 
-        // Release lock before persisting
-        drop(cache);
+        let lock = self.refresh_lock(account_id)?;
+        let _refreshing = lock.lock().await;
+
+        // Refreshed by another caller while this one waited
+        let token = self.get_auth_token(account_id).await?;
+        if !token.is_access_expiring(skew) {
+            return Ok(token);
+        }
+
+        if token.is_refresh_expired() {
+            return Err(AuthError::TokenExpired);
+        }
+
+        let new_token = client.refresh_auth_token(&token).await?;
+        self.save_auth_token(&new_token).await?;
+
+        Ok(new_token)
+    }
+
+    /// Refresh an account's session now and save the new token
+    ///
+    /// # Arguments
+    /// * `account_id` - Account to refresh
+    /// * `client` - Client for the account's PDS
+    ///
+    /// # Returns
+    /// The new token
+    ///
+    /// # Note
+    /// Counts as a use of the account: `last_used_at` is bumped (debounced by
+    /// `LAST_USED_DEBOUNCE`) in the same write as the token.
+    pub async fn refresh_token(
+        &self,
+        account_id: &str,
+        client: &ATProtocolClient,
+    ) -> Result<AuthToken, AuthError> {
+        // Same single-flight lock as `get_valid_token`
+        let lock = self.refresh_lock(account_id)?;
+        let _refreshing = lock.lock().await;
+
+        let old_token = self.get_auth_token(account_id).await?;
+        let new_token = client.refresh_auth_token(&old_token).await?;
+
+        // Release lock before persisting (the guard can't be held across an await)
+        {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
+
+            cache.tokens.insert(account_id.to_string(), new_token.clone());
+            if let Some(account) = cache.accounts.get_mut(account_id) {
+                touch_last_used(account);
+            }
+        }
 
         // Persist to disk
-        self.persist()
+        self.persist().await?;
+        Ok(new_token)
     }
 
-    /// Save an account (persisted to disk)
-    pub async fn save_account(&self, account: &Account) -> Result<(), AuthError> {
-        let mut cache = self.cache.lock().map_err(|e| {
-            AuthError::StorageError(format!("Cache lock error: {}", e))
+    /// Refresh an account's session, retrying the save until the new token reads back
+    ///
+    /// # Arguments
+    /// * `account_id` - Account to refresh
+    /// * `client` - Client for the account's PDS
+    /// * `max_save_attempts` - Saves tried before giving up (at least one)
+    ///
+    /// # Note
+    /// Holds the same single-flight lock as `get_valid_token` across the refresh and
+    /// every save, so no concurrent refresh replays the rotated refresh token. Unlike
+    /// `refresh_token`, `last_used_at` is not bumped.
+    pub async fn refresh_token_with_report(
+        &self,
+        account_id: &str,
+        client: &ATProtocolClient,
+        max_save_attempts: u32,
+    ) -> Result<TokenRefreshReport, AuthError> {
+        let lock = self.refresh_lock(account_id)?;
+        let _refreshing = lock.lock().await;
+
+        let previous = self.get_auth_token(account_id).await?;
+        let refresh_started = std::time::Instant::now();
+        let refreshed = client.refresh_auth_token(&previous).await;
+        let refresh_time = refresh_started.elapsed();
+
+        let mut save_attempts = 0;
+        let outcome = match refreshed {
+            Err(e) => Err(TokenRefreshFailure::Refresh(e)),
+            Ok(refreshed) => {
+                let mut saved: Result<AuthToken, AuthError> =
+                    Err(AuthError::StorageError("Token not saved".to_string()));
+                while save_attempts < max_save_attempts.max(1)
+                    && !matches!(&saved, Ok(stored) if stored.refresh_jwt == refreshed.refresh_jwt)
+                {
+                    save_attempts += 1;
+                    saved = match self.save_auth_token(&refreshed).await {
+                        Ok(()) => self.get_auth_token(account_id).await,
+                        Err(e) => Err(e),
+                    };
+                }
+
+                match saved {
+                    Ok(stored) if stored.refresh_jwt == refreshed.refresh_jwt => Ok(stored),
+                    Ok(_) => Err(TokenRefreshFailure::Mismatch),
+                    Err(e) => Err(TokenRefreshFailure::Save(e)),
+                }
+            }
+        };
+
+        Ok(TokenRefreshReport {
+            previous,
+            refresh_time,
+            save_attempts,
+            outcome,
+        })
+    }
+
+    /// Record that an account's token was used
+    ///
+    /// # Note
+    /// `last_used_at` is only rewritten when it is older than `LAST_USED_DEBOUNCE`, so
+    /// back-to-back API calls cost at most one write per minute. Unknown accounts are
+    /// ignored.
+    pub async fn mark_account_used(&self, account_id: &str) -> Result<(), AuthError> {
+        // Release lock before persisting (the guard can't be held across an await)
+        let touched = {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
+
+            cache.accounts.get_mut(account_id).is_some_and(touch_last_used)
+        };
+
+        if !touched {
+            return Ok(());
+        }
+
+        // Persist to disk
+        self.persist().await
+    }
+
+    /// Early-refresh window used by `get_valid_token`
+    pub fn token_refresh_skew(&self) -> Duration {
+        Duration::from_secs(self.refresh_skew_secs.load(Ordering::SeqCst))
+    }
+
+    /// Set the early-refresh window (from `AppSettings::token_refresh_skew`)
+    pub fn set_token_refresh_skew(&self, skew: Duration) {
+        self.refresh_skew_secs.store(skew.as_secs(), Ordering::SeqCst);
+    }
+
+    /// Lock serializing the token refreshes of an account
+    fn refresh_lock(&self, account_id: &str) -> Result<Arc<tokio::sync::Mutex<()>>, AuthError> {
+        let mut locks = self.refresh_locks.lock().map_err(|e| {
+            AuthError::StorageError(format!("Refresh lock error: {}", e))
         })?;
 
-        cache.accounts.insert(account.id.clone(), account.clone());
+        Ok(locks.entry(account_id.to_string()).or_default().clone())
+    }
+
+    /// Get the access JWT of an account for direct API calls, refreshing it if needed
+    ///
+    /// # Arguments
+    /// * `account_id` - Account whose access token is needed
+    /// * `client` - Client for the account's PDS (used for the refresh call)
+    ///
+    /// # Returns
+    /// The access JWT only; the refresh JWT never leaves the backend
+    ///
+    /// # Note
+    /// An unknown account fails with `AuthError::AccountNotFound`, a token that can't be
+    /// refreshed with `AuthError::TokenExpired` (see `get_valid_token`).
+    pub async fn get_access_token(
+        &self,
+        account_id: &str,
+        client: &ATProtocolClient,
+    ) -> Result<String, AuthError> {
+        self.get_account(account_id).await?;
+        let token = self.get_valid_token(account_id, client).await?;
+        self.mark_account_used(account_id).await?;
+
+        Ok(token.access_jwt)
+    }
 
-        // Release lock before persisting
-        drop(cache);
+    /// Save an account (persisted to disk)
+    pub async fn save_account(&self, account: &Account) -> Result<(), AuthError> {
+        // Release lock before persisting (the guard can't be held across an await)
+        {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
+
+            cache.accounts.insert(account.id.clone(), account.clone());
+        }
 
         // Persist to disk
-        self.persist()
+        self.persist().await
     }
 
     /// Get an account by ID
@@ -127,48 +706,804 @@ impl StorageManager {
             .ok_or_else(|| AuthError::AccountNotFound(account_id.to_string()))
     }
 
-    /// List all accounts
+    /// List all accounts, most recently used first
+    ///
+    /// # Note
+    /// `last_used_at` is compared as a parsed RFC 3339 time (so offsets and fractional
+    /// seconds order correctly); unparseable timestamps sort last. Ties are broken by
+    /// handle, so the order is stable between launches.
     pub async fn list_accounts(&self) -> Result<Vec<Account>, AuthError> {
         let cache = self.cache.lock().map_err(|e| {
             AuthError::StorageError(format!("Cache lock error: {}", e))
         })?;
 
-        Ok(cache.accounts.values().cloned().collect())
+        let mut accounts: Vec<Account> = cache.accounts.values().cloned().collect();
+        accounts.sort_by_cached_key(|account| {
+            let last_used = chrono::DateTime::parse_from_rfc3339(&account.last_used_at)
+                .map(|t| t.timestamp_micros())
+                .unwrap_or(i64::MIN);
+            (std::cmp::Reverse(last_used), account.handle.clone())
+        });
+        Ok(accounts)
     }
 
-    /// Delete an account
-    pub async fn delete_account(&self, account_id: &str) -> Result<(), AuthError> {
-        let mut cache = self.cache.lock().map_err(|e| {
-            AuthError::StorageError(format!("Cache lock error: {}", e))
-        })?;
+    /// Change fields of the stored account in place
+    ///
+    /// # Arguments
+    /// * `account_id` - Account to change
+    /// * `change` - Applied to the current stored account; returns whether it changed
+    ///
+    /// # Returns
+    /// The account after the change
+    ///
+    /// # Note
+    /// The change is applied to the cached account under the cache lock, so edits made
+    /// since the caller last read the account are kept. Nothing is written when
+    /// `change` returns false.
+    pub async fn modify_account<F>(&self, account_id: &str, change: F) -> Result<Account, AuthError>
+    where
+        F: FnOnce(&mut Account) -> bool,
+    {
+        // Release lock before persisting (the guard can't be held across an await)
+        let (account, changed) = {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
 
-        cache.accounts.remove(account_id);
+            let account = cache
+                .accounts
+                .get_mut(account_id)
+                .ok_or_else(|| AuthError::AccountNotFound(account_id.to_string()))?;
+            let changed = change(account);
+            (account.clone(), changed)
+        };
 
-        // Release lock before persisting
-        drop(cache);
+        if changed {
+            // Persist to disk
+            self.persist().await?;
+        }
+        Ok(account)
+    }
+
+    /// Make an account the only active one (in a single write)
+    ///
+    /// # Arguments
+    /// * `account_id` - Account to activate; its `last_used_at` is set to now
+    ///
+    /// # Returns
+    /// The activated account
+    pub async fn switch_active_account(&self, account_id: &str) -> Result<Account, AuthError> {
+        // Release lock before persisting (the guard can't be held across an await)
+        let active = {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
+
+            if !cache.accounts.contains_key(account_id) {
+                return Err(AuthError::AccountNotFound(account_id.to_string()));
+            }
+            let now = chrono::Utc::now().to_rfc3339();
+            for account in cache.accounts.values_mut() {
+                account.is_active = account.id == account_id;
+                if account.is_active {
+                    account.last_used_at = now.clone();
+                }
+            }
+            cache.accounts[account_id].clone()
+        };
+
+        // Persist to disk
+        self.persist().await?;
+        Ok(active)
+    }
+
+    /// Delete an account together with its authentication token in a single write
+    ///
+    /// # Returns
+    /// Whether a token was stored for the account
+    ///
+    /// # Note
+    /// Both are removed before the store is saved once, so an interrupted or failed
+    /// write never leaves a token without its account (or the reverse) on disk.
+    pub async fn remove_account(&self, account_id: &str) -> Result<bool, AuthError> {
+        // Release lock before persisting (the guard can't be held across an await)
+        let token_removed = {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
+
+            if cache.accounts.remove(account_id).is_none() {
+                return Err(AuthError::AccountNotFound(account_id.to_string()));
+            }
+            cache.tokens.remove(account_id).is_some()
+        };
 
         // Persist to disk
-        self.persist()
+        self.persist().await?;
+        Ok(token_removed)
     }
 
     /// Clear all stored data (for logout all or reset)
-    #[allow(dead_code)]
+    ///
+    /// # Note
+    /// The storage files (including the key file) are deleted and the store is opened
+    /// again as a new one, with the built-in password or the platform key provider; a
+    /// passphrase-protected store, even a locked one, is reset too.
     pub async fn clear_all(&self) -> Result<(), AuthError> {
-        let mut cache = self.cache.lock().map_err(|e| {
+        // Release lock before persisting (the guard can't be held across an await)
+        {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
+
+            cache.accounts.clear();
+            cache.tokens.clear();
+        }
+
+        // Also clear persistent storage
+        self.persistence.lock().await.clear().await?;
+
+        // Start a new store; writes with the old key would lack a key file
+        self.reload().await
+    }
+
+    /// Export every account and token as a backup encrypted with its own password
+    ///
+    /// # Arguments
+    /// * `password` - Password protecting the backup (not the storage passphrase)
+    pub fn export_backup(&self, password: &str) -> Result<Vec<u8>, AuthError> {
+        if self.is_locked() {
+            return Err(AuthError::StorageError("Storage is locked".to_string()));
+        }
+
+        let cache = self.cache.lock().map_err(|e| {
             AuthError::StorageError(format!("Cache lock error: {}", e))
         })?;
 
-        cache.accounts.clear();
-        cache.tokens.clear();
+        storage_backup::seal(&cache, password)
+    }
 
-        // Release lock before persisting
-        drop(cache);
+    /// Import the accounts and tokens of a backup produced by `export_backup`
+    ///
+    /// # Arguments
+    /// * `data` - Backup bytes
+    /// * `password` - Backup password
+    /// * `mode` - Merge into (skipping accounts whose ID exists) or replace the store
+    ///
+    /// # Note
+    /// A wrong password is rejected by the backup's verifier before the payload is
+    /// decrypted; the backup is fully decrypted and parsed before the store is changed.
+    /// Imported accounts keep their active flag; callers should repair the active account.
+    pub async fn import_backup(
+        &self,
+        data: &[u8],
+        password: &str,
+        mode: ImportMode,
+    ) -> Result<BackupImport, AuthError> {
+        if self.is_locked() {
+            return Err(AuthError::StorageError("Storage is locked".to_string()));
+        }
 
-        // Also clear persistent storage
-        let persistence = self.persistence.lock().map_err(|e| {
-            AuthError::StorageError(format!("Persistence lock error: {}", e))
-        })?;
+        let mut backup = storage_backup::open(data, password)?;
+
+        let mut report = BackupImport::default();
+
+        // Release lock before persisting (the guard can't be held across an await)
+        {
+            let mut cache = self.cache.lock().map_err(|e| {
+                AuthError::StorageError(format!("Cache lock error: {}", e))
+            })?;
+
+            if mode == ImportMode::Replace {
+                cache.accounts.clear();
+                cache.tokens.clear();
+            }
+            for (id, account) in backup.accounts {
+                if cache.accounts.contains_key(&id) {
+                    report.skipped.push(id);
+                    continue;
+                }
+                if let Some(token) = backup.tokens.remove(&id) {
+                    cache.tokens.insert(id.clone(), token);
+                }
+                cache.accounts.insert(id.clone(), account);
+                report.imported.push(id);
+            }
+            report.imported.sort();
+            report.skipped.sort();
+        }
+
+        self.persist().await?;
+        Ok(report)
+    }
+}
+
+/// Set `last_used_at` to now unless it was set within `LAST_USED_DEBOUNCE`
+///
+/// # Returns
+/// Whether the account was changed
+fn touch_last_used(account: &mut Account) -> bool {
+    let now = chrono::Utc::now();
+    let debounce = chrono::Duration::from_std(LAST_USED_DEBOUNCE).unwrap_or_default();
+    let recent = chrono::DateTime::parse_from_rfc3339(&account.last_used_at)
+        .is_ok_and(|last_used| now < last_used + debounce);
+    if !recent {
+        account.last_used_at = now.to_rfc3339();
+    }
+    !recent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::Utc;
+    use mockito::Server;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn token_expiring_in(access: chrono::Duration, refresh: chrono::Duration) -> AuthToken {
+        let now = Utc::now();
+        AuthToken {
+            account_id: "alice".to_string(),
+            access_jwt: "old-access".to_string(),
+            refresh_jwt: "old-refresh".to_string(),
+            issued_at: now.to_rfc3339(),
+            access_expires_at: (now + access).to_rfc3339(),
+            refresh_expires_at: (now + refresh).to_rfc3339(),
+            session_string: None,
+            dpop_key: None,
+            oauth_issuer: None,
+            oauth_client_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_valid_token_refreshes_within_skew() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut server = Server::new_async().await;
+        let refresh = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .match_header("authorization", "Bearer old-refresh")
+            .with_status(200)
+            .with_body(
+                r#"{"accessJwt":"new-access","refreshJwt":"new-refresh","did":"did:plc:alice",
+                    "handle":"alice.bsky.social"}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        // Still valid for ten minutes: used as is
+        let fresh = token_expiring_in(chrono::Duration::minutes(10), chrono::Duration::days(30));
+        storage.save_auth_token(&fresh).await.unwrap();
+        let token = storage.get_valid_token("alice", &client).await.unwrap();
+        assert_eq!(token.access_jwt, "old-access");
+
+        // Expires in 30 seconds: refreshed early and saved
+        let expiring =
+            token_expiring_in(chrono::Duration::seconds(30), chrono::Duration::days(30));
+        storage.save_auth_token(&expiring).await.unwrap();
+        let token = storage.get_valid_token("alice", &client).await.unwrap();
+
+        refresh.assert_async().await;
+        assert_eq!(token.access_jwt, "new-access");
+        assert_eq!(storage.get_auth_token("alice").await.unwrap().refresh_jwt, "new-refresh");
+    }
+
+    #[tokio::test]
+    async fn test_get_valid_token_uses_configured_skew() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut server = Server::new_async().await;
+        let refresh = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .with_status(200)
+            .with_body(
+                r#"{"accessJwt":"new-access","refreshJwt":"new-refresh","did":"did:plc:alice",
+                    "handle":"alice.bsky.social"}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+        let token = token_expiring_in(chrono::Duration::minutes(5), chrono::Duration::days(30));
+        storage.save_auth_token(&token).await.unwrap();
+
+        // Outside the default 60 second window
+        let token = storage.get_valid_token("alice", &client).await.unwrap();
+        assert_eq!(token.access_jwt, "old-access");
+
+        let settings = crate::types::AppSettings {
+            token_refresh_skew_secs: Some(600),
+            ..Default::default()
+        };
+        storage.set_token_refresh_skew(settings.token_refresh_skew());
+        let token = storage.get_valid_token("alice", &client).await.unwrap();
+        assert_eq!(token.access_jwt, "new-access");
+        refresh.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_get_valid_token_refreshes_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut server = Server::new_async().await;
+        let refresh = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .match_header("authorization", "Bearer old-refresh")
+            .with_status(200)
+            .with_body(
+                r#"{"accessJwt":"new-access","refreshJwt":"new-refresh","did":"did:plc:alice",
+                    "handle":"alice.bsky.social"}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+        let expiring = token_expiring_in(chrono::Duration::seconds(5), chrono::Duration::days(30));
+        storage.save_auth_token(&expiring).await.unwrap();
+
+        let (first, second) = tokio::join!(
+            storage.get_valid_token("alice", &client),
+            storage.get_valid_token("alice", &client)
+        );
+
+        refresh.assert_async().await;
+        assert_eq!(first.unwrap().refresh_jwt, "new-refresh");
+        assert_eq!(second.unwrap().refresh_jwt, "new-refresh");
+    }
+
+    #[tokio::test]
+    async fn test_get_access_token_refreshes_and_hides_refresh_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut server = Server::new_async().await;
+        let refresh = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .match_header("authorization", "Bearer old-refresh")
+            .with_status(200)
+            .with_body(
+                r#"{"accessJwt":"new-access","refreshJwt":"new-refresh","did":"did:plc:alice",
+                    "handle":"alice.bsky.social"}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        let unknown = storage.get_access_token("alice", &client).await;
+        assert!(matches!(unknown, Err(AuthError::AccountNotFound(_))));
+        storage.save_account(&test_account()).await.unwrap();
+
+        // Fresh: returned as is
+        let fresh = token_expiring_in(chrono::Duration::minutes(10), chrono::Duration::days(30));
+        storage.save_auth_token(&fresh).await.unwrap();
+        assert_eq!(storage.get_access_token("alice", &client).await.unwrap(), "old-access");
+
+        // Expired but refreshable: refreshed first
+        let expired = token_expiring_in(-chrono::Duration::minutes(1), chrono::Duration::days(30));
+        storage.save_auth_token(&expired).await.unwrap();
+        assert_eq!(storage.get_access_token("alice", &client).await.unwrap(), "new-access");
+        refresh.assert_async().await;
+
+        // Dead refresh token: the user has to log in again
+        let dead = token_expiring_in(-chrono::Duration::hours(1), -chrono::Duration::hours(1));
+        storage.save_auth_token(&dead).await.unwrap();
+        let result = storage.get_access_token("alice", &client).await;
+        assert!(matches!(result, Err(AuthError::TokenExpired)));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_bumps_last_used_at_once_per_debounce_window() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut server = Server::new_async().await;
+        let refresh = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .with_status(200)
+            .with_body(
+                r#"{"accessJwt":"new-access","refreshJwt":"old-refresh","did":"did:plc:alice",
+                    "handle":"alice.bsky.social"}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+        let mut account = test_account();
+        account.last_used_at = "2024-01-01T00:00:00Z".to_string();
+        storage.save_account(&account).await.unwrap();
+        let token = token_expiring_in(chrono::Duration::minutes(10), chrono::Duration::days(30));
+        storage.save_auth_token(&token).await.unwrap();
+
+        storage.refresh_token("alice", &client).await.unwrap();
+        let last_used = storage.get_account("alice").await.unwrap().last_used_at;
+        assert!(last_used > account.last_used_at);
+
+        // Within the window: the token is saved, the timestamp is left alone
+        let writes = storage.persistence_metrics().await.unwrap().total_writes;
+        let token = storage.refresh_token("alice", &client).await.unwrap();
+        refresh.assert_async().await;
+        assert_eq!(storage.get_auth_token("alice").await.unwrap().access_jwt, token.access_jwt);
+        assert_eq!(storage.get_account("alice").await.unwrap().last_used_at, last_used);
+        assert_eq!(storage.persistence_metrics().await.unwrap().total_writes, writes + 1);
+
+        // Plain token use within the window doesn't write at all
+        storage.mark_account_used("alice").await.unwrap();
+        assert_eq!(storage.persistence_metrics().await.unwrap().total_writes, writes + 1);
+    }
+
+    fn test_account() -> Account {
+        test_support::account("alice")
+    }
+
+    #[tokio::test]
+    async fn test_default_store_migrates_to_passphrase() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::open(data_dir.clone()).await.unwrap();
+        assert!(!storage.is_locked());
+        storage.save_account(&test_account()).await.unwrap();
+
+        assert!(matches!(storage.set_passphrase("short").await, Err(AuthError::InvalidInput(_))));
+        storage.set_passphrase("correct horse battery").await.unwrap();
+        assert!(storage.security_audit().await.unwrap().clean);
+        drop(storage);
+
+        // The default password no longer opens the store
+        assert!(StorageManager::new(data_dir.clone()).await.is_err());
+        let reopened = StorageManager::new_with_password(data_dir, "correct horse battery")
+            .await
+            .unwrap();
+        assert_eq!(reopened.list_accounts().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_locked_store_refuses_writes_until_unlocked() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account()).await.unwrap();
+        storage.set_passphrase("correct horse battery").await.unwrap();
+        drop(storage);
+
+        let storage = StorageManager::open(data_dir.clone()).await.unwrap();
+        assert!(storage.is_locked());
+        assert!(storage.list_accounts().await.unwrap().is_empty());
+        assert!(storage.save_account(&test_account()).await.is_err());
+
+        let wrong = storage.unlock("wrong passphrase").await;
+        assert!(matches!(wrong, Err(AuthError::InvalidCredentials(_))));
+        assert!(storage.is_locked());
+
+        storage.unlock("correct horse battery").await.unwrap();
+        assert!(!storage.is_locked());
+        assert_eq!(storage.list_accounts().await.unwrap()[0].id, "alice");
+        storage.remove_account("alice").await.unwrap();
+        drop(storage);
+        let reopened = StorageManager::new_with_password(data_dir, "correct horse battery")
+            .await
+            .unwrap();
+        assert!(reopened.list_accounts().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_store_opens_from_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::open(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account()).await.unwrap();
+        storage.remove_account("alice").await.unwrap();
+        drop(storage);
+        fs::write(data_dir.join(persistence::STORAGE_FILE), "trunc").unwrap();
+
+        // Not mistaken for a passphrase-protected store
+        let storage = StorageManager::open(data_dir).await.unwrap();
+        assert!(!storage.is_locked());
+        assert_eq!(storage.list_accounts().await.unwrap()[0].id, "alice");
+        let recovery = storage.recovery().unwrap().unwrap();
+        assert!(recovery.contains(persistence::RECOVERED_FROM_BACKUP));
+
+        // The next save replaces the damaged file
+        storage.save_account(&test_account()).await.unwrap();
+        storage.reload().await.unwrap();
+        assert!(storage.recovery().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_restore_files_reloads_restored_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account()).await.unwrap();
+        let with_alice = fs::read(data_dir.join(persistence::STORAGE_FILE)).unwrap();
+        storage.save_account(&test_support::account("bob")).await.unwrap();
+
+        // A failed restore leaves the loaded store alone
+        let failed = storage
+            .restore_files(|| Err(AuthError::StorageError("swap failed".to_string())))
+            .await;
+        assert!(matches!(failed, Err(AuthError::StorageError(_))));
+        assert_eq!(storage.list_accounts().await.unwrap().len(), 2);
+
+        let storage_file = data_dir.join(persistence::STORAGE_FILE);
+        storage
+            .restore_files(move || {
+                fs::write(storage_file, with_alice)
+                    .map_err(|e| AuthError::StorageError(e.to_string()))
+            })
+            .await
+            .unwrap();
+        let accounts = storage.list_accounts().await.unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].id, "alice");
+    }
+
+    #[tokio::test]
+    async fn test_change_password_rotates_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account()).await.unwrap();
+        storage.set_passphrase("first passphrase").await.unwrap();
+        let salt_before = std::fs::read(data_dir.join(keyfile::KEY_FILE)).unwrap();
+
+        let wrong = storage.change_password("not the passphrase", "second passphrase").await;
+        assert!(matches!(wrong, Err(AuthError::InvalidCredentials(_))));
+        let again = storage.set_passphrase("second passphrase").await;
+        assert!(matches!(again, Err(AuthError::InvalidInput(_))));
+
+        storage.change_password("first passphrase", "second passphrase").await.unwrap();
+
+        assert_ne!(std::fs::read(data_dir.join(keyfile::KEY_FILE)).unwrap(), salt_before);
+        storage.reload().await.unwrap();
+        assert!(storage.is_locked());
+        let old = storage.unlock("first passphrase").await;
+        assert!(matches!(old, Err(AuthError::InvalidCredentials(_))));
+        storage.unlock("second passphrase").await.unwrap();
+        assert_eq!(storage.list_accounts().await.unwrap()[0].id, "alice");
+
+        // Writes after the rotation use the new key
+        storage.remove_account("alice").await.unwrap();
+        drop(storage);
+        let reopened =
+            StorageManager::new_with_password(data_dir, "second passphrase").await.unwrap();
+        assert!(reopened.list_accounts().await.unwrap().is_empty());
+    }
+
+    fn named_account(id: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            did: format!("did:plc:{}", id),
+            handle: format!("{}.bsky.social", id),
+            ..test_account()
+        }
+    }
+
+    /// Backup of a fresh store holding the given accounts and alice's token
+    async fn backup_of(ids: &[&str]) -> Vec<u8> {
+        let temp_dir = TempDir::new().unwrap();
+        let source = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        for id in ids {
+            source.save_account(&named_account(id)).await.unwrap();
+        }
+        let token = token_expiring_in(chrono::Duration::hours(1), chrono::Duration::days(30));
+        source.save_auth_token(&token).await.unwrap();
+        source.export_backup("backup password").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_import_backup_merge_skips_existing_ids() {
+        let backup = backup_of(&["alice", "bob"]).await;
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut existing = named_account("alice");
+        existing.handle = "kept.bsky.social".to_string();
+        storage.save_account(&existing).await.unwrap();
+        storage.save_account(&named_account("carol")).await.unwrap();
+
+        let wrong = storage.import_backup(&backup, "wrong password", ImportMode::Merge).await;
+        assert!(matches!(wrong, Err(AuthError::StorageError(_))));
+        assert_eq!(storage.list_accounts().await.unwrap().len(), 2);
+
+        let report =
+            storage.import_backup(&backup, "backup password", ImportMode::Merge).await.unwrap();
+
+        assert_eq!(report.imported, vec!["bob"]);
+        assert_eq!(report.skipped, vec!["alice"]);
+        assert_eq!(storage.list_accounts().await.unwrap().len(), 3);
+        assert_eq!(storage.get_account("alice").await.unwrap().handle, "kept.bsky.social");
+        // The duplicate's token is skipped along with it
+        assert!(storage.get_auth_token("alice").await.is_err());
+        drop(storage);
+        let reopened = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        assert!(reopened.get_account("bob").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_import_backup_replace_discards_current_data() {
+        let backup = backup_of(&["alice", "bob"]).await;
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        storage.save_account(&named_account("carol")).await.unwrap();
+        let mut token = token_expiring_in(chrono::Duration::hours(1), chrono::Duration::days(30));
+        token.account_id = "carol".to_string();
+        storage.save_auth_token(&token).await.unwrap();
+
+        let report =
+            storage.import_backup(&backup, "backup password", ImportMode::Replace).await.unwrap();
+
+        assert_eq!(report.imported, vec!["alice", "bob"]);
+        assert!(report.skipped.is_empty());
+        assert!(storage.get_account("carol").await.is_err());
+        assert!(storage.get_auth_token("carol").await.is_err());
+        assert_eq!(storage.get_auth_token("alice").await.unwrap().refresh_jwt, "old-refresh");
+    }
+
+    #[tokio::test]
+    async fn test_switch_active_account_leaves_exactly_one_active() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        for id in ["alice", "bob", "carol"] {
+            let mut account = named_account(id);
+            account.last_used_at = "2024-01-01T00:00:00+00:00".to_string();
+            storage.save_account(&account).await.unwrap();
+        }
+
+        let active = storage.switch_active_account("bob").await.unwrap();
+
+        assert!(active.is_active);
+        assert_ne!(active.last_used_at, "2024-01-01T00:00:00+00:00");
+        let accounts = storage.list_accounts().await.unwrap();
+        let active_ids: Vec<&str> =
+            accounts.iter().filter(|a| a.is_active).map(|a| a.id.as_str()).collect();
+        assert_eq!(active_ids, vec!["bob"]);
+        let alice = storage.get_account("alice").await.unwrap();
+        assert_eq!(alice.last_used_at, "2024-01-01T00:00:00+00:00");
+
+        let unknown = storage.switch_active_account("mallory").await;
+        assert!(matches!(unknown, Err(AuthError::AccountNotFound(_))));
+        assert!(storage.get_account("bob").await.unwrap().is_active);
+    }
+
+    #[tokio::test]
+    async fn test_list_accounts_is_ordered_by_recency() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        // Lexically "2024-05-01T09..." sorts after "2024-05-01T08...", but the offset
+        // makes carol's timestamp the earliest
+        for (id, last_used_at) in [
+            ("alice", "2024-05-01T08:00:00Z"),
+            ("bob", "2024-05-01T08:00:00Z"),
+            ("carol", "2024-05-01T09:30:00+02:00"),
+            ("dave", "2024-05-02T00:00:00.5Z"),
+        ] {
+            let mut account = named_account(id);
+            account.last_used_at = last_used_at.to_string();
+            storage.save_account(&account).await.unwrap();
+        }
+
+        for _ in 0..3 {
+            let ids: Vec<String> =
+                storage.list_accounts().await.unwrap().into_iter().map(|a| a.id).collect();
+            assert_eq!(ids, vec!["dave", "alice", "bob", "carol"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remove_account_deletes_token_in_one_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account()).await.unwrap();
+        let token = token_expiring_in(chrono::Duration::hours(1), chrono::Duration::days(30));
+        storage.save_auth_token(&token).await.unwrap();
+        let writes = storage.persistence_metrics().await.unwrap().total_writes;
+
+        assert!(storage.remove_account("alice").await.unwrap());
+        assert_eq!(storage.persistence_metrics().await.unwrap().total_writes, writes + 1);
+        let missing = storage.remove_account("alice").await;
+        assert!(matches!(missing, Err(AuthError::AccountNotFound(_))));
+
+        drop(storage);
+        let reopened = StorageManager::new(data_dir).await.unwrap();
+        assert!(reopened.get_account("alice").await.is_err());
+        assert!(reopened.get_auth_token("alice").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_saves_do_not_block_the_runtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        let events = Mutex::new(Vec::new());
+
+        let save = |id: &'static str| {
+            let (storage, events) = (&storage, &events);
+            async move {
+                storage.save_account(&named_account(id)).await.unwrap();
+                events.lock().unwrap().push(id);
+            }
+        };
+        // Polled after the saves on the single-threaded test runtime, so it only finishes
+        // first if the saves yield while encrypting and writing
+        let heartbeat = async {
+            tokio::task::yield_now().await;
+            events.lock().unwrap().push("heartbeat");
+        };
+        tokio::join!(save("alice"), save("bob"), save("carol"), heartbeat);
+
+        assert_eq!(events.lock().unwrap()[0], "heartbeat");
+        drop(storage);
+        let reopened = StorageManager::new(data_dir).await.unwrap();
+        assert_eq!(reopened.list_accounts().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_key_provider_replaces_password_for_new_stores() {
+        let provider = key_provider::tests::MemoryKeyProvider::default();
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+
+        let storage = StorageManager::open_with_key_provider(data_dir.clone(), Some(&provider))
+            .await
+            .unwrap();
+        assert!(!storage.is_locked());
+        storage.save_account(&test_account()).await.unwrap();
+        let audit = storage.security_audit().await.unwrap();
+        assert!(audit.clean && audit.findings.is_empty());
+        drop(storage);
+
+        let reopened = StorageManager::open_with_key_provider(data_dir.clone(), Some(&provider))
+            .await
+            .unwrap();
+        assert_eq!(reopened.list_accounts().await.unwrap()[0].id, "alice");
+        assert_eq!(reopened.key_derivation_time().await.unwrap(), Duration::ZERO);
+        drop(reopened);
+
+        // Without the provider's key the store is not readable with the password
+        assert!(StorageManager::open(data_dir).await.unwrap().is_locked());
+
+        // Existing password stores keep using the password
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+        storage.save_account(&test_account()).await.unwrap();
+        drop(storage);
+        let storage =
+            StorageManager::open_with_key_provider(data_dir, Some(&provider)).await.unwrap();
+        assert!(!storage.is_locked());
+        assert_eq!(storage.list_accounts().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_valid_token_with_expired_refresh_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let mut server = Server::new_async().await;
+        let refresh = server
+            .mock("POST", "/xrpc/com.atproto.server.refreshSession")
+            .with_status(400)
+            .with_body(r#"{"error":"ExpiredToken","message":"Token has expired"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let client = ATProtocolClient::with_base_url(&server.url());
+
+        // Expired locally: no refresh attempt
+        let expired = token_expiring_in(-chrono::Duration::hours(1), -chrono::Duration::hours(1));
+        storage.save_auth_token(&expired).await.unwrap();
+        let result = storage.get_valid_token("alice", &client).await;
+        assert!(matches!(result, Err(AuthError::TokenExpired)));
+
+        // Rejected by the server (e.g., revoked)
+        let revoked = token_expiring_in(-chrono::Duration::hours(1), chrono::Duration::days(30));
+        storage.save_auth_token(&revoked).await.unwrap();
+        let result = storage.get_valid_token("alice", &client).await;
 
-        persistence.clear()
+        refresh.assert_async().await;
+        assert!(matches!(result, Err(AuthError::TokenExpired)));
     }
 }
@@ -0,0 +1,95 @@
+/**
+ * Passphrase generation
+ *
+ * Diceware-style passphrases drawn from a bundled wordlist of 1296 (6^4) short English
+ * words, so a passphrase can also be rolled by hand with four dice per word.
+ */
+
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use serde::Serialize;
+
+/// Bundled wordlist (one lowercase word per line)
+const WORDLIST: &str = include_str!("wordlist.txt");
+
+/// Fewest words in a generated passphrase
+pub const MIN_PASSPHRASE_WORDS: u32 = 4;
+
+/// Most words in a generated passphrase
+pub const MAX_PASSPHRASE_WORDS: u32 = 12;
+
+/// Separator between passphrase words
+const WORD_SEPARATOR: &str = "-";
+
+/// A generated passphrase
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedPassphrase {
+    pub passphrase: String,
+    /// Number of words (after clamping)
+    pub words: u32,
+    /// Entropy estimate in bits (`words * log2(wordlist size)`)
+    pub entropy_bits: f64,
+}
+
+fn wordlist() -> Vec<&'static str> {
+    WORDLIST.lines().filter(|line| !line.is_empty()).collect()
+}
+
+/// Generate a random passphrase
+///
+/// # Arguments
+/// * `words` - Requested word count, clamped to 4..=12
+///
+/// # Note
+/// Words are chosen independently and uniformly with the OS random number generator.
+pub fn generate_passphrase(words: u32) -> GeneratedPassphrase {
+    let words = words.clamp(MIN_PASSPHRASE_WORDS, MAX_PASSPHRASE_WORDS);
+    let list = wordlist();
+
+    let chosen: Vec<&str> = (0..words)
+        .filter_map(|_| list.choose(&mut OsRng).copied())
+        .collect();
+
+    GeneratedPassphrase {
+        passphrase: chosen.join(WORD_SEPARATOR),
+        words,
+        entropy_bits: words as f64 * (list.len() as f64).log2(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_wordlist_is_diceware_sized_and_unique() {
+        let list = wordlist();
+        assert_eq!(list.len(), 6usize.pow(4));
+        assert_eq!(list.iter().collect::<HashSet<_>>().len(), list.len());
+        assert!(list.iter().all(|word| word.chars().all(|c| c.is_ascii_lowercase())));
+    }
+
+    #[test]
+    fn test_word_count_is_clamped() {
+        for (requested, expected) in [(0, 4), (4, 4), (7, 7), (12, 12), (100, 12)] {
+            let generated = generate_passphrase(requested);
+            assert_eq!(generated.words, expected);
+            assert_eq!(generated.passphrase.split(WORD_SEPARATOR).count(), expected as usize);
+        }
+    }
+
+    #[test]
+    fn test_passphrases_differ_and_entropy_scales() {
+        let first = generate_passphrase(6);
+        let second = generate_passphrase(6);
+        assert_ne!(first.passphrase, second.passphrase);
+
+        let short = generate_passphrase(4).entropy_bits;
+        let long = generate_passphrase(8).entropy_bits;
+        assert!((long - 2.0 * short).abs() < 1e-9);
+        // log2(1296) ≈ 10.34 bits per word
+        assert!((short - 41.36).abs() < 0.01);
+    }
+}
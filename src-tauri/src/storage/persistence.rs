@@ -4,16 +4,198 @@
  * Manages secure storage of accounts and tokens to disk
  */
 
-use crate::storage::crypto::{decrypt, derive_key_from_password, encrypt, generate_salt};
+use crate::storage::crypto::{
+    decrypt, derive_key_with_params, generate_salt, verify_roundtrip, CipherAlgorithm,
+};
+use crate::storage::keyfile::{
+    commit_staged_key_file, load_or_create_key_params, remove_key_file, stage_key_file,
+    staged_key_file, KdfParams, REKEY_SUFFIX,
+};
+use crate::storage::key_provider::KeyProvider;
 use crate::types::{Account, AuthError, AuthToken};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use fs2::FileExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+/// Encrypted accounts/tokens file
+pub(crate) const STORAGE_FILE: &str = "storage.enc";
+/// Previous storage file, kept by `save` for recovery
+pub(crate) const STORAGE_BACKUP_FILE: &str = "storage.enc.bak";
+/// Start of the error `load_with_source` reports after recovering from the backup
+pub(crate) const RECOVERED_FROM_BACKUP: &str =
+    "Primary storage file corrupt, recovered from backup";
+/// Advisory lock held while a process has the store open
+const LOCK_FILE: &str = "storage.lock";
+/// Built-in password used until a user master password is set
+pub(crate) const DEFAULT_STORAGE_PASSWORD: &str = "taurisky_default_password_v1";
+
+/// Current schema version of the decrypted storage data
+pub(crate) const STORAGE_VERSION: u32 = 1;
+
+/// Upgrade of the decrypted storage JSON by one version (index `i`: `i` to `i + 1`)
+type StorageMigration = fn(&mut serde_json::Map<String, Value>) -> Result<(), String>;
+
+/// Migrations applied by `load`, one per version step up to `STORAGE_VERSION`
+const STORAGE_MIGRATIONS: [StorageMigration; STORAGE_VERSION as usize] = [
+    // 0 -> 1: data written before versioning already has the version 1 shape
+    |_| Ok(()),
+];
+
+/// Upgrade decrypted storage JSON to the version reached by `migrations`
+///
+/// # Arguments
+/// * `value` - Decrypted storage data (without a `version` field: version 0)
+/// * `migrations` - Migration steps (`STORAGE_MIGRATIONS` outside tests)
+///
+/// # Returns
+/// The data tagged with the new version; data from a newer version is rejected
+fn migrate_storage(mut value: Value, migrations: &[StorageMigration]) -> Result<Value, AuthError> {
+    let target = migrations.len() as u32;
+    let object = value.as_object_mut().ok_or_else(|| {
+        AuthError::StorageError("Storage data is not a JSON object".to_string())
+    })?;
+
+    let version = object.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    if version > target {
+        return Err(AuthError::StorageError(format!(
+            "Storage version {} is newer than supported version {}",
+            version, target
+        )));
+    }
+
+    for (step, migration) in migrations.iter().enumerate().skip(version as usize) {
+        migration(object).map_err(|e| {
+            AuthError::StorageError(format!(
+                "Failed to migrate storage from version {}: {}",
+                step, e
+            ))
+        })?;
+    }
+    object.insert("version".to_string(), Value::from(target));
+
+    Ok(value)
+}
+
+/// Header byte of a gzip-compressed payload (the first plaintext byte, so it is
+/// authenticated with the data)
+const PAYLOAD_GZIP: u8 = 0x01;
+
+/// Compress serialized storage data behind its header byte
+///
+/// # Note
+/// The output is wiped when dropped; flate2's internal buffers are not.
+fn compress_payload(json: &[u8]) -> Result<Zeroizing<Vec<u8>>, AuthError> {
+    let mut encoder = GzEncoder::new(vec![PAYLOAD_GZIP], Compression::default());
+    encoder
+        .write_all(json)
+        .and_then(|_| encoder.finish())
+        .map(Zeroizing::new)
+        .map_err(|e| AuthError::StorageError(format!("Compression failed: {}", e)))
+}
+
+/// Undo `compress_payload`
+///
+/// Payloads starting with `{` are uncompressed JSON written before compression was
+/// added and are returned as is.
+fn decompress_payload(payload: Zeroizing<Vec<u8>>) -> Result<Zeroizing<Vec<u8>>, AuthError> {
+    match payload.first() {
+        Some(b'{') => Ok(payload),
+        Some(&PAYLOAD_GZIP) => {
+            let mut json = Zeroizing::new(Vec::new());
+            GzDecoder::new(&payload[1..])
+                .read_to_end(&mut json)
+                .map_err(|e| AuthError::StorageError(format!("Decompression failed: {}", e)))?;
+            Ok(json)
+        }
+        other => Err(AuthError::StorageError(format!(
+            "Unsupported storage payload header {:02x?}",
+            other
+        ))),
+    }
+}
+
+/// Serialize, compress and encrypt storage data
+fn encode_storage(
+    data: &StorageData,
+    cipher: CipherAlgorithm,
+    key: &[u8],
+) -> Result<String, AuthError> {
+    let json_bytes = Zeroizing::new(serde_json::to_vec(data).map_err(|e| {
+        AuthError::StorageError(format!("Failed to serialize storage data: {}", e))
+    })?);
+    let payload = compress_payload(&json_bytes)?;
+    cipher
+        .encrypt(&payload, key)
+        .map_err(|e| AuthError::StorageError(format!("Encryption failed: {}", e)))
+}
+
+/// Decrypt and decompress a storage file into JSON (before migration)
+fn decode_storage_value(encrypted_data: &str, key: &[u8]) -> Result<Value, AuthError> {
+    // Decrypted tokens are wiped once parsed
+    let decrypted_bytes = decompress_payload(Zeroizing::new(
+        decrypt(encrypted_data, key)
+            .map_err(|e| AuthError::StorageError(format!("Decryption failed: {}", e)))?,
+    ))?;
+
+    serde_json::from_slice(&decrypted_bytes)
+        .map_err(|e| AuthError::StorageError(format!("Failed to parse storage data: {}", e)))
+}
+
+/// Decrypt, decompress and parse the contents of a storage file
+fn decode_storage(encrypted_data: &str, key: &[u8]) -> Result<StorageData, AuthError> {
+    // Deserialize JSON, upgrading data written by older versions
+    let value = decode_storage_value(encrypted_data, key)?;
+    serde_json::from_value(migrate_storage(value, &STORAGE_MIGRATIONS)?).map_err(|e| {
+        AuthError::StorageError(format!("Failed to parse storage data: {}", e))
+    })
+}
+
+/// Number of recent write durations kept for metrics
+const WRITE_SAMPLES: usize = 64;
+
+/// File `PersistentStorage::load_with_source` read the data from
+#[derive(Debug)]
+pub enum LoadSource {
+    /// `storage.enc` (or no storage file yet)
+    Primary,
+    /// `storage.enc.bak`, with the `AuthError::StorageError` (starting with
+    /// `RECOVERED_FROM_BACKUP`) describing why the primary file was not used
+    Backup(AuthError),
+}
+
+/// Result of `PersistentStorage::verify_encryption`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionCheck {
+    /// Whether every check passed
+    pub ok: bool,
+    /// Cipher in use (e.g., "AES-256-GCM")
+    pub algorithm: &'static str,
+    /// Whether the existing storage file was decrypted with the live key
+    /// (false when there is no storage file yet)
+    pub data_file_checked: bool,
+    /// Reason the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
 
 /// Container for all persistent data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageData {
+    /// Schema version (`STORAGE_VERSION` once loaded; files written before versioning
+    /// have none)
+    #[serde(default)]
+    pub version: u32,
     /// All registered accounts
     pub accounts: HashMap<String, Account>,
     /// Authentication tokens (sensitive)
@@ -23,20 +205,161 @@ pub struct StorageData {
 impl StorageData {
     pub fn new() -> Self {
         Self {
+            version: STORAGE_VERSION,
             accounts: HashMap::new(),
             tokens: HashMap::new(),
         }
     }
 }
 
+/// Write latency summary of the storage file
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistenceMetrics {
+    /// Successful writes since start
+    pub total_writes: u64,
+    /// Number of recent writes the timings below are computed from
+    pub sample_count: usize,
+    /// Fastest recent write in milliseconds
+    pub min_ms: f64,
+    /// Slowest recent write in milliseconds
+    pub max_ms: f64,
+    /// Average recent write in milliseconds
+    pub avg_ms: f64,
+}
+
+/// Bounded ring buffer of recent write durations
+#[derive(Default)]
+struct WriteMetrics {
+    /// (recent durations, total writes)
+    inner: Mutex<(VecDeque<Duration>, u64)>,
+}
+
+impl WriteMetrics {
+    fn record(&self, duration: Duration) {
+        if let Ok(mut inner) = self.inner.lock() {
+            let (samples, total) = &mut *inner;
+            if samples.len() == WRITE_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(duration);
+            *total += 1;
+        }
+    }
+
+    fn summary(&self) -> PersistenceMetrics {
+        let Ok(inner) = self.inner.lock() else {
+            return PersistenceMetrics::default();
+        };
+        let (samples, total) = &*inner;
+        let ms: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+
+        if ms.is_empty() {
+            return PersistenceMetrics {
+                total_writes: *total,
+                ..PersistenceMetrics::default()
+            };
+        }
+
+        PersistenceMetrics {
+            total_writes: *total,
+            sample_count: ms.len(),
+            min_ms: ms.iter().copied().fold(f64::INFINITY, f64::min),
+            max_ms: ms.iter().copied().fold(0.0, f64::max),
+            avg_ms: ms.iter().sum::<f64>() / ms.len() as f64,
+        }
+    }
+}
+
+/// Path of the storage file staged by a key rotation
+fn staged_data_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(format!("{}.{}", STORAGE_FILE, REKEY_SUFFIX))
+}
+
+/// Finish or roll back a key rotation that was interrupted by a crash
+///
+/// `rekey` stages the new key file, then the re-encrypted data, and installs them in
+/// the same order. A staged key file means nothing was installed yet, so the staged
+/// files are discarded; staged data alone means the new key file is already live, so
+/// the data is installed too.
+fn recover_interrupted_rekey(data_dir: &Path) -> Result<(), AuthError> {
+    let staged_key = staged_key_file(data_dir);
+    let staged_data = staged_data_file(data_dir);
+
+    if staged_key.exists() {
+        let _ = fs::remove_file(&staged_data);
+        return fs::remove_file(&staged_key).map_err(|e| {
+            AuthError::StorageError(format!("Failed to discard staged key file: {}", e))
+        });
+    }
+
+    if staged_data.exists() {
+        fs::rename(&staged_data, data_dir.join(STORAGE_FILE)).map_err(|e| {
+            AuthError::StorageError(format!("Failed to finish key rotation: {}", e))
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Exclusive OS-level lock on a data directory's store (released on drop)
+struct StorageLock {
+    file: fs::File,
+}
+
+impl StorageLock {
+    /// Lock the store of a data directory
+    ///
+    /// # Note
+    /// Fails immediately (without waiting) if another process holds the lock.
+    fn acquire(data_dir: &Path) -> Result<Self, AuthError> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(data_dir.join(LOCK_FILE))
+            .map_err(|e| AuthError::StorageError(format!("Failed to open lock file: {}", e)))?;
+
+        file.try_lock_exclusive().map_err(|e| {
+            AuthError::StorageError(format!(
+                "Storage is in use by another instance of the app: {}",
+                e
+            ))
+        })?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
 /// File-based persistent storage with encryption
+///
+/// The data directory stays locked against other processes while an instance (or one
+/// created from it with `reopen`) is alive.
 pub struct PersistentStorage {
     /// Path to encrypted storage file
     data_file: PathBuf,
-    /// Path to salt file
-    salt_file: PathBuf,
-    /// Encryption key derived from password
-    encryption_key: Vec<u8>,
+    /// Encryption key derived from password (wiped from memory on drop)
+    encryption_key: Zeroizing<Vec<u8>>,
+    /// Recent write durations
+    write_metrics: WriteMetrics,
+    /// Time spent deriving the key on open (zero when a key provider supplied it)
+    key_derivation_time: Duration,
+    /// Whether the key is derived from `DEFAULT_STORAGE_PASSWORD`
+    default_password: bool,
+    /// Whether the key is held by an OS key provider instead of derived from a password
+    os_key_protection: bool,
+    /// Data directory lock (shared with instances created by `reopen`)
+    lock: Arc<StorageLock>,
+    /// Cipher new writes are encrypted with (reads follow each file's version byte)
+    cipher: CipherAlgorithm,
+    /// Argon2 parameters stored with the salt (the key is derived with these)
+    kdf: KdfParams,
 }
 
 impl PersistentStorage {
@@ -45,92 +368,459 @@ impl PersistentStorage {
     /// # Arguments
     /// * `data_dir` - Directory to store encrypted files
     /// * `password` - Master password for encryption (in production, use app-specific password)
-    pub fn new(data_dir: PathBuf, password: &str) -> Result<Self, AuthError> {
+    /// * `cipher` - Cipher for new writes (existing files are read with the cipher that
+    ///   wrote them)
+    ///
+    /// # Note
+    /// Fails with `AuthError::StorageError` if another process has the store open.
+    pub fn new(
+        data_dir: PathBuf,
+        password: &str,
+        cipher: CipherAlgorithm,
+    ) -> Result<Self, AuthError> {
         // Ensure data directory exists
         fs::create_dir_all(&data_dir).map_err(|e| {
             AuthError::StorageError(format!("Failed to create data directory: {}", e))
         })?;
 
-        let data_file = data_dir.join("storage.enc");
-        let salt_file = data_dir.join("salt.bin");
+        let lock = Arc::new(StorageLock::acquire(&data_dir)?);
+        let mut storage = Self::open_locked(data_dir, password, lock)?;
+        storage.cipher = cipher;
+        Ok(storage)
+    }
 
-        // Load or generate salt
-        let salt = if salt_file.exists() {
-            fs::read(&salt_file).map_err(|e| {
-                AuthError::StorageError(format!("Failed to read salt file: {}", e))
-            })?
-        } else {
-            let salt = generate_salt();
-            fs::write(&salt_file, &salt).map_err(|e| {
-                AuthError::StorageError(format!("Failed to write salt file: {}", e))
-            })?;
-            salt
-        };
+    /// Create a persistent storage instance keyed by a key provider (e.g., the OS
+    /// keychain) instead of a password
+    ///
+    /// # Arguments
+    /// * `data_dir` - Directory to store encrypted files
+    /// * `provider` - Key provider (generates the key on first use)
+    ///
+    /// # Note
+    /// The key is not derived from a passphrase, so `verify_password` never matches;
+    /// a store created this way only opens while the provider still has the key.
+    pub fn with_key_provider(
+        data_dir: PathBuf,
+        provider: &dyn KeyProvider,
+    ) -> Result<Self, AuthError> {
+        fs::create_dir_all(&data_dir).map_err(|e| {
+            AuthError::StorageError(format!("Failed to create data directory: {}", e))
+        })?;
 
-        // Derive encryption key from password
-        let encryption_key = derive_key_from_password(password, &salt)
-            .map_err(|e| AuthError::StorageError(format!("Key derivation failed: {}", e)))?;
+        let lock = Arc::new(StorageLock::acquire(&data_dir)?);
+        Self::open_with_provider(data_dir, provider, lock)
+    }
+
+    /// Open the store again with a key provider, keeping this instance's lock
+    ///
+    /// # Arguments
+    /// * `provider` - Key provider
+    pub fn reopen_with_key_provider(&self, provider: &dyn KeyProvider) -> Result<Self, AuthError> {
+        let mut storage =
+            Self::open_with_provider(self.data_dir(), provider, Arc::clone(&self.lock))?;
+        storage.cipher = self.cipher;
+        Ok(storage)
+    }
+
+    /// Open the store of an already locked data directory with a provider's key
+    fn open_with_provider(
+        data_dir: PathBuf,
+        provider: &dyn KeyProvider,
+        lock: Arc<StorageLock>,
+    ) -> Result<Self, AuthError> {
+        recover_interrupted_rekey(&data_dir)?;
+
+        // Unused for the key, but a store without a key file would be quarantined as
+        // broken if it is ever opened through the password path
+        let (_, kdf) = load_or_create_key_params(&data_dir, &[STORAGE_FILE])?;
+
+        let encryption_key = provider.load_or_create_key().map_err(|e| {
+            AuthError::StorageError(format!("Key provider {} failed: {}", provider.name(), e))
+        })?;
 
         Ok(Self {
-            data_file,
-            salt_file,
+            data_file: data_dir.join(STORAGE_FILE),
             encryption_key,
+            write_metrics: WriteMetrics::default(),
+            key_derivation_time: Duration::ZERO,
+            default_password: false,
+            os_key_protection: true,
+            lock,
+            cipher: CipherAlgorithm::default(),
+            kdf,
+        })
+    }
+
+    /// Open the store again with another password, keeping this instance's lock
+    ///
+    /// # Arguments
+    /// * `password` - Master password for encryption
+    ///
+    /// # Note
+    /// Used to replace an open instance in place (unlock, reload); opening with `new`
+    /// instead would fail while this instance holds the lock. Key derivation runs on
+    /// the blocking thread pool.
+    pub async fn reopen(&self, password: &str) -> Result<Self, AuthError> {
+        let (data_dir, lock) = (self.data_dir(), Arc::clone(&self.lock));
+        let password = Zeroizing::new(password.to_string());
+
+        let mut storage = tokio::task::spawn_blocking(move || {
+            Self::open_locked(data_dir, &password, lock)
         })
+        .await
+        .map_err(|e| AuthError::StorageError(format!("Storage task failed: {}", e)))??;
+        storage.cipher = self.cipher;
+        Ok(storage)
+    }
+
+    /// Open the store of an already locked data directory
+    fn open_locked(
+        data_dir: PathBuf,
+        password: &str,
+        lock: Arc<StorageLock>,
+    ) -> Result<Self, AuthError> {
+        let data_file = data_dir.join(STORAGE_FILE);
+
+        recover_interrupted_rekey(&data_dir)?;
+
+        // Load or generate salt and parameters (a broken key file never reaches key
+        // derivation)
+        let (salt, kdf) = load_or_create_key_params(&data_dir, &[STORAGE_FILE])?;
+
+        let mut storage = Self {
+            data_file,
+            encryption_key: Zeroizing::new(Vec::new()),
+            write_metrics: WriteMetrics::default(),
+            key_derivation_time: Duration::ZERO,
+            default_password: password == DEFAULT_STORAGE_PASSWORD,
+            os_key_protection: false,
+            lock,
+            cipher: CipherAlgorithm::default(),
+            kdf,
+        };
+
+        // Derive encryption key from password
+        let derivation_started = Instant::now();
+        storage.encryption_key = derive_key_with_params(password, &salt, &storage.kdf)
+            .map_err(|e| AuthError::StorageError(format!("Key derivation failed: {}", e)))?;
+        storage.key_derivation_time = derivation_started.elapsed();
+
+        Ok(storage)
     }
 
     /// Load storage data from disk
-    pub fn load(&self) -> Result<StorageData, AuthError> {
-        if !self.data_file.exists() {
+    ///
+    /// Data of an older schema version is migrated in memory; the next save writes
+    /// it back as `STORAGE_VERSION`. Falls back to `storage.enc.bak` like
+    /// `load_with_source`.
+    pub async fn load(&self) -> Result<StorageData, AuthError> {
+        self.load_with_source().await.map(|(data, _)| data)
+    }
+
+    /// Load storage data from disk, falling back to the backup of the previous save
+    ///
+    /// # Returns
+    /// The data and the file it was read from. When `storage.enc` is missing, truncated
+    /// or doesn't decrypt with the key but `storage.enc.bak` loads, the data of the
+    /// backup is returned with `LoadSource::Backup`. If neither loads, the primary
+    /// file's error is returned.
+    pub async fn load_with_source(&self) -> Result<(StorageData, LoadSource), AuthError> {
+        let backup_file = self.data_file.with_file_name(STORAGE_BACKUP_FILE);
+        if !self.data_file.exists() && !backup_file.exists() {
             // No data file yet - return empty storage
-            return Ok(StorageData::new());
+            return Ok((StorageData::new(), LoadSource::Primary));
+        }
+
+        let primary_error = match self.read_data_file(&self.data_file).await {
+            Ok(data) => return Ok((data, LoadSource::Primary)),
+            Err(e) => e,
+        };
+        match self.read_data_file(&backup_file).await {
+            Ok(data) => Ok((
+                data,
+                LoadSource::Backup(AuthError::StorageError(format!(
+                    "{}: {}",
+                    RECOVERED_FROM_BACKUP, primary_error
+                ))),
+            )),
+            Err(_) => Err(primary_error),
         }
+    }
 
+    /// Read, decrypt and parse one storage file
+    async fn read_data_file(&self, path: &Path) -> Result<StorageData, AuthError> {
         // Read encrypted data
-        let encrypted_data = fs::read_to_string(&self.data_file).map_err(|e| {
+        let encrypted_data = tokio::fs::read_to_string(path).await.map_err(|e| {
             AuthError::StorageError(format!("Failed to read storage file: {}", e))
         })?;
 
-        // Decrypt data
-        let decrypted_bytes = decrypt(&encrypted_data, &self.encryption_key)
-            .map_err(|e| AuthError::StorageError(format!("Decryption failed: {}", e)))?;
+        // Decryption and parsing are CPU-bound; keep them off the async workers
+        let key = self.encryption_key.clone();
+        tokio::task::spawn_blocking(move || decode_storage(&encrypted_data, &key))
+            .await
+            .map_err(|e| AuthError::StorageError(format!("Storage task failed: {}", e)))?
+    }
+
+    /// Schema version of the storage file as written (before migration)
+    ///
+    /// # Returns
+    /// None when there is no storage file yet; files written before versioning report 0
+    pub async fn stored_version(&self) -> Result<Option<u32>, AuthError> {
+        if !self.data_file.exists() {
+            return Ok(None);
+        }
+        let encrypted_data = tokio::fs::read_to_string(&self.data_file).await.map_err(|e| {
+            AuthError::StorageError(format!("Failed to read storage file: {}", e))
+        })?;
 
-        // Deserialize JSON
-        serde_json::from_slice(&decrypted_bytes).map_err(|e| {
-            AuthError::StorageError(format!("Failed to parse storage data: {}", e))
-        })
+        let key = self.encryption_key.clone();
+        let value = tokio::task::spawn_blocking(move || decode_storage_value(&encrypted_data, &key))
+            .await
+            .map_err(|e| AuthError::StorageError(format!("Storage task failed: {}", e)))??;
+        Ok(Some(value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32))
     }
 
     /// Save storage data to disk
-    pub fn save(&self, data: &StorageData) -> Result<(), AuthError> {
-        // Serialize to JSON
-        let json_bytes = serde_json::to_vec(data).map_err(|e| {
-            AuthError::StorageError(format!("Failed to serialize storage data: {}", e))
+    ///
+    /// The data is written to `storage.enc.tmp` and renamed into place, so an interrupted
+    /// write leaves the previous store intact. The file being replaced is kept as
+    /// `storage.enc.bak` if it still decrypts (a damaged file never overwrites a good
+    /// backup). The duration of each successful save is recorded for `metrics`.
+    ///
+    /// # Note
+    /// Serialization and encryption run on the blocking thread pool, file I/O on
+    /// `tokio::fs`. Callers serialize concurrent saves (see `StorageManager::persist`).
+    pub async fn save(&self, data: &StorageData) -> Result<(), AuthError> {
+        let started = Instant::now();
+
+        // Serialize to JSON, compress and encrypt
+        let data = data.clone();
+        let key = self.encryption_key.clone();
+        let cipher = self.cipher;
+        let encrypted_data =
+            tokio::task::spawn_blocking(move || encode_storage(&data, cipher, &key))
+                .await
+                .map_err(|e| AuthError::StorageError(format!("Storage task failed: {}", e)))??;
+
+        // Write to temp file, then rename so a crash never leaves a partial store
+        let temp_file = self.data_file.with_file_name(format!("{}.tmp", STORAGE_FILE));
+        tokio::fs::write(&temp_file, encrypted_data).await.map_err(|e| {
+            AuthError::StorageError(format!("Failed to write temp storage file: {}", e))
         })?;
 
-        // Encrypt data
-        let encrypted_data = encrypt(&json_bytes, &self.encryption_key)
-            .map_err(|e| AuthError::StorageError(format!("Encryption failed: {}", e)))?;
+        // Keep the previous store; `load_with_source` also covers a crash before the
+        // temp file is renamed into place
+        if self.data_file.exists() && self.read_data_file(&self.data_file).await.is_ok() {
+            let backup_file = self.data_file.with_file_name(STORAGE_BACKUP_FILE);
+            tokio::fs::rename(&self.data_file, backup_file).await.map_err(|e| {
+                AuthError::StorageError(format!("Failed to keep storage backup: {}", e))
+            })?;
+        }
 
-        // Write to file
-        fs::write(&self.data_file, encrypted_data).map_err(|e| {
-            AuthError::StorageError(format!("Failed to write storage file: {}", e))
+        // Atomic rename (both files live in the data directory)
+        tokio::fs::rename(&temp_file, &self.data_file).await.map_err(|e| {
+            AuthError::StorageError(format!("Failed to rename temp storage file: {}", e))
         })?;
 
+        self.write_metrics.record(started.elapsed());
+        Ok(())
+    }
+
+    /// Check that the live key encrypts and decrypts correctly
+    ///
+    /// Round-trips a known test vector, then decrypts (without parsing or writing) the
+    /// existing storage file to confirm the key is the one the data was encrypted with.
+    pub fn verify_encryption(&self) -> EncryptionCheck {
+        let failed = |data_file_checked, error: String| EncryptionCheck {
+            ok: false,
+            algorithm: self.cipher.name(),
+            data_file_checked,
+            error: Some(error),
+        };
+
+        if let Err(e) = verify_roundtrip(self.cipher, &self.encryption_key) {
+            return failed(false, format!("Round-trip failed: {}", e));
+        }
+
+        let data_file_checked = self.data_file.exists();
+        if data_file_checked {
+            let checked = fs::read_to_string(&self.data_file)
+                .map_err(|e| format!("Failed to read storage file: {}", e))
+                .and_then(|encrypted| decrypt(&encrypted, &self.encryption_key));
+            if let Err(e) = checked {
+                return failed(true, format!("Storage file does not decrypt: {}", e));
+            }
+        }
+
+        EncryptionCheck {
+            ok: true,
+            algorithm: self.cipher.name(),
+            data_file_checked,
+            error: None,
+        }
+    }
+
+    /// Directory holding the storage files
+    pub fn data_dir(&self) -> PathBuf {
+        self.data_file
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default()
+    }
+
+    /// Argon2 parameters the key is derived with
+    pub fn kdf_params(&self) -> &KdfParams {
+        &self.kdf
+    }
+
+    /// Time the key derivation took when the storage was opened
+    pub fn key_derivation_time(&self) -> Duration {
+        self.key_derivation_time
+    }
+
+    /// Whether the key is derived from the built-in default password
+    pub fn uses_default_password(&self) -> bool {
+        self.default_password
+    }
+
+    /// Whether the key is held by an OS key provider (e.g., the keychain)
+    pub fn has_os_key_protection(&self) -> bool {
+        self.os_key_protection
+    }
+
+    /// Write latency of recent saves
+    pub fn metrics(&self) -> PersistenceMetrics {
+        self.write_metrics.summary()
+    }
+
+    /// Whether a password derives the key the store is encrypted with
+    ///
+    /// # Arguments
+    /// * `password` - Password to check
+    ///
+    /// # Note
+    /// Key derivation runs on the blocking thread pool.
+    pub async fn verify_password(&self, password: &str) -> Result<bool, AuthError> {
+        let (data_dir, data_file) = (self.data_dir(), self.data_file.clone());
+        let current_key = self.encryption_key.clone();
+        let password = Zeroizing::new(password.to_string());
+
+        tokio::task::spawn_blocking(move || {
+            let (salt, kdf) = load_or_create_key_params(&data_dir, &[STORAGE_FILE])?;
+            let key = derive_key_with_params(&password, &salt, &kdf)
+                .map_err(|e| AuthError::StorageError(format!("Key derivation failed: {}", e)))?;
+
+            if !data_file.exists() {
+                return Ok(key == current_key);
+            }
+            let encrypted_data = fs::read_to_string(&data_file).map_err(|e| {
+                AuthError::StorageError(format!("Failed to read storage file: {}", e))
+            })?;
+            Ok(decrypt(&encrypted_data, &key).is_ok())
+        })
+        .await
+        .map_err(|e| AuthError::StorageError(format!("Storage task failed: {}", e)))?
+    }
+
+    /// Re-encrypt the data with a key derived from a new password and a fresh salt
+    ///
+    /// The new key file and storage file are staged (flushed to disk) before either is
+    /// installed, and an interrupted rotation is completed or rolled back on the next
+    /// open (see `recover_interrupted_rekey`), so a crash never leaves a key file and
+    /// storage file that don't match. The new key is derived with the current
+    /// (`KdfParams::current`) parameters, upgrading stores created with older ones.
+    ///
+    /// # Arguments
+    /// * `password` - New master password
+    /// * `data` - Current contents of the store
+    ///
+    /// # Note
+    /// Key derivation, encryption and staging run on the blocking thread pool.
+    pub async fn rekey(&mut self, password: &str, data: &StorageData) -> Result<(), AuthError> {
+        let (data_dir, data_file) = (self.data_dir(), self.data_file.clone());
+        let (data, cipher) = (data.clone(), self.cipher);
+        let password_copy = Zeroizing::new(password.to_string());
+
+        let installed = tokio::task::spawn_blocking(move || {
+            let salt = generate_salt();
+            let kdf = KdfParams::current();
+
+            let derivation_started = Instant::now();
+            let key = derive_key_with_params(&password_copy, &salt, &kdf)
+                .map_err(|e| AuthError::StorageError(format!("Key derivation failed: {}", e)))?;
+            let derivation_time = derivation_started.elapsed();
+
+            let encrypted_data = encode_storage(&data, cipher, &key)?;
+
+            let staged_data = staged_data_file(&data_dir);
+            let staged = stage_key_file(&data_dir, &salt, &kdf)
+                .and_then(|_| {
+                    fs::File::create(&staged_data)
+                        .and_then(|mut file| {
+                            file.write_all(encrypted_data.as_bytes())
+                                .and_then(|_| file.sync_all())
+                        })
+                        .map_err(|e| {
+                            AuthError::StorageError(format!("Failed to stage storage file: {}", e))
+                        })
+                })
+                .and_then(|_| commit_staged_key_file(&data_dir));
+            if let Err(e) = staged {
+                // Nothing is installed yet; the old key and data stay valid
+                let _ = recover_interrupted_rekey(&data_dir);
+                return Err(e);
+            }
+
+            fs::rename(&staged_data, &data_file).map_err(|e| {
+                AuthError::StorageError(format!("Failed to install storage file: {}", e))
+            })?;
+
+            Ok((key, kdf, derivation_time))
+        })
+        .await
+        .map_err(|e| AuthError::StorageError(format!("Storage task failed: {}", e)))?;
+        let (key, kdf, derivation_time) = installed?;
+
+        // The previous key is wiped as it is dropped
+        self.encryption_key = key;
+        self.kdf = kdf;
+
+        // The backup is still encrypted with the old key
+        self.remove_backup_file()?;
+
+        self.key_derivation_time = derivation_time;
+        self.default_password = password == DEFAULT_STORAGE_PASSWORD;
+        Ok(())
+    }
+
+    /// Delete `storage.enc.bak`, if any
+    fn remove_backup_file(&self) -> Result<(), AuthError> {
+        let backup_file = self.data_file.with_file_name(STORAGE_BACKUP_FILE);
+        if backup_file.exists() {
+            fs::remove_file(&backup_file).map_err(|e| {
+                AuthError::StorageError(format!("Failed to delete storage backup: {}", e))
+            })?;
+        }
         Ok(())
     }
 
     /// Clear all stored data (delete files)
-    pub fn clear(&self) -> Result<(), AuthError> {
+    pub async fn clear(&self) -> Result<(), AuthError> {
         if self.data_file.exists() {
-            fs::remove_file(&self.data_file).map_err(|e| {
+            tokio::fs::remove_file(&self.data_file).await.map_err(|e| {
                 AuthError::StorageError(format!("Failed to delete storage file: {}", e))
             })?;
         }
-        if self.salt_file.exists() {
-            fs::remove_file(&self.salt_file).map_err(|e| {
-                AuthError::StorageError(format!("Failed to delete salt file: {}", e))
+        let backup_file = self.data_file.with_file_name(STORAGE_BACKUP_FILE);
+        if backup_file.exists() {
+            tokio::fs::remove_file(&backup_file).await.map_err(|e| {
+                AuthError::StorageError(format!("Failed to delete storage backup: {}", e))
             })?;
         }
+        let data_dir = self.data_dir();
+        tokio::task::spawn_blocking(move || remove_key_file(&data_dir))
+            .await
+            .map_err(|e| AuthError::StorageError(format!("Storage task failed: {}", e)))??;
         Ok(())
     }
 }
@@ -138,13 +828,19 @@ impl PersistentStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::crypto::{
+        derive_key_from_password, encrypt, CIPHER_VERSION_XCHACHA20_POLY1305,
+    };
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use crate::storage::crypto::CipherAlgorithm::{Aes256Gcm, XChaCha20Poly1305};
     use tempfile::tempdir;
     use uuid::Uuid;
 
-    #[test]
-    fn test_storage_save_and_load() {
+    #[tokio::test]
+    async fn test_storage_save_and_load() {
         let temp_dir = tempdir().unwrap();
-        let storage = PersistentStorage::new(temp_dir.path().to_path_buf(), "test_password")
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir, "test_password", Aes256Gcm)
             .expect("Storage creation should succeed");
 
         // Create test data
@@ -162,15 +858,18 @@ mod tests {
             created_at: chrono::Utc::now().to_rfc3339(),
             last_used_at: chrono::Utc::now().to_rfc3339(),
             is_active: true,
+            refresh_failure_count: 0,
+            next_refresh_not_before: None,
+            note: None,
         };
 
         data.accounts.insert(account_id.clone(), account.clone());
 
         // Save data
-        storage.save(&data).expect("Save should succeed");
+        storage.save(&data).await.expect("Save should succeed");
 
         // Load data
-        let loaded_data = storage.load().expect("Load should succeed");
+        let loaded_data = storage.load().await.expect("Load should succeed");
 
         assert_eq!(loaded_data.accounts.len(), 1);
         assert_eq!(
@@ -178,4 +877,293 @@ mod tests {
             "test.bsky.social"
         );
     }
+
+    #[tokio::test]
+    async fn test_partial_temp_file_never_replaces_store() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir, "pw", Aes256Gcm).unwrap();
+        let data: StorageData = serde_json::from_str(VERSION_1_DATA).unwrap();
+        storage.save(&data).await.unwrap();
+        let temp_file = temp_dir.path().join(format!("{}.tmp", STORAGE_FILE));
+        assert!(!temp_file.exists());
+
+        // A save interrupted mid-write leaves a truncated temp file behind
+        fs::write(&temp_file, "trunc").unwrap();
+        let loaded = storage.load().await.unwrap();
+        assert!(loaded.accounts.contains_key("alice"));
+
+        // A save that fails before the rename leaves the store untouched
+        fs::remove_file(&temp_file).unwrap();
+        fs::create_dir(&temp_file).unwrap();
+        assert!(storage.save(&StorageData::new()).await.is_err());
+        assert!(storage.load().await.unwrap().accounts.contains_key("alice"));
+
+        // The next successful save replaces the leftover temp file
+        fs::remove_dir(&temp_file).unwrap();
+        fs::write(&temp_file, "trunc").unwrap();
+        storage.save(&StorageData::new()).await.unwrap();
+        assert!(!temp_file.exists());
+        assert!(storage.load().await.unwrap().accounts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_primary_is_recovered_from_backup() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir, "pw", Aes256Gcm).unwrap();
+        let data_file = temp_dir.path().join(STORAGE_FILE);
+        let backup_file = temp_dir.path().join(STORAGE_BACKUP_FILE);
+        let alice: StorageData = serde_json::from_str(VERSION_1_DATA).unwrap();
+
+        // Without a backup a corrupt primary fails to load
+        storage.save(&alice).await.unwrap();
+        assert!(!backup_file.exists());
+        fs::write(&data_file, "trunc").unwrap();
+        assert!(storage.load().await.is_err());
+
+        // Saves keep the previous (decryptable) store only
+        storage.save(&alice).await.unwrap();
+        assert!(!backup_file.exists());
+        storage.save(&StorageData::new()).await.unwrap();
+        let (loaded, source) = storage.load_with_source().await.unwrap();
+        assert!(loaded.accounts.is_empty());
+        assert!(matches!(source, LoadSource::Primary));
+
+        // A truncated primary is replaced by the previous save
+        let encrypted = fs::read_to_string(&data_file).unwrap();
+        fs::write(&data_file, &encrypted[..encrypted.len() / 2]).unwrap();
+        let (loaded, source) = storage.load_with_source().await.unwrap();
+        assert!(loaded.accounts.contains_key("alice"));
+        match source {
+            LoadSource::Backup(AuthError::StorageError(message)) => {
+                assert!(message.starts_with(RECOVERED_FROM_BACKUP))
+            }
+            other => panic!("expected a backup recovery, got {:?}", other),
+        }
+
+        // Saving over the damaged primary leaves the good backup in place
+        storage.save(&StorageData::new()).await.unwrap();
+        fs::remove_file(&data_file).unwrap();
+        assert!(storage.load().await.unwrap().accounts.contains_key("alice"));
+
+        // Without a decryptable backup the primary's error is reported
+        fs::write(&data_file, "trunc").unwrap();
+        fs::write(&backup_file, "trunc").unwrap();
+        match storage.load().await {
+            Err(AuthError::StorageError(message)) => {
+                assert!(!message.starts_with(RECOVERED_FROM_BACKUP))
+            }
+            other => panic!("expected a storage error, got {:?}", other),
+        }
+    }
+
+    /// Hand-written version 1 storage data
+    const VERSION_1_DATA: &str = r#"{
+        "version": 1,
+        "accounts": {
+            "alice": {
+                "id": "alice", "did": "did:plc:alice", "handle": "alice.bsky.social",
+                "serverUrl": "https://bsky.social", "createdAt": "2024-01-01T00:00:00Z",
+                "lastUsedAt": "2024-01-01T00:00:00Z", "isActive": true
+            }
+        },
+        "tokens": {}
+    }"#;
+
+    #[tokio::test]
+    async fn test_payload_is_compressed_and_uncompressed_files_still_load() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir, "pw", Aes256Gcm).unwrap();
+        let data_file = temp_dir.path().join(STORAGE_FILE);
+        let mut data: StorageData = serde_json::from_str(VERSION_1_DATA).unwrap();
+        let alice = data.accounts["alice"].clone();
+        for i in 0..20 {
+            let id = format!("user{}", i);
+            data.accounts.insert(id.clone(), Account { id, ..alice.clone() });
+        }
+
+        storage.save(&data).await.unwrap();
+        let payload = decrypt(&fs::read_to_string(&data_file).unwrap(), &storage.encryption_key)
+            .unwrap();
+        assert_eq!(payload[0], PAYLOAD_GZIP);
+        assert!(payload.len() < serde_json::to_vec(&data).unwrap().len() / 2);
+        assert_eq!(storage.load().await.unwrap().accounts.len(), 21);
+
+        // Files written before compression hold the JSON itself
+        let json = serde_json::to_vec(&data).unwrap();
+        fs::write(&data_file, encrypt(&json, &storage.encryption_key).unwrap()).unwrap();
+        assert_eq!(storage.load().await.unwrap().accounts.len(), 21);
+
+        fs::write(&data_file, encrypt(b"\x07data", &storage.encryption_key).unwrap()).unwrap();
+        assert!(storage.load().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_version_1_data_loads_and_migrates() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir, "pw", Aes256Gcm).unwrap();
+        let encrypted = encrypt(VERSION_1_DATA.as_bytes(), &storage.encryption_key).unwrap();
+        fs::write(temp_dir.path().join(STORAGE_FILE), encrypted).unwrap();
+
+        let loaded = storage.load().await.unwrap();
+        assert_eq!(loaded.version, STORAGE_VERSION);
+        assert_eq!(loaded.accounts["alice"].handle, "alice.bsky.social");
+
+        // Simulated version 2: handles move to a nested "identity" object
+        let to_v2: StorageMigration = |data| {
+            for account in data["accounts"].as_object_mut().ok_or("no accounts")?.values_mut() {
+                let handle = account["handle"].take();
+                account["identity"] = serde_json::json!({ "handle": handle });
+            }
+            Ok(())
+        };
+        let v1: Value = serde_json::from_str(VERSION_1_DATA).unwrap();
+        let migrated = migrate_storage(v1, &[STORAGE_MIGRATIONS[0], to_v2]).unwrap();
+        assert_eq!(migrated["version"], 2);
+        assert_eq!(migrated["accounts"]["alice"]["identity"]["handle"], "alice.bsky.social");
+        assert_eq!(migrated["accounts"]["alice"]["did"], "did:plc:alice");
+
+        // Data from a newer version is not silently reinterpreted
+        let result = migrate_storage(migrated, &STORAGE_MIGRATIONS);
+        assert!(matches!(result, Err(AuthError::StorageError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_rekey_is_recovered() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let data = StorageData::new();
+        let storage = PersistentStorage::new(data_dir.clone(), "old_password", Aes256Gcm).unwrap();
+        storage.save(&data).await.unwrap();
+        drop(storage);
+
+        // Crash while staging: the staged files are discarded, the old key still works
+        stage_key_file(&data_dir, &generate_salt(), &KdfParams::current()).unwrap();
+        fs::write(staged_data_file(&data_dir), "partial").unwrap();
+        let reopened = PersistentStorage::new(data_dir.clone(), "old_password", Aes256Gcm).unwrap();
+        assert!(reopened.verify_encryption().ok);
+        assert!(!staged_key_file(&data_dir).exists());
+        assert!(!staged_data_file(&data_dir).exists());
+        drop(reopened);
+
+        // Crash after the new key file was installed: the staged data is installed on open
+        let salt = generate_salt();
+        let key = derive_key_from_password("new_password", &salt).unwrap();
+        let encrypted = encrypt(&serde_json::to_vec(&data).unwrap(), &key).unwrap();
+        stage_key_file(&data_dir, &salt, &KdfParams::legacy()).unwrap();
+        fs::write(staged_data_file(&data_dir), encrypted).unwrap();
+        commit_staged_key_file(&data_dir).unwrap();
+
+        let reopened = PersistentStorage::new(data_dir.clone(), "new_password", Aes256Gcm).unwrap();
+        assert!(reopened.verify_encryption().ok);
+        assert!(!staged_data_file(&data_dir).exists());
+    }
+
+    #[tokio::test]
+    async fn test_xchacha_store_is_read_by_any_configured_cipher() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir.clone(), "pw", XChaCha20Poly1305).unwrap();
+        let data: StorageData = serde_json::from_str(VERSION_1_DATA).unwrap();
+        storage.save(&data).await.unwrap();
+        assert_eq!(storage.verify_encryption().algorithm, "XChaCha20-Poly1305");
+        assert!(storage.verify_encryption().ok);
+        drop(storage);
+
+        let raw = BASE64.decode(fs::read_to_string(data_dir.join(STORAGE_FILE)).unwrap());
+        assert_eq!(raw.unwrap()[0], CIPHER_VERSION_XCHACHA20_POLY1305);
+
+        // The version byte selects the cipher, whatever new writes use
+        let reopened = PersistentStorage::new(data_dir, "pw", Aes256Gcm).unwrap();
+        assert!(reopened.load().await.unwrap().accounts.contains_key("alice"));
+        let algorithm = reopened.reopen("pw").await.unwrap().verify_encryption().algorithm;
+        assert_eq!(algorithm, "AES-256-GCM");
+    }
+
+    #[tokio::test]
+    async fn test_second_instance_is_locked_out() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir.clone(), "test_password", Aes256Gcm).unwrap();
+
+        let second = PersistentStorage::new(data_dir.clone(), "test_password", Aes256Gcm);
+        assert!(matches!(second, Err(AuthError::StorageError(_))));
+
+        // Reopening shares the lock, which is only released once every instance is gone
+        let reopened = storage.reopen("test_password").await.unwrap();
+        drop(storage);
+        assert!(PersistentStorage::new(data_dir.clone(), "test_password", Aes256Gcm).is_err());
+        drop(reopened);
+        assert!(PersistentStorage::new(data_dir, "test_password", Aes256Gcm).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_verify_encryption() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir.clone(), "test_password", Aes256Gcm).unwrap();
+
+        let check = storage.verify_encryption();
+        assert!(check.ok);
+        assert!(!check.data_file_checked);
+
+        storage.save(&StorageData::new()).await.unwrap();
+        let before = fs::read(data_dir.join(STORAGE_FILE)).unwrap();
+        let check = storage.verify_encryption();
+        assert!(check.ok);
+        assert!(check.data_file_checked);
+        assert_eq!(check.algorithm, "AES-256-GCM");
+        assert_eq!(fs::read(data_dir.join(STORAGE_FILE)).unwrap(), before);
+
+        // Same salt, different password: the round-trip works but the data does not decrypt
+        let wrong = storage.reopen("wrong_password").await.unwrap();
+        let check = wrong.verify_encryption();
+        assert!(!check.ok);
+        assert!(check.error.unwrap().starts_with("Storage file does not decrypt"));
+
+        // A malformed key fails the round-trip itself
+        let mut bad_key = storage.reopen("test_password").await.unwrap();
+        bad_key.encryption_key = Zeroizing::new(vec![0u8; 7]);
+        let check = bad_key.verify_encryption();
+        assert!(!check.ok);
+        assert!(!check.data_file_checked);
+    }
+
+    #[tokio::test]
+    async fn test_save_records_write_metrics() {
+        let temp_dir = tempdir().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = PersistentStorage::new(data_dir, "test_password", Aes256Gcm)
+            .expect("Storage creation should succeed");
+        assert_eq!(storage.metrics().total_writes, 0);
+
+        for _ in 0..5 {
+            storage.save(&StorageData::new()).await.unwrap();
+        }
+
+        let metrics = storage.metrics();
+        assert_eq!(metrics.total_writes, 5);
+        assert_eq!(metrics.sample_count, 5);
+        assert!(metrics.min_ms > 0.0);
+        assert!(metrics.min_ms <= metrics.avg_ms && metrics.avg_ms <= metrics.max_ms);
+        assert!(metrics.max_ms < 10_000.0);
+    }
+
+    #[test]
+    fn test_write_metrics_are_bounded() {
+        let metrics = WriteMetrics::default();
+
+        for ms in 1..=(WRITE_SAMPLES as u64 + 10) {
+            metrics.record(Duration::from_millis(ms));
+        }
+
+        let summary = metrics.summary();
+        assert_eq!(summary.total_writes, WRITE_SAMPLES as u64 + 10);
+        assert_eq!(summary.sample_count, WRITE_SAMPLES);
+        // The oldest (fastest) samples were dropped
+        assert_eq!(summary.min_ms, 11.0);
+    }
 }
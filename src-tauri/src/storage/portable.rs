@@ -0,0 +1,345 @@
+/**
+ * Portable account/deck export
+ *
+ * Produces an unencrypted, documented list of accounts and their column layouts that
+ * other AT Protocol clients can consume. Only public identifiers are included: no
+ * tokens, emails or other secrets.
+ */
+
+use crate::storage::columns::load_columns;
+use crate::storage::StorageManager;
+use crate::types::{Account, ColumnType, ColumnWidth, DeckColumnConfig};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Schema identifier of the JSON export
+pub const PORTABLE_SCHEMA: &str = "taurisky.portable/1";
+
+/// Output format of `export_portable`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortableFormat {
+    /// JSON following `PORTABLE_SCHEMA`
+    Json,
+    /// OPML 2.0 flavored XML (one outline per account, nested outlines per column)
+    Opml,
+}
+
+/// JSON export document
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortableExport {
+    /// Always `PORTABLE_SCHEMA`
+    pub schema: &'static str,
+    /// Accounts, ordered by handle then DID
+    pub accounts: Vec<PortableAccount>,
+}
+
+/// Public account identifiers and deck layout
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortableAccount {
+    pub did: String,
+    pub handle: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    pub server_url: String,
+    /// Columns of the account, ordered by position
+    pub columns: Vec<PortableColumn>,
+}
+
+/// Column layout entry
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortableColumn {
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub position: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<ColumnWidth>,
+    /// Feed generator URI (feed columns only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed_uri: Option<String>,
+}
+
+impl PortableColumn {
+    fn from_config(column: &DeckColumnConfig) -> Self {
+        let feed_uri = column
+            .settings
+            .as_ref()
+            .and_then(|settings| settings.get("feedUri"))
+            .and_then(|uri| uri.as_str())
+            .map(str::to_string);
+
+        Self {
+            column_type: column.column_type.clone(),
+            title: column.title.clone(),
+            position: column.position,
+            width: column.width.clone(),
+            feed_uri,
+        }
+    }
+}
+
+/// Build the export document (deterministic ordering)
+///
+/// # Arguments
+/// * `accounts` - Stored accounts
+/// * `columns` - Deck columns of all accounts
+pub fn build_portable(accounts: &[Account], columns: &[DeckColumnConfig]) -> PortableExport {
+    let mut accounts: Vec<&Account> = accounts.iter().collect();
+    accounts.sort_by(|a, b| a.handle.cmp(&b.handle).then_with(|| a.did.cmp(&b.did)));
+
+    let accounts = accounts
+        .into_iter()
+        .map(|account| {
+            let mut own: Vec<&DeckColumnConfig> =
+                columns.iter().filter(|column| column.did == account.did).collect();
+            own.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.id.cmp(&b.id)));
+
+            PortableAccount {
+                did: account.did.clone(),
+                handle: account.handle.clone(),
+                display_name: account.display_name.clone(),
+                server_url: account.server_url.clone(),
+                columns: own.into_iter().map(PortableColumn::from_config).collect(),
+            }
+        })
+        .collect();
+
+    PortableExport {
+        schema: PORTABLE_SCHEMA,
+        accounts,
+    }
+}
+
+/// Escape text for use in an XML attribute value
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            // Other control characters are not allowed in XML 1.0
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Lowercase serde name of a unit enum variant (e.g., `ColumnType::Timeline` -> "timeline")
+fn variant_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Render the export document as OPML 2.0
+fn render_opml(export: &PortableExport) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<opml version=\"2.0\">\n");
+    xml.push_str("  <head>\n");
+    xml.push_str(&format!("    <title>{}</title>\n", PORTABLE_SCHEMA));
+    xml.push_str("  </head>\n");
+    xml.push_str("  <body>\n");
+
+    for account in &export.accounts {
+        let text = account.display_name.as_deref().unwrap_or(&account.handle);
+        xml.push_str(&format!(
+            "    <outline type=\"account\" text=\"{}\" handle=\"{}\" did=\"{}\" \
+             serverUrl=\"{}\">\n",
+            escape_xml(text),
+            escape_xml(&account.handle),
+            escape_xml(&account.did),
+            escape_xml(&account.server_url)
+        ));
+
+        for column in &account.columns {
+            let column_type = variant_name(&column.column_type);
+            let mut attributes = format!(
+                "type=\"column\" text=\"{}\" columnType=\"{}\" position=\"{}\"",
+                escape_xml(column.title.as_deref().unwrap_or(&column_type)),
+                column_type,
+                column.position
+            );
+            if let Some(width) = &column.width {
+                attributes.push_str(&format!(" width=\"{}\"", variant_name(width)));
+            }
+            if let Some(feed_uri) = &column.feed_uri {
+                attributes.push_str(&format!(" feedUri=\"{}\"", escape_xml(feed_uri)));
+            }
+            xml.push_str(&format!("      <outline {} />\n", attributes));
+        }
+
+        xml.push_str("    </outline>\n");
+    }
+
+    xml.push_str("  </body>\n");
+    xml.push_str("</opml>\n");
+    xml
+}
+
+/// Render accounts and columns in the given portable format
+///
+/// # Arguments
+/// * `accounts` - Stored accounts
+/// * `columns` - Deck columns of all accounts
+/// * `format` - Output format
+pub fn render_portable(
+    accounts: &[Account],
+    columns: &[DeckColumnConfig],
+    format: PortableFormat,
+) -> Result<String, String> {
+    let export = build_portable(accounts, columns);
+
+    match format {
+        PortableFormat::Json => serde_json::to_string_pretty(&export)
+            .map_err(|e| format!("Failed to serialize portable export: {}", e)),
+        PortableFormat::Opml => Ok(render_opml(&export)),
+    }
+}
+
+/// Export all accounts and their columns in a portable format
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory (columns file location)
+/// * `format` - Output format
+///
+/// # Note
+/// Output is deterministic for the same stored data (no timestamps are included).
+pub async fn export_portable(
+    storage: &StorageManager,
+    data_dir: &Path,
+    format: PortableFormat,
+) -> Result<String, String> {
+    let accounts = storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?;
+    let columns = load_columns(&data_dir.to_path_buf())?;
+
+    render_portable(&accounts, &columns, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+    use std::collections::HashMap;
+
+    fn account(handle: &str, did: &str) -> Account {
+        Account {
+            did: did.to_string(),
+            handle: handle.to_string(),
+            email: Some("secret@example.com".to_string()),
+            display_name: Some("Tom & \"Jerry\" <3".to_string()),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            last_used_at: "2024-01-01T00:00:00Z".to_string(),
+            note: Some("private note".to_string()),
+            ..test_support::account(&format!("id-{}", handle))
+        }
+    }
+
+    fn column(did: &str, position: u32, column_type: ColumnType) -> DeckColumnConfig {
+        let settings = (column_type == ColumnType::Feed).then(|| {
+            HashMap::from([(
+                "feedUri".to_string(),
+                serde_json::json!("at://did:plc:gen/app.bsky.feed.generator/a&b"),
+            )])
+        });
+        DeckColumnConfig {
+            id: format!("col-{}", position),
+            did: did.to_string(),
+            column_type,
+            title: None,
+            position,
+            width: Some(ColumnWidth::Medium),
+            settings,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn fixture() -> (Vec<Account>, Vec<DeckColumnConfig>) {
+        let accounts = vec![account("zed.test", "did:plc:zed"), account("amy.test", "did:plc:amy")];
+        let columns = vec![
+            column("did:plc:amy", 1, ColumnType::Feed),
+            column("did:plc:amy", 0, ColumnType::Timeline),
+            column("did:plc:zed", 0, ColumnType::Notifications),
+        ];
+        (accounts, columns)
+    }
+
+    #[test]
+    fn test_json_export_omits_secrets() {
+        let (accounts, columns) = fixture();
+
+        let json = render_portable(&accounts, &columns, PortableFormat::Json).unwrap();
+
+        for secret in ["email", "secret@example.com", "accessJwt", "refreshJwt", "note", "id-"] {
+            assert!(!json.contains(secret), "export leaked {}", secret);
+        }
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema"], PORTABLE_SCHEMA);
+        assert_eq!(value["accounts"][0]["handle"], "amy.test");
+        assert_eq!(value["accounts"][0]["columns"][0]["type"], "timeline");
+        assert_eq!(
+            value["accounts"][0]["columns"][1]["feedUri"],
+            "at://did:plc:gen/app.bsky.feed.generator/a&b"
+        );
+
+        // Deterministic regardless of input order
+        let (mut accounts, mut columns) = fixture();
+        accounts.reverse();
+        columns.reverse();
+        assert_eq!(render_portable(&accounts, &columns, PortableFormat::Json).unwrap(), json);
+    }
+
+    #[test]
+    fn test_opml_export_is_well_formed() {
+        let (accounts, columns) = fixture();
+
+        let xml = render_portable(&accounts, &columns, PortableFormat::Opml).unwrap();
+
+        let mut reader = Reader::from_str(&xml);
+        let mut outlines = 0;
+        loop {
+            match reader.read_event() {
+                Ok(Event::Eof) => break,
+                Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.name().as_ref() == b"outline" => {
+                    for attribute in e.attributes() {
+                        attribute.unwrap().unescape_value().unwrap();
+                    }
+                    outlines += 1;
+                }
+                Ok(_) => {}
+                Err(e) => panic!("malformed OPML: {}", e),
+            }
+        }
+        assert_eq!(outlines, 5);
+        assert!(!xml.contains("secret@example.com"));
+        assert!(xml.contains("Tom &amp; &quot;Jerry&quot; &lt;3"));
+    }
+
+    #[test]
+    fn test_format_is_validated() {
+        assert_eq!(
+            serde_json::from_str::<PortableFormat>("\"opml\"").unwrap(),
+            PortableFormat::Opml
+        );
+        assert!(serde_json::from_str::<PortableFormat>("\"csv\"").is_err());
+    }
+}
@@ -0,0 +1,519 @@
+/**
+ * Column preset export/import
+ *
+ * A preset is a versioned, shareable snapshot of the deck columns. Imported presets
+ * come from outside the app, so every column is validated before anything is applied.
+ */
+
+use crate::storage::columns::{
+    account_dids, ensure_known_dids, load_columns, lock_columns, save_columns,
+};
+use crate::storage::StorageManager;
+use crate::types::{ColumnType, ColumnWidth, DeckColumnConfig};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Current columns preset format version
+pub const COLUMNS_PRESET_VERSION: u32 = 1;
+
+/// Maximum number of columns a preset may contain
+pub(crate) const MAX_PRESET_COLUMNS: usize = 50;
+
+/// Maximum serialized size of a single column's settings (16 KiB)
+const MAX_COLUMN_SETTINGS_BYTES: usize = 16 * 1024;
+
+/// Maximum column title length (characters); longer titles are truncated
+const MAX_COLUMN_TITLE_CHARS: usize = 100;
+
+/// Exported columns preset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnsPreset {
+    /// Preset format version
+    pub version: u32,
+    /// Column configurations
+    pub columns: Vec<DeckColumnConfig>,
+}
+
+/// How serious a preset validation issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    /// The value is fixed up during canonicalization
+    Warning,
+    /// The preset cannot be imported
+    Fatal,
+}
+
+/// A single problem found in a preset
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetIssue {
+    /// Index of the offending column (None for preset-level issues)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column_index: Option<usize>,
+    /// Offending field (e.g., "type", "settings.feedUri")
+    pub field: String,
+    /// Human-readable description
+    pub message: String,
+    /// Issue severity
+    pub severity: IssueSeverity,
+}
+
+/// Result of validating a preset without applying it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetValidation {
+    /// Whether the preset can be imported (no fatal issues)
+    pub valid: bool,
+    /// Every issue found
+    pub issues: Vec<PresetIssue>,
+    /// Canonicalized columns (empty when the preset is not valid)
+    pub columns: Vec<DeckColumnConfig>,
+}
+
+impl PresetValidation {
+    /// Summary of the fatal issues, for error messages
+    fn fatal_summary(&self) -> String {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == IssueSeverity::Fatal)
+            .map(|issue| match issue.column_index {
+                Some(index) => format!("columns[{}].{}: {}", index, issue.field, issue.message),
+                None => format!("{}: {}", issue.field, issue.message),
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Check that a string looks like an AT-URI (at://authority[/collection[/rkey]])
+fn is_valid_at_uri(uri: &str) -> bool {
+    let Some(rest) = uri.strip_prefix("at://") else {
+        return false;
+    };
+
+    let mut segments = rest.split('/');
+    let authority_ok = segments.next().is_some_and(|authority| {
+        !authority.is_empty()
+            && (authority.starts_with("did:") || authority.contains('.'))
+            && !authority.chars().any(|c| c.is_whitespace())
+    });
+
+    authority_ok
+        && uri.len() <= 8 * 1024
+        && segments.all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || ".-_:~".contains(c))
+        })
+}
+
+/// Check that a string looks like a DID (did:method:identifier)
+fn is_valid_did(did: &str) -> bool {
+    let mut parts = did.splitn(3, ':');
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some("did"), Some(method), Some(id))
+            if !method.is_empty()
+                && method.chars().all(|c| c.is_ascii_lowercase())
+                && !id.is_empty()
+                && !id.chars().any(|c| c.is_whitespace())
+    )
+}
+
+/// Validate a single raw column, returning its canonical form if it has no fatal issue
+fn validate_column(
+    index: usize,
+    raw: &Value,
+    issues: &mut Vec<PresetIssue>,
+) -> Option<DeckColumnConfig> {
+    let mut issue = |field: &str, message: String, severity: IssueSeverity| {
+        issues.push(PresetIssue {
+            column_index: Some(index),
+            field: field.to_string(),
+            message,
+            severity,
+        });
+    };
+    let mut fatal = false;
+
+    let Some(object) = raw.as_object() else {
+        issue("", "column must be an object".to_string(), IssueSeverity::Fatal);
+        return None;
+    };
+
+    let column_type = match object.get("type").cloned() {
+        Some(value) => match serde_json::from_value::<ColumnType>(value.clone()) {
+            Ok(column_type) => Some(column_type),
+            Err(_) => {
+                issue("type", format!("unknown column type {}", value), IssueSeverity::Fatal);
+                None
+            }
+        },
+        None => {
+            issue("type", "missing column type".to_string(), IssueSeverity::Fatal);
+            None
+        }
+    };
+
+    let did = match object.get("did").and_then(Value::as_str).map(str::trim) {
+        Some(did) if is_valid_did(did) => Some(did.to_string()),
+        Some(did) => {
+            issue("did", format!("invalid DID '{}'", did), IssueSeverity::Fatal);
+            None
+        }
+        None => {
+            issue("did", "missing account DID".to_string(), IssueSeverity::Fatal);
+            None
+        }
+    };
+
+    let title = match object.get("title") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(title)) => {
+            let title = title.trim();
+            if title.chars().count() > MAX_COLUMN_TITLE_CHARS {
+                issue(
+                    "title",
+                    format!(
+                        "title longer than {} characters is truncated",
+                        MAX_COLUMN_TITLE_CHARS
+                    ),
+                    IssueSeverity::Warning,
+                );
+            }
+            let title: String = title.chars().take(MAX_COLUMN_TITLE_CHARS).collect();
+            (!title.is_empty()).then_some(title)
+        }
+        Some(_) => {
+            issue("title", "title must be a string".to_string(), IssueSeverity::Fatal);
+            fatal = true;
+            None
+        }
+    };
+
+    let width = match object.get("width") {
+        None | Some(Value::Null) => Some(ColumnWidth::Medium),
+        Some(value) => match serde_json::from_value::<ColumnWidth>(value.clone()) {
+            Ok(width) => Some(width),
+            Err(_) => {
+                issue(
+                    "width",
+                    format!("unknown width {} is reset to medium", value),
+                    IssueSeverity::Warning,
+                );
+                Some(ColumnWidth::Medium)
+            }
+        },
+    };
+
+    let settings = match object.get("settings") {
+        None | Some(Value::Null) => None,
+        Some(Value::Object(settings)) => {
+            let size = Value::Object(settings.clone()).to_string().len();
+            if size > MAX_COLUMN_SETTINGS_BYTES {
+                issue(
+                    "settings",
+                    format!(
+                        "settings are {} bytes (maximum {})",
+                        size, MAX_COLUMN_SETTINGS_BYTES
+                    ),
+                    IssueSeverity::Fatal,
+                );
+                fatal = true;
+            }
+
+            for (key, value) in settings {
+                if !key.to_ascii_lowercase().ends_with("uri") {
+                    continue;
+                }
+                match value.as_str() {
+                    Some(uri) if is_valid_at_uri(uri) => {}
+                    _ => {
+                        issue(
+                            &format!("settings.{}", key),
+                            format!("invalid AT-URI {}", value),
+                            IssueSeverity::Fatal,
+                        );
+                        fatal = true;
+                    }
+                }
+            }
+
+            Some(settings.clone().into_iter().collect())
+        }
+        Some(_) => {
+            issue("settings", "settings must be an object".to_string(), IssueSeverity::Fatal);
+            fatal = true;
+            None
+        }
+    };
+
+    let (Some(column_type), Some(did)) = (column_type, did) else {
+        return None;
+    };
+    if fatal {
+        return None;
+    }
+
+    let now = Utc::now().to_rfc3339();
+    Some(DeckColumnConfig {
+        id: Uuid::new_v4().to_string(),
+        did,
+        column_type,
+        title,
+        position: index as u32,
+        width,
+        settings,
+        created_at: now.clone(),
+        updated_at: now,
+    })
+}
+
+/// Validate and canonicalize a columns preset without applying it
+///
+/// Canonical columns get fresh IDs and timestamps and contiguous positions in
+/// preset order. All issues are collected rather than stopping at the first one.
+///
+/// # Arguments
+/// * `preset` - Preset JSON as produced by `export_columns_preset`
+pub fn validate_columns_preset(preset: &str) -> PresetValidation {
+    let mut issues = Vec::new();
+    let preset_issue = |field: &str, message: String| PresetIssue {
+        column_index: None,
+        field: field.to_string(),
+        message,
+        severity: IssueSeverity::Fatal,
+    };
+
+    let invalid = |issues| PresetValidation {
+        valid: false,
+        issues,
+        columns: Vec::new(),
+    };
+
+    let value: Value = match serde_json::from_str(preset) {
+        Ok(value) => value,
+        Err(e) => return invalid(vec![preset_issue("", format!("invalid JSON: {}", e))]),
+    };
+
+    match value.get("version").and_then(Value::as_u64) {
+        Some(version) if version <= COLUMNS_PRESET_VERSION as u64 => {}
+        Some(version) => issues.push(preset_issue(
+            "version",
+            format!(
+                "preset version {} is newer than supported version {}",
+                version, COLUMNS_PRESET_VERSION
+            ),
+        )),
+        None => issues.push(preset_issue("version", "missing preset version".to_string())),
+    }
+
+    let Some(raw_columns) = value.get("columns").and_then(Value::as_array) else {
+        issues.push(preset_issue("columns", "missing columns array".to_string()));
+        return invalid(issues);
+    };
+
+    if raw_columns.is_empty() {
+        issues.push(preset_issue("columns", "at least one column is required".to_string()));
+    }
+    if raw_columns.len() > MAX_PRESET_COLUMNS {
+        issues.push(preset_issue(
+            "columns",
+            format!(
+                "preset has {} columns (maximum {})",
+                raw_columns.len(),
+                MAX_PRESET_COLUMNS
+            ),
+        ));
+    }
+
+    let columns: Vec<Option<DeckColumnConfig>> = raw_columns
+        .iter()
+        .enumerate()
+        .map(|(index, raw)| validate_column(index, raw, &mut issues))
+        .collect();
+
+    if issues.iter().any(|issue| issue.severity == IssueSeverity::Fatal) {
+        return invalid(issues);
+    }
+
+    PresetValidation {
+        valid: true,
+        issues,
+        columns: columns.into_iter().flatten().collect(),
+    }
+}
+
+/// Export the current deck columns as a preset
+pub fn export_columns_preset(data_dir: &Path) -> Result<String, String> {
+    let preset = ColumnsPreset {
+        version: COLUMNS_PRESET_VERSION,
+        columns: load_columns(&data_dir.to_path_buf())?,
+    };
+
+    serde_json::to_string_pretty(&preset).map_err(|e| format!("Failed to serialize preset: {}", e))
+}
+
+/// Validate a preset and replace the deck columns with it
+///
+/// Nothing is saved if the preset has any fatal issue or a column whose DID matches
+/// no stored account.
+pub async fn import_columns_preset(
+    storage: &StorageManager,
+    data_dir: &Path,
+    preset: &str,
+) -> Result<Vec<DeckColumnConfig>, String> {
+    let validation = validate_columns_preset(preset);
+
+    if !validation.valid {
+        return Err(format!("Invalid columns preset: {}", validation.fatal_summary()));
+    }
+    ensure_known_dids(&account_dids(storage).await?, &validation.columns)?;
+
+    let _guard = lock_columns()?;
+    save_columns(&data_dir.to_path_buf(), validation.columns.clone())?;
+    Ok(validation.columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate_clean_preset() {
+        let preset = json!({
+            "version": 1,
+            "columns": [
+                { "id": "a", "did": "did:plc:alice", "type": "timeline", "position": 3 },
+                {
+                    "id": "b",
+                    "did": "did:plc:alice",
+                    "type": "notifications",
+                    "title": "  Mentions  ",
+                    "width": "xs",
+                    "settings": { "feedUri": "at://did:plc:alice/app.bsky.feed.generator/cats" }
+                }
+            ]
+        })
+        .to_string();
+
+        let validation = validate_columns_preset(&preset);
+
+        assert!(validation.valid);
+        assert!(validation.issues.is_empty());
+        assert_eq!(validation.columns.len(), 2);
+        assert_eq!(validation.columns[0].position, 0);
+        assert_eq!(validation.columns[0].width, Some(ColumnWidth::Medium));
+        assert_eq!(validation.columns[1].position, 1);
+        assert_eq!(validation.columns[1].title.as_deref(), Some("Mentions"));
+        assert_ne!(validation.columns[0].id, "a");
+    }
+
+    #[test]
+    fn test_validate_preset_reports_every_failure() {
+        let preset = json!({
+            "version": 1,
+            "columns": [
+                { "did": "did:plc:alice", "type": "bogus" },
+                { "did": "not-a-did", "type": "timeline" },
+                {
+                    "did": "did:plc:alice",
+                    "type": "timeline",
+                    "settings": { "listUri": "https://example.com/list" }
+                },
+                {
+                    "did": "did:plc:alice",
+                    "type": "timeline",
+                    "settings": { "blob": "x".repeat(MAX_COLUMN_SETTINGS_BYTES) }
+                },
+                { "did": "did:plc:alice", "type": "timeline", "width": "huge" }
+            ]
+        })
+        .to_string();
+
+        let validation = validate_columns_preset(&preset);
+
+        assert!(!validation.valid);
+        assert!(validation.columns.is_empty());
+
+        let fatal: Vec<(Option<usize>, &str)> = validation
+            .issues
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Fatal)
+            .map(|i| (i.column_index, i.field.as_str()))
+            .collect();
+        assert_eq!(
+            fatal,
+            vec![
+                (Some(0), "type"),
+                (Some(1), "did"),
+                (Some(2), "settings.listUri"),
+                (Some(3), "settings"),
+            ]
+        );
+        assert!(validation
+            .issues
+            .iter()
+            .any(|i| i.column_index == Some(4) && i.severity == IssueSeverity::Warning));
+    }
+
+    #[test]
+    fn test_validate_preset_rejects_too_many_columns() {
+        let columns: Vec<Value> = (0..=MAX_PRESET_COLUMNS)
+            .map(|_| json!({ "did": "did:plc:alice", "type": "timeline" }))
+            .collect();
+        let preset = json!({ "version": 1, "columns": columns }).to_string();
+
+        let validation = validate_columns_preset(&preset);
+
+        assert!(!validation.valid);
+        assert!(validation
+            .issues
+            .iter()
+            .any(|i| i.column_index.is_none() && i.field == "columns"));
+    }
+
+    #[tokio::test]
+    async fn test_import_refuses_invalid_preset() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let preset = json!({
+            "version": 1,
+            "columns": [{ "did": "did:plc:alice", "type": "bogus" }]
+        })
+        .to_string();
+
+        let err = import_columns_preset(&storage, temp_dir.path(), &preset).await.unwrap_err();
+
+        assert!(err.contains("columns[0].type"));
+        assert!(load_columns(&temp_dir.path().to_path_buf()).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage = crate::test_support::storage_with_account(&data_dir).await;
+
+        let preset = export_columns_preset(&data_dir).unwrap();
+        let imported = import_columns_preset(&storage, &data_dir, &preset).await.unwrap();
+
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].did, "did:plc:alice");
+        assert_eq!(load_columns(&data_dir).unwrap()[0].id, imported[0].id);
+
+        // Columns of accounts that are not logged in are refused
+        let foreign = preset.replace("did:plc:alice", "did:plc:gone");
+        let err = import_columns_preset(&storage, &data_dir, &foreign).await.unwrap_err();
+        assert!(err.contains("did:plc:gone"));
+        assert_eq!(load_columns(&data_dir).unwrap()[0].id, imported[0].id);
+    }
+}
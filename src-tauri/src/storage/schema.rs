@@ -0,0 +1,461 @@
+/**
+ * Schema version checks for on-disk files
+ *
+ * Reports the schema version of each persisted file and migrates outdated ones
+ * in a single coordinated pass
+ */
+
+use crate::storage::columns::{migrate_legacy_columns, COLUMNS_FILE};
+use crate::storage::persistence::{STORAGE_FILE, STORAGE_VERSION};
+use crate::storage::settings::SETTINGS_FILE;
+use crate::storage::StorageManager;
+use crate::types::{DeckColumnConfig, SETTINGS_VERSION};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Current columns file schema version (columns keyed by account DID)
+///
+/// Version 1 files are a bare JSON array; `migrate_legacy_columns` converts them.
+pub const COLUMNS_VERSION: u32 = 2;
+
+/// Schema status of a single file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSchemaStatus {
+    /// File name inside the data directory
+    pub file: String,
+    /// Whether the file exists
+    pub present: bool,
+    /// Detected schema version (before migration)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Schema version this build writes
+    pub current_version: u32,
+    /// Whether the file was migrated during this check
+    pub migrated: bool,
+    /// Problem preventing use or migration of the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// Result of a schema check over all files
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaReport {
+    /// Per-file status
+    pub files: Vec<FileSchemaStatus>,
+    /// Whether migrations were skipped because a file is unsupported
+    pub migrations_skipped: bool,
+}
+
+/// A pending migration of one file
+enum PlannedMigration {
+    /// Replace a JSON file with its migrated contents
+    Rewrite { file: &'static str, contents: String },
+    /// Convert the legacy flat columns file (see `migrate_legacy_columns`)
+    LegacyColumns,
+    /// Write the store back as `STORAGE_VERSION` (`load` only migrates it in memory)
+    Store,
+}
+
+/// Status of one file and its pending migration
+type FilePlan = (FileSchemaStatus, Option<PlannedMigration>);
+
+/// Inspect the settings file and plan its migration
+fn check_settings(data_dir: &Path) -> FilePlan {
+    let mut status = FileSchemaStatus {
+        file: SETTINGS_FILE.to_string(),
+        present: false,
+        version: None,
+        current_version: SETTINGS_VERSION,
+        migrated: false,
+        warning: None,
+    };
+
+    let Some(mut value) = read_json(data_dir, SETTINGS_FILE, &mut status) else {
+        return (status, None);
+    };
+
+    let Some(object) = value.as_object_mut() else {
+        status.warning = Some("Settings file is not a JSON object".to_string());
+        return (status, None);
+    };
+
+    // Files written before versioning have no version field
+    let version = object.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    status.version = Some(version);
+
+    if version > SETTINGS_VERSION {
+        status.warning = Some(format!(
+            "Settings version {} is newer than supported version {}",
+            version, SETTINGS_VERSION
+        ));
+        return (status, None);
+    }
+
+    if version == SETTINGS_VERSION {
+        return (status, None);
+    }
+
+    // v0 -> v1: only the version field was added
+    object.insert("version".to_string(), Value::from(SETTINGS_VERSION));
+
+    let migration = serde_json::to_string_pretty(&value)
+        .ok()
+        .map(|contents| PlannedMigration::Rewrite {
+            file: SETTINGS_FILE,
+            contents,
+        });
+    (status, migration)
+}
+
+/// Inspect the columns file and plan the conversion of a legacy flat file
+fn check_columns(data_dir: &Path) -> FilePlan {
+    let mut status = FileSchemaStatus {
+        file: COLUMNS_FILE.to_string(),
+        present: false,
+        version: None,
+        current_version: COLUMNS_VERSION,
+        migrated: false,
+        warning: None,
+    };
+
+    let Some(value) = read_json(data_dir, COLUMNS_FILE, &mut status) else {
+        return (status, None);
+    };
+
+    let version = match &value {
+        Value::Array(_) => Some(1),
+        Value::Object(object) => object
+            .get("version")
+            .and_then(Value::as_u64)
+            .map(|v| v as u32),
+        _ => None,
+    };
+
+    status.version = version;
+
+    let migration = match version {
+        Some(version) if version > COLUMNS_VERSION => {
+            status.warning = Some(format!(
+                "Columns version {} is newer than supported version {}",
+                version, COLUMNS_VERSION
+            ));
+            None
+        }
+        // Parsed up front so a bad legacy file blocks the plan instead of failing midway
+        Some(1) => match serde_json::from_value::<Vec<DeckColumnConfig>>(value) {
+            Ok(_) => Some(PlannedMigration::LegacyColumns),
+            Err(e) => {
+                status.warning = Some(format!("Failed to parse legacy columns: {}", e));
+                None
+            }
+        },
+        Some(_) => None,
+        None => {
+            status.warning = Some("Unrecognized columns file format".to_string());
+            None
+        }
+    };
+
+    (status, migration)
+}
+
+/// Inspect the encrypted store's version and plan its rewrite
+///
+/// # Arguments
+/// * `stored_version` - Version read from the decrypted store (None: no storage file)
+fn check_storage(stored_version: Result<Option<u32>, String>) -> FilePlan {
+    let mut status = FileSchemaStatus {
+        file: STORAGE_FILE.to_string(),
+        present: false,
+        version: None,
+        current_version: STORAGE_VERSION,
+        migrated: false,
+        warning: None,
+    };
+
+    let mut migration = None;
+    match stored_version {
+        Ok(None) => {}
+        Ok(Some(version)) => {
+            status.present = true;
+            status.version = Some(version);
+            if version > STORAGE_VERSION {
+                status.warning = Some(format!(
+                    "Storage version {} is newer than supported version {}",
+                    version, STORAGE_VERSION
+                ));
+            } else if version < STORAGE_VERSION {
+                migration = Some(PlannedMigration::Store);
+            }
+        }
+        Err(e) => {
+            status.present = true;
+            status.warning = Some(e);
+        }
+    }
+
+    (status, migration)
+}
+
+/// Read and parse a JSON file, recording presence and parse failures in `status`
+fn read_json(data_dir: &Path, file: &str, status: &mut FileSchemaStatus) -> Option<Value> {
+    let path = data_dir.join(file);
+    if !path.exists() {
+        return None;
+    }
+    status.present = true;
+
+    let parsed = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read file: {}", e))
+        .and_then(|content| {
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse JSON: {}", e))
+        });
+
+    match parsed {
+        Ok(value) => Some(value),
+        Err(e) => {
+            status.warning = Some(e);
+            None
+        }
+    }
+}
+
+/// Write a file atomically (temp file + rename)
+fn write_atomic(data_dir: &Path, file: &str, contents: &str) -> Result<(), String> {
+    let path = data_dir.join(file);
+    let temp_path = data_dir.join(format!("{}.tmp", file));
+
+    fs::write(&temp_path, contents).map_err(|e| format!("Failed to write temp file: {}", e))?;
+    fs::rename(&temp_path, &path).map_err(|e| format!("Failed to rename temp file: {}", e))
+}
+
+/// Inspect every file and plan the migrations of the outdated ones
+///
+/// # Returns
+/// The per-file plans (settings, columns, store) and whether migrations must be
+/// skipped because a file is unreadable or newer than this build supports
+///
+/// # Note
+/// A store that can't be read (e.g., still locked) is reported with a warning but
+/// doesn't block the other migrations; only a store newer than this build does.
+fn plan_migrations(
+    data_dir: &Path,
+    stored_version: Result<Option<u32>, String>,
+) -> (Vec<FilePlan>, bool) {
+    let settings = check_settings(data_dir);
+    let columns = check_columns(data_dir);
+    let storage = check_storage(stored_version);
+
+    let storage_newer = storage.0.version.is_some_and(|version| version > STORAGE_VERSION);
+    let migrations_skipped =
+        settings.0.warning.is_some() || columns.0.warning.is_some() || storage_newer;
+
+    (vec![settings, columns, storage], migrations_skipped)
+}
+
+/// Check the schema version of every persisted file and migrate outdated ones
+///
+/// All files are inspected before anything is written. If any file is unreadable or
+/// newer than this build supports, no migration is applied, so the data directory is
+/// never left half-migrated.
+///
+/// # Arguments
+/// * `data_dir` - Data directory
+/// * `storage` - Storage manager (reports and rewrites the encrypted store)
+///
+/// # Note
+/// The store is rewritten first and the settings last: the legacy columns conversion
+/// reads the store's accounts.
+pub async fn check_schema_versions(
+    data_dir: &Path,
+    storage: &StorageManager,
+) -> Result<SchemaReport, String> {
+    let stored_version = storage
+        .stored_schema_version()
+        .await
+        .map_err(|e| format!("Failed to read storage version: {}", e));
+    let (mut plans, migrations_skipped) = plan_migrations(data_dir, stored_version);
+
+    if !migrations_skipped {
+        for (status, migration) in plans.iter_mut().rev() {
+            let Some(migration) = migration.take() else {
+                continue;
+            };
+            status.migrated = match migration {
+                PlannedMigration::Store => {
+                    storage
+                        .rewrite_store()
+                        .await
+                        .map_err(|e| format!("Failed to rewrite storage: {}", e))?;
+                    true
+                }
+                PlannedMigration::LegacyColumns => {
+                    migrate_legacy_columns(storage, &data_dir.to_path_buf()).await?.migrated
+                }
+                PlannedMigration::Rewrite { file, contents } => {
+                    write_atomic(data_dir, file, &contents)?;
+                    true
+                }
+            };
+        }
+    }
+
+    Ok(SchemaReport {
+        files: plans.into_iter().map(|(status, _)| status).collect(),
+        migrations_skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::columns::{get_default_columns, load_columns};
+    use crate::storage::settings::load_settings;
+    use tempfile::TempDir;
+
+    fn status<'a>(report: &'a SchemaReport, file: &str) -> &'a FileSchemaStatus {
+        report.files.iter().find(|f| f.file == file).unwrap()
+    }
+
+    /// Write a store from before versioning (no `version` field) holding account "alice"
+    async fn seed_unversioned_store(data_dir: &Path) {
+        let storage = crate::test_support::storage_with_account(data_dir).await;
+        storage.cache.lock().unwrap().version = 0;
+        storage.persist().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mixed_versions_are_reported_and_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+
+        // Unversioned (v0) settings and store next to a v1 (flat) columns file
+        fs::write(
+            data_dir.join(SETTINGS_FILE),
+            r#"{"defaultServerUrl":"https://pds.example.com"}"#,
+        )
+        .unwrap();
+        seed_unversioned_store(&data_dir).await;
+        let legacy_columns = get_default_columns("did:plc:alice");
+        fs::write(
+            data_dir.join(COLUMNS_FILE),
+            serde_json::to_string(&legacy_columns).unwrap(),
+        )
+        .unwrap();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+
+        let report = check_schema_versions(&data_dir, &storage).await.unwrap();
+        assert!(!report.migrations_skipped);
+
+        let settings = status(&report, SETTINGS_FILE);
+        assert_eq!(settings.version, Some(0));
+        assert!(settings.migrated);
+
+        let columns = status(&report, COLUMNS_FILE);
+        assert_eq!(columns.version, Some(1));
+        assert!(columns.migrated);
+
+        let store = status(&report, STORAGE_FILE);
+        assert_eq!(store.version, Some(0));
+        assert!(store.migrated);
+
+        let loaded = load_settings(&data_dir).unwrap();
+        assert_eq!(loaded.version, SETTINGS_VERSION);
+        assert_eq!(
+            loaded.default_server_url.as_deref(),
+            Some("https://pds.example.com")
+        );
+        let file: Value =
+            serde_json::from_str(&fs::read_to_string(data_dir.join(COLUMNS_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(file["version"], COLUMNS_VERSION);
+        assert_eq!(load_columns(&data_dir).unwrap().len(), legacy_columns.len());
+        assert_eq!(storage.stored_schema_version().await.unwrap(), Some(STORAGE_VERSION));
+        assert!(storage.get_account("alice").await.is_ok());
+
+        // Second pass has nothing left to migrate
+        let report = check_schema_versions(&data_dir, &storage).await.unwrap();
+        assert!(report.files.iter().all(|f| !f.migrated));
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_file_blocks_all_migrations() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+
+        let legacy_settings = r#"{"defaultServerUrl":"https://pds.example.com"}"#;
+        fs::write(data_dir.join(SETTINGS_FILE), legacy_settings).unwrap();
+        seed_unversioned_store(&data_dir).await;
+        fs::write(
+            data_dir.join(COLUMNS_FILE),
+            r#"{"version":99,"columns":[]}"#,
+        )
+        .unwrap();
+        let storage = StorageManager::new(data_dir.clone()).await.unwrap();
+
+        let report = check_schema_versions(&data_dir, &storage).await.unwrap();
+        assert!(report.migrations_skipped);
+        assert!(status(&report, COLUMNS_FILE).warning.is_some());
+        assert!(!status(&report, SETTINGS_FILE).migrated);
+        assert!(!status(&report, STORAGE_FILE).migrated);
+
+        // Settings and store were left untouched
+        let content = fs::read_to_string(data_dir.join(SETTINGS_FILE)).unwrap();
+        assert_eq!(content, legacy_settings);
+        assert_eq!(storage.stored_schema_version().await.unwrap(), Some(0));
+
+        // A legacy columns file that doesn't parse blocks them as well
+        fs::write(data_dir.join(COLUMNS_FILE), r#"[{"id":"broken"}]"#).unwrap();
+        let report = check_schema_versions(&data_dir, &storage).await.unwrap();
+        assert!(report.migrations_skipped);
+        assert_eq!(status(&report, COLUMNS_FILE).version, Some(1));
+        assert!(report.files.iter().all(|f| !f.migrated));
+    }
+
+    #[tokio::test]
+    async fn test_missing_files_reported_absent() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageManager::new(temp_dir.path().to_path_buf()).await.unwrap();
+
+        let report = check_schema_versions(temp_dir.path(), &storage).await.unwrap();
+
+        assert_eq!(report.files.len(), 3);
+        assert!(report.files.iter().all(|f| !f.present && f.version.is_none()));
+        assert!(!report.migrations_skipped);
+    }
+
+    #[tokio::test]
+    async fn test_storage_version_is_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let legacy_settings = r#"{"defaultServerUrl":"https://pds.example.com"}"#;
+        fs::write(data_dir.join(SETTINGS_FILE), legacy_settings).unwrap();
+
+        let storage = crate::test_support::storage_with_account(&data_dir).await;
+        let report = check_schema_versions(&data_dir, &storage).await.unwrap();
+
+        let store = status(&report, STORAGE_FILE);
+        assert!(store.present);
+        assert_eq!(store.version, Some(STORAGE_VERSION));
+        assert!(store.warning.is_none());
+        assert!(!store.migrated);
+        assert!(status(&report, SETTINGS_FILE).migrated);
+
+        // A store from a newer build blocks the other migrations
+        fs::write(data_dir.join(SETTINGS_FILE), legacy_settings).unwrap();
+        let (plans, skipped) = plan_migrations(&data_dir, Ok(Some(STORAGE_VERSION + 1)));
+        assert!(skipped);
+        assert!(plans[2].0.warning.is_some());
+
+        // An unreadable (locked) store is reported but doesn't block them
+        let (plans, skipped) = plan_migrations(&data_dir, Err("locked".to_string()));
+        assert!(!skipped);
+        assert_eq!(plans[2].0.warning.as_deref(), Some("locked"));
+        assert!(plans[2].1.is_none());
+        assert!(matches!(plans[0].1, Some(PlannedMigration::Rewrite { .. })));
+    }
+}
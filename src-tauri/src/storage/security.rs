@@ -0,0 +1,220 @@
+/**
+ * Encryption settings audit
+ *
+ * Checks the live store for known weaknesses (built-in password, weak key derivation,
+ * unprotected key, undecryptable data) and turns them into actionable findings for the
+ * security screen.
+ */
+
+use crate::storage::keyfile::KdfParams;
+use crate::storage::persistence::{EncryptionCheck, PersistentStorage};
+use serde::Serialize;
+use std::cmp::Reverse;
+
+/// Minimum Argon2id memory cost in KiB (OWASP recommendation)
+pub const MIN_KDF_MEMORY_KIB: u32 = 19 * 1024;
+
+/// Minimum Argon2id iterations (OWASP recommendation for 19 MiB)
+pub const MIN_KDF_ITERATIONS: u32 = 2;
+
+/// How urgent a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// One weakness and what to do about it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityFinding {
+    /// Stable identifier (e.g., "default_password")
+    pub id: &'static str,
+    pub severity: FindingSeverity,
+    /// What is wrong
+    pub message: String,
+    /// What the user can do about it
+    pub action: String,
+}
+
+/// Encryption settings of a store
+#[derive(Debug, Clone)]
+pub struct SecurityPosture {
+    /// Key is derived from the built-in default password
+    pub default_password: bool,
+    /// Parameters the key is derived with
+    pub kdf: KdfParams,
+    /// Key is held by an OS key provider instead of derived from a password
+    pub os_key_protection: bool,
+    /// Round-trip/decryption check of the live key
+    pub encryption: EncryptionCheck,
+}
+
+/// Result of `security_audit`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityAudit {
+    /// Cipher the store is encrypted with
+    pub algorithm: &'static str,
+    pub kdf: KdfParams,
+    /// Findings, most severe first
+    pub findings: Vec<SecurityFinding>,
+    /// No warning or critical findings
+    pub clean: bool,
+}
+
+/// Evaluate a store's encryption settings
+///
+/// # Arguments
+/// * `posture` - Encryption settings to check
+pub fn audit(posture: &SecurityPosture) -> SecurityAudit {
+    let mut findings = Vec::new();
+
+    if posture.default_password {
+        findings.push(SecurityFinding {
+            id: "default_password",
+            severity: FindingSeverity::Critical,
+            message: "The store is encrypted with the built-in default password, so anyone \
+                      with a copy of the data directory can decrypt it"
+                .to_string(),
+            action: "Set a master password".to_string(),
+        });
+    }
+
+    let kdf = &posture.kdf;
+    if kdf.algorithm != "argon2id"
+        || kdf.memory_kib < MIN_KDF_MEMORY_KIB
+        || kdf.iterations < MIN_KDF_ITERATIONS
+    {
+        findings.push(SecurityFinding {
+            id: "weak_kdf",
+            severity: FindingSeverity::Warning,
+            message: format!(
+                "Key derivation ({}, {} KiB, {} iterations) is below the minimum of \
+                 argon2id with {} KiB and {} iterations",
+                kdf.algorithm, kdf.memory_kib, kdf.iterations, MIN_KDF_MEMORY_KIB,
+                MIN_KDF_ITERATIONS
+            ),
+            action: "Re-encrypt the store with stronger key derivation parameters".to_string(),
+        });
+    }
+
+    if !posture.encryption.ok {
+        findings.push(SecurityFinding {
+            id: "encryption_check_failed",
+            severity: FindingSeverity::Critical,
+            message: format!(
+                "The current key failed the {} check: {}",
+                posture.encryption.algorithm,
+                posture.encryption.error.as_deref().unwrap_or("unknown error")
+            ),
+            action: "Restore the data directory from a backup".to_string(),
+        });
+    }
+
+    if !posture.os_key_protection {
+        findings.push(SecurityFinding {
+            id: "no_os_key_protection",
+            severity: FindingSeverity::Info,
+            message: "The encryption key is derived from a password, not held by the OS keychain"
+                .to_string(),
+            action: "No action needed; builds with the keychain feature keep the key there"
+                .to_string(),
+        });
+    }
+
+    findings.sort_by_key(|finding| Reverse(finding.severity));
+    let clean = findings.iter().all(|finding| finding.severity == FindingSeverity::Info);
+
+    SecurityAudit {
+        algorithm: posture.encryption.algorithm,
+        kdf: posture.kdf.clone(),
+        findings,
+        clean,
+    }
+}
+
+/// Audit an open store
+///
+/// # Note
+/// The storage file is read to verify the key but never modified.
+pub(crate) fn audit_storage(persistence: &PersistentStorage) -> SecurityAudit {
+    audit(&SecurityPosture {
+        default_password: persistence.uses_default_password(),
+        kdf: persistence.kdf_params().clone(),
+        os_key_protection: persistence.has_os_key_protection(),
+        encryption: persistence.verify_encryption(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::crypto::CipherAlgorithm::Aes256Gcm;
+    use crate::storage::crypto::CIPHER_ALGORITHM;
+    use crate::storage::key_provider::tests::MemoryKeyProvider;
+    use crate::storage::persistence::{StorageData, DEFAULT_STORAGE_PASSWORD};
+    use tempfile::TempDir;
+
+    fn finding_ids(audit: &SecurityAudit) -> Vec<&'static str> {
+        audit.findings.iter().map(|finding| finding.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_default_password_store_is_flagged() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+        let storage =
+            PersistentStorage::new(data_dir, DEFAULT_STORAGE_PASSWORD, Aes256Gcm).unwrap();
+        storage.save(&StorageData::new()).await.unwrap();
+
+        let audit = audit_storage(&storage);
+
+        assert!(!audit.clean);
+        assert_eq!(audit.algorithm, "AES-256-GCM");
+        assert_eq!(finding_ids(&audit), vec!["default_password", "no_os_key_protection"]);
+        assert_eq!(audit.findings[0].severity, FindingSeverity::Critical);
+        assert_eq!(audit.findings[0].action, "Set a master password");
+    }
+
+    #[tokio::test]
+    async fn test_upgraded_store_is_clean() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = PersistentStorage::with_key_provider(
+            temp_dir.path().to_path_buf(),
+            &MemoryKeyProvider::default(),
+        )
+        .unwrap();
+        storage.save(&StorageData::new()).await.unwrap();
+
+        let audit = audit_storage(&storage);
+
+        assert!(audit.clean);
+        assert!(audit.findings.is_empty());
+    }
+
+    #[test]
+    fn test_weak_kdf_and_failed_check_are_reported() {
+        let audit = audit(&SecurityPosture {
+            default_password: false,
+            kdf: KdfParams {
+                algorithm: "argon2id".to_string(),
+                memory_kib: 4096,
+                iterations: 1,
+                parallelism: 1,
+            },
+            os_key_protection: true,
+            encryption: EncryptionCheck {
+                ok: false,
+                algorithm: CIPHER_ALGORITHM,
+                data_file_checked: true,
+                error: Some("Storage file does not decrypt".to_string()),
+            },
+        });
+
+        assert!(!audit.clean);
+        assert_eq!(finding_ids(&audit), vec!["encryption_check_failed", "weak_kdf"]);
+    }
+}
@@ -0,0 +1,548 @@
+/**
+ * Application settings storage management
+ *
+ * Handles reading and writing user preferences to/from JSON file
+ */
+
+use crate::auth::{parse_proxy, ATProtocolClient};
+use crate::storage::TOKEN_REFRESH_SKEW;
+use crate::types::{AppSettings, AuthError, SETTINGS_VERSION};
+use reqwest::header::HeaderValue;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+pub(crate) const SETTINGS_FILE: &str = "settings.json";
+
+/// Load settings from file
+///
+/// Returns default settings if the file doesn't exist yet. Invalid values are
+/// repaired in memory (see `load_settings_repaired`).
+pub fn load_settings(data_dir: &Path) -> Result<AppSettings, String> {
+    load_settings_repaired(data_dir).map(|(settings, _)| settings)
+}
+
+/// A value reset to its default while loading the settings file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsRepair {
+    /// Settings key (camelCase, as in the file)
+    pub field: String,
+    /// Why the value was dropped
+    pub reason: String,
+}
+
+/// Typed keys of `AppSettings` (everything else is a forward-compat key)
+const KNOWN_KEYS: [&str; 10] = [
+    "version",
+    "defaultServerUrl",
+    "requestCacheTtlSecs",
+    "locale",
+    "tokenRefreshSkewSecs",
+    "httpProxy",
+    "requestTimeoutSecs",
+    "maxRetries",
+    "retryBackoffMs",
+    "userAgent",
+];
+
+/// Load settings, resetting invalid values to their defaults
+///
+/// Known keys with a wrong type are dropped and out-of-range values are reset;
+/// unknown keys are preserved. The file itself is not rewritten.
+///
+/// # Returns
+/// The repaired settings and the list of repairs made
+pub fn load_settings_repaired(
+    data_dir: &Path,
+) -> Result<(AppSettings, Vec<SettingsRepair>), String> {
+    let settings_path = data_dir.join(SETTINGS_FILE);
+
+    if !settings_path.exists() {
+        return Ok((AppSettings::default(), Vec::new()));
+    }
+
+    let content = fs::read_to_string(&settings_path)
+        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+    let mut object: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse settings JSON: {}", e))?;
+
+    let mut repairs = Vec::new();
+    for key in KNOWN_KEYS {
+        let Some(value) = object.get(key) else {
+            continue;
+        };
+        let single = serde_json::Map::from_iter([(key.to_string(), value.clone())]);
+        if let Err(e) = serde_json::from_value::<AppSettings>(single.into()) {
+            object.remove(key);
+            repairs.push(SettingsRepair {
+                field: key.to_string(),
+                reason: format!("Invalid type: {}", e),
+            });
+        }
+    }
+
+    let mut settings: AppSettings = serde_json::from_value(object.into())
+        .map_err(|e| format!("Failed to parse settings JSON: {}", e))?;
+    repairs.extend(settings.repair());
+
+    Ok((settings, repairs))
+}
+
+/// Maximum request cache TTL (1 hour)
+const MAX_REQUEST_CACHE_TTL_SECS: u64 = 3600;
+
+/// Maximum early-refresh window for access tokens (10 minutes)
+const MAX_TOKEN_REFRESH_SKEW_SECS: u64 = 600;
+
+/// Maximum per-request timeout (5 minutes)
+const MAX_REQUEST_TIMEOUT_SECS: u64 = 300;
+
+/// Maximum number of attempts per request
+const MAX_RETRIES: u32 = 10;
+
+/// Maximum base retry backoff (1 minute)
+const MAX_RETRY_BACKOFF_MS: u64 = 60_000;
+
+/// Maximum User-Agent length in bytes
+const MAX_USER_AGENT_LEN: usize = 256;
+
+/// Check that a locale looks like a BCP 47 language tag (e.g., "ja", "en-US", "zh-Hant-TW")
+fn is_valid_locale(locale: &str) -> bool {
+    let mut subtags = locale.split('-');
+
+    let language_ok = subtags.next().is_some_and(|lang| {
+        (2..=3).contains(&lang.len()) && lang.chars().all(|c| c.is_ascii_alphabetic())
+    });
+
+    language_ok
+        && locale.len() <= 35
+        && subtags.all(|tag| {
+            (1..=8).contains(&tag.len()) && tag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+impl AppSettings {
+    /// Out-of-range values as (camelCase key, message)
+    ///
+    /// The default server URL and version are checked by `validate` itself.
+    pub(crate) fn invalid_fields(&self) -> Vec<(&'static str, String)> {
+        let mut invalid = Vec::new();
+
+        if let Some(ttl) = self.request_cache_ttl_secs {
+            if ttl > MAX_REQUEST_CACHE_TTL_SECS {
+                invalid.push((
+                    "requestCacheTtlSecs",
+                    format!(
+                        "requestCacheTtlSecs must be between 0 and {} (got {})",
+                        MAX_REQUEST_CACHE_TTL_SECS, ttl
+                    ),
+                ));
+            }
+        }
+
+        if let Some(locale) = &self.locale {
+            if !is_valid_locale(locale) {
+                invalid.push((
+                    "locale",
+                    format!("locale must be a BCP 47 language tag (got '{}')", locale),
+                ));
+            }
+        }
+
+        if let Some(skew) = self.token_refresh_skew_secs {
+            if skew > MAX_TOKEN_REFRESH_SKEW_SECS {
+                invalid.push((
+                    "tokenRefreshSkewSecs",
+                    format!(
+                        "tokenRefreshSkewSecs must be between 0 and {} (got {})",
+                        MAX_TOKEN_REFRESH_SKEW_SECS, skew
+                    ),
+                ));
+            }
+        }
+
+        if let Some(proxy) = &self.http_proxy {
+            if let Err(AuthError::InvalidServerUrl(reason)) = parse_proxy(proxy) {
+                invalid.push(("httpProxy", format!("Invalid httpProxy: {}", reason)));
+            }
+        }
+
+        if let Some(timeout) = self.request_timeout_secs {
+            if !(1..=MAX_REQUEST_TIMEOUT_SECS).contains(&timeout) {
+                invalid.push((
+                    "requestTimeoutSecs",
+                    format!(
+                        "requestTimeoutSecs must be between 1 and {} (got {})",
+                        MAX_REQUEST_TIMEOUT_SECS, timeout
+                    ),
+                ));
+            }
+        }
+
+        if let Some(retries) = self.max_retries {
+            if !(1..=MAX_RETRIES).contains(&retries) {
+                invalid.push((
+                    "maxRetries",
+                    format!("maxRetries must be between 1 and {} (got {})", MAX_RETRIES, retries),
+                ));
+            }
+        }
+
+        if let Some(backoff) = self.retry_backoff_ms {
+            if !(1..=MAX_RETRY_BACKOFF_MS).contains(&backoff) {
+                invalid.push((
+                    "retryBackoffMs",
+                    format!(
+                        "retryBackoffMs must be between 1 and {} (got {})",
+                        MAX_RETRY_BACKOFF_MS, backoff
+                    ),
+                ));
+            }
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            if user_agent.len() > MAX_USER_AGENT_LEN {
+                invalid.push((
+                    "userAgent",
+                    format!(
+                        "userAgent must be at most {} bytes (got {})",
+                        MAX_USER_AGENT_LEN,
+                        user_agent.len()
+                    ),
+                ));
+            } else if HeaderValue::from_str(user_agent).is_err() {
+                invalid.push((
+                    "userAgent",
+                    "userAgent must only contain printable ASCII characters".to_string(),
+                ));
+            }
+        }
+
+        invalid
+    }
+
+    /// Validate value ranges, normalizing the default server URL
+    pub fn validate(&mut self) -> Result<(), String> {
+        if self.version > SETTINGS_VERSION {
+            return Err(format!(
+                "Settings version {} is newer than supported version {}",
+                self.version, SETTINGS_VERSION
+            ));
+        }
+
+        if let Some(url) = self.default_server_url.take() {
+            let url = ATProtocolClient::normalize_server_url(Some(url))
+                .map_err(|e| format!("Invalid defaultServerUrl: {}", e))?;
+            self.default_server_url = Some(url);
+        }
+
+        match self.invalid_fields().into_iter().next() {
+            Some((_, message)) => Err(message),
+            None => Ok(()),
+        }
+    }
+
+    /// Reset invalid values to their defaults, normalizing the default server URL
+    fn repair(&mut self) -> Vec<SettingsRepair> {
+        let mut repairs = Vec::new();
+
+        if let Some(url) = self.default_server_url.take() {
+            match ATProtocolClient::normalize_server_url(Some(url)) {
+                Ok(url) => self.default_server_url = Some(url),
+                Err(e) => repairs.push(SettingsRepair {
+                    field: "defaultServerUrl".to_string(),
+                    reason: format!("Invalid defaultServerUrl: {}", e),
+                }),
+            }
+        }
+
+        for (field, reason) in self.invalid_fields() {
+            match field {
+                "requestCacheTtlSecs" => self.request_cache_ttl_secs = None,
+                "locale" => self.locale = None,
+                "tokenRefreshSkewSecs" => self.token_refresh_skew_secs = None,
+                "httpProxy" => self.http_proxy = None,
+                "requestTimeoutSecs" => self.request_timeout_secs = None,
+                "maxRetries" => self.max_retries = None,
+                "retryBackoffMs" => self.retry_backoff_ms = None,
+                "userAgent" => self.user_agent = None,
+                _ => continue,
+            }
+            repairs.push(SettingsRepair {
+                field: field.to_string(),
+                reason,
+            });
+        }
+
+        repairs
+    }
+}
+
+/// Repair the settings file in place
+///
+/// # Returns
+/// The repairs made (the file is only rewritten when there were any)
+pub fn repair_settings(data_dir: &Path) -> Result<Vec<SettingsRepair>, String> {
+    let (settings, repairs) = load_settings_repaired(data_dir)?;
+
+    if !repairs.is_empty() {
+        save_settings(data_dir, settings)?;
+    }
+
+    Ok(repairs)
+}
+
+/// Serialize settings for transfer to another machine
+pub fn export_settings(data_dir: &Path) -> Result<String, String> {
+    let mut settings = load_settings(data_dir)?;
+    settings.version = SETTINGS_VERSION;
+
+    serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))
+}
+
+/// Validate and save settings exported by `export_settings`
+///
+/// Unknown keys are preserved for forward compatibility; known keys with a wrong
+/// type or an out-of-range value are rejected and nothing is saved.
+pub fn import_settings(data_dir: &Path, json: &str) -> Result<AppSettings, String> {
+    let mut settings: AppSettings =
+        serde_json::from_str(json).map_err(|e| format!("Invalid settings: {}", e))?;
+
+    settings.validate()?;
+    save_settings(data_dir, settings.clone())?;
+
+    settings.version = SETTINGS_VERSION;
+    Ok(settings)
+}
+
+/// Save settings to file
+///
+/// Validates the settings first, then uses atomic write (temp file + rename)
+pub fn save_settings(data_dir: &Path, mut settings: AppSettings) -> Result<(), String> {
+    settings.validate()?;
+
+    settings.version = SETTINGS_VERSION;
+
+    fs::create_dir_all(data_dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+
+    let settings_path = data_dir.join(SETTINGS_FILE);
+    let temp_path = data_dir.join(format!("{}.tmp", SETTINGS_FILE));
+
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    fs::write(&temp_path, json).map_err(|e| format!("Failed to write temp file: {}", e))?;
+
+    fs::rename(&temp_path, &settings_path)
+        .map_err(|e| format!("Failed to rename temp file: {}", e))?;
+
+    Ok(())
+}
+
+impl AppSettings {
+    /// Resolve the server URL for a login
+    ///
+    /// An explicit `server_url` always wins; otherwise the configured default is used,
+    /// falling back to https://bsky.social. The result is normalized.
+    ///
+    /// # Arguments
+    /// * `server_url` - Server URL given for this login
+    /// * `allow_insecure_localhost` - Accept http:// for loopback hosts
+    ///   (`ClientConfig::allow_insecure_localhost`)
+    pub fn resolve_server_url(
+        &self,
+        server_url: Option<String>,
+        allow_insecure_localhost: bool,
+    ) -> Result<String, AuthError> {
+        ATProtocolClient::normalize_server_url_with(
+            server_url.or_else(|| self.default_server_url.clone()),
+            allow_insecure_localhost,
+        )
+    }
+
+    /// Early-refresh window for access tokens (`TOKEN_REFRESH_SKEW` when unset)
+    ///
+    /// Values above the 10 minute maximum are capped.
+    pub fn token_refresh_skew(&self) -> Duration {
+        self.token_refresh_skew_secs
+            .map(|secs| Duration::from_secs(secs.min(MAX_TOKEN_REFRESH_SKEW_SECS)))
+            .unwrap_or(TOKEN_REFRESH_SKEW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_token_refresh_skew_defaults_and_caps() {
+        assert_eq!(AppSettings::default().token_refresh_skew(), TOKEN_REFRESH_SKEW);
+
+        let settings = AppSettings {
+            token_refresh_skew_secs: Some(300),
+            ..Default::default()
+        };
+        assert_eq!(settings.token_refresh_skew(), Duration::from_secs(300));
+
+        let settings = AppSettings {
+            token_refresh_skew_secs: Some(3600),
+            ..Default::default()
+        };
+        assert_eq!(settings.token_refresh_skew(), Duration::from_secs(600));
+    }
+
+    #[test]
+    fn test_default_server_url_used_when_not_given() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+
+        let settings = AppSettings {
+            default_server_url: Some("pds.example.com".to_string()),
+            ..Default::default()
+        };
+        save_settings(&data_dir, settings).unwrap();
+
+        let loaded = load_settings(&data_dir).unwrap();
+        assert_eq!(
+            loaded.default_server_url.as_deref(),
+            Some("https://pds.example.com")
+        );
+        assert_eq!(
+            loaded.resolve_server_url(None, false).unwrap(),
+            "https://pds.example.com"
+        );
+    }
+
+    #[test]
+    fn test_explicit_server_url_overrides_default() {
+        let settings = AppSettings {
+            default_server_url: Some("https://pds.example.com".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            settings
+                .resolve_server_url(Some("other.example.org".to_string()), false)
+                .unwrap(),
+            "https://other.example.org"
+        );
+        assert_eq!(
+            AppSettings::default().resolve_server_url(None, false).unwrap(),
+            "https://bsky.social"
+        );
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        let settings = AppSettings {
+            default_server_url: Some("https://pds.example.com".to_string()),
+            locale: Some("ja-JP".to_string()),
+            token_refresh_skew_secs: Some(120),
+            ..Default::default()
+        };
+        save_settings(source_dir.path(), settings).unwrap();
+
+        let exported = export_settings(source_dir.path()).unwrap();
+        import_settings(target_dir.path(), &exported).unwrap();
+
+        let imported = load_settings(target_dir.path()).unwrap();
+        assert_eq!(imported.version, SETTINGS_VERSION);
+        assert_eq!(imported.locale.as_deref(), Some("ja-JP"));
+        assert_eq!(imported.token_refresh_skew_secs, Some(120));
+        assert_eq!(
+            imported.default_server_url.as_deref(),
+            Some("https://pds.example.com")
+        );
+    }
+
+    #[test]
+    fn test_import_preserves_unknown_keys() {
+        let temp_dir = TempDir::new().unwrap();
+
+        import_settings(
+            temp_dir.path(),
+            r#"{"version":1,"locale":"en","futureOption":{"enabled":true}}"#,
+        )
+        .unwrap();
+
+        let exported = export_settings(temp_dir.path()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&exported).unwrap();
+        assert_eq!(value["futureOption"]["enabled"], true);
+        assert_eq!(value["locale"], "en");
+    }
+
+    #[test]
+    fn test_import_rejects_out_of_range_skew() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let result = import_settings(temp_dir.path(), r#"{"tokenRefreshSkewSecs":86400}"#);
+
+        assert_eq!(
+            result.unwrap_err(),
+            "tokenRefreshSkewSecs must be between 0 and 600 (got 86400)"
+        );
+        assert!(!temp_dir.path().join(SETTINGS_FILE).exists());
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_values() {
+        let temp_dir = TempDir::new().unwrap();
+
+        assert!(import_settings(temp_dir.path(), r#"{"locale":"not a locale"}"#).is_err());
+        assert!(import_settings(temp_dir.path(), r#"{"requestCacheTtlSecs":"soon"}"#).is_err());
+        assert!(import_settings(temp_dir.path(), r#"{"version":99}"#).is_err());
+        assert!(import_settings(temp_dir.path(), r#"{"userAgent":"a\r\nX-Injected: 1"}"#).is_err());
+        let long_agent = format!(r#"{{"userAgent":"{}"}}"#, "a".repeat(MAX_USER_AGENT_LEN + 1));
+        assert!(import_settings(temp_dir.path(), &long_agent).is_err());
+    }
+
+    #[test]
+    fn test_invalid_default_server_url_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_path_buf();
+
+        let settings = AppSettings {
+            default_server_url: Some("http://pds.example.com".to_string()),
+            ..Default::default()
+        };
+
+        assert!(save_settings(&data_dir, settings).is_err());
+        assert!(load_settings(&data_dir).unwrap().default_server_url.is_none());
+    }
+
+    #[test]
+    fn test_load_repairs_invalid_values() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(SETTINGS_FILE),
+            r#"{"version":1,"locale":"not a locale","tokenRefreshSkewSecs":86400,
+                "maxRetries":"many","requestTimeoutSecs":20,"userAgent":"bad\u0007agent",
+                "futureOption":[1,2]}"#,
+        )
+        .unwrap();
+
+        let (settings, repairs) = load_settings_repaired(temp_dir.path()).unwrap();
+
+        let fields: Vec<&str> = repairs.iter().map(|r| r.field.as_str()).collect();
+        assert_eq!(fields, vec!["maxRetries", "locale", "tokenRefreshSkewSecs", "userAgent"]);
+        assert_eq!(settings.locale, None);
+        assert_eq!(settings.token_refresh_skew_secs, None);
+        assert_eq!(settings.max_retries, None);
+        assert_eq!(settings.user_agent, None);
+        assert_eq!(settings.request_timeout_secs, Some(20));
+        assert_eq!(settings.extra["futureOption"], serde_json::json!([1, 2]));
+
+        // Repairing rewrites the file; a second pass finds nothing
+        assert_eq!(repair_settings(temp_dir.path()).unwrap().len(), 4);
+        assert!(repair_settings(temp_dir.path()).unwrap().is_empty());
+        assert_eq!(load_settings(temp_dir.path()).unwrap().extra.len(), 1);
+    }
+}
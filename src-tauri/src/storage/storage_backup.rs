@@ -0,0 +1,253 @@
+/**
+ * Password-encrypted account store backups
+ *
+ * Seals every account and token of the store under a backup password (independent of
+ * the storage passphrase), so the accounts can be restored or merged on another device
+ */
+
+use crate::storage::crypto::{
+    decrypt_with_aad, derive_key_from_password, encrypt_with_aad, generate_salt,
+};
+use crate::storage::persistence::StorageData;
+use crate::types::AuthError;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Current storage backup format version (2: the payload is bound to `BACKUP_AAD`)
+pub const STORAGE_BACKUP_VERSION: u32 = 2;
+
+/// Oldest backup format version `open` still reads (1: no associated data)
+const MIN_STORAGE_BACKUP_VERSION: u32 = 1;
+
+/// Associated data binding the payload to its purpose, so ciphertexts from other
+/// files encrypted under the same password are rejected
+const BACKUP_AAD: &[u8] = b"taurisky-storage-backup";
+
+/// Plaintext of the password verifier (encrypted under the backup key and `BACKUP_AAD`)
+const VERIFIER_PLAINTEXT: &[u8] = b"taurisky-storage-backup-verifier";
+
+/// AES-GCM nonce + authentication tag bytes around the ciphertext
+const SEALED_OVERHEAD: usize = 12 + 16;
+
+/// How `StorageManager::import_backup` combines a backup with the current store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Add accounts (and their tokens) whose ID is not stored yet
+    Merge,
+    /// Discard the current accounts and tokens first
+    Replace,
+}
+
+/// Result of `StorageManager::import_backup`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupImport {
+    /// IDs of the imported accounts (sorted)
+    pub imported: Vec<String>,
+    /// IDs of the backed-up accounts that already existed (sorted, merge only)
+    pub skipped: Vec<String>,
+}
+
+/// Outer (unencrypted) backup envelope
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupEnvelope {
+    /// Backup format version
+    version: u32,
+    /// Key derivation salt (base64)
+    salt: String,
+    /// Encrypted `VERIFIER_PLAINTEXT`, checked before the payload is decrypted
+    /// (absent in backups written before it was added)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    verifier: Option<String>,
+    /// Encrypted `StorageData` (base64 nonce + ciphertext)
+    data: String,
+}
+
+/// Encrypt storage data as a backup
+///
+/// # Arguments
+/// * `data` - Accounts and tokens to back up
+/// * `password` - Password protecting the backup
+pub(crate) fn seal(data: &StorageData, password: &str) -> Result<Vec<u8>, AuthError> {
+    if password.is_empty() {
+        return Err(AuthError::InvalidInput("Backup password must not be empty".to_string()));
+    }
+
+    let payload = serde_json::to_vec(data).map_err(|e| {
+        AuthError::StorageError(format!("Failed to serialize backup: {}", e))
+    })?;
+
+    let salt = generate_salt();
+    let key = derive_key_from_password(password, &salt)
+        .map_err(|e| AuthError::StorageError(format!("Key derivation failed: {}", e)))?;
+    let encrypt = |plain: &[u8]| {
+        encrypt_with_aad(plain, &key, BACKUP_AAD)
+            .map_err(|e| AuthError::StorageError(format!("Encryption failed: {}", e)))
+    };
+
+    serde_json::to_vec_pretty(&BackupEnvelope {
+        version: STORAGE_BACKUP_VERSION,
+        salt: BASE64.encode(&salt),
+        verifier: Some(encrypt(VERIFIER_PLAINTEXT)?),
+        data: encrypt(&payload)?,
+    })
+    .map_err(|e| AuthError::StorageError(format!("Failed to serialize backup: {}", e)))
+}
+
+/// A parsed backup with its derived key, not yet decrypted
+struct UnlockedBackup {
+    envelope: BackupEnvelope,
+    key: Zeroizing<Vec<u8>>,
+    aad: &'static [u8],
+}
+
+impl UnlockedBackup {
+    /// Parse and version-check a backup and derive its key
+    fn new(backup: &[u8], password: &str) -> Result<Self, AuthError> {
+        let malformed = |e: String| AuthError::StorageError(format!("Malformed backup: {}", e));
+
+        let envelope: BackupEnvelope =
+            serde_json::from_slice(backup).map_err(|e| malformed(e.to_string()))?;
+        if !(MIN_STORAGE_BACKUP_VERSION..=STORAGE_BACKUP_VERSION).contains(&envelope.version) {
+            return Err(AuthError::StorageError(format!(
+                "Unsupported backup version {} (expected {})",
+                envelope.version, STORAGE_BACKUP_VERSION
+            )));
+        }
+        let aad: &'static [u8] = if envelope.version >= 2 { BACKUP_AAD } else { &[] };
+
+        let salt = BASE64.decode(&envelope.salt).map_err(|e| malformed(e.to_string()))?;
+        // Anything shorter than nonce + tag cannot be a ciphertext, whatever the password
+        let sealed = BASE64.decode(&envelope.data).map_err(|e| malformed(e.to_string()))?;
+        if sealed.len() < SEALED_OVERHEAD {
+            return Err(malformed("encrypted data is truncated".to_string()));
+        }
+
+        let key = derive_key_from_password(password, &salt)
+            .map_err(|e| AuthError::StorageError(format!("Key derivation failed: {}", e)))?;
+
+        Ok(Self { envelope, key, aad })
+    }
+
+    /// Whether the key opens the backup (by its verifier, else by decrypting the payload)
+    fn password_matches(&self) -> bool {
+        match &self.envelope.verifier {
+            Some(verifier) => decrypt_with_aad(verifier, &self.key, self.aad)
+                .is_ok_and(|plain| plain == VERIFIER_PLAINTEXT),
+            None => self.decrypt_payload().is_ok(),
+        }
+    }
+
+    fn decrypt_payload(&self) -> Result<Vec<u8>, AuthError> {
+        decrypt_with_aad(&self.envelope.data, &self.key, self.aad).map_err(|_| {
+            AuthError::StorageError(
+                "Wrong backup password (the backup does not decrypt)".to_string(),
+            )
+        })
+    }
+}
+
+/// Check a backup password without decrypting the payload
+///
+/// # Arguments
+/// * `backup` - Backup bytes
+/// * `password` - Password to check
+///
+/// # Returns
+/// Whether the password opens the backup (false for a malformed backup)
+///
+/// # Note
+/// Only the small verifier is decrypted; backups written before the verifier was
+/// added fall back to decrypting the payload.
+pub fn verify_backup_password(backup: &[u8], password: &str) -> bool {
+    UnlockedBackup::new(backup, password).is_ok_and(|unlocked| unlocked.password_matches())
+}
+
+/// Decrypt a backup produced by `seal`
+///
+/// # Arguments
+/// * `backup` - Backup bytes
+/// * `password` - Backup password
+///
+/// # Returns
+/// The backed-up data; a wrong password and a malformed backup fail with distinct
+/// `AuthError::StorageError` messages
+///
+/// # Note
+/// A wrong password is rejected by the verifier before the payload is decrypted.
+pub(crate) fn open(backup: &[u8], password: &str) -> Result<StorageData, AuthError> {
+    let unlocked = UnlockedBackup::new(backup, password)?;
+    if unlocked.envelope.verifier.is_some() && !unlocked.password_matches() {
+        return Err(AuthError::StorageError(
+            "Wrong backup password (the verifier does not decrypt)".to_string(),
+        ));
+    }
+
+    let payload = unlocked.decrypt_payload()?;
+    serde_json::from_slice(&payload)
+        .map_err(|e| AuthError::StorageError(format!("Malformed backup: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrong_password_and_malformed_backup_are_distinguished() {
+        let backup = seal(&StorageData::new(), "backup password").unwrap();
+        assert!(open(&backup, "backup password").unwrap().accounts.is_empty());
+        assert!(matches!(seal(&StorageData::new(), ""), Err(AuthError::InvalidInput(_))));
+
+        let message = |result: Result<StorageData, AuthError>| match result {
+            Err(AuthError::StorageError(message)) => message,
+            other => panic!("expected a storage error, got {:?}", other),
+        };
+        assert!(message(open(&backup, "wrong password")).starts_with("Wrong backup password"));
+        assert!(message(open(b"not json", "backup password")).starts_with("Malformed backup"));
+
+        let mut envelope: serde_json::Value = serde_json::from_slice(&backup).unwrap();
+        envelope["data"] = serde_json::json!("AAAA");
+        let truncated = serde_json::to_vec(&envelope).unwrap();
+        assert!(message(open(&truncated, "backup password")).starts_with("Malformed backup"));
+
+        // A version 2 payload relabelled as version 1 loses its associated data
+        let mut relabelled: serde_json::Value = serde_json::from_slice(&backup).unwrap();
+        relabelled["version"] = serde_json::json!(1);
+        let relabelled = serde_json::to_vec(&relabelled).unwrap();
+        assert!(message(open(&relabelled, "backup password")).starts_with("Wrong backup"));
+
+        envelope["version"] = serde_json::json!(STORAGE_BACKUP_VERSION + 1);
+        let future = serde_json::to_vec(&envelope).unwrap();
+        assert!(message(open(&future, "backup password")).starts_with("Unsupported backup"));
+    }
+
+    #[test]
+    fn test_verifier_checks_password_before_payload() {
+        let backup = seal(&StorageData::new(), "backup password").unwrap();
+        assert!(verify_backup_password(&backup, "backup password"));
+        assert!(!verify_backup_password(&backup, "wrong password"));
+        assert!(!verify_backup_password(b"not json", "backup password"));
+
+        // A verifier that does not decrypt rejects the backup, even with an intact payload
+        let mut envelope: serde_json::Value = serde_json::from_slice(&backup).unwrap();
+        let other = seal(&StorageData::new(), "other password").unwrap();
+        let other: serde_json::Value = serde_json::from_slice(&other).unwrap();
+        envelope["verifier"] = other["verifier"].clone();
+        let mismatched = serde_json::to_vec(&envelope).unwrap();
+        assert!(!verify_backup_password(&mismatched, "backup password"));
+        assert!(matches!(
+            open(&mismatched, "backup password"),
+            Err(AuthError::StorageError(message)) if message.starts_with("Wrong backup password")
+        ));
+
+        // Backups written before the verifier fall back to the payload
+        envelope.as_object_mut().unwrap().remove("verifier");
+        let legacy = serde_json::to_vec(&envelope).unwrap();
+        assert!(verify_backup_password(&legacy, "backup password"));
+        assert!(!verify_backup_password(&legacy, "wrong password"));
+        assert!(open(&legacy, "backup password").is_ok());
+    }
+}
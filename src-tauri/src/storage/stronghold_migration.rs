@@ -0,0 +1,142 @@
+/**
+ * Stronghold vault migration
+ *
+ * The Stronghold plugin used to derive its snapshot key with `DefaultHasher`, which
+ * is neither stable across Rust versions nor a password KDF. The plugin now uses
+ * Argon2 with a per-install salt; this module retires vaults from the old scheme.
+ */
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Salt file used by the Argon2 password hash of the Stronghold plugin
+pub(crate) const STRONGHOLD_SALT_FILE: &str = "stronghold-salt.txt";
+
+/// Marker written once the migration has run
+const MIGRATION_MARKER_FILE: &str = "stronghold-migration.done";
+
+/// Directory (inside the data dir) receiving retired legacy vaults
+const LEGACY_VAULT_DIR: &str = "legacy-vault";
+
+/// File extensions used for Stronghold snapshots
+const VAULT_EXTENSIONS: [&str; 2] = ["hold", "stronghold"];
+
+/// Outcome of `migrate_stronghold_vault`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrongholdMigrationReport {
+    /// The migration had already run; nothing was done
+    pub already_migrated: bool,
+    /// Legacy vault files moved into the legacy-vault directory
+    pub retired_vaults: Vec<String>,
+}
+
+/// Retire Stronghold vaults written with the legacy `DefaultHasher` key (runs once)
+///
+/// # Arguments
+/// * `data_dir` - App data directory holding the vault snapshots
+///
+/// # Note
+/// The legacy hash yields an 8-byte key while Stronghold only accepts 32-byte keys,
+/// so a legacy snapshot cannot be decrypted and re-keyed with Argon2. Existing
+/// snapshot files are instead moved unchanged into `legacy-vault/` (nothing is
+/// deleted) so the Argon2-keyed vault starts from a clean path.
+pub fn migrate_stronghold_vault(data_dir: &Path) -> Result<StrongholdMigrationReport, String> {
+    let marker_path = data_dir.join(MIGRATION_MARKER_FILE);
+
+    if marker_path.exists() {
+        return Ok(StrongholdMigrationReport {
+            already_migrated: true,
+            retired_vaults: Vec::new(),
+        });
+    }
+
+    fs::create_dir_all(data_dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+
+    // A salt file means the Argon2 scheme already created the current vault
+    let has_argon2_vault = data_dir.join(STRONGHOLD_SALT_FILE).exists();
+    let mut retired_vaults = Vec::new();
+
+    if !has_argon2_vault {
+        let entries =
+            fs::read_dir(data_dir).map_err(|e| format!("Failed to read data dir: {}", e))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_vault = path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| VAULT_EXTENSIONS.contains(&ext));
+            if !is_vault {
+                continue;
+            }
+
+            let backup_dir = data_dir.join(LEGACY_VAULT_DIR);
+            fs::create_dir_all(&backup_dir)
+                .map_err(|e| format!("Failed to create legacy vault dir: {}", e))?;
+
+            let file_name = entry.file_name();
+            fs::rename(&path, backup_dir.join(&file_name))
+                .map_err(|e| format!("Failed to move legacy vault: {}", e))?;
+            retired_vaults.push(file_name.to_string_lossy().into_owned());
+        }
+    }
+
+    fs::write(&marker_path, chrono::Utc::now().to_rfc3339())
+        .map_err(|e| format!("Failed to write migration marker: {}", e))?;
+
+    retired_vaults.sort();
+    Ok(StrongholdMigrationReport {
+        already_migrated: false,
+        retired_vaults,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_legacy_vault_survives_and_migrates_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let legacy_bytes = b"legacy snapshot fixture".to_vec();
+        fs::write(data_dir.join("vault.hold"), &legacy_bytes).unwrap();
+        fs::write(data_dir.join("settings.json"), "{}").unwrap();
+
+        let report = migrate_stronghold_vault(data_dir).unwrap();
+
+        assert!(!report.already_migrated);
+        assert_eq!(report.retired_vaults, vec!["vault.hold".to_string()]);
+        assert!(!data_dir.join("vault.hold").exists());
+        assert_eq!(
+            fs::read(data_dir.join(LEGACY_VAULT_DIR).join("vault.hold")).unwrap(),
+            legacy_bytes
+        );
+        assert!(data_dir.join("settings.json").exists());
+
+        // A vault created afterwards is left alone
+        fs::write(data_dir.join("vault.hold"), b"new vault").unwrap();
+        let report = migrate_stronghold_vault(data_dir).unwrap();
+
+        assert!(report.already_migrated);
+        assert!(report.retired_vaults.is_empty());
+        assert_eq!(fs::read(data_dir.join("vault.hold")).unwrap(), b"new vault");
+    }
+
+    #[test]
+    fn test_vault_with_argon2_salt_is_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        fs::write(data_dir.join(STRONGHOLD_SALT_FILE), "salt").unwrap();
+        fs::write(data_dir.join("vault.hold"), b"argon2 vault").unwrap();
+
+        let report = migrate_stronghold_vault(data_dir).unwrap();
+
+        assert!(report.retired_vaults.is_empty());
+        assert!(data_dir.join("vault.hold").exists());
+    }
+}
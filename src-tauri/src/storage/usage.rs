@@ -0,0 +1,174 @@
+/**
+ * Per-account storage attribution
+ *
+ * Splits the space used by the columns file, cached avatars and the encrypted
+ * account store across accounts for the storage-management screen.
+ */
+
+use crate::api::avatars::cached_avatar_bytes;
+use crate::storage::columns_footprint::columns_storage_report;
+use crate::storage::persistence::STORAGE_FILE;
+use crate::storage::StorageManager;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Bytes attributed to one account
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStorageUsage {
+    pub did: String,
+    pub handle: String,
+    /// Size of the account's columns in the columns file
+    pub columns_bytes: u64,
+    /// Cached avatar bytes (split evenly between accounts sharing an avatar URL)
+    pub avatar_bytes: u64,
+    /// Estimated share of the encrypted store (proportional to the serialized
+    /// account + token size)
+    pub store_bytes: u64,
+    /// Sum of the above
+    pub total_bytes: u64,
+}
+
+/// Attribute stored bytes to each account
+///
+/// # Arguments
+/// * `storage` - Storage manager
+/// * `data_dir` - App data directory
+///
+/// # Returns
+/// One entry per account, largest total first (ties ordered by DID)
+pub async fn storage_by_account(
+    storage: &StorageManager,
+    data_dir: &Path,
+) -> Result<Vec<AccountStorageUsage>, String> {
+    let accounts = storage
+        .list_accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))?;
+    let columns = columns_storage_report(data_dir)?.per_account_bytes;
+
+    let mut avatar_users: HashMap<&str, u64> = HashMap::new();
+    for avatar in accounts.iter().filter_map(|a| a.avatar.as_deref()) {
+        *avatar_users.entry(avatar).or_insert(0) += 1;
+    }
+
+    // Serialized size of each account's account + token records
+    let mut record_bytes = Vec::with_capacity(accounts.len());
+    for account in &accounts {
+        let mut bytes = serde_json::to_vec(account).map(|v| v.len() as u64).unwrap_or(0);
+        if let Ok(token) = storage.get_auth_token(&account.id).await {
+            bytes += serde_json::to_vec(&token).map(|v| v.len() as u64).unwrap_or(0);
+        }
+        record_bytes.push(bytes);
+    }
+    let record_total: u64 = record_bytes.iter().sum();
+    let store_file_bytes =
+        fs::metadata(data_dir.join(STORAGE_FILE)).map(|m| m.len()).unwrap_or(0);
+
+    let mut usage: Vec<AccountStorageUsage> = accounts
+        .iter()
+        .zip(record_bytes)
+        .map(|(account, records)| {
+            let columns_bytes = columns.get(&account.did).copied().unwrap_or(0);
+            let avatar_bytes = account
+                .avatar
+                .as_deref()
+                .map(|url| cached_avatar_bytes(data_dir, url) / avatar_users[url])
+                .unwrap_or(0);
+            let store_bytes = if record_total == 0 {
+                0
+            } else {
+                (store_file_bytes as u128 * records as u128 / record_total as u128) as u64
+            };
+
+            AccountStorageUsage {
+                did: account.did.clone(),
+                handle: account.handle.clone(),
+                columns_bytes,
+                avatar_bytes,
+                store_bytes,
+                total_bytes: columns_bytes + avatar_bytes + store_bytes,
+            }
+        })
+        .collect();
+
+    usage.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes).then_with(|| a.did.cmp(&b.did)));
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::avatars::AvatarCache;
+    use crate::storage::columns::{get_default_columns, save_columns};
+    use crate::test_support;
+    use crate::types::Account;
+    use mockito::Server;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn account(id: &str, avatar: &str) -> Account {
+        Account {
+            avatar: Some(avatar.to_string()),
+            is_active: id == "alice",
+            ..test_support::account(id)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attribution_orders_by_usage() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let storage = StorageManager::new(data_dir.to_path_buf()).await.unwrap();
+
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/big.jpg")
+            .with_status(200)
+            .with_body(vec![b'x'; 20_000])
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/small.jpg")
+            .with_status(200)
+            .with_body(vec![b'x'; 1_000])
+            .create_async()
+            .await;
+        let big = format!("{}/big.jpg", server.url());
+        let small = format!("{}/small.jpg", server.url());
+        let avatars = AvatarCache::new(data_dir, Duration::from_secs(3600));
+        avatars.get(&big).await.unwrap();
+        avatars.get(&small).await.unwrap();
+
+        // Bob: one column, large avatar; Alice: three columns, small avatar shared with Carol
+        for account in [account("alice", &small), account("bob", &big), account("carol", &small)] {
+            storage.save_account(&account).await.unwrap();
+        }
+        let mut columns = Vec::new();
+        for (did, count) in [("did:plc:alice", 3), ("did:plc:bob", 1)] {
+            for _ in 0..count {
+                let mut column = get_default_columns(did).remove(0);
+                column.position = columns.len() as u32;
+                columns.push(column);
+            }
+        }
+        save_columns(&data_dir.to_path_buf(), columns).unwrap();
+
+        let usage = storage_by_account(&storage, data_dir).await.unwrap();
+
+        let order: Vec<&str> = usage.iter().map(|u| u.did.as_str()).collect();
+        assert_eq!(order, vec!["did:plc:bob", "did:plc:alice", "did:plc:carol"]);
+        let by_did = |did: &str| usage.iter().find(|u| u.did == did).unwrap();
+        assert!(by_did("did:plc:alice").columns_bytes > 2 * by_did("did:plc:bob").columns_bytes);
+        assert_eq!(by_did("did:plc:carol").columns_bytes, 0);
+        // The shared avatar is split between Alice and Carol
+        assert_eq!(by_did("did:plc:alice").avatar_bytes, by_did("did:plc:carol").avatar_bytes);
+        let shared = cached_avatar_bytes(data_dir, &small);
+        assert_eq!(by_did("did:plc:alice").avatar_bytes, shared / 2);
+        assert!(usage.iter().all(|u| u.store_bytes > 0));
+        let store_total: u64 = usage.iter().map(|u| u.store_bytes).sum();
+        assert!(store_total <= fs::metadata(data_dir.join(STORAGE_FILE)).unwrap().len());
+    }
+}
@@ -0,0 +1,122 @@
+/**
+ * Storage writability checks
+ *
+ * Lets the app warn about a read-only or full data directory before a save or
+ * import fails halfway through
+ */
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Result of probing the data directory
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageWritability {
+    /// Whether the directory exists (or could be created)
+    pub exists: bool,
+    /// Whether a file could be written inside the directory
+    pub writable: bool,
+    /// Free space available to the current user, in bytes (None if unknown)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_bytes: Option<u64>,
+    /// Why the directory is not usable (None when writable)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check that the data directory exists, is writable and report its free space
+///
+/// # Arguments
+/// * `data_dir` - Directory to probe (created if missing)
+///
+/// # Note
+/// Writability is verified by creating and deleting a small probe file;
+/// permission bits alone are not reliable across platforms.
+pub fn check_storage_writable(data_dir: &Path) -> StorageWritability {
+    if let Err(e) = fs::create_dir_all(data_dir) {
+        return StorageWritability {
+            exists: data_dir.is_dir(),
+            writable: false,
+            available_bytes: None,
+            error: Some(format!("Failed to create data dir: {}", e)),
+        };
+    }
+
+    let available_bytes = fs2::available_space(data_dir).ok();
+    let probe_path = data_dir.join(format!(".write-probe-{}.tmp", Uuid::new_v4()));
+
+    let error = match fs::write(&probe_path, b"probe") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe_path);
+            None
+        }
+        Err(e) => Some(format!("Data dir is not writable: {}", e)),
+    };
+
+    StorageWritability {
+        exists: true,
+        writable: error.is_none(),
+        available_bytes,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_writable_dir_reports_space() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("nested");
+
+        let result = check_storage_writable(&data_dir);
+
+        assert!(result.exists);
+        assert!(result.writable);
+        assert!(result.error.is_none());
+        assert!(result.available_bytes.is_some_and(|bytes| bytes > 0));
+        // Only the (created) directory remains; the probe is cleaned up
+        assert_eq!(fs::read_dir(&data_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_uncreatable_dir_is_not_writable() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("file");
+        fs::write(&file_path, "not a directory").unwrap();
+
+        let result = check_storage_writable(&file_path.join("data"));
+
+        assert!(!result.exists);
+        assert!(!result.writable);
+        assert!(result.error.is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_only_dir_is_not_writable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("readonly");
+        fs::create_dir(&data_dir).unwrap();
+        fs::set_permissions(&data_dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Privileged users (e.g. root in CI containers) ignore permission bits
+        if fs::write(data_dir.join("check"), b"").is_ok() {
+            fs::set_permissions(&data_dir, fs::Permissions::from_mode(0o755)).unwrap();
+            return;
+        }
+
+        let result = check_storage_writable(&data_dir);
+        fs::set_permissions(&data_dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(result.exists);
+        assert!(!result.writable);
+        assert!(result.error.is_some());
+    }
+}
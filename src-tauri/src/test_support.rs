@@ -0,0 +1,57 @@
+/**
+ * Shared test fixtures
+ *
+ * Accounts and stores used by the unit tests of several modules
+ */
+
+use crate::storage::columns::{get_default_columns, save_columns};
+use crate::storage::StorageManager;
+use crate::types::{Account, AuthToken, SessionResponse};
+use chrono::Utc;
+use std::path::Path;
+
+/// Active bsky.social account with DID `did:plc:<id>` and handle `<id>.bsky.social`
+pub fn account(id: &str) -> Account {
+    let now = Utc::now().to_rfc3339();
+    Account {
+        id: id.to_string(),
+        did: format!("did:plc:{}", id),
+        handle: format!("{}.bsky.social", id),
+        email: None,
+        display_name: None,
+        avatar: None,
+        server_url: "https://bsky.social".to_string(),
+        created_at: now.clone(),
+        last_used_at: now,
+        is_active: true,
+        refresh_failure_count: 0,
+        next_refresh_not_before: None,
+        note: None,
+    }
+}
+
+/// Store holding account "alice", its token ("secret-access" / "secret-refresh") and a
+/// default timeline column
+pub async fn storage_with_account(data_dir: &Path) -> StorageManager {
+    let storage = StorageManager::new(data_dir.to_path_buf()).await.unwrap();
+
+    storage.save_account(&account("alice")).await.unwrap();
+    storage
+        .save_auth_token(&AuthToken::from_session(
+            "alice",
+            SessionResponse {
+                access_jwt: "secret-access".to_string(),
+                refresh_jwt: "secret-refresh".to_string(),
+                did: "did:plc:alice".to_string(),
+                handle: "alice.bsky.social".to_string(),
+                email: None,
+                display_name: None,
+                avatar: None,
+            },
+        ))
+        .await
+        .unwrap();
+    save_columns(&data_dir.to_path_buf(), get_default_columns("did:plc:alice")).unwrap();
+
+    storage
+}
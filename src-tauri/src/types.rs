@@ -4,8 +4,11 @@
  * These types are serialized/deserialized for communication with the frontend
  */
 
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
 
 /// Bluesky account entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +37,30 @@ pub struct Account {
     pub last_used_at: String,
     /// Active status
     pub is_active: bool,
+    /// Consecutive automatic refresh failures
+    #[serde(default)]
+    pub refresh_failure_count: u32,
+    /// Earliest time the next automatic refresh may run (ISO 8601)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_refresh_not_before: Option<String>,
+    /// Private user note (e.g., "work alt")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Display metadata changes for `update_account` (None leaves a field unchanged)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountPatch {
+    /// New display name (a blank string clears it)
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// New avatar URL (a blank string clears it)
+    #[serde(default)]
+    pub avatar: Option<String>,
+    /// New private note (a blank string clears it)
+    #[serde(default)]
+    pub note: Option<String>,
 }
 
 /// AT Protocol authentication token
@@ -55,6 +82,53 @@ pub struct AuthToken {
     /// AT Protocol session string (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub session_string: Option<String>,
+    /// Private key the tokens are DPoP-bound to (OAuth sessions only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dpop_key: Option<String>,
+    /// Authorization server that issued the tokens (OAuth sessions only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth_issuer: Option<String>,
+    /// Client ID the tokens were issued to (OAuth sessions only, needed to refresh)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth_client_id: Option<String>,
+}
+
+impl AuthToken {
+    /// Build a token from a createSession/refreshSession response
+    pub fn from_session(account_id: &str, session: SessionResponse) -> Self {
+        let now = Utc::now();
+        Self {
+            account_id: account_id.to_string(),
+            access_jwt: session.access_jwt,
+            refresh_jwt: session.refresh_jwt,
+            issued_at: now.to_rfc3339(),
+            // AT Protocol tokens typically expire in ~90 minutes for access, ~60 days for refresh
+            access_expires_at: (now + chrono::Duration::minutes(90)).to_rfc3339(),
+            refresh_expires_at: (now + chrono::Duration::days(60)).to_rfc3339(),
+            session_string: None,
+            dpop_key: None,
+            oauth_issuer: None,
+            oauth_client_id: None,
+        }
+    }
+
+    /// Whether the access token expires within `skew` (unparseable timestamps count as expired)
+    pub fn is_access_expiring(&self, skew: Duration) -> bool {
+        expires_within(&self.access_expires_at, skew)
+    }
+
+    /// Whether the refresh token has expired (unparseable timestamps count as expired)
+    pub fn is_refresh_expired(&self) -> bool {
+        expires_within(&self.refresh_expires_at, Duration::ZERO)
+    }
+}
+
+/// Whether an RFC 3339 expiry timestamp falls within `skew` from now
+fn expires_within(expires_at: &str, skew: Duration) -> bool {
+    let skew = chrono::Duration::from_std(skew).unwrap_or(chrono::Duration::MAX);
+    chrono::DateTime::parse_from_rfc3339(expires_at)
+        .map(|expires_at| expires_at <= Utc::now() + skew)
+        .unwrap_or(true)
 }
 
 /// Login credentials input
@@ -102,6 +176,8 @@ pub enum AuthErrorType {
     NetworkError,
     /// Server error
     ServerError,
+    /// Server temporarily unavailable (502/503/504)
+    Unavailable,
     /// Token expired
     TokenExpired,
     /// Invalid server URL
@@ -110,6 +186,14 @@ pub enum AuthErrorType {
     AccountNotFound,
     /// Storage error
     StorageError,
+    /// Rate limited by the server
+    RateLimited,
+    /// Session identity doesn't match the requested handle
+    IdentityMismatch,
+    /// Invalid request parameter
+    InvalidInput,
+    /// Email two-factor code required to log in
+    AuthFactorTokenRequired,
     /// Unknown error
     Unknown,
 }
@@ -126,6 +210,10 @@ pub enum AuthError {
     #[error("Server error: {0}")]
     ServerError(String),
 
+    /// Gateway errors (502/503/504), e.g. a PDS restarting behind a reverse proxy
+    #[error("Server unavailable: {0}")]
+    Unavailable(String),
+
     #[error("Token expired")]
     TokenExpired,
 
@@ -138,27 +226,186 @@ pub enum AuthError {
     #[error("Storage error: {0}")]
     StorageError(String),
 
+    #[error("Rate limited by server")]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[error("Identity mismatch: {0}")]
+    IdentityMismatch(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Auth factor token required")]
+    AuthFactorTokenRequired,
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
 
 impl AuthError {
     /// Get error type
-    #[allow(dead_code)]
     pub fn error_type(&self) -> AuthErrorType {
         match self {
             AuthError::InvalidCredentials(_) => AuthErrorType::InvalidCredentials,
             AuthError::NetworkError(_) => AuthErrorType::NetworkError,
             AuthError::ServerError(_) => AuthErrorType::ServerError,
+            AuthError::Unavailable(_) => AuthErrorType::Unavailable,
             AuthError::TokenExpired => AuthErrorType::TokenExpired,
             AuthError::InvalidServerUrl(_) => AuthErrorType::InvalidServerUrl,
             AuthError::AccountNotFound(_) => AuthErrorType::AccountNotFound,
             AuthError::StorageError(_) => AuthErrorType::StorageError,
+            AuthError::RateLimited { .. } => AuthErrorType::RateLimited,
+            AuthError::IdentityMismatch(_) => AuthErrorType::IdentityMismatch,
+            AuthError::InvalidInput(_) => AuthErrorType::InvalidInput,
+            AuthError::AuthFactorTokenRequired => AuthErrorType::AuthFactorTokenRequired,
             AuthError::Unknown(_) => AuthErrorType::Unknown,
         }
     }
 }
 
+/// Error returned by commands to the frontend (the TS `AuthError`)
+///
+/// Keeps the error type next to the message so the UI can localize it and offer a
+/// retry only for errors worth retrying.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandError {
+    #[serde(rename = "type")]
+    pub error_type: AuthErrorType,
+    /// Human-readable message (English)
+    pub message: String,
+    /// Seconds to wait before retrying (rate limits only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl CommandError {
+    /// Create an error of a given type
+    pub fn new(error_type: AuthErrorType, message: impl Into<String>) -> Self {
+        Self {
+            error_type,
+            message: message.into(),
+            retry_after_secs: None,
+        }
+    }
+
+    /// Wrap an `AuthError`, prefixing its message with what was being done
+    ///
+    /// # Arguments
+    /// * `context` - Failed operation (e.g., "Login failed")
+    /// * `error` - Underlying error
+    pub fn context(context: &str, error: AuthError) -> Self {
+        let retry_after_secs = match &error {
+            AuthError::RateLimited { retry_after } => retry_after.map(|d| d.as_secs()),
+            _ => None,
+        };
+
+        Self {
+            error_type: error.error_type(),
+            message: format!("{}: {}", context, error),
+            retry_after_secs,
+        }
+    }
+
+    /// Wrap an error from a helper that reports storage failures as strings
+    pub fn storage(message: String) -> Self {
+        Self::new(AuthErrorType::StorageError, message)
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Lets commands that still return `String` use `?` on helpers returning `CommandError`
+impl From<CommandError> for String {
+    fn from(error: CommandError) -> Self {
+        error.message
+    }
+}
+
+/// Current settings file schema version
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Application-wide settings (persisted as plain JSON, no secrets)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSettings {
+    /// Settings schema version (files written before versioning are version 0)
+    #[serde(default)]
+    pub version: u32,
+    /// PDS server URL used when login is called without an explicit server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_server_url: Option<String>,
+    /// Lifetime in seconds of cached read-only XRPC responses (default: 30)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_cache_ttl_secs: Option<u64>,
+    /// UI locale as a BCP 47 language tag (e.g., "ja", "en-US")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Seconds before access token expiry at which it is refreshed early
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_refresh_skew_secs: Option<u64>,
+    /// Proxy for all HTTP requests (e.g., "http://proxy.local:8080")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_proxy: Option<String>,
+    /// Per-request timeout in seconds (default: 30)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
+    /// Total attempts for retryable requests (default: 3; 1 disables retries)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    /// Delay before the first retry in milliseconds, doubled for each further retry
+    /// (default: 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_backoff_ms: Option<u64>,
+    /// User-Agent header sent with every request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Keys written by newer versions of the app (preserved as-is)
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            default_server_url: None,
+            request_cache_ttl_secs: None,
+            locale: None,
+            token_refresh_skew_secs: None,
+            http_proxy: None,
+            request_timeout_secs: None,
+            max_retries: None,
+            retry_backoff_ms: None,
+            user_agent: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+/// Actor profile from app.bsky.actor.getProfile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileView {
+    /// Actor DID
+    pub did: String,
+    /// Actor handle
+    pub handle: String,
+    /// Display name (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Avatar URL (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    /// Profile description (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
 /// Deck column configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -187,12 +434,29 @@ pub struct DeckColumnConfig {
     pub updated_at: String,
 }
 
+/// Changes for `update_column` (None leaves a field unchanged)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnPatch {
+    /// New title (a blank string clears it)
+    #[serde(default)]
+    pub title: Option<String>,
+    /// New width
+    #[serde(default)]
+    pub width: Option<ColumnWidth>,
+    /// New settings (replace the current settings)
+    #[serde(default)]
+    pub settings: Option<HashMap<String, serde_json::Value>>,
+}
+
 /// Column type enum
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ColumnType {
     Timeline,
     Notifications,
+    /// Custom feed generator (feed URI in the `feedUri` setting)
+    Feed,
 }
 
 /// Column width enum (7-stage width settings)
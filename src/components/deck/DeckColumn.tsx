@@ -35,7 +35,11 @@ const DeckColumn: React.FC<DeckColumnProps> = ({ column }) => {
         {/* Placeholder content for MVP */}
         <div className="text-gray-500 text-sm text-center py-8">
           <p className="mb-2">
-            {column.type === 'timeline' ? 'タイムライン' : '通知'}
+            {column.type === 'timeline'
+              ? 'タイムライン'
+              : column.type === 'feed'
+                ? 'フィード'
+                : '通知'}
           </p>
           <p className="text-xs text-gray-400">
             コンテンツは次のフェーズで実装されます
@@ -6,7 +6,7 @@
 
 import React, { createContext, useContext, useState, useCallback, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import type { Account } from '../types/auth';
+import type { Account, LoginResult } from '../types/auth';
 
 interface AccountsContextValue {
   /** List of all registered accounts */
@@ -80,7 +80,7 @@ export const AccountsProvider: React.FC<{ children: React.ReactNode }> = ({ chil
       setIsLoading(true);
 
       try {
-        const account = await invoke<Account>('add_account', {
+        const account = await invoke<LoginResult>('add_account', {
           identifier: handle,
           password,
           serverUrl,
@@ -6,7 +6,7 @@
 
 import React, { createContext, useContext, useState, useCallback, useEffect, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import type { Account, AuthToken } from '../types/auth';
+import type { Account, AuthError, AuthToken, LoginResult } from '../types/auth';
 
 interface AuthContextValue {
   /** Current authenticated user */
@@ -15,8 +15,13 @@ interface AuthContextValue {
   isAuthenticated: boolean;
   /** Whether the auth state is being initialized */
   isLoading: boolean;
-  /** Login with credentials */
-  login: (handle: string, password: string, serverUrl?: string) => Promise<void>;
+  /** Login with credentials (authFactorToken: emailed 2FA code, if requested) */
+  login: (
+    handle: string,
+    password: string,
+    serverUrl?: string,
+    authFactorToken?: string
+  ) => Promise<void>;
   /** Logout current user */
   logout: () => Promise<void>;
   /** Error message from last operation */
@@ -29,6 +34,17 @@ interface AuthContextValue {
 
 const AuthContext = createContext<AuthContextValue | undefined>(undefined);
 
+/**
+ * Message of an error rejected by invoke (commands reject with an AuthError object or a string)
+ */
+const errorMessage = (err: unknown): string => {
+  if (err instanceof Error) return err.message;
+  if (typeof err === 'object' && err !== null && 'message' in err) {
+    return (err as AuthError).message;
+  }
+  return String(err);
+};
+
 // Token refresh interval: 5 minutes before expiration
 const TOKEN_REFRESH_BUFFER_MS = 5 * 60 * 1000; // 5 minutes
 
@@ -119,15 +135,21 @@ export const AuthProvider: React.FC<{ children: React.ReactNode }> = ({ children
   /**
    * Login with handle and password
    */
-  const login = useCallback(async (handle: string, password: string, serverUrl?: string) => {
+  const login = useCallback(async (
+    handle: string,
+    password: string,
+    serverUrl?: string,
+    authFactorToken?: string
+  ) => {
     setError(null);
     setIsLoading(true);
 
     try {
-      const account = await invoke<Account>('login', {
+      const account = await invoke<LoginResult>('login', {
         identifier: handle,
         password,
         serverUrl,
+        authFactorToken,
       });
 
       setCurrentUser(account);
@@ -135,9 +157,9 @@ export const AuthProvider: React.FC<{ children: React.ReactNode }> = ({ children
       // Notify other contexts that accounts have changed
       window.dispatchEvent(new Event('accounts-changed'));
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
-      setError(errorMessage);
-      throw new Error(errorMessage);
+      const message = errorMessage(err);
+      setError(message);
+      throw new Error(message);
     } finally {
       setIsLoading(false);
     }
@@ -159,9 +181,9 @@ export const AuthProvider: React.FC<{ children: React.ReactNode }> = ({ children
       // Notify other contexts that accounts have changed
       window.dispatchEvent(new Event('accounts-changed'));
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
-      setError(errorMessage);
-      throw new Error(errorMessage);
+      const message = errorMessage(err);
+      setError(message);
+      throw new Error(message);
     } finally {
       setIsLoading(false);
     }
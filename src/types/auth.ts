@@ -28,6 +28,20 @@ export interface Account {
   lastUsedAt: string;
   /** Active status */
   isActive: boolean;
+  /** Consecutive automatic refresh failures */
+  refreshFailureCount?: number;
+  /** Earliest time the next automatic refresh may run */
+  nextRefreshNotBefore?: string;
+  /** Private user note (e.g., "work alt") */
+  note?: string;
+}
+
+/**
+ * Result of `login` / `add_account`
+ */
+export interface LoginResult extends Account {
+  /** Set when the password does not look like an app password (login still succeeded) */
+  passwordWarning?: string;
 }
 
 /**
@@ -48,6 +62,8 @@ export interface AuthToken {
   refreshExpiresAt: string;
   /** AT Protocol session string (optional) */
   sessionString?: string;
+  /** Private key the tokens are DPoP-bound to (OAuth sessions only) */
+  dpopKey?: string;
 }
 
 /**
@@ -92,6 +108,8 @@ export enum AuthErrorType {
   NetworkError = "network_error",
   /** Server error */
   ServerError = "server_error",
+  /** Server temporarily unavailable (502/503/504) */
+  Unavailable = "unavailable",
   /** Token expired */
   TokenExpired = "token_expired",
   /** Invalid server URL */
@@ -100,6 +118,14 @@ export enum AuthErrorType {
   AccountNotFound = "account_not_found",
   /** Storage error */
   StorageError = "storage_error",
+  /** Rate limited by the server */
+  RateLimited = "rate_limited",
+  /** Session identity doesn't match the requested handle */
+  IdentityMismatch = "identity_mismatch",
+  /** Invalid request parameter */
+  InvalidInput = "invalid_input",
+  /** Email two-factor code required to log in */
+  AuthFactorTokenRequired = "auth_factor_token_required",
   /** Unknown error */
   Unknown = "unknown",
 }
@@ -114,6 +140,8 @@ export interface AuthError {
   message: string;
   /** Original error (optional) */
   cause?: string;
+  /** Seconds to wait before retrying (rate limits only) */
+  retryAfterSecs?: number;
 }
 
 /**
@@ -146,6 +174,8 @@ export interface DeckColumnConfig {
 export enum ColumnType {
   Timeline = "timeline",
   Notifications = "notifications",
+  /** Custom feed generator (feed URI in the `feedUri` setting) */
+  Feed = "feed",
 }
 
 /**